@@ -0,0 +1,62 @@
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+/// Max number of stored URLs, the oldest ones are removed first, so buttons of old videos stop working
+const MAX_ENTRIES: usize = 4096;
+const RETENTION: Duration = Duration::from_secs(86_400);
+const CALLBACK_DATA_PREFIX: &str = "aof";
+
+/// URLs of videos and times of their buttons by token
+type Buttons = HashMap<Box<str>, (Box<str>, Instant)>;
+
+lazy_static! {
+    static ref AUDIO_BUTTONS: Mutex<Buttons> = Mutex::default();
+}
+
+/// Stores the URL of the video and returns callback data of its audio button.
+/// URLs don't fit in the 64 bytes of callback data, so the data has a token of the URL.
+#[must_use]
+pub fn callback_data(url: &str) -> String {
+    let token: Box<str> = Uuid::new_v4().simple().to_string().into();
+    let mut buttons = AUDIO_BUTTONS.lock().unwrap();
+
+    buttons.retain(|_, (_, created_at)| created_at.elapsed() < RETENTION);
+
+    if buttons.len() >= MAX_ENTRIES {
+        let oldest_token = buttons
+            .iter()
+            .min_by_key(|(_, (_, created_at))| *created_at)
+            .map(|(token, _)| token.clone());
+
+        if let Some(oldest_token) = oldest_token {
+            buttons.remove(&oldest_token);
+        }
+    }
+
+    let data = format!("{CALLBACK_DATA_PREFIX}:{token}");
+    buttons.insert(token, (url.into(), Instant::now()));
+
+    data
+}
+
+/// Returns the token or `None` if the callback data isn't from an audio button
+#[must_use]
+pub fn token_from_callback_data(data: &str) -> Option<&str> {
+    data.strip_prefix(CALLBACK_DATA_PREFIX)?.strip_prefix(':')
+}
+
+/// Returns the URL of the video or `None` if the button is expired
+#[must_use]
+pub fn url(token: &str) -> Option<Box<str>> {
+    AUDIO_BUTTONS
+        .lock()
+        .unwrap()
+        .get(token)
+        .filter(|(_, created_at)| created_at.elapsed() < RETENTION)
+        .map(|(url, _)| url.clone())
+}
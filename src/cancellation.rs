@@ -0,0 +1,255 @@
+use crate::cmd::process;
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+const CALLBACK_DATA_PREFIX: &str = "cnl";
+
+tokio::task_local! {
+    static TASK_TOKEN: CancelToken;
+}
+
+thread_local! {
+    static THREAD_TOKEN: RefCell<Option<CancelToken>> = const { RefCell::new(None) };
+}
+
+#[must_use]
+pub fn to_callback_data(key: &str) -> String {
+    format!("{CALLBACK_DATA_PREFIX}:{key}")
+}
+
+/// Returns the key of the download or `None` if the callback data isn't from the cancel button
+#[must_use]
+pub fn from_callback_data(data: &str) -> Option<&str> {
+    data.strip_prefix(CALLBACK_DATA_PREFIX)?
+        .strip_prefix(':')
+        .filter(|key| !key.is_empty())
+}
+
+/// Cancellation of a download.
+/// Processes spawned by [`process::TrackedCommand`] in the token scope are killed when it's cancelled, later ones fail to start.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    cancelled: Arc<watch::Sender<bool>>,
+    /// Process groups of spawned processes
+    pids: Arc<Mutex<Vec<u32>>>,
+}
+
+impl Default for CancelToken {
+    fn default() -> Self {
+        Self {
+            cancelled: Arc::new(watch::channel(false).0),
+            pids: Arc::default(),
+        }
+    }
+}
+
+impl CancelToken {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        if self.cancelled.send_replace(true) {
+            return;
+        }
+
+        for pid in self.pids.lock().unwrap().drain(..) {
+            let _ = process::kill_group(pid);
+        }
+    }
+
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Waits until the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut receiver = self.cancelled.subscribe();
+
+        // The sender is held by the token, so the channel isn't closed while waiting
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+
+    /// Tracks the process to kill it on cancellation, it's killed right away if the token is already cancelled
+    pub fn track(&self, pid: u32) {
+        let mut pids = self.pids.lock().unwrap();
+
+        if self.is_cancelled() {
+            let _ = process::kill_group(pid);
+        } else {
+            pids.push(pid);
+        }
+    }
+
+    /// Runs the future in the token scope, blocking closures spawned by [`crate::telemetry::spawn_blocking`] inherit it
+    pub async fn scope<F: Future>(&self, future: F) -> F::Output {
+        TASK_TOKEN.scope(self.clone(), future).await
+    }
+
+    /// Sets the token of the current thread until the guard is dropped
+    #[must_use]
+    pub fn enter(&self) -> EnterGuard {
+        let previous = THREAD_TOKEN.with(|token| token.replace(Some(self.clone())));
+
+        EnterGuard { previous }
+    }
+
+    /// Token of the current task or thread
+    #[must_use]
+    pub fn current() -> Option<Self> {
+        TASK_TOKEN
+            .try_with(Clone::clone)
+            .ok()
+            .or_else(|| THREAD_TOKEN.with(|token| token.borrow().clone()))
+    }
+}
+
+/// Restores the previous token of the thread when dropped
+pub struct EnterGuard {
+    previous: Option<CancelToken>,
+}
+
+impl Drop for EnterGuard {
+    fn drop(&mut self) {
+        THREAD_TOKEN.with(|token| *token.borrow_mut() = self.previous.take());
+    }
+}
+
+#[derive(Debug)]
+struct Registered {
+    user_id: i64,
+    token: CancelToken,
+}
+
+/// Downloads in progress that can be cancelled, by the key passed in callback data of the cancel button
+#[derive(Debug, Clone, Default)]
+pub struct CancelStore {
+    downloads: Arc<Mutex<HashMap<Box<str>, Registered>>>,
+}
+
+impl CancelStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a download that can be cancelled only by the user, it's removed when the returned value is dropped
+    pub fn insert(&self, user_id: i64) -> Cancellable {
+        let key: Box<str> = Uuid::new_v4().simple().to_string().into();
+        let token = CancelToken::new();
+
+        self.downloads.lock().unwrap().insert(
+            key.clone(),
+            Registered {
+                user_id,
+                token: token.clone(),
+            },
+        );
+
+        Cancellable {
+            key,
+            token,
+            store: self.clone(),
+        }
+    }
+
+    pub fn remove(&self, key: &str) {
+        self.downloads.lock().unwrap().remove(key);
+    }
+
+    /// Returns `false` if the download is finished or belongs to another user
+    pub fn cancel(&self, key: &str, user_id: i64) -> bool {
+        let mut downloads = self.downloads.lock().unwrap();
+
+        match downloads.get(key) {
+            Some(download) if download.user_id == user_id => {
+                let download = downloads.remove(key).unwrap();
+                drop(downloads);
+
+                download.token.cancel();
+
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Download registered in [`CancelStore`], it's removed from the store when dropped
+pub struct Cancellable {
+    pub key: Box<str>,
+    pub token: CancelToken,
+    store: CancelStore,
+}
+
+impl Drop for Cancellable {
+    fn drop(&mut self) {
+        self.store.remove(&self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{cmd::process::TrackedCommand as _, config::ProcessLimits};
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_callback_data() {
+        assert_eq!(from_callback_data(&to_callback_data("key")), Some("key"));
+        assert_eq!(from_callback_data("cnl:"), None);
+        assert_eq!(from_callback_data("pls:key:d"), None);
+    }
+
+    #[test]
+    fn test_cancel_kills_tracked_processes() {
+        let token = CancelToken::new();
+        let mut child = {
+            let _entered = token.enter();
+
+            process::command("sleep", ProcessLimits::default())
+                .arg("5")
+                .spawn_tracked()
+                .unwrap()
+        };
+
+        token.cancel();
+
+        let status = process::wait_timeout(&mut child, Duration::from_secs(5)).unwrap();
+        assert!(status.is_some_and(|status| !status.success()));
+
+        let _entered = token.enter();
+        assert!(process::command("true", ProcessLimits::default()).spawn_tracked().is_err());
+    }
+
+    #[test]
+    fn test_only_owner_cancels() {
+        let store = CancelStore::new();
+        let Cancellable { key, token, .. } = &store.insert(1);
+
+        assert!(!store.cancel(key, 2));
+        assert!(!token.is_cancelled());
+        assert!(store.cancel(key, 1));
+        assert!(token.is_cancelled());
+        assert!(!store.cancel(key, 1));
+    }
+
+    #[test]
+    fn test_dropped_download_is_removed() {
+        let store = CancelStore::new();
+        let key = store.insert(1).key.clone();
+
+        assert!(!store.cancel(&key, 1));
+    }
+}
@@ -0,0 +1,238 @@
+use crate::fs::JsonFile;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use url::Url;
+
+/// Media downloaded from links without commands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaType {
+    #[default]
+    Video,
+    Audio,
+}
+
+impl MediaType {
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "video" => Some(Self::Video),
+            "audio" => Some(Self::Audio),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Video => "video",
+            Self::Audio => "audio",
+        }
+    }
+}
+
+/// Settings of a chat changed by its admins.
+/// Settings missing in the saved file, for example ones added after it was saved, have their default values.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    /// Whether links in messages without commands are downloaded, commands work regardless of it
+    pub auto_download_enabled: bool,
+    /// Whether videos are sent with a button to their source, it turns off media groups because they can't have buttons
+    pub source_button_enabled: bool,
+    /// Whether videos are sent with a button to get their audio, it turns off media groups like the source button
+    pub audio_button_enabled: bool,
+    /// Whether captions of sent media have a link to their source
+    pub link_is_visible: bool,
+    /// Whether captions of sent videos have the description of their source
+    pub description_enabled: bool,
+    /// Media downloaded from links in messages without commands, `/vd` and `/ad` work regardless of it
+    pub default_media_type: MediaType,
+    /// Domains of links downloaded without commands, links of all domains are downloaded if it's empty.
+    /// Subdomains match their parent domain, for example `m.youtube.com` matches `youtube.com`.
+    pub include_domains: Vec<Box<str>>,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            auto_download_enabled: true,
+            source_button_enabled: false,
+            audio_button_enabled: false,
+            link_is_visible: false,
+            description_enabled: false,
+            default_media_type: MediaType::Video,
+            include_domains: vec![],
+        }
+    }
+}
+
+impl ChatConfig {
+    /// Videos with buttons are sent one by one, because media groups can't have buttons
+    #[must_use]
+    pub const fn video_buttons_enabled(&self) -> bool {
+        self.source_button_enabled || self.audio_button_enabled
+    }
+
+    /// Whether the link is downloaded without a command, chats without included domains download links of all domains
+    #[must_use]
+    pub fn is_domain_allowed(&self, url: &str) -> bool {
+        if self.include_domains.is_empty() {
+            return true;
+        }
+
+        Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(ToOwned::to_owned))
+            .is_some_and(|host| {
+                self.include_domains
+                    .iter()
+                    .any(|domain| host == **domain || host.ends_with(&format!(".{domain}")))
+            })
+    }
+}
+
+/// Settings per chat, chats without changed settings use the default ones.
+/// Settings are saved to the file in the background on every change and loaded from it on startup, so they're kept across restarts.
+#[allow(clippy::module_name_repetitions)]
+#[derive(Debug, Clone)]
+pub struct ChatConfigStore {
+    chats: Arc<Mutex<HashMap<i64, ChatConfig>>>,
+    file: JsonFile,
+}
+
+impl ChatConfigStore {
+    /// Loads settings saved to the file, there are no changed settings if it doesn't exist
+    /// # Errors
+    /// Returns [`io::Error`] if the file can't be read or parsed
+    pub fn load(path: PathBuf) -> Result<Self, io::Error> {
+        let file = JsonFile::new(path);
+
+        Ok(Self {
+            chats: Arc::new(Mutex::new(file.read()?)),
+            file,
+        })
+    }
+
+    #[must_use]
+    pub fn get(&self, chat_id: i64) -> ChatConfig {
+        self.chats.lock().unwrap().get(&chat_id).cloned().unwrap_or_default()
+    }
+
+    /// Changes settings of the chat and saves all settings.
+    /// If saving fails, the change is kept until restart.
+    fn update<R>(&self, chat_id: i64, f: impl FnOnce(&mut ChatConfig) -> R) -> R {
+        let mut chats = self.chats.lock().unwrap();
+        let result = f(chats.entry(chat_id).or_default());
+
+        self.file.save_in_background(&*chats);
+
+        result
+    }
+
+    pub fn set_auto_download_enabled(&self, chat_id: i64, enabled: bool) {
+        self.update(chat_id, |chat_config| chat_config.auto_download_enabled = enabled);
+    }
+
+    pub fn set_source_button_enabled(&self, chat_id: i64, enabled: bool) {
+        self.update(chat_id, |chat_config| chat_config.source_button_enabled = enabled);
+    }
+
+    pub fn set_audio_button_enabled(&self, chat_id: i64, enabled: bool) {
+        self.update(chat_id, |chat_config| chat_config.audio_button_enabled = enabled);
+    }
+
+    pub fn set_link_is_visible(&self, chat_id: i64, visible: bool) {
+        self.update(chat_id, |chat_config| chat_config.link_is_visible = visible);
+    }
+
+    pub fn set_description_enabled(&self, chat_id: i64, enabled: bool) {
+        self.update(chat_id, |chat_config| chat_config.description_enabled = enabled);
+    }
+
+    pub fn set_default_media_type(&self, chat_id: i64, media_type: MediaType) {
+        self.update(chat_id, |chat_config| chat_config.default_media_type = media_type);
+    }
+
+    /// Adds the domain to the included ones, returns `false` if it's already included
+    pub fn include_domain(&self, chat_id: i64, domain: &str) -> bool {
+        self.update(chat_id, |chat_config| {
+            if chat_config
+                .include_domains
+                .iter()
+                .any(|included_domain| **included_domain == *domain)
+            {
+                return false;
+            }
+
+            chat_config.include_domains.push(domain.into());
+
+            true
+        })
+    }
+
+    /// Removes the domain from the included ones, returns `false` if it isn't included
+    pub fn exclude_domain(&self, chat_id: i64, domain: &str) -> bool {
+        self.update(chat_id, |chat_config| {
+            let len = chat_config.include_domains.len();
+
+            chat_config.include_domains.retain(|included_domain| **included_domain != *domain);
+
+            chat_config.include_domains.len() != len
+        })
+    }
+
+    /// Moves settings of a group upgraded to a supergroup to the new chat ID
+    pub fn migrate(&self, from_chat_id: i64, to_chat_id: i64) {
+        let mut chats = self.chats.lock().unwrap();
+        let Some(chat_config) = chats.remove(&from_chat_id) else {
+            return;
+        };
+
+        chats.insert(to_chat_id, chat_config);
+
+        self.file.save_in_background(&*chats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_is_allowed_without_included_domains() {
+        assert!(ChatConfig::default().is_domain_allowed("https://example.com/video"));
+        assert!(ChatConfig::default().is_domain_allowed("not a url"));
+    }
+
+    #[test]
+    fn test_domain_is_allowed() {
+        let chat_config = ChatConfig {
+            include_domains: vec!["youtube.com".into(), "youtu.be".into()],
+            ..ChatConfig::default()
+        };
+
+        assert!(chat_config.is_domain_allowed("https://youtube.com/watch?v=abc"));
+        assert!(chat_config.is_domain_allowed("https://m.youtube.com/watch?v=abc"));
+        assert!(chat_config.is_domain_allowed("https://youtu.be/abc"));
+        assert!(!chat_config.is_domain_allowed("https://notyoutube.com/watch?v=abc"));
+        assert!(!chat_config.is_domain_allowed("https://youtube.com.example.com/video"));
+        assert!(!chat_config.is_domain_allowed("not a url"));
+    }
+
+    #[test]
+    fn test_missing_settings_have_default_values() {
+        let chat_config: ChatConfig = serde_json::from_str(r#"{"source_button_enabled": true}"#).unwrap();
+
+        assert!(chat_config.source_button_enabled);
+        assert!(chat_config.auto_download_enabled);
+        assert_eq!(chat_config.default_media_type, MediaType::Video);
+    }
+}
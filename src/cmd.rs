@@ -1,5 +1,14 @@
 pub mod ffmpeg;
+pub mod process;
+pub mod whisper;
 pub mod ytdl;
 
-pub use ffmpeg::{convert_to_jpg, merge_streams};
-pub use ytdl::{download_audio_to_path, download_to_pipe, download_video_to_path, get_media_or_playlist_info};
+pub use ffmpeg::{
+    convert_audio, convert_to_animation, convert_to_jpg, convert_to_wav, crop_to_square, cut, detect_hw_encoder, download_hls,
+    merge_streams, remove_segments, transcode_to_h264, H264Encoder,
+};
+pub use whisper::transcribe_to_srt;
+pub use ytdl::{
+    download_audio_to_path, download_subtitles_to_path, download_to_pipe, download_video_to_path, get_media_or_playlist_info,
+    get_playlist_entries, get_version, run_update,
+};
@@ -1,10 +1,14 @@
+use super::process::{self, TrackedCommand as _};
+use crate::config::ProcessLimits;
+
 use std::{
-    io,
+    fs, io,
     os::fd::RawFd,
     path::Path,
-    process::{Child, Command, Stdio},
+    process::{Child, Stdio},
+    time::Duration,
 };
-use tracing::instrument;
+use tracing::{event, instrument, Level};
 
 /// Merge the video and audio streams into a single file.
 /// # Errors
@@ -17,8 +21,9 @@ pub fn merge_streams(
     audio_fd: RawFd,
     extension: impl AsRef<str>,
     output_path: impl AsRef<Path>,
+    limits: ProcessLimits,
 ) -> Result<Child, io::Error> {
-    Command::new("/usr/bin/ffmpeg")
+    process::command("/usr/bin/ffmpeg", limits)
         .args([
             "-y",
             "-hide_banner",
@@ -47,16 +52,16 @@ pub fn merge_streams(
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::inherit())
-        .spawn()
+        .spawn_tracked()
 }
 
 /// Convert image to `jpg` format.
 /// # Errors
 /// Returns [`io::Error`] if the spawn child process fails.
-pub fn convert_to_jpg(input_url: impl AsRef<str>, output_path: impl AsRef<Path>) -> Result<(), io::Error> {
+pub fn convert_to_jpg(input_url: impl AsRef<str>, output_path: impl AsRef<Path>, limits: ProcessLimits) -> Result<(), io::Error> {
     let input_url = input_url.as_ref();
 
-    Command::new("/usr/bin/ffmpeg")
+    process::command("/usr/bin/ffmpeg", limits)
         .args([
             "-y",
             "-hide_banner",
@@ -69,7 +74,614 @@ pub fn convert_to_jpg(input_url: impl AsRef<str>, output_path: impl AsRef<Path>)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::inherit())
-        .spawn()?
+        .spawn_tracked()?
         .wait()
         .map(|_| ())
 }
+
+/// Crop the video to a centered square and scale it down to `size`, as required for video notes.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), %size))]
+pub fn crop_to_square(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    size: u32,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args([
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-i",
+            input_path.as_ref().to_string_lossy().as_ref(),
+            "-vf",
+            &format!("crop='min(iw,ih)':'min(iw,ih)',scale='min({size},iw)':'min({size},ih)'"),
+            "-c:v",
+            "libx264",
+            "-c:a",
+            "aac",
+            "-nostats",
+            "-preset",
+            "ultrafast",
+            output_path.as_ref().to_string_lossy().as_ref(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+/// Cut the part of the video from `start` with `duration` in seconds without re-encoding.
+/// The part starts from the nearest keyframe, so it can start a bit earlier.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), %start, %duration))]
+pub fn cut(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    start: f64,
+    duration: f64,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args([
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-ss",
+            &start.to_string(),
+            "-i",
+            input_path.as_ref().to_string_lossy().as_ref(),
+            "-t",
+            &duration.to_string(),
+            "-c",
+            "copy",
+            "-avoid_negative_ts",
+            "make_zero",
+            "-nostats",
+            output_path.as_ref().to_string_lossy().as_ref(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+/// Remove the segments given as start and end in seconds from the video without re-encoding.
+/// Segments should be sorted and not overlap. The kept parts are joined with the concat demuxer, its list is written to `list_path`.
+/// # Errors
+/// Returns [`io::Error`] if writing the list fails or the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), segments_len = segments.len()))]
+pub fn remove_segments(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    list_path: impl AsRef<Path>,
+    segments: &[(f64, f64)],
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    fs::write(&list_path, concat_list(&input_path.as_ref().to_string_lossy(), segments))?;
+
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args([
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+            list_path.as_ref().to_string_lossy().as_ref(),
+            "-c",
+            "copy",
+            "-avoid_negative_ts",
+            "make_zero",
+            "-nostats",
+            output_path.as_ref().to_string_lossy().as_ref(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+/// List of the concat demuxer with parts of the input between the segments
+fn concat_list(input_path: &str, segments: &[(f64, f64)]) -> String {
+    // Quotes in the path are escaped the way the concat demuxer expects
+    let input_path = input_path.replace('\'', r"'\''");
+    let mut parts = vec![];
+    let mut start = 0.0;
+
+    for (segment_start, segment_end) in segments {
+        if *segment_start > start {
+            parts.push(format!("file '{input_path}'\ninpoint {start}\noutpoint {segment_start}"));
+        }
+
+        start = *segment_end;
+    }
+
+    // The last part lasts until the end of the video
+    parts.push(format!("file '{input_path}'\ninpoint {start}"));
+
+    format!("ffconcat version 1.0\n{}\n", parts.join("\n"))
+}
+
+/// Convert the video to an MP4 without audio, so Telegram shows it as a looping animation.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy()))]
+pub fn convert_to_animation(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>, limits: ProcessLimits) -> Result<(), io::Error> {
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args([
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-i",
+            input_path.as_ref().to_string_lossy().as_ref(),
+            "-an",
+            // H264 requires even dimensions
+            "-vf",
+            "scale=trunc(iw/2)*2:trunc(ih/2)*2",
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            "-movflags",
+            "+faststart",
+            "-nostats",
+            "-preset",
+            "ultrafast",
+            output_path.as_ref().to_string_lossy().as_ref(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+/// `VA-API` render device, it's the first GPU on most hosts
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+/// H264 encoder of `FFmpeg`, hardware ones take much less CPU
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum H264Encoder {
+    #[default]
+    Software,
+    Nvenc,
+    Qsv,
+    Vaapi,
+}
+
+impl H264Encoder {
+    /// Hardware encoders in the order they're probed
+    const HARDWARE: [Self; 3] = [Self::Nvenc, Self::Qsv, Self::Vaapi];
+
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Software => "libx264",
+            Self::Nvenc => "h264_nvenc",
+            Self::Qsv => "h264_qsv",
+            Self::Vaapi => "h264_vaapi",
+        }
+    }
+
+    const fn input_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Vaapi => &["-vaapi_device", VAAPI_DEVICE],
+            Self::Software | Self::Nvenc | Self::Qsv => &[],
+        }
+    }
+
+    /// Filter converting frames to the pixel format accepted by the encoder
+    const fn format_filter(self) -> &'static str {
+        match self {
+            Self::Software | Self::Nvenc => "format=yuv420p",
+            Self::Qsv => "format=nv12",
+            Self::Vaapi => "format=nv12,hwupload",
+        }
+    }
+
+    /// Encoders don't share a constant quality option, so the CRF is passed to the closest one
+    fn quality_args(self, crf: u8) -> Vec<String> {
+        let crf = crf.to_string();
+
+        match self {
+            Self::Software => vec!["-crf".to_owned(), crf, "-preset".to_owned(), "veryfast".to_owned()],
+            Self::Nvenc => vec!["-rc".to_owned(), "vbr".to_owned(), "-cq".to_owned(), crf],
+            Self::Qsv => vec!["-global_quality".to_owned(), crf, "-preset".to_owned(), "veryfast".to_owned()],
+            Self::Vaapi => vec!["-qp".to_owned(), crf],
+        }
+    }
+}
+
+/// Finds the first hardware H264 encoder that can encode a test video.
+/// `FFmpeg` lists encoders it's built with, even if there is no device for them, so they're checked by encoding.
+#[instrument(skip_all)]
+pub fn detect_hw_encoder(limits: ProcessLimits) -> Option<H264Encoder> {
+    H264Encoder::HARDWARE.into_iter().find(|encoder| {
+        let mut args = vec!["-hide_banner", "-loglevel", "error"];
+        args.extend(encoder.input_args());
+        args.extend([
+            "-f",
+            "lavfi",
+            "-i",
+            "color=size=256x256:duration=0.1",
+            "-vf",
+            encoder.format_filter(),
+            "-c:v",
+            encoder.as_str(),
+            "-f",
+            "null",
+            "-",
+        ]);
+
+        let result = process::command("/usr/bin/ffmpeg", limits)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status_tracked();
+
+        match result {
+            Ok(status) => {
+                event!(Level::DEBUG, encoder = encoder.as_str(), %status, "Hardware encoder probed");
+
+                status.success()
+            }
+            Err(err) => {
+                event!(Level::WARN, %err, encoder = encoder.as_str(), "Error probing hardware encoder");
+
+                false
+            }
+        }
+    })
+}
+
+/// Re-encode the video to H264 with AAC audio in MP4, which Telegram clients play everywhere.
+/// The bitrate in kbps is capped if it's passed, so the output fits the size limit.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), encoder = encoder.as_str(), %crf, ?max_bitrate))]
+pub fn transcode_to_h264(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    encoder: H264Encoder,
+    crf: u8,
+    max_bitrate: Option<u64>,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let args = transcode_args(
+        &input_path.as_ref().to_string_lossy(),
+        &output_path.as_ref().to_string_lossy(),
+        encoder,
+        crf,
+        max_bitrate,
+    );
+
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+fn transcode_args(input_path: &str, output_path: &str, encoder: H264Encoder, crf: u8, max_bitrate: Option<u64>) -> Vec<String> {
+    let mut args: Vec<String> = ["-y", "-hide_banner", "-loglevel", "error"]
+        .into_iter()
+        .chain(encoder.input_args().iter().copied())
+        .map(ToOwned::to_owned)
+        .collect();
+    args.extend([
+        "-i".to_owned(),
+        input_path.to_owned(),
+        // H264 requires even dimensions
+        "-vf".to_owned(),
+        format!("scale=trunc(iw/2)*2:trunc(ih/2)*2,{}", encoder.format_filter()),
+        "-c:v".to_owned(),
+        encoder.as_str().to_owned(),
+    ]);
+    args.extend(encoder.quality_args(crf));
+    if let Some(max_bitrate) = max_bitrate {
+        args.extend([
+            "-maxrate".to_owned(),
+            format!("{max_bitrate}k"),
+            "-bufsize".to_owned(),
+            format!("{}k", max_bitrate * 2),
+        ]);
+    }
+    args.extend([
+        "-c:a".to_owned(),
+        "aac".to_owned(),
+        "-movflags".to_owned(),
+        "+faststart".to_owned(),
+        "-nostats".to_owned(),
+        output_path.to_owned(),
+    ]);
+
+    args
+}
+
+/// Convert audio with the encoder, keeping its metadata, and set the bitrate in kbps if it's passed.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), %encoder, ?bitrate))]
+pub fn convert_audio(
+    input_path: impl AsRef<Path>,
+    output_path: impl AsRef<Path>,
+    encoder: &str,
+    bitrate: Option<u16>,
+    loudness_target: Option<f64>,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let args = convert_audio_args(
+        &input_path.as_ref().to_string_lossy(),
+        &output_path.as_ref().to_string_lossy(),
+        encoder,
+        bitrate,
+        loudness_target,
+    );
+
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+fn convert_audio_args(
+    input_path: &str,
+    output_path: &str,
+    encoder: &str,
+    bitrate: Option<u16>,
+    loudness_target: Option<f64>,
+) -> Vec<String> {
+    let mut args = vec![
+        "-y".to_owned(),
+        "-hide_banner".to_owned(),
+        "-loglevel".to_owned(),
+        "error".to_owned(),
+        "-i".to_owned(),
+        input_path.to_owned(),
+        "-vn".to_owned(),
+        "-c:a".to_owned(),
+        encoder.to_owned(),
+    ];
+    if let Some(bitrate) = bitrate {
+        args.extend(["-b:a".to_owned(), format!("{bitrate}k")]);
+    }
+    // Single pass of EBU R128 normalization, the true peak is lowered to leave headroom for lossy encoders
+    if let Some(loudness_target) = loudness_target {
+        args.extend(["-af".to_owned(), format!("loudnorm=I={loudness_target}:TP=-1.5:LRA=11")]);
+    }
+    args.extend(["-nostats".to_owned(), output_path.to_owned()]);
+
+    args
+}
+
+/// Download the HLS stream of the playlist URL to an MP4 file without re-encoding.
+/// Writing stops at `max_file_size` bytes, `FFmpeg` exits successfully then, so callers should check the output size.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails, times out or exits with an error.
+#[instrument(skip_all, fields(%input_url, output_path = %output_path.as_ref().as_os_str().to_string_lossy()))]
+pub fn download_hls(
+    input_url: &str,
+    output_path: impl AsRef<Path>,
+    max_file_size: u64,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let mut child = process::command("/usr/bin/ffmpeg", limits)
+        .args(download_hls_args(input_url, &output_path.as_ref().to_string_lossy(), max_file_size))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn_tracked()?;
+
+    let Some(status) = process::wait_timeout(&mut child, Duration::from_secs(timeout))? else {
+        event!(Level::ERROR, "FFmpeg process timed out");
+
+        process::kill(&child)?;
+
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "FFmpeg process timed out"));
+    };
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+fn download_hls_args(input_url: &str, output_path: &str, max_file_size: u64) -> Vec<String> {
+    [
+        "-y",
+        "-hide_banner",
+        "-loglevel",
+        "error",
+        "-i",
+        input_url,
+        "-c",
+        "copy",
+        // ADTS headers of AAC in MPEG-TS segments aren't allowed in MP4
+        "-bsf:a",
+        "aac_adtstoasc",
+        "-movflags",
+        "+faststart",
+        "-nostats",
+        "-fs",
+        &max_file_size.to_string(),
+        "-f",
+        "mp4",
+        output_path,
+    ]
+    .map(ToOwned::to_owned)
+    .to_vec()
+}
+
+/// Convert audio to 16 kHz mono WAV, the only input format of `whisper.cpp`.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy()))]
+pub fn convert_to_wav(input_path: impl AsRef<Path>, output_path: impl AsRef<Path>, limits: ProcessLimits) -> Result<(), io::Error> {
+    let status = process::command("/usr/bin/ffmpeg", limits)
+        .args([
+            "-y",
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-i",
+            input_path.as_ref().to_string_lossy().as_ref(),
+            "-vn",
+            "-ar",
+            "16000",
+            "-ac",
+            "1",
+            "-c:a",
+            "pcm_s16le",
+            "-nostats",
+            output_path.as_ref().to_string_lossy().as_ref(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status_tracked()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("FFmpeg exited with status `{status}`")));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_list() {
+        assert_eq!(
+            concat_list("/tmp/video.mp4", &[(0.0, 5.0), (30.5, 60.0)]),
+            "ffconcat version 1.0\n\
+             file '/tmp/video.mp4'\ninpoint 5\noutpoint 30.5\n\
+             file '/tmp/video.mp4'\ninpoint 60\n"
+        );
+        assert_eq!(
+            concat_list("/tmp/video.mp4", &[(10.0, 20.0)]),
+            "ffconcat version 1.0\n\
+             file '/tmp/video.mp4'\ninpoint 0\noutpoint 10\n\
+             file '/tmp/video.mp4'\ninpoint 20\n"
+        );
+    }
+
+    #[test]
+    fn test_concat_list_escapes_quotes() {
+        assert_eq!(
+            concat_list("/tmp/it's.mp4", &[]),
+            "ffconcat version 1.0\nfile '/tmp/it'\\''s.mp4'\ninpoint 0\n"
+        );
+    }
+
+    #[test]
+    fn test_transcode_args() {
+        let args = transcode_args("in.webm", "out.mp4", H264Encoder::Software, 23, None);
+
+        assert_eq!(args[..6], ["-y", "-hide_banner", "-loglevel", "error", "-i", "in.webm"]);
+        assert!(args.windows(2).any(|pair| pair == ["-c:v", "libx264"]));
+        assert!(args.windows(2).any(|pair| pair == ["-crf", "23"]));
+        assert!(!args.iter().any(|arg| arg == "-maxrate"));
+        assert_eq!(args.last().map(String::as_str), Some("out.mp4"));
+
+        let args = transcode_args("in.webm", "out.mp4", H264Encoder::Vaapi, 23, Some(1500));
+
+        // The device is set before the input
+        assert_eq!(args[4..8], ["-vaapi_device", VAAPI_DEVICE, "-i", "in.webm"]);
+        assert!(args
+            .windows(2)
+            .any(|pair| pair == ["-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2,format=nv12,hwupload"]));
+        assert!(args.windows(2).any(|pair| pair == ["-qp", "23"]));
+        assert!(args.windows(4).any(|pair| pair == ["-maxrate", "1500k", "-bufsize", "3000k"]));
+    }
+
+    #[test]
+    fn test_convert_audio_args() {
+        let args = convert_audio_args("in.webm", "out.mp3", "libmp3lame", None, None);
+
+        assert_eq!(
+            args,
+            [
+                "-y",
+                "-hide_banner",
+                "-loglevel",
+                "error",
+                "-i",
+                "in.webm",
+                "-vn",
+                "-c:a",
+                "libmp3lame",
+                "-nostats",
+                "out.mp3"
+            ]
+        );
+
+        let args = convert_audio_args("in.webm", "out.m4a", "aac", Some(128), Some(-16.0));
+
+        assert!(args.windows(2).any(|pair| pair == ["-b:a", "128k"]));
+        assert!(args.windows(2).any(|pair| pair == ["-af", "loudnorm=I=-16:TP=-1.5:LRA=11"]));
+        assert_eq!(args.last().map(String::as_str), Some("out.m4a"));
+    }
+
+    #[test]
+    fn test_download_hls_args() {
+        let args = download_hls_args("https://example.com/playlist.m3u8", "out.mp4", 50_000_000);
+
+        assert!(args.windows(2).any(|pair| pair == ["-i", "https://example.com/playlist.m3u8"]));
+        assert!(args.windows(2).any(|pair| pair == ["-c", "copy"]));
+        assert!(args.windows(2).any(|pair| pair == ["-fs", "50000000"]));
+        assert_eq!(args[args.len() - 3..], ["-f", "mp4", "out.mp4"]);
+    }
+}
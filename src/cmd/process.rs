@@ -0,0 +1,170 @@
+use crate::{cancellation::CancelToken, config::ProcessLimits};
+
+use nix::{
+    sys::{
+        resource::{setrlimit, Resource},
+        signal::{killpg, Signal},
+    },
+    unistd::Pid,
+};
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::process::CommandExt as _,
+    process::{Child, Command, ExitStatus},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Interval of checking whether a process waited by [`wait_timeout`] exited
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Creates a command that is run in its own process group with the resource limits,
+/// so a runaway extractor or `FFmpeg` can't exhaust the host
+pub fn command(program: impl AsRef<OsStr>, limits: ProcessLimits) -> Command {
+    let mut command = Command::new(program);
+    command.process_group(0);
+
+    // SAFETY: `setrlimit` is async-signal-safe and doesn't allocate, so it can be called between `fork` and `exec`
+    unsafe {
+        command.pre_exec(move || {
+            for (resource, limit) in [
+                (Resource::RLIMIT_CPU, limits.cpu_time),
+                (Resource::RLIMIT_AS, limits.memory),
+                (Resource::RLIMIT_FSIZE, limits.file_size),
+            ] {
+                if let Some(limit) = limit {
+                    setrlimit(resource, limit, limit)?;
+                }
+            }
+
+            Ok(())
+        });
+    }
+
+    command
+}
+
+/// Spawns commands tracked by the cancel token of the current task or thread, if it's set,
+/// so their processes are killed if the download is cancelled
+pub trait TrackedCommand {
+    /// # Errors
+    /// Returns [`io::Error`] if the process can't be spawned or the download is already cancelled
+    fn spawn_tracked(&mut self) -> Result<Child, io::Error>;
+
+    /// Spawns the command and waits for it to exit
+    /// # Errors
+    /// Returns [`io::Error`] if the process can't be spawned or waited or the download is already cancelled
+    fn status_tracked(&mut self) -> Result<ExitStatus, io::Error> {
+        self.spawn_tracked()?.wait()
+    }
+}
+
+impl TrackedCommand for Command {
+    fn spawn_tracked(&mut self) -> Result<Child, io::Error> {
+        let token = CancelToken::current();
+
+        if token.as_ref().is_some_and(CancelToken::is_cancelled) {
+            return Err(io::Error::new(io::ErrorKind::Interrupted, "Download is cancelled"));
+        }
+
+        let child = self.spawn()?;
+
+        if let Some(token) = token {
+            token.track(child.id());
+        }
+
+        Ok(child)
+    }
+}
+
+/// Waits for the process to exit for up to the timeout by polling its status, so no thread is spawned for a wait.
+/// # Errors
+/// Returns [`io::Error`] if the status of the process can't be got
+/// # Returns
+/// Returns `None` if the process is still running after the timeout
+pub fn wait_timeout(child: &mut Child, timeout: Duration) -> Result<Option<ExitStatus>, io::Error> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+
+        thread::sleep(WAIT_POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Kills the process with all processes of its group, like `FFmpeg` spawned by `yt-dlp` to merge formats
+/// # Errors
+/// Returns [`io::Error`] if the signal can't be sent
+pub fn kill(child: &Child) -> Result<(), io::Error> {
+    kill_group(child.id())
+}
+
+/// Kills the process group, processes are the leaders of their groups, so the group ID is the ID of the process
+/// # Errors
+/// Returns [`io::Error`] if the signal can't be sent
+pub fn kill_group(pgid: u32) -> Result<(), io::Error> {
+    #[allow(clippy::cast_possible_wrap)]
+    let pgid = Pid::from_raw(pgid as i32);
+
+    killpg(pgid, Signal::SIGKILL).map_err(io::Error::from)
+}
+
+/// Kills the process group of an async child process when dropped, unless it's disarmed after the process exits.
+/// A future waiting for the process can be dropped at any `await`, for example when the handler is aborted,
+/// so the process is killed here instead of being left running.
+pub struct KillGuard {
+    pid: Option<u32>,
+}
+
+impl KillGuard {
+    #[must_use]
+    pub fn new(child: &tokio::process::Child) -> Self {
+        Self { pid: child.id() }
+    }
+
+    pub fn disarm(&mut self) {
+        self.pid = None;
+    }
+}
+
+impl Drop for KillGuard {
+    fn drop(&mut self) {
+        if let Some(pid) = self.pid {
+            let _ = kill_group(pid);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_timeout_returns_exit_status() {
+        let mut child = command("true", ProcessLimits::default()).spawn().unwrap();
+
+        let status = wait_timeout(&mut child, Duration::from_secs(5)).unwrap();
+
+        assert!(status.is_some_and(|status| status.success()));
+    }
+
+    #[test]
+    fn test_wait_timeout_returns_none_if_running() {
+        let mut child = command("sleep", ProcessLimits::default()).arg("5").spawn().unwrap();
+
+        let status = wait_timeout(&mut child, Duration::from_millis(100)).unwrap();
+
+        assert!(status.is_none());
+
+        kill(&child).unwrap();
+        child.wait().unwrap();
+    }
+}
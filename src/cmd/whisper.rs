@@ -0,0 +1,54 @@
+use super::process::{self, TrackedCommand as _};
+use crate::config::ProcessLimits;
+
+use std::{io, path::Path, process::Stdio, time::Duration};
+use tracing::{event, instrument, Level};
+
+/// Transcribe the 16 kHz WAV file with the `whisper.cpp` CLI to `{output_path_without_extension}.srt`.
+/// The language is detected if it's `None`.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails, times out or exits with an error.
+#[instrument(skip_all, fields(input_path = %input_path.as_ref().as_os_str().to_string_lossy(), ?language))]
+pub fn transcribe_to_srt(
+    executable_path: impl AsRef<str>,
+    model_path: impl AsRef<Path>,
+    input_path: impl AsRef<Path>,
+    output_path_without_extension: impl AsRef<Path>,
+    language: Option<&str>,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let mut child = process::command(executable_path.as_ref(), limits)
+        .args([
+            "--model",
+            model_path.as_ref().to_string_lossy().as_ref(),
+            "--file",
+            input_path.as_ref().to_string_lossy().as_ref(),
+            "--language",
+            language.unwrap_or("auto"),
+            "--output-srt",
+            "--output-file",
+            output_path_without_extension.as_ref().to_string_lossy().as_ref(),
+            "--no-prints",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn_tracked()?;
+
+    let Some(exit_code) = process::wait_timeout(&mut child, Duration::from_secs(timeout))? else {
+        event!(Level::ERROR, "Child process timed out");
+
+        process::kill(&child)?;
+
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Whisper timed out"));
+    };
+
+    if !exit_code.success() {
+        event!(Level::ERROR, "Child process exited with error status: {exit_code}");
+
+        return Err(io::Error::other(format!("Whisper exited with status `{exit_code}`")));
+    }
+
+    Ok(())
+}
@@ -1,16 +1,24 @@
-use crate::models::{VideoInYT, VideosInYT};
+use super::process::{self, TrackedCommand as _};
+use crate::{
+    config::ProcessLimits,
+    metrics::YT_DLP_PROCESS_DURATION,
+    models::{PlaylistEntry, VideoInYT, VideosInYT},
+};
 
-use serde::de::Error as _;
-use serde_json::{json, Value};
+use serde::de::DeserializeOwned;
 use std::{
-    io::{self, Read},
+    fmt::{self, Display, Formatter},
+    io,
     os::fd::OwnedFd,
     path::Path,
     process::{Child, Command, Stdio},
     time::Duration,
 };
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncReadExt as _, BufReader},
+    time::timeout,
+};
 use tracing::{event, Level};
-use wait_timeout::ChildExt as _;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -18,6 +26,67 @@ pub enum Error {
     Io(#[from] io::Error),
     #[error("JSON error: {0}")]
     Json(#[from] serde_json::Error),
+    #[error("Youtube-dl failed: {0}")]
+    Failed(FailureCause),
+}
+
+/// Cause of a `yt-dlp` failure recognized by its error output.
+/// Retrying doesn't help with these causes, so the user gets a specific message instead of "try again later".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureCause {
+    GeoRestricted,
+    AgeRestricted,
+    Private,
+    NotFound,
+    Drm,
+    LoginRequired,
+}
+
+impl FailureCause {
+    /// Patterns are checked in order, because some messages match several causes,
+    /// for example "Private video. Sign in if you've been granted access" isn't an age restriction.
+    const PATTERNS: &'static [(&'static str, Self)] = &[
+        ("drm protected", Self::Drm),
+        ("private video", Self::Private),
+        ("this video is private", Self::Private),
+        ("confirm your age", Self::AgeRestricted),
+        ("age-restricted", Self::AgeRestricted),
+        ("age restricted", Self::AgeRestricted),
+        ("inappropriate for some users", Self::AgeRestricted),
+        ("sign in to confirm", Self::LoginRequired),
+        ("login required", Self::LoginRequired),
+        ("use --cookies", Self::LoginRequired),
+        ("available in your country", Self::GeoRestricted),
+        ("not available from your location", Self::GeoRestricted),
+        ("geo restriction", Self::GeoRestricted),
+        ("geo-restricted", Self::GeoRestricted),
+        ("http error 404", Self::NotFound),
+        ("video unavailable", Self::NotFound),
+        ("does not exist", Self::NotFound),
+    ];
+
+    #[must_use]
+    pub fn from_stderr(stderr: &str) -> Option<Self> {
+        let stderr = stderr.to_lowercase();
+
+        Self::PATTERNS
+            .iter()
+            .find(|(pattern, _)| stderr.contains(pattern))
+            .map(|(_, cause)| *cause)
+    }
+}
+
+impl Display for FailureCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GeoRestricted => f.write_str("geo restricted"),
+            Self::AgeRestricted => f.write_str("age restricted"),
+            Self::Private => f.write_str("private"),
+            Self::NotFound => f.write_str("not found"),
+            Self::Drm => f.write_str("DRM protected"),
+            Self::LoginRequired => f.write_str("login required"),
+        }
+    }
 }
 
 /// Download stream to a pipe.
@@ -32,8 +101,10 @@ pub fn download_to_pipe(
     executable_path: impl AsRef<str>,
     url: impl AsRef<str>,
     format: impl AsRef<str>,
+    extra_args: &[String],
+    limits: ProcessLimits,
 ) -> Result<Child, io::Error> {
-    let args = [
+    let mut args = vec![
         "--ignore-config",
         "--abort-on-error",
         "--no-colors",
@@ -50,17 +121,16 @@ pub fn download_to_pipe(
         "--no-check-formats",
         "--http-chunk-size",
         "10M",
-        "-f",
-        format.as_ref(),
-        url.as_ref(),
     ];
+    args.extend(extra_args.iter().map(String::as_str));
+    args.extend(["-f", format.as_ref(), url.as_ref()]);
 
-    Command::new(executable_path.as_ref())
+    process::command(executable_path.as_ref(), limits)
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::from(fd))
         .stderr(Stdio::null())
-        .spawn()
+        .spawn_tracked()
 }
 
 pub fn download_video_to_path(
@@ -68,11 +138,44 @@ pub fn download_video_to_path(
     url: impl AsRef<str>,
     format: impl AsRef<str>,
     output_dir_path: impl AsRef<Path>,
+    extra_args: &[String],
     timeout: u64,
+    limits: ProcessLimits,
 ) -> Result<(), io::Error> {
     let output_dir_path = output_dir_path.as_ref().to_string_lossy();
+    let args = download_video_args(&output_dir_path, format.as_ref(), url.as_ref(), extra_args);
 
-    let args = [
+    let _timer = YT_DLP_PROCESS_DURATION.with_label_values(&["download_video"]).start_timer();
+
+    let mut child = process::command(executable_path.as_ref(), limits)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn_tracked()?;
+
+    let Some(exit_code) = process::wait_timeout(&mut child, Duration::from_secs(timeout))? else {
+        event!(Level::ERROR, "Child process timed out");
+
+        process::kill(&child)?;
+
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Youtube-dl timed out"));
+    };
+
+    if !exit_code.success() {
+        event!(Level::ERROR, "Child process exited with error status: {exit_code}");
+
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Youtube-dl exited with status `{exit_code}`"),
+        ));
+    }
+
+    Ok(())
+}
+
+fn download_video_args<'a>(output_dir_path: &'a str, format: &'a str, url: &'a str, extra_args: &'a [String]) -> Vec<&'a str> {
+    let mut args = vec![
         "--no-update",
         "--ignore-config",
         "--abort-on-error",
@@ -80,7 +183,7 @@ pub fn download_video_to_path(
         "--socket-timeout",
         "5",
         "--paths",
-        output_dir_path.as_ref(),
+        output_dir_path,
         "--output",
         "%(id)s.%(ext)s",
         "--no-playlist",
@@ -93,22 +196,64 @@ pub fn download_video_to_path(
         "--no-check-formats",
         "--http-chunk-size",
         "10M",
-        "-f",
-        format.as_ref(),
-        url.as_ref(),
     ];
+    args.extend(extra_args.iter().map(String::as_str));
+    args.extend(["-f", format, url]);
 
-    let mut child = Command::new(executable_path.as_ref())
+    args
+}
+
+/// Download subtitles in `vtt` format to the directory without the media itself.
+/// Auto-generated subtitles are used if the video doesn't have regular ones.
+pub fn download_subtitles_to_path(
+    executable_path: impl AsRef<str>,
+    url: impl AsRef<str>,
+    languages: impl AsRef<str>,
+    output_dir_path: impl AsRef<Path>,
+    extra_args: &[String],
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<(), io::Error> {
+    let output_dir_path = output_dir_path.as_ref().to_string_lossy();
+
+    let mut args = vec![
+        "--no-update",
+        "--ignore-config",
+        "--abort-on-error",
+        "--no-colors",
+        "--socket-timeout",
+        "5",
+        "--paths",
+        output_dir_path.as_ref(),
+        "--output",
+        "%(id)s.%(ext)s",
+        "--no-playlist",
+        "--skip-download",
+        "--write-subs",
+        "--write-auto-subs",
+        "--sub-format",
+        "vtt",
+        "--sub-langs",
+        languages.as_ref(),
+        "--quiet",
+        "--no-progress",
+    ];
+    args.extend(extra_args.iter().map(String::as_str));
+    args.push(url.as_ref());
+
+    let _timer = YT_DLP_PROCESS_DURATION.with_label_values(&["download_subtitles"]).start_timer();
+
+    let mut child = process::command(executable_path.as_ref(), limits)
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::inherit())
-        .spawn()?;
+        .spawn_tracked()?;
 
-    let Some(exit_code) = child.wait_timeout(Duration::from_secs(timeout))? else {
+    let Some(exit_code) = process::wait_timeout(&mut child, Duration::from_secs(timeout))? else {
         event!(Level::ERROR, "Child process timed out");
 
-        child.kill()?;
+        process::kill(&child)?;
 
         return Err(io::Error::new(io::ErrorKind::TimedOut, "Youtube-dl timed out"));
     };
@@ -125,17 +270,70 @@ pub fn download_video_to_path(
     Ok(())
 }
 
+/// Download audio to the directory.
+/// If `track_number` is passed, it's embedded in the file metadata, so players keep the album order.
+#[allow(clippy::too_many_arguments)]
 pub fn download_audio_to_path(
     executable_path: impl AsRef<str>,
     url: impl AsRef<str>,
     format: impl AsRef<str>,
     output_extension: impl AsRef<str>,
     output_dir_path: impl AsRef<Path>,
+    track_number: Option<usize>,
+    extra_args: &[String],
     timeout: u64,
+    limits: ProcessLimits,
 ) -> Result<(), io::Error> {
     let output_dir_path = output_dir_path.as_ref().to_string_lossy();
+    // `--parse-metadata` treats the numeric `FROM` part as a literal value instead of a field name
+    let track_number_metadata = track_number.map(|track_number| format!("{track_number}:%(track_number)s"));
+    let args = download_audio_args(
+        &output_dir_path,
+        format.as_ref(),
+        output_extension.as_ref(),
+        track_number_metadata.as_deref(),
+        url.as_ref(),
+        extra_args,
+    );
+
+    let _timer = YT_DLP_PROCESS_DURATION.with_label_values(&["download_audio"]).start_timer();
+
+    let mut child = process::command(executable_path.as_ref(), limits)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .spawn_tracked()?;
 
-    let args = [
+    let Some(exit_code) = process::wait_timeout(&mut child, Duration::from_secs(timeout))? else {
+        event!(Level::ERROR, "Child process timed out");
+
+        process::kill(&child)?;
+
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Youtube-dl timed out"));
+    };
+
+    if !exit_code.success() {
+        event!(Level::ERROR, "Child process exited with error status: {exit_code}");
+
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Youtube-dl exited with status `{exit_code}`"),
+        ));
+    }
+
+    Ok(())
+}
+
+fn download_audio_args<'a>(
+    output_dir_path: &'a str,
+    format: &'a str,
+    output_extension: &'a str,
+    track_number_metadata: Option<&'a str>,
+    url: &'a str,
+    extra_args: &'a [String],
+) -> Vec<&'a str> {
+    let mut args = vec![
         "--no-update",
         "--ignore-config",
         "--abort-on-error",
@@ -143,14 +341,14 @@ pub fn download_audio_to_path(
         "--socket-timeout",
         "5",
         "--paths",
-        output_dir_path.as_ref(),
+        output_dir_path,
         "--output",
         "%(id)s.%(ext)s",
         "--prefer-ffmpeg",
         "--hls-prefer-ffmpeg",
         "--extract-audio",
         "--audio-format",
-        output_extension.as_ref(),
+        output_extension,
         "--no-playlist",
         "--write-all-thumbnail",
         "--no-mtime",
@@ -159,45 +357,44 @@ pub fn download_audio_to_path(
         "--no-simulate",
         "--no-progress",
         "--no-check-formats",
-        "-f",
-        format.as_ref(),
-        url.as_ref(),
     ];
 
-    let mut child = Command::new(executable_path.as_ref())
-        .args(args)
-        .stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::inherit())
-        .spawn()?;
+    if let Some(track_number_metadata) = track_number_metadata {
+        args.extend(["--parse-metadata", track_number_metadata, "--embed-metadata"]);
+    }
 
-    let Some(exit_code) = child.wait_timeout(Duration::from_secs(timeout))? else {
-        event!(Level::ERROR, "Child process timed out");
+    args.extend(extra_args.iter().map(String::as_str));
+    args.extend(["-f", format, url]);
 
-        child.kill()?;
+    args
+}
 
-        return Err(io::Error::new(io::ErrorKind::TimedOut, "Youtube-dl timed out"));
-    };
+/// Gets info of the media or all entries of the playlist.
+/// The process is killed if it times out or the returned future is dropped.
+pub async fn get_media_or_playlist_info(
+    executable_path: impl AsRef<str>,
+    url: impl AsRef<str>,
+    allow_playlist: bool,
+    extra_args: &[String],
+    timeout_secs: u64,
+    limits: ProcessLimits,
+) -> Result<VideosInYT, Error> {
+    let args = info_args(url.as_ref(), allow_playlist, extra_args);
 
-    if !exit_code.success() {
-        event!(Level::ERROR, "Child process exited with error status: {exit_code}");
+    let mut videos: Vec<VideoInYT> = dump_json(executable_path.as_ref(), &args, timeout_secs, limits).await?;
 
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            format!("Youtube-dl exited with status `{exit_code}`"),
-        ));
+    // Some extractors don't fill playlist fields for entries, so we use the position in the playlist
+    if videos.len() > 1 {
+        for (index, video) in videos.iter_mut().enumerate() {
+            video.playlist_index.get_or_insert(index + 1);
+        }
     }
 
-    Ok(())
+    Ok(VideosInYT::new(videos))
 }
 
-pub fn get_media_or_playlist_info(
-    executable_path: impl AsRef<str>,
-    url: impl AsRef<str>,
-    allow_playlist: bool,
-    timeout: u64,
-) -> Result<VideosInYT, Error> {
-    let args = [
+fn info_args<'a>(url: &'a str, allow_playlist: bool, extra_args: &'a [String]) -> Vec<&'a str> {
+    let mut args = vec![
         "--no-update",
         "--ignore-config",
         "--abort-on-error",
@@ -215,55 +412,267 @@ pub fn get_media_or_playlist_info(
         "--simulate",
         "--no-progress",
         "--no-check-formats",
-        "-J",
-        url.as_ref(),
+        "--dump-json",
+    ];
+    args.extend(extra_args.iter().map(String::as_str));
+    args.push(url);
+
+    args
+}
+
+/// Gets titles, durations and thumbnails of all entries of the playlist without their formats.
+/// It's much faster than [`get_media_or_playlist_info`] for large playlists, because pages of the entries aren't extracted.
+/// The process is killed if it times out or the returned future is dropped.
+pub async fn get_playlist_entries(
+    executable_path: impl AsRef<str>,
+    url: impl AsRef<str>,
+    extra_args: &[String],
+    timeout_secs: u64,
+    limits: ProcessLimits,
+) -> Result<Vec<PlaylistEntry>, Error> {
+    let mut args = vec![
+        "--no-update",
+        "--ignore-config",
+        "--abort-on-error",
+        "--no-color",
+        "--socket-timeout",
+        "5",
+        "--yes-playlist",
+        "--flat-playlist",
+        "--quiet",
+        "--skip-download",
+        "--simulate",
+        "--no-progress",
+        "--dump-json",
     ];
+    args.extend(extra_args.iter().map(String::as_str));
+    args.push(url.as_ref());
+
+    let mut entries: Vec<PlaylistEntry> = dump_json(executable_path.as_ref(), &args, timeout_secs, limits).await?;
+
+    for (index, entry) in entries.iter_mut().enumerate() {
+        entry.playlist_index.get_or_insert(index + 1);
+    }
 
-    let mut child = Command::new(executable_path.as_ref())
+    Ok(entries)
+}
+
+/// Runs `yt-dlp` with the `--dump-json` argument and parses each line of the output.
+/// Lines are parsed as they're read, so the raw output isn't kept, but all entries are collected before they're returned:
+/// callers need the whole playlist to count, sort and cache it.
+async fn dump_json<T: DeserializeOwned>(
+    executable_path: &str,
+    args: &[&str],
+    timeout_secs: u64,
+    limits: ProcessLimits,
+) -> Result<Vec<T>, Error> {
+    let _timer = YT_DLP_PROCESS_DURATION.with_label_values(&["info"]).start_timer();
+
+    let mut child = tokio::process::Command::from(process::command(executable_path, limits))
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::piped())
-        .stderr(Stdio::inherit())
+        .stderr(Stdio::piped())
         .spawn()?;
+    let mut kill_guard = process::KillGuard::new(&child);
 
-    let mut stdout = vec![];
-    let child_stdout = child.stdout.take();
-    io::copy(&mut child_stdout.unwrap(), &mut stdout)?;
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr_reader = child.stderr.take().unwrap();
 
-    let Some(exit_code) = child.wait_timeout(Duration::from_secs(timeout))? else {
-        event!(Level::ERROR, "Child process timed out");
+    // Stderr is drained concurrently, so the process doesn't block on a full stderr pipe.
+    let read_stdout = async {
+        let mut videos = vec![];
+
+        while let Some(line) = stdout.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let video: T = serde_json::from_str(&line).map_err(|err| {
+                event!(Level::ERROR, %err, "Error parsing media info");
+
+                err
+            })?;
+            videos.push(video);
+        }
 
-        child.kill()?;
+        Ok::<_, Error>(videos)
+    };
+    let read_stderr = async {
+        let mut stderr = String::new();
+        let _ = stderr_reader.read_to_string(&mut stderr).await;
+
+        Ok(stderr)
+    };
+
+    let Ok(result) = timeout(Duration::from_secs(timeout_secs), async {
+        let (videos, stderr) = tokio::try_join!(read_stdout, read_stderr)?;
+        let exit_code = child.wait().await?;
+
+        Ok::<_, Error>((videos, stderr, exit_code))
+    })
+    .await
+    else {
+        event!(Level::ERROR, "Child process timed out");
 
         return Err(io::Error::new(io::ErrorKind::TimedOut, "Youtube-dl timed out").into());
     };
+    let (videos, stderr, exit_code) = result?;
+
+    kill_guard.disarm();
 
     if !exit_code.success() {
-        event!(Level::ERROR, "Child process exited with error status: {exit_code}");
+        event!(Level::ERROR, %stderr, "Child process exited with error status: {exit_code}");
+
+        if let Some(cause) = FailureCause::from_stderr(&stderr) {
+            return Err(Error::Failed(cause));
+        }
 
         return Err(io::Error::new(io::ErrorKind::Other, format!("Youtube-dl exited with status `{exit_code}`")).into());
     }
 
-    let mut stderr = vec![];
-    if let Some(mut reader) = child.stderr {
-        reader.read_to_end(&mut stderr)?;
+    if !stderr.is_empty() {
+        event!(Level::DEBUG, %stderr, "Child process wrote to stderr");
     }
 
-    let value: Value = serde_json::from_slice(&stdout)?;
+    Ok(videos)
+}
 
-    if value["_type"] == json!("playlist") {
-        let mut videos = vec![];
+/// Runs the command and returns its trimmed output.
+/// The output is read while waiting for the process, so a process that hangs with its output open is still killed on timeout.
+async fn get_output_with_timeout(command: Command, timeout_secs: u64) -> Result<String, io::Error> {
+    let child = tokio::process::Command::from(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let mut kill_guard = process::KillGuard::new(&child);
 
-        let entries = value["entries"].as_array().ok_or(serde_json::Error::custom("No entries found"))?;
+    let Ok(output) = timeout(Duration::from_secs(timeout_secs), child.wait_with_output()).await else {
+        event!(Level::ERROR, "Child process timed out");
 
-        for entry in entries {
-            videos.push(serde_json::from_value(entry.clone())?);
-        }
+        return Err(io::Error::new(io::ErrorKind::TimedOut, "Child process timed out"));
+    };
+    let output = output?;
+
+    kill_guard.disarm();
+
+    if !output.status.success() {
+        event!(Level::ERROR, "Child process exited with error status: {}", output.status);
+
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("Child process exited with status `{}`", output.status),
+        ));
+    }
 
-        Ok(VideosInYT::new(videos))
-    } else {
-        let video: VideoInYT = serde_json::from_value(value)?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Get version of `yt-dlp`.
+/// # Errors
+/// Returns [`io::Error`] if the spawn child process fails, it times out or exits with an error status
+pub async fn get_version(executable_path: impl AsRef<str>, timeout_secs: u64, limits: ProcessLimits) -> Result<String, io::Error> {
+    let mut command = process::command(executable_path.as_ref(), limits);
+    command.arg("--version");
+
+    get_output_with_timeout(command, timeout_secs).await
+}
+
+/// Update `yt-dlp` by the command, for example `yt-dlp -U` or `pip install -U yt-dlp`.
+/// Returns the command output. The process is killed if it times out or the returned future is dropped.
+/// # Errors
+/// Returns [`io::Error`] if the command is empty, the spawn child process fails, it times out or exits with an error status
+pub async fn run_update(update_command: &[String], timeout_secs: u64, limits: ProcessLimits) -> Result<String, io::Error> {
+    let Some((program, args)) = update_command.split_first() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Update command is empty"));
+    };
+
+    let mut command = process::command(program, limits);
+    command.args(args);
+
+    get_output_with_timeout(command, timeout_secs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Value of the option, it's the next argument
+    fn option_value<'a>(args: &[&'a str], option: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|arg| *arg == option)
+            .and_then(|index| args.get(index + 1))
+            .copied()
+    }
+
+    #[test]
+    fn test_download_video_args() {
+        let extra_args = ["--cookies".to_owned(), "cookies.txt".to_owned()];
+        let args = download_video_args("/tmp/dir", "137+140", "https://youtu.be/abc", &extra_args);
+
+        assert_eq!(option_value(&args, "--paths"), Some("/tmp/dir"));
+        assert_eq!(option_value(&args, "--cookies"), Some("cookies.txt"));
+        assert!(args.contains(&"--no-playlist"));
+        assert!(args.contains(&"--ignore-config"));
+        // The format and the URL are last, so extra args can't be taken for their values
+        assert_eq!(args[args.len() - 3..], ["-f", "137+140", "https://youtu.be/abc"]);
+    }
+
+    #[test]
+    fn test_download_audio_args() {
+        let args = download_audio_args("/tmp/dir", "140", "mp3", None, "https://youtu.be/abc", &[]);
+
+        assert_eq!(option_value(&args, "--audio-format"), Some("mp3"));
+        assert!(args.contains(&"--extract-audio"));
+        assert!(!args.contains(&"--parse-metadata"));
+        assert_eq!(args[args.len() - 3..], ["-f", "140", "https://youtu.be/abc"]);
+
+        let args = download_audio_args("/tmp/dir", "140", "m4a", Some("3:%(track_number)s"), "https://youtu.be/abc", &[]);
+
+        assert_eq!(option_value(&args, "--parse-metadata"), Some("3:%(track_number)s"));
+        assert!(args.contains(&"--embed-metadata"));
+    }
+
+    #[test]
+    fn test_info_args() {
+        let extra_args = ["--proxy".to_owned(), "socks5://127.0.0.1:1080".to_owned()];
+        let args = info_args("https://youtube.com/playlist?list=abc", true, &extra_args);
+
+        assert!(args.contains(&"--yes-playlist"));
+        assert!(!args.contains(&"--no-playlist"));
+        assert!(args.contains(&"--dump-json"));
+        assert_eq!(option_value(&args, "--proxy"), Some("socks5://127.0.0.1:1080"));
+        assert_eq!(args.last(), Some(&"https://youtube.com/playlist?list=abc"));
+
+        let args = info_args("https://youtu.be/abc", false, &[]);
+
+        assert!(args.contains(&"--no-playlist"));
+        assert!(!args.contains(&"--yes-playlist"));
+    }
 
-        Ok(VideosInYT::new(vec![video]))
+    #[test]
+    fn test_failure_cause_from_stderr() {
+        assert_eq!(
+            FailureCause::from_stderr("ERROR: [youtube] abc: Private video. Sign in if you've been granted access to this video"),
+            Some(FailureCause::Private)
+        );
+        assert_eq!(
+            FailureCause::from_stderr("ERROR: [youtube] abc: Sign in to confirm your age. This video may be inappropriate for some users."),
+            Some(FailureCause::AgeRestricted)
+        );
+        assert_eq!(
+            FailureCause::from_stderr("ERROR: [youtube] abc: Sign in to confirm you're not a bot. Use --cookies-from-browser"),
+            Some(FailureCause::LoginRequired)
+        );
+        assert_eq!(
+            FailureCause::from_stderr("ERROR: [youtube] abc: The uploader has not made this video available in your country"),
+            Some(FailureCause::GeoRestricted)
+        );
+        assert_eq!(
+            FailureCause::from_stderr("ERROR: [generic] Unable to download webpage: HTTP Error 404"),
+            Some(FailureCause::NotFound)
+        );
+        assert_eq!(FailureCause::from_stderr("ERROR: unable to download video data: timed out"), None);
     }
 }
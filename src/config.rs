@@ -1,27 +1,482 @@
+use crate::{cmd::H264Encoder, locale::Locale, models::FormatStrategy, sponsorblock};
+
 use std::{
     borrow::Cow,
+    collections::HashMap,
     env::{self, VarError},
-    num::ParseIntError,
+    net::{AddrParseError, SocketAddr},
+    num::{ParseFloatError, ParseIntError},
+    path::{Path, PathBuf},
     str::ParseBoolError,
+    time::Duration,
 };
+use url::Url;
+
+fn get_host(url: &str) -> Option<String> {
+    Url::parse(url).ok().and_then(|url| url.host_str().map(ToOwned::to_owned))
+}
+
+/// Subdomains match their parent domain, for example `m.youtube.com` matches `youtube.com`
+#[must_use]
+pub fn host_matches_domain(host: &str, domain: &str) -> bool {
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
 
 #[derive(Clone, Debug)]
 pub struct Bot {
     pub token: String,
     pub source_code_url: String,
     pub receiver_video_chat_id: i64,
+    /// Users allowed to run admin commands, like `/ytdlp_update`, and to send media to other chats
+    pub admin_ids: Vec<i64>,
+    /// Chats and channels where media downloaded in the chat is additionally reposted
+    pub mirrors: HashMap<i64, Vec<i64>>,
+    /// Max number of URLs downloaded from a single message
+    pub max_urls_per_message: usize,
+    /// Chats where the message with the link is deleted after its media is sent, so the chat keeps only the media
+    pub clean_chat_ids: Vec<i64>,
+    /// URL of a self-hosted Bot API server, for example `http://localhost:8081`. The official server is used if it's `None`.
+    pub api_url: Option<String>,
+    /// Language of system texts, like command descriptions and admin command replies.
+    /// It's also used for users whose language isn't supported.
+    pub locale: Locale,
+    /// Chats where replies are in this language instead of the language of the user
+    pub chat_locales: HashMap<i64, Locale>,
+    /// How media is delivered to chats without an override
+    pub media_delivery: MediaDelivery,
+    /// Chats where media is delivered in this way instead of the default one
+    pub chat_media_deliveries: HashMap<i64, MediaDelivery>,
+}
+
+/// How downloaded media gets to the chat
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MediaDelivery {
+    /// Media is uploaded to the receiver chat and sent to the chat by its file ID.
+    /// It works for any chat, even if the bot can't upload files there, and lets media be grouped.
+    #[default]
+    Staged,
+    /// Single media is uploaded right to the chat as a reply, so it's uploaded once and doesn't need the receiver chat.
+    /// Media of playlists is still staged, because it's sent in media groups.
+    Direct,
+}
+
+impl MediaDelivery {
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "staged" => Some(Self::Staged),
+            "direct" => Some(Self::Direct),
+            _ => None,
+        }
+    }
+}
+
+impl Bot {
+    #[must_use]
+    pub fn get_mirror_chat_ids(&self, chat_id: i64) -> &[i64] {
+        self.mirrors.get(&chat_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// URL to download a file by its path returned by `getFile`
+    #[must_use]
+    pub fn file_url(&self, file_path: &str) -> String {
+        let api_url = self
+            .api_url
+            .as_deref()
+            .map_or(DEFAULT_BOT_API_URL, |api_url| api_url.trim_end_matches('/'));
+
+        format!("{api_url}/file/bot{token}/{file_path}", token = self.token)
+    }
+
+    #[must_use]
+    pub fn is_clean_chat(&self, chat_id: i64) -> bool {
+        self.clean_chat_ids.contains(&chat_id)
+    }
+
+    /// Language of replies to the user in the chat: the chat override, the language of the user if it's supported or the bot language
+    #[must_use]
+    pub fn user_locale(&self, chat_id: i64, language_code: Option<&str>) -> Locale {
+        self.chat_locales
+            .get(&chat_id)
+            .copied()
+            .or_else(|| language_code.and_then(Locale::from_code))
+            .unwrap_or(self.locale)
+    }
+
+    #[must_use]
+    pub fn media_delivery(&self, chat_id: i64) -> MediaDelivery {
+        self.chat_media_deliveries.get(&chat_id).copied().unwrap_or(self.media_delivery)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct YtDlp {
     pub full_path: String,
     pub max_file_size: u64,
+    pub max_format_attempts: u8,
+    /// How much frame rate counts against resolution when picking a format that fits the size limit.
+    /// Zero ignores frame rate, one makes doubling it as good as doubling the height.
+    pub fps_weight: f64,
+    /// Max estimated size in bytes of all media of a playlist, it's checked before downloading
+    pub max_total_size: Option<u64>,
+    /// Extra arguments passed to `yt-dlp` for specific domains, for example `--extractor-args` or `--add-header`.
+    /// Subdomains match their parent domain.
+    pub domain_args: HashMap<String, Vec<String>>,
+    /// Command to update `yt-dlp`, the first element is a program
+    pub update_command: Vec<String>,
+    /// Cookie files for specific domains, they're used only for media that requires signing in.
+    /// Subdomains match their parent domain.
+    pub cookies: HashMap<String, PathBuf>,
+    /// Formats preferred for specific domains, like formats without a watermark.
+    /// Subdomains match their parent domain.
+    pub format_strategies: HashMap<String, FormatStrategy>,
+    /// Time in seconds media info of a URL is reused for, so repeated requests don't run `yt-dlp` again. It's disabled if it's zero.
+    pub info_cache_ttl: u64,
+    /// Max time in seconds to download a recording of a finished live stream.
+    /// The download timeout of recordings is raised by their duration up to this value.
+    pub max_download_timeout: u64,
+    /// Integrated loudness in LUFS that audios are normalized to with `normalize=1`
+    pub normalize_target_lufs: f64,
+    /// Whether sponsored and other `SponsorBlock` segments are removed from videos
+    pub sponsorblock_enabled: bool,
+    /// Categories of `SponsorBlock` segments removed by default, they're overridden by the `sb=` parameter of a message
+    pub sponsorblock_categories: Vec<String>,
+    /// Piped and Invidious instances to get info of YouTube videos from if `yt-dlp` fails
+    pub fallback_instances: Vec<FallbackInstance>,
+}
+
+/// API of an alternative YouTube frontend
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FallbackApi {
+    Piped,
+    Invidious,
+}
+
+#[derive(Clone, Debug)]
+pub struct FallbackInstance {
+    pub api: FallbackApi,
+    /// API URL without a trailing slash
+    pub url: String,
+}
+
+impl FallbackInstance {
+    /// Parses the instance in the `piped:https://pipedapi.example.com` or `invidious:https://invidious.example.com` format
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let (api, url) = value.trim().split_once(':')?;
+        let api = match api.to_lowercase().as_str() {
+            "piped" => FallbackApi::Piped,
+            "invidious" => FallbackApi::Invidious,
+            _ => return None,
+        };
+
+        Url::parse(url).ok()?;
+
+        Some(Self {
+            api,
+            url: url.trim_end_matches('/').to_owned(),
+        })
+    }
+}
+
+impl YtDlp {
+    /// Domains with extra arguments, cookies or preferred formats
+    #[must_use]
+    pub fn configured_domains(&self) -> Vec<String> {
+        let mut domains = self
+            .domain_args
+            .keys()
+            .chain(self.cookies.keys())
+            .chain(self.format_strategies.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        domains.sort_unstable();
+        domains.dedup();
+
+        domains
+    }
+
+    #[must_use]
+    pub fn get_extra_args(&self, url: &str) -> Vec<String> {
+        let Some(host) = get_host(url) else {
+            return vec![];
+        };
+
+        self.domain_args
+            .iter()
+            .filter(|(domain, _)| host_matches_domain(&host, domain))
+            .flat_map(|(_, args)| args.iter().cloned())
+            .collect()
+    }
+
+    /// Arguments to pass the cookie file of the host, if it's set
+    #[must_use]
+    pub fn get_cookies_args(&self, url: &str) -> Option<Vec<String>> {
+        let host = get_host(url)?;
+        let (_, path) = self.cookies.iter().find(|(domain, _)| host_matches_domain(&host, domain))?;
+
+        Some(vec!["--cookies".to_owned(), path.to_string_lossy().into_owned()])
+    }
+
+    /// Strategy of formats for the host, it's empty if it isn't set
+    #[must_use]
+    pub fn get_format_strategy(&self, url: &str) -> FormatStrategy {
+        let Some(host) = get_host(url) else {
+            return FormatStrategy::default();
+        };
+
+        self.format_strategies
+            .iter()
+            .find(|(domain, _)| host_matches_domain(&host, domain))
+            .map(|(_, strategy)| strategy.clone())
+            .unwrap_or_default()
+    }
+
+    /// Extra arguments to download the media, with the cookies of the host if the media requires signing in
+    #[must_use]
+    pub fn get_media_extra_args(&self, url: &str, requires_cookies: bool) -> Vec<String> {
+        let mut extra_args = self.get_extra_args(url);
+
+        if requires_cookies {
+            extra_args.extend(self.get_cookies_args(url).into_iter().flatten());
+        }
+
+        extra_args
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Http {
+    /// Address of the health-check and metrics server. The server is disabled if it's `None`.
+    pub address: Option<SocketAddr>,
+    /// Public URL of the server, used to build download links for media exceeding the Telegram limits.
+    /// Download links are disabled if it's `None`.
+    pub public_url: Option<String>,
+    pub link_max_file_size: u64,
+    /// How long download links are available, in seconds
+    pub link_retention: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Max number of retries after the first attempt
+    pub attempts: u8,
+    /// Initial delay between retries, it grows exponentially
+    pub backoff: Duration,
+    /// Max time spent on retries, no retries are made after it
+    pub max_elapsed: Duration,
+}
+
+impl RetryPolicy {
+    const fn new(attempts: u8) -> Self {
+        Self {
+            attempts,
+            backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF),
+            max_elapsed: Duration::from_secs(DEFAULT_RETRY_MAX_ELAPSED),
+        }
+    }
+}
+
+/// Retry policies per operation class
+#[derive(Clone, Copy, Debug)]
+pub struct Retries {
+    pub telegram_send: RetryPolicy,
+    /// Albums are retried more, because a failed album loses all its media, and they hit flood limits more often
+    pub telegram_send_media_group: RetryPolicy,
+    pub yt_dlp_info: RetryPolicy,
+    pub yt_dlp_download: RetryPolicy,
+    pub thumbnail: RetryPolicy,
+}
+
+#[derive(Clone, Debug)]
+pub struct DataDir {
+    /// Directory for files kept across restarts, unlike the work dir it isn't cleaned up
+    pub path: PathBuf,
+}
+
+impl DataDir {
+    /// File with settings of chats changed by their admins
+    #[must_use]
+    pub fn chat_config_path(&self) -> PathBuf {
+        self.path.join(CHAT_CONFIG_FILE_NAME)
+    }
+
+    /// File with chats receiving `/broadcast`
+    #[must_use]
+    pub fn known_chats_path(&self) -> PathBuf {
+        self.path.join(KNOWN_CHATS_FILE_NAME)
+    }
+
+    /// File with download jobs interrupted by a restart
+    #[must_use]
+    pub fn jobs_path(&self) -> PathBuf {
+        self.path.join(JOBS_FILE_NAME)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WorkDir {
+    /// Directory for temporary download folders, all its subdirectories are owned by the bot
+    pub path: PathBuf,
+    /// Download folders not modified for this number of seconds are considered leaked and removed
+    pub stale_after: u64,
+    /// Path of the work dir on a local Bot API server sharing it, for example by a Docker volume.
+    /// If it's set, the server works in local mode and files are sent by a local file URI instead of being uploaded.
+    pub server_path: Option<PathBuf>,
+}
+
+impl WorkDir {
+    /// Returns `None` if the work dir isn't shared with a local Bot API server or the file is outside of it
+    #[must_use]
+    pub fn local_file_uri(&self, path: &Path) -> Option<String> {
+        let server_path = self.server_path.as_ref()?;
+        let relative_path = path.strip_prefix(&self.path).ok()?;
+
+        Some(format!("file://{}", server_path.join(relative_path).display()))
+    }
+}
+
+/// Opt-in summaries of long videos by their subtitles, generated by an LLM
+#[derive(Clone, Debug)]
+pub struct Summary {
+    /// OpenAI-compatible chat completions endpoint. Summaries are disabled if it's `None`.
+    pub llm_url: Option<String>,
+    pub llm_api_key: Option<String>,
+    pub llm_model: String,
+    /// Chats opted in to summaries
+    pub chat_ids: Vec<i64>,
+    /// Min video duration in seconds to make a summary
+    pub min_duration: u64,
+    /// Subtitle languages in `yt-dlp` `--sub-langs` format
+    pub subtitle_languages: String,
+    /// Timeout in seconds for each step: subtitles download and LLM request
+    pub timeout: u64,
+}
+
+impl Summary {
+    #[must_use]
+    pub fn is_enabled_for(&self, chat_id: i64) -> bool {
+        self.llm_url.is_some() && self.chat_ids.contains(&chat_id)
+    }
+}
+
+/// Transcription of media by its audio with Whisper for the `/transcribe` command.
+/// The API is used if it's set, otherwise the `whisper.cpp` binary, transcription is disabled if neither is set.
+#[derive(Clone, Debug)]
+pub struct Transcription {
+    /// OpenAI-compatible audio transcriptions endpoint
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub api_model: String,
+    /// Max size in bytes of audio uploaded to the endpoint, the official API doesn't accept files larger than 25 MB
+    pub api_max_file_size: u64,
+    /// Path to the `whisper.cpp` CLI, it's used with `whisper_cpp_model_path`
+    pub whisper_cpp_full_path: Option<String>,
+    pub whisper_cpp_model_path: Option<PathBuf>,
+    /// Language of the media as ISO 639-1 code, it's detected if it's `None`
+    pub language: Option<String>,
+    /// Max duration in seconds of media to transcribe
+    pub max_duration: u64,
+    /// Timeout in seconds of the transcription
+    pub timeout: u64,
+}
+
+impl Transcription {
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.api_url.is_some() || (self.whisper_cpp_full_path.is_some() && self.whisper_cpp_model_path.is_some())
+    }
+
+    /// Max size of downloaded audio, so an audio format that the endpoint accepts is picked
+    #[must_use]
+    pub fn max_audio_file_size(&self, max_file_size: u64) -> u64 {
+        if self.api_url.is_some() {
+            max_file_size.min(self.api_max_file_size)
+        } else {
+            max_file_size
+        }
+    }
+}
+
+/// Export of tracing spans to an OpenTelemetry collector by OTLP over HTTP
+#[derive(Clone, Debug)]
+pub struct Telemetry {
+    /// Base URL of the collector, for example `http://localhost:4318`. Spans aren't exported if it's `None`.
+    pub otlp_endpoint: Option<String>,
+    /// Name of the service in exported spans
+    pub service_name: String,
+}
+
+/// Resource limits of `yt-dlp` and `FFmpeg` processes, there is no limit if it's `None`
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ProcessLimits {
+    /// CPU time in seconds
+    pub cpu_time: Option<u64>,
+    /// Virtual memory in bytes
+    pub memory: Option<u64>,
+    /// Size in bytes of a file written by the process
+    pub file_size: Option<u64>,
+    /// Time in seconds the `FFmpeg` merge may go without writing to the output before it's considered stalled
+    pub merge_stall_timeout: Option<u64>,
+}
+
+/// Re-encoding of videos with codecs that Telegram mobile clients can't play well to H264
+#[derive(Clone, Copy, Debug)]
+pub struct Transcode {
+    pub enabled: bool,
+    /// Constant rate factor of `libx264`, the bitrate is also capped to fit the max file size
+    pub crf: u8,
+    /// Whether a hardware encoder found on startup is used instead of `libx264`
+    pub hw_accel: bool,
+    /// Encoder found on startup, it's the software one until it's detected
+    pub encoder: H264Encoder,
+}
+
+impl Default for Transcode {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            crf: DEFAULT_TRANSCODE_CRF,
+            hw_accel: false,
+            encoder: H264Encoder::Software,
+        }
+    }
+}
+
+/// Concurrency of media downloads.
+/// Media with an estimated size up to `fast_lane_max_file_size` is downloaded in its own lane, so it isn't stuck behind large downloads.
+#[derive(Clone, Copy, Debug)]
+pub struct Queue {
+    /// Max number of concurrent downloads in the main lane, there is no queue if it's `None`
+    pub concurrency: Option<usize>,
+    pub fast_lane_concurrency: usize,
+    pub fast_lane_max_file_size: u64,
+}
+
+/// Limit of requests to the Telegram Bot API shared by all chats, so large playlists don't cause flood-waits
+#[derive(Clone, Copy, Debug)]
+pub struct SendRate {
+    /// Requests per second, requests aren't limited if it's zero
+    pub per_second: f64,
+    /// Max number of requests made at once after a pause
+    pub burst: u32,
 }
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub bot: Bot,
     pub yt_dlp: YtDlp,
+    pub http: Http,
+    pub retries: Retries,
+    pub work_dir: WorkDir,
+    pub data_dir: DataDir,
+    pub summary: Summary,
+    pub transcription: Transcription,
+    pub process_limits: ProcessLimits,
+    pub transcode: Transcode,
+    pub queue: Queue,
+    pub send_rate: SendRate,
+    pub telemetry: Telemetry,
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -31,11 +486,110 @@ pub enum ErrorKind {
     #[error(transparent)]
     ParseInt(#[from] ParseIntError),
     #[error(transparent)]
+    ParseFloat(#[from] ParseFloatError),
+    #[error(transparent)]
     ParseBool(#[from] ParseBoolError),
+    #[error(transparent)]
+    ParseAddr(#[from] AddrParseError),
+    #[error(transparent)]
+    ParseJson(#[from] serde_json::Error),
+    #[error("Unsupported locale: {0}")]
+    UnsupportedLocale(Box<str>),
+    #[error("Unsupported media delivery: {0}")]
+    UnsupportedMediaDelivery(Box<str>),
+    #[error("Unsupported fallback instance: {0}")]
+    UnsupportedFallbackInstance(Box<str>),
+    #[error("Unsupported SponsorBlock categories: {0}")]
+    UnsupportedSponsorBlockCategories(Box<str>),
+    #[error("WORK_DIR_STALE_AFTER ({stale_after}) should be greater than HTTP_LINK_RETENTION ({link_retention})")]
+    WorkDirStaleBeforeLinksExpire { stale_after: u64, link_retention: u64 },
+}
+
+const DEFAULT_BOT_API_URL: &str = "https://api.telegram.org";
+const DEFAULT_BOT_MAX_URLS_PER_MESSAGE: usize = 5;
+const DEFAULT_YT_DLP_MAX_FORMAT_ATTEMPTS: u8 = 3;
+const DEFAULT_YT_DLP_INFO_CACHE_TTL: u64 = 300;
+const DEFAULT_YT_DLP_MAX_DOWNLOAD_TIMEOUT: u64 = 1800;
+const DEFAULT_YT_DLP_NORMALIZE_TARGET_LUFS: f64 = -16.0;
+const DEFAULT_YT_DLP_SPONSORBLOCK_CATEGORIES: &str = "sponsor";
+const DEFAULT_YT_DLP_FPS_WEIGHT: f64 = 0.25;
+const DEFAULT_HTTP_LINK_MAX_FILE_SIZE: u64 = 4_000_000_000;
+const DEFAULT_HTTP_LINK_RETENTION: u64 = 3600;
+const DEFAULT_RETRY_BACKOFF: u64 = 500;
+const DEFAULT_RETRY_MAX_ELAPSED: u64 = 900;
+const DEFAULT_RETRY_TELEGRAM_SEND_ATTEMPTS: u8 = 2;
+const DEFAULT_RETRY_TELEGRAM_SEND_MEDIA_GROUP_ATTEMPTS: u8 = 4;
+const DEFAULT_WORK_DIR_NAME: &str = "ytdl_tg_bot";
+const DEFAULT_DATA_DIR: &str = "data";
+const CHAT_CONFIG_FILE_NAME: &str = "chat_config.json";
+const KNOWN_CHATS_FILE_NAME: &str = "known_chats.json";
+const JOBS_FILE_NAME: &str = "jobs.json";
+const DEFAULT_WORK_DIR_STALE_AFTER: u64 = 21600;
+const DEFAULT_PROCESS_MERGE_STALL_TIMEOUT: u64 = 60;
+const DEFAULT_TRANSCODE_CRF: u8 = 23;
+const DEFAULT_QUEUE_FAST_LANE_CONCURRENCY: usize = 4;
+const DEFAULT_QUEUE_FAST_LANE_MAX_FILE_SIZE: u64 = 20_000_000;
+const DEFAULT_SEND_RATE_PER_SECOND: f64 = 25.0;
+const DEFAULT_SEND_RATE_BURST: u32 = 30;
+const DEFAULT_SUMMARY_LLM_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_SUMMARY_MIN_DURATION: u64 = 1200;
+const DEFAULT_SUMMARY_SUBTITLE_LANGUAGES: &str = "en.*";
+const DEFAULT_SUMMARY_TIMEOUT: u64 = 20;
+const DEFAULT_TELEMETRY_SERVICE_NAME: &str = "ytdl_tg_bot";
+const DEFAULT_TRANSCRIPTION_API_MODEL: &str = "whisper-1";
+const DEFAULT_TRANSCRIPTION_API_MAX_FILE_SIZE: u64 = 25_000_000;
+const DEFAULT_TRANSCRIPTION_MAX_DURATION: u64 = 3600;
+const DEFAULT_TRANSCRIPTION_TIMEOUT: u64 = 600;
+
+fn get_optional_env(key: &'static str) -> Result<Option<String>, ErrorKind> {
+    match env::var(key) {
+        Ok(value) if value.is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value)),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(err) => Err(ErrorKind::Env {
+            source: err,
+            key: key.into(),
+        }),
+    }
+}
+
+fn get_optional_ids_env(key: &'static str) -> Result<Vec<i64>, ErrorKind> {
+    match get_optional_env(key)? {
+        Some(value) => value.split(',').map(|id| id.trim().parse().map_err(ErrorKind::ParseInt)).collect(),
+        None => Ok(vec![]),
+    }
+}
+
+fn get_retry_policy(
+    attempts_key: &'static str,
+    backoff_key: &'static str,
+    max_elapsed_key: &'static str,
+    default: RetryPolicy,
+) -> Result<RetryPolicy, ErrorKind> {
+    Ok(RetryPolicy {
+        attempts: match get_optional_env(attempts_key)? {
+            Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+            None => default.attempts,
+        },
+        backoff: match get_optional_env(backoff_key)? {
+            Some(value) => Duration::from_millis(value.parse().map_err(ErrorKind::ParseInt)?),
+            None => default.backoff,
+        },
+        max_elapsed: match get_optional_env(max_elapsed_key)? {
+            Some(value) => Duration::from_secs(value.parse().map_err(ErrorKind::ParseInt)?),
+            None => default.max_elapsed,
+        },
+    })
 }
 
+#[allow(clippy::too_many_lines)]
 pub fn read_config_from_env() -> Result<Config, ErrorKind> {
-    Ok(Config {
+    let yt_dlp_full_path = env::var("YT_DLP_FULL_PATH").map_err(|err| ErrorKind::Env {
+        source: err,
+        key: "YT_DLP_FULL_PATH".into(),
+    })?;
+
+    let config = Config {
         bot: Bot {
             token: env::var("BOT_TOKEN").map_err(|err| ErrorKind::Env {
                 source: err,
@@ -52,12 +606,54 @@ pub fn read_config_from_env() -> Result<Config, ErrorKind> {
                 })?
                 .parse()
                 .map_err(ErrorKind::ParseInt)?,
+            admin_ids: get_optional_ids_env("BOT_ADMIN_IDS")?,
+            mirrors: match get_optional_env("BOT_MIRRORS")? {
+                Some(value) => serde_json::from_str(&value).map_err(ErrorKind::ParseJson)?,
+                None => HashMap::new(),
+            },
+            max_urls_per_message: match get_optional_env("BOT_MAX_URLS_PER_MESSAGE")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_BOT_MAX_URLS_PER_MESSAGE,
+            },
+            clean_chat_ids: get_optional_ids_env("BOT_CLEAN_CHAT_IDS")?,
+            api_url: get_optional_env("BOT_API_URL")?,
+            locale: match get_optional_env("BOT_LOCALE")? {
+                Some(value) => Locale::from_code(&value).ok_or_else(|| ErrorKind::UnsupportedLocale(value.into_boxed_str()))?,
+                None => Locale::default(),
+            },
+            chat_locales: match get_optional_env("BOT_CHAT_LOCALES")? {
+                Some(value) => serde_json::from_str::<HashMap<i64, String>>(&value)
+                    .map_err(ErrorKind::ParseJson)?
+                    .into_iter()
+                    .map(|(chat_id, code)| match Locale::from_code(&code) {
+                        Some(locale) => Ok((chat_id, locale)),
+                        None => Err(ErrorKind::UnsupportedLocale(code.into_boxed_str())),
+                    })
+                    .collect::<Result<_, _>>()?,
+                None => HashMap::new(),
+            },
+            media_delivery: match get_optional_env("BOT_MEDIA_DELIVERY")? {
+                Some(value) => MediaDelivery::parse(&value).ok_or_else(|| ErrorKind::UnsupportedMediaDelivery(value.into_boxed_str()))?,
+                None => MediaDelivery::default(),
+            },
+            chat_media_deliveries: match get_optional_env("BOT_CHAT_MEDIA_DELIVERIES")? {
+                Some(value) => serde_json::from_str::<HashMap<i64, String>>(&value)
+                    .map_err(ErrorKind::ParseJson)?
+                    .into_iter()
+                    .map(|(chat_id, value)| match MediaDelivery::parse(&value) {
+                        Some(media_delivery) => Ok((chat_id, media_delivery)),
+                        None => Err(ErrorKind::UnsupportedMediaDelivery(value.into_boxed_str())),
+                    })
+                    .collect::<Result<_, _>>()?,
+                None => HashMap::new(),
+            },
         },
         yt_dlp: YtDlp {
-            full_path: env::var("YT_DLP_FULL_PATH").map_err(|err| ErrorKind::Env {
-                source: err,
-                key: "YT_DLP_FULL_PATH".into(),
-            })?,
+            update_command: match get_optional_env("YT_DLP_UPDATE_COMMAND")? {
+                Some(value) => value.split_whitespace().map(ToOwned::to_owned).collect(),
+                None => vec![yt_dlp_full_path.clone(), "-U".to_owned()],
+            },
+            full_path: yt_dlp_full_path,
             max_file_size: env::var("YT_DLP_MAX_FILE_SIZE")
                 .map_err(|err| ErrorKind::Env {
                     source: err,
@@ -65,6 +661,231 @@ pub fn read_config_from_env() -> Result<Config, ErrorKind> {
                 })?
                 .parse()
                 .map_err(ErrorKind::ParseInt)?,
+            max_format_attempts: match get_optional_env("YT_DLP_MAX_FORMAT_ATTEMPTS")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_YT_DLP_MAX_FORMAT_ATTEMPTS,
+            },
+            fps_weight: match get_optional_env("YT_DLP_FPS_WEIGHT")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseFloat)?,
+                None => DEFAULT_YT_DLP_FPS_WEIGHT,
+            },
+            max_total_size: get_optional_env("YT_DLP_MAX_TOTAL_SIZE")?
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseInt)?,
+            domain_args: match get_optional_env("YT_DLP_DOMAIN_ARGS")? {
+                Some(value) => serde_json::from_str(&value).map_err(ErrorKind::ParseJson)?,
+                None => HashMap::new(),
+            },
+            cookies: match get_optional_env("YT_DLP_COOKIES")? {
+                Some(value) => serde_json::from_str(&value).map_err(ErrorKind::ParseJson)?,
+                None => HashMap::new(),
+            },
+            format_strategies: match get_optional_env("YT_DLP_FORMAT_STRATEGIES")? {
+                Some(value) => serde_json::from_str(&value).map_err(ErrorKind::ParseJson)?,
+                None => HashMap::new(),
+            },
+            info_cache_ttl: match get_optional_env("YT_DLP_INFO_CACHE_TTL")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_YT_DLP_INFO_CACHE_TTL,
+            },
+            max_download_timeout: match get_optional_env("YT_DLP_MAX_DOWNLOAD_TIMEOUT")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_YT_DLP_MAX_DOWNLOAD_TIMEOUT,
+            },
+            normalize_target_lufs: match get_optional_env("YT_DLP_NORMALIZE_TARGET_LUFS")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseFloat)?,
+                None => DEFAULT_YT_DLP_NORMALIZE_TARGET_LUFS,
+            },
+            sponsorblock_enabled: match get_optional_env("YT_DLP_SPONSORBLOCK")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseBool)?,
+                None => false,
+            },
+            sponsorblock_categories: {
+                let value = get_optional_env("YT_DLP_SPONSORBLOCK_CATEGORIES")?
+                    .unwrap_or_else(|| DEFAULT_YT_DLP_SPONSORBLOCK_CATEGORIES.to_owned());
+
+                match sponsorblock::parse_categories(&value) {
+                    Some(categories) => categories,
+                    None => return Err(ErrorKind::UnsupportedSponsorBlockCategories(value.into_boxed_str())),
+                }
+            },
+            fallback_instances: match get_optional_env("YT_DLP_FALLBACK_INSTANCES")? {
+                Some(value) => value
+                    .split(',')
+                    .map(|instance| {
+                        FallbackInstance::parse(instance).ok_or_else(|| ErrorKind::UnsupportedFallbackInstance(instance.into()))
+                    })
+                    .collect::<Result<_, _>>()?,
+                None => vec![],
+            },
         },
-    })
+        http: Http {
+            address: get_optional_env("HTTP_SERVER_ADDRESS")?
+                .map(|address| address.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseAddr)?,
+            public_url: get_optional_env("HTTP_PUBLIC_URL")?,
+            link_max_file_size: match get_optional_env("HTTP_LINK_MAX_FILE_SIZE")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_HTTP_LINK_MAX_FILE_SIZE,
+            },
+            link_retention: match get_optional_env("HTTP_LINK_RETENTION")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_HTTP_LINK_RETENTION,
+            },
+        },
+        retries: Retries {
+            telegram_send: get_retry_policy(
+                "RETRY_TELEGRAM_SEND_ATTEMPTS",
+                "RETRY_TELEGRAM_SEND_BACKOFF",
+                "RETRY_TELEGRAM_SEND_MAX_ELAPSED",
+                RetryPolicy::new(DEFAULT_RETRY_TELEGRAM_SEND_ATTEMPTS),
+            )?,
+            telegram_send_media_group: get_retry_policy(
+                "RETRY_TELEGRAM_SEND_MEDIA_GROUP_ATTEMPTS",
+                "RETRY_TELEGRAM_SEND_MEDIA_GROUP_BACKOFF",
+                "RETRY_TELEGRAM_SEND_MEDIA_GROUP_MAX_ELAPSED",
+                RetryPolicy::new(DEFAULT_RETRY_TELEGRAM_SEND_MEDIA_GROUP_ATTEMPTS),
+            )?,
+            yt_dlp_info: get_retry_policy(
+                "RETRY_YT_DLP_INFO_ATTEMPTS",
+                "RETRY_YT_DLP_INFO_BACKOFF",
+                "RETRY_YT_DLP_INFO_MAX_ELAPSED",
+                RetryPolicy::new(0),
+            )?,
+            yt_dlp_download: get_retry_policy(
+                "RETRY_YT_DLP_DOWNLOAD_ATTEMPTS",
+                "RETRY_YT_DLP_DOWNLOAD_BACKOFF",
+                "RETRY_YT_DLP_DOWNLOAD_MAX_ELAPSED",
+                RetryPolicy::new(0),
+            )?,
+            thumbnail: get_retry_policy(
+                "RETRY_THUMBNAIL_ATTEMPTS",
+                "RETRY_THUMBNAIL_BACKOFF",
+                "RETRY_THUMBNAIL_MAX_ELAPSED",
+                RetryPolicy::new(0),
+            )?,
+        },
+        work_dir: WorkDir {
+            path: get_optional_env("WORK_DIR")?.map_or_else(|| env::temp_dir().join(DEFAULT_WORK_DIR_NAME), PathBuf::from),
+            stale_after: match get_optional_env("WORK_DIR_STALE_AFTER")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_WORK_DIR_STALE_AFTER,
+            },
+            server_path: get_optional_env("WORK_DIR_SERVER_PATH")?.map(PathBuf::from),
+        },
+        data_dir: DataDir {
+            path: get_optional_env("DATA_DIR")?.map_or_else(|| PathBuf::from(DEFAULT_DATA_DIR), PathBuf::from),
+        },
+        summary: Summary {
+            llm_url: get_optional_env("SUMMARY_LLM_URL")?,
+            llm_api_key: get_optional_env("SUMMARY_LLM_API_KEY")?,
+            llm_model: get_optional_env("SUMMARY_LLM_MODEL")?.unwrap_or_else(|| DEFAULT_SUMMARY_LLM_MODEL.to_owned()),
+            chat_ids: get_optional_ids_env("SUMMARY_CHAT_IDS")?,
+            min_duration: match get_optional_env("SUMMARY_MIN_DURATION")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_SUMMARY_MIN_DURATION,
+            },
+            subtitle_languages: get_optional_env("SUMMARY_SUBTITLE_LANGUAGES")?
+                .unwrap_or_else(|| DEFAULT_SUMMARY_SUBTITLE_LANGUAGES.to_owned()),
+            timeout: match get_optional_env("SUMMARY_TIMEOUT")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_SUMMARY_TIMEOUT,
+            },
+        },
+        transcription: Transcription {
+            api_url: get_optional_env("TRANSCRIPTION_API_URL")?,
+            api_key: get_optional_env("TRANSCRIPTION_API_KEY")?,
+            api_model: get_optional_env("TRANSCRIPTION_API_MODEL")?.unwrap_or_else(|| DEFAULT_TRANSCRIPTION_API_MODEL.to_owned()),
+            api_max_file_size: match get_optional_env("TRANSCRIPTION_API_MAX_FILE_SIZE")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_TRANSCRIPTION_API_MAX_FILE_SIZE,
+            },
+            whisper_cpp_full_path: get_optional_env("WHISPER_CPP_FULL_PATH")?,
+            whisper_cpp_model_path: get_optional_env("WHISPER_CPP_MODEL_PATH")?.map(PathBuf::from),
+            language: get_optional_env("TRANSCRIPTION_LANGUAGE")?,
+            max_duration: match get_optional_env("TRANSCRIPTION_MAX_DURATION")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_TRANSCRIPTION_MAX_DURATION,
+            },
+            timeout: match get_optional_env("TRANSCRIPTION_TIMEOUT")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_TRANSCRIPTION_TIMEOUT,
+            },
+        },
+        process_limits: ProcessLimits {
+            cpu_time: get_optional_env("PROCESS_MAX_CPU_TIME")?
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseInt)?,
+            memory: get_optional_env("PROCESS_MAX_MEMORY")?
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseInt)?,
+            file_size: get_optional_env("PROCESS_MAX_FILE_SIZE")?
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseInt)?,
+            // Zero disables the stall detection, so only the merge timeout is left
+            merge_stall_timeout: match get_optional_env("PROCESS_MERGE_STALL_TIMEOUT")? {
+                Some(value) => Some(value.parse().map_err(ErrorKind::ParseInt)?).filter(|timeout| *timeout > 0),
+                None => Some(DEFAULT_PROCESS_MERGE_STALL_TIMEOUT),
+            },
+        },
+        transcode: Transcode {
+            enabled: match get_optional_env("TRANSCODE_INCOMPATIBLE_CODECS")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseBool)?,
+                None => false,
+            },
+            crf: match get_optional_env("TRANSCODE_CRF")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_TRANSCODE_CRF,
+            },
+            hw_accel: match get_optional_env("TRANSCODE_HW_ACCEL")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseBool)?,
+                None => false,
+            },
+            encoder: H264Encoder::Software,
+        },
+        queue: Queue {
+            concurrency: get_optional_env("QUEUE_CONCURRENCY")?
+                .map(|value| value.parse())
+                .transpose()
+                .map_err(ErrorKind::ParseInt)?,
+            fast_lane_concurrency: match get_optional_env("QUEUE_FAST_LANE_CONCURRENCY")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_QUEUE_FAST_LANE_CONCURRENCY,
+            },
+            fast_lane_max_file_size: match get_optional_env("QUEUE_FAST_LANE_MAX_FILE_SIZE")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_QUEUE_FAST_LANE_MAX_FILE_SIZE,
+            },
+        },
+        send_rate: SendRate {
+            per_second: match get_optional_env("SEND_RATE_PER_SECOND")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseFloat)?,
+                None => DEFAULT_SEND_RATE_PER_SECOND,
+            },
+            burst: match get_optional_env("SEND_RATE_BURST")? {
+                Some(value) => value.parse().map_err(ErrorKind::ParseInt)?,
+                None => DEFAULT_SEND_RATE_BURST,
+            },
+        },
+        telemetry: Telemetry {
+            otlp_endpoint: get_optional_env("OTLP_ENDPOINT")?,
+            service_name: get_optional_env("OTLP_SERVICE_NAME")?.unwrap_or_else(|| DEFAULT_TELEMETRY_SERVICE_NAME.to_owned()),
+        },
+    };
+
+    // Files of download links are kept in the work dir, so they would be removed before the links expire
+    let links_enabled = config.http.address.is_some() && config.http.public_url.is_some();
+    if links_enabled && config.work_dir.stale_after <= config.http.link_retention {
+        return Err(ErrorKind::WorkDirStaleBeforeLinksExpire {
+            stale_after: config.work_dir.stale_after,
+            link_retention: config.http.link_retention,
+        });
+    }
+
+    Ok(config)
 }
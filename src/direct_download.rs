@@ -0,0 +1,205 @@
+use crate::{
+    cmd::download_hls,
+    config::ProcessLimits,
+    models::{VideoInYT, VideosInYT},
+};
+
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use serde_json::json;
+use std::{
+    fs::{self, File},
+    io::{self, Read as _},
+    path::Path,
+    time::Duration,
+};
+use tracing::{event, instrument, Level};
+use url::Url;
+
+/// Timeout of the probe, it's short because a regular page is extracted by `yt-dlp` after it
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Format ID of a media file, it's downloaded as is
+const FILE_FORMAT_ID: &str = "direct";
+/// Format ID of an HLS playlist, its segments are joined by `FFmpeg`
+const HLS_FORMAT_ID: &str = "hls";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ErrorKind {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Media exceeds the max file size of {max_size} bytes")]
+    TooLarge { max_size: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Video,
+    Audio,
+    Hls,
+}
+
+impl Kind {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "mp4" | "m4v" | "mov" => Some(Self::Video),
+            "mp3" | "m4a" | "flac" | "opus" => Some(Self::Audio),
+            "m3u8" => Some(Self::Hls),
+            _ => None,
+        }
+    }
+
+    fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "video/mp4" | "video/x-m4v" | "video/quicktime" => Some(Self::Video),
+            "audio/mpeg" | "audio/mp3" | "audio/mp4" | "audio/x-m4a" | "audio/flac" | "audio/x-flac" | "audio/opus" => Some(Self::Audio),
+            "application/vnd.apple.mpegurl" | "application/x-mpegurl" | "audio/mpegurl" | "audio/x-mpegurl" => Some(Self::Hls),
+            _ => None,
+        }
+    }
+}
+
+/// Extension of the last path segment in lowercase
+fn extension(url: &Url) -> Option<String> {
+    let file_name = url.path_segments()?.next_back()?;
+    let (_, extension) = file_name.rsplit_once('.')?;
+
+    Some(extension.to_lowercase())
+}
+
+/// Probes the URL with a HEAD request and returns its kind if it points directly at a media file or an HLS playlist.
+/// Only URLs with a media extension are probed, so links to pages don't wait for an extra request.
+/// The content type decides the kind, a generic binary type falls back to the extension.
+async fn probe(url: &Url) -> Option<(Kind, String, Option<u64>)> {
+    let extension = extension(url)?;
+    let by_extension = Kind::from_extension(&extension)?;
+
+    let response = match reqwest::Client::new().head(url.as_str()).timeout(PROBE_TIMEOUT).send().await {
+        Ok(response) => response.error_for_status().ok()?,
+        Err(err) => {
+            event!(Level::DEBUG, %err, "Error probing URL");
+
+            return None;
+        }
+    };
+    let headers = response.headers();
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase());
+    let kind = match content_type.as_deref() {
+        Some("application/octet-stream" | "binary/octet-stream") | None => by_extension,
+        Some(content_type) => Kind::from_content_type(content_type)?,
+    };
+    let size = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    event!(Level::DEBUG, ?content_type, ?kind, ?size, "URL points directly at media");
+
+    Some((kind, extension, size))
+}
+
+/// Gets the media info of the URL in the format of `yt-dlp` info without `yt-dlp`, so direct links skip the extraction.
+/// Returns `None` if the URL doesn't point directly at a media file or an HLS playlist.
+#[instrument(skip_all, fields(%url))]
+pub async fn media_info(url: &str) -> Option<VideosInYT> {
+    let parsed_url = Url::parse(url).ok()?;
+    let (kind, extension, size) = probe(&parsed_url).await?;
+
+    let file_name = parsed_url.path_segments()?.next_back()?;
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    // The ID is used in file names
+    let id: String = stem
+        .chars()
+        .filter(|char| char.is_ascii_alphanumeric() || matches!(char, '-' | '_'))
+        .take(64)
+        .collect();
+
+    // Codecs are unknown without downloading the file, so the format kind is got by the extension
+    let format = match kind {
+        Kind::Video => json!({
+            "format_id": FILE_FORMAT_ID,
+            "url": url,
+            "ext": extension,
+            "filesize": size,
+        }),
+        Kind::Audio => json!({
+            "format_id": FILE_FORMAT_ID,
+            "url": url,
+            "ext": extension,
+            "vcodec": "none",
+            "filesize": size,
+        }),
+        // The size of the playlist is the size of its text, not the media
+        Kind::Hls => json!({
+            "format_id": HLS_FORMAT_ID,
+            "url": url,
+            "ext": "mp4",
+        }),
+    };
+    let info = json!({
+        "id": if id.is_empty() { FILE_FORMAT_ID } else { id.as_str() },
+        "title": stem,
+        "original_url": url,
+        "formats": [format],
+    });
+
+    match serde_json::from_value::<VideoInYT>(info) {
+        Ok(mut video) => {
+            video.is_direct = true;
+
+            Some(VideosInYT::new(vec![video]))
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error building media info");
+
+            None
+        }
+    }
+}
+
+/// Downloads the media of the format to the path, an HLS playlist is downloaded by `FFmpeg` and a file by a GET request.
+/// The size of the media isn't known or can't be trusted before the download, so the download is stopped at the max file size.
+/// # Errors
+/// Returns [`ErrorKind`] if the request, the writing or `FFmpeg` fails, or the media exceeds the max file size
+#[instrument(skip_all, fields(%url, %format_id))]
+pub fn download_to_path(
+    url: &str,
+    format_id: &str,
+    path: impl AsRef<Path>,
+    max_file_size: u64,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<(), ErrorKind> {
+    let path = path.as_ref();
+
+    let exceeded = if format_id == HLS_FORMAT_ID {
+        download_hls(url, path, max_file_size, timeout, limits)?;
+
+        // `FFmpeg` stops writing at the max file size without failing, so the output of the max size is cut
+        fs::metadata(path)?.len() >= max_file_size
+    } else {
+        let response = reqwest::blocking::Client::new()
+            .get(url)
+            .timeout(Duration::from_secs(timeout))
+            .send()?
+            .error_for_status()?;
+        let mut file = File::create(path)?;
+
+        // A byte over the max file size is read to know that the media exceeds it
+        io::copy(&mut response.take(max_file_size.saturating_add(1)), &mut file)? > max_file_size
+    };
+
+    if exceeded {
+        event!(Level::WARN, max_file_size, "Media exceeds the max file size");
+
+        return Err(ErrorKind::TooLarge { max_size: max_file_size });
+    }
+
+    event!(Level::DEBUG, "Media downloaded");
+
+    Ok(())
+}
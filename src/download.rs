@@ -1,7 +1,17 @@
 use crate::{
-    cmd::{convert_to_jpg, download_audio_to_path, download_to_pipe, download_video_to_path, merge_streams, ytdl},
+    cmd::{
+        convert_audio, convert_to_animation, convert_to_jpg, crop_to_square, cut, download_audio_to_path, download_to_pipe,
+        download_video_to_path, get_media_or_playlist_info, get_playlist_entries, merge_streams, process, remove_segments,
+        transcode_to_h264,
+        ytdl::{self, FailureCause},
+        H264Encoder,
+    },
+    config::{ProcessLimits, Retries, RetryPolicy, Transcode, YtDlp},
+    direct_download,
     fs::get_best_thumbnail_path_in_dir,
-    models::{AudioInFS, VideoInFS, VideoInYT},
+    info_cache,
+    models::{combined_format, AudioConversion, AudioInFS, PlaylistEntry, VideoInFS, VideoInYT, VideosInYT},
+    retry, stats, youtube_fallback,
 };
 use nix::{
     fcntl::{fcntl, FcntlArg::F_SETFD, FdFlag},
@@ -10,15 +20,21 @@ use nix::{
 
 use reqwest::blocking::Client;
 use std::{
-    fs::File,
+    fs::{self, File},
     io::{self, Write},
     os::fd::{FromRawFd as _, OwnedFd},
     path::{Path, PathBuf},
+    process::Child,
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tracing::{event, field, instrument, Level, Span};
-use wait_timeout::ChildExt as _;
+
+const MERGE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Bitrate in kbps left for the audio when the video bitrate of a transcoded video is capped
+const TRANSCODE_AUDIO_BITRATE: u64 = 160;
+/// Seconds of the download timeout for each second of a live stream recording
+const LIVE_RECORDING_TIMEOUT_PER_SECOND: f64 = 0.5;
 
 #[derive(thiserror::Error, Debug)]
 pub enum RangeDownloadKind {
@@ -36,6 +52,182 @@ pub enum StreamErrorKind {
     Io(#[from] io::Error),
     #[error(transparent)]
     RangeDownload(#[from] RangeDownloadKind),
+    #[error("FFmpeg didn't write the output for {stall_timeout} seconds")]
+    Stalled { stall_timeout: u64 },
+    #[error(transparent)]
+    DirectDownload(#[from] direct_download::ErrorKind),
+}
+
+/// Returns the timeout in seconds to download the media.
+/// Recordings of finished live streams can take hours, so their timeout is raised by duration up to `max_timeout`.
+#[must_use]
+pub fn download_timeout(video: &VideoInYT, timeout: u64, max_timeout: u64) -> u64 {
+    match video.duration {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Some(duration) if video.is_live_recording() => {
+            ((duration * LIVE_RECORDING_TIMEOUT_PER_SECOND) as u64).clamp(timeout, max_timeout.max(timeout))
+        }
+        _ => timeout,
+    }
+}
+
+/// Gets the media info, reusing the cached one if the URL was requested recently.
+/// See [`media_info_uncached`] for details.
+#[instrument(skip_all, fields(%url, domain = stats::get_domain(url).as_deref(), cache_hit = field::Empty))]
+pub async fn media_info(
+    yt_dlp_config: &YtDlp,
+    url: &str,
+    allow_playlist: bool,
+    retry_policy: &RetryPolicy,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<VideosInYT, ytdl::Error> {
+    if yt_dlp_config.info_cache_ttl == 0 {
+        return media_info_uncached(yt_dlp_config, url, allow_playlist, retry_policy, timeout, limits).await;
+    }
+
+    let ttl = Duration::from_secs(yt_dlp_config.info_cache_ttl);
+
+    if let Some(videos) = info_cache::get(url, allow_playlist, ttl) {
+        Span::current().record("cache_hit", true);
+
+        event!(Level::DEBUG, "Got media info from the cache");
+
+        return Ok(videos);
+    }
+
+    Span::current().record("cache_hit", false);
+
+    let videos = media_info_uncached(yt_dlp_config, url, allow_playlist, retry_policy, timeout, limits).await?;
+    info_cache::insert(url, allow_playlist, videos.clone(), ttl);
+
+    Ok(videos)
+}
+
+/// Sets the format strategy of the site of each media from the config.
+/// Media of a playlist can be from other sites than the playlist, so the strategy is found by the URL of each media.
+fn with_format_strategies(yt_dlp_config: &YtDlp, videos: VideosInYT) -> VideosInYT {
+    if yt_dlp_config.format_strategies.is_empty() {
+        return videos;
+    }
+
+    VideosInYT::new(
+        videos
+            .map(|mut video| {
+                video.format_strategy = yt_dlp_config.get_format_strategy(&video.original_url);
+                video
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Gets the media info with `yt-dlp`, falling back to Piped and Invidious instances for YouTube videos if it fails.
+/// URLs that point directly at media files or HLS playlists skip `yt-dlp`, see [`direct_download::media_info`].
+/// See [`media_info_from_yt_dlp`] for details.
+async fn media_info_uncached(
+    yt_dlp_config: &YtDlp,
+    url: &str,
+    allow_playlist: bool,
+    retry_policy: &RetryPolicy,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<VideosInYT, ytdl::Error> {
+    if let Some(videos) = direct_download::media_info(url).await {
+        return Ok(videos);
+    }
+
+    let err = match media_info_from_yt_dlp(yt_dlp_config, url, allow_playlist, None, retry_policy, timeout, limits).await {
+        Ok(videos) => return Ok(with_format_strategies(yt_dlp_config, videos)),
+        Err(err) => err,
+    };
+
+    match youtube_fallback::media_info(&yt_dlp_config.fallback_instances, url, timeout).await {
+        Some(videos) => {
+            event!(Level::WARN, %err, "yt-dlp failed to get media info, it's got from a fallback instance");
+
+            Ok(videos)
+        }
+        None => Err(err),
+    }
+}
+
+/// Gets the media info of the playlist entry at the position, starting from 1.
+/// Only the entry is extracted, so it's fast even for large playlists.
+#[instrument(skip_all, fields(%url, playlist_index))]
+pub async fn playlist_entry_info(
+    yt_dlp_config: &YtDlp,
+    url: &str,
+    playlist_index: usize,
+    retry_policy: &RetryPolicy,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<VideosInYT, ytdl::Error> {
+    media_info_from_yt_dlp(yt_dlp_config, url, true, Some(playlist_index), retry_policy, timeout, limits)
+        .await
+        .map(|videos| with_format_strategies(yt_dlp_config, videos))
+}
+
+/// Gets entries of the playlist without their formats, see [`get_playlist_entries`] for details
+#[instrument(skip_all, fields(%url))]
+pub async fn playlist_entries(
+    yt_dlp_config: &YtDlp,
+    url: &str,
+    retry_policy: &RetryPolicy,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<Vec<PlaylistEntry>, ytdl::Error> {
+    let extra_args = yt_dlp_config.get_extra_args(url);
+
+    retry::future(retry_policy, "info", || {
+        get_playlist_entries(&yt_dlp_config.full_path, url, &extra_args, timeout, limits)
+    })
+    .await
+}
+
+/// Gets the media info, retrying with the cookies of the host if the media requires signing in.
+/// Media got with the cookies is marked, so it's downloaded with them too.
+/// If the playlist index is set, only the entry at the position is extracted.
+async fn media_info_from_yt_dlp(
+    yt_dlp_config: &YtDlp,
+    url: &str,
+    allow_playlist: bool,
+    playlist_index: Option<usize>,
+    retry_policy: &RetryPolicy,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<VideosInYT, ytdl::Error> {
+    let mut extra_args = yt_dlp_config.get_extra_args(url);
+    if let Some(playlist_index) = playlist_index {
+        extra_args.extend(["--playlist-items".to_owned(), playlist_index.to_string()]);
+    }
+    let result = retry::future(retry_policy, "info", || {
+        get_media_or_playlist_info(&yt_dlp_config.full_path, url, allow_playlist, &extra_args, timeout, limits)
+    })
+    .await;
+
+    let Err(ytdl::Error::Failed(FailureCause::AgeRestricted | FailureCause::LoginRequired)) = result else {
+        return result;
+    };
+    let Some(cookies_args) = yt_dlp_config.get_cookies_args(url) else {
+        return result;
+    };
+
+    event!(Level::INFO, "Media requires signing in, retry with cookies");
+
+    let extra_args = [extra_args, cookies_args].concat();
+    let videos = retry::future(retry_policy, "info", || {
+        get_media_or_playlist_info(&yt_dlp_config.full_path, url, allow_playlist, &extra_args, timeout, limits)
+    })
+    .await?;
+
+    Ok(VideosInYT::new(
+        videos
+            .map(|mut video| {
+                video.requires_cookies = true;
+                video
+            })
+            .collect::<Vec<_>>(),
+    ))
 }
 
 #[cfg(not(target_family = "unix"))]
@@ -43,16 +235,30 @@ pub fn video(
     _video: VideoInYT,
     _video_id_or_url: impl AsRef<str>,
     _max_file_size: u64,
+    _fps_weight: f64,
+    _max_format_attempts: u8,
     _executable_ytdl_path: impl AsRef<str>,
+    _extra_args: &[String],
+    _retries: &Retries,
     _temp_dir: &TempDir,
+    _custom_thumbnail_url: Option<&str>,
+    _requested_format_id: Option<&str>,
+    _transcode: Transcode,
+    _limits: ProcessLimits,
 ) -> Result<VideoInFS, StreamErrorKind> {
     unimplemented!("This function is only implemented for Unix systems");
 }
 
-fn get_thumbnail_path(url: impl AsRef<str>, id: impl AsRef<str>, temp_dir_path: impl AsRef<Path>) -> Option<PathBuf> {
+fn get_thumbnail_path(
+    url: impl AsRef<str>,
+    id: impl AsRef<str>,
+    temp_dir_path: impl AsRef<Path>,
+    retry_policy: &RetryPolicy,
+    limits: ProcessLimits,
+) -> Option<PathBuf> {
     let path = temp_dir_path.as_ref().join(format!("{}.jpg", id.as_ref()));
 
-    match convert_to_jpg(url, &path) {
+    match retry::blocking(retry_policy, "thumbnail", || convert_to_jpg(url.as_ref(), &path, limits)) {
         Ok(()) => Some(path),
         Err(err) => {
             event!(Level::ERROR, %err, "Error downloading thumbnail");
@@ -63,37 +269,65 @@ fn get_thumbnail_path(url: impl AsRef<str>, id: impl AsRef<str>, temp_dir_path:
     }
 }
 
-const RANGE_CHUNK_SIZE: i32 = 1024 * 1024 * 10;
+/// The custom thumbnail is used instead of the video one, which is the fallback if the custom one fails
+fn get_video_thumbnail_path(
+    video: &VideoInYT,
+    custom_thumbnail_url: Option<&str>,
+    temp_dir_path: impl AsRef<Path>,
+    retry_policy: &RetryPolicy,
+    limits: ProcessLimits,
+) -> Option<PathBuf> {
+    custom_thumbnail_url
+        .and_then(|url| get_thumbnail_path(url, &video.id, &temp_dir_path, retry_policy, limits))
+        .or_else(|| {
+            video
+                .thumbnail()
+                .and_then(|url| get_thumbnail_path(url, &video.id, &temp_dir_path, retry_policy, limits))
+        })
+}
+
+/// Tries the thumbnail URLs in order, each one once.
+/// It's a last resort if the thumbnail download failed with retries during the media download.
+pub fn thumbnail_from_urls(urls: &[String], temp_dir_path: impl AsRef<Path>, limits: ProcessLimits) -> Option<PathBuf> {
+    let path = temp_dir_path.as_ref().join("thumbnail_retry.jpg");
+
+    urls.iter().find_map(|url| match convert_to_jpg(url, &path, limits) {
+        Ok(()) if path.exists() => Some(path.clone()),
+        Ok(()) => None,
+        Err(err) => {
+            event!(Level::WARN, %err, "Error downloading thumbnail");
+
+            None
+        }
+    })
+}
+
+const RANGE_CHUNK_SIZE: u64 = 1024 * 1024 * 10;
 
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 fn range_download_to_write<W: Write>(client: &Client, url: impl AsRef<str>, filesize: f64, write: &mut W) -> Result<(), RangeDownloadKind> {
     let url = url.as_ref();
 
-    let mut start: i32 = 0;
+    let mut start: u64 = 0;
     let mut end = RANGE_CHUNK_SIZE;
 
     loop {
         event!(Level::TRACE, start, end, "Download chunk");
 
-        if end >= filesize as i32 {
-            let buf = client.get(format!("{url}&range={start}-")).send()?.bytes()?;
-
-            if buf.is_empty() {
-                break;
-            }
-
-            write.write_all(&buf)?;
+        if end >= filesize as u64 {
+            client.get(format!("{url}&range={start}-")).send()?.copy_to(write)?;
 
             break;
         }
 
-        let buf = client.get(format!("{url}&range={start}-{end}")).send()?.bytes()?;
+        // The chunk is streamed into the pipe, so it isn't buffered in memory as a whole.
+        // Zero-copy `splice` isn't possible here, because the response is decrypted by TLS in user space.
+        let copied_len = client.get(format!("{url}&range={start}-{end}")).send()?.copy_to(write)?;
 
-        if buf.is_empty() {
+        if copied_len == 0 {
             break;
         }
 
-        write.write_all(&buf)?;
-
         start = end + 1;
         end += RANGE_CHUNK_SIZE;
     }
@@ -101,31 +335,245 @@ fn range_download_to_write<W: Write>(client: &Client, url: impl AsRef<str>, file
     Ok(())
 }
 
+/// Downloads the video with the best format that fits the size.
+/// Videos with codecs that Telegram mobile clients can't play well are re-encoded to H264 if it's enabled.
 #[cfg(target_family = "unix")]
-#[instrument(skip_all, fields(url = %video.original_url, format_id, file_path, extension))]
+#[instrument(skip_all, fields(url = %video.original_url, media_type = "video", file_size = field::Empty))]
+#[allow(clippy::too_many_arguments)]
 pub fn video(
     video: VideoInYT,
     max_file_size: u64,
+    fps_weight: f64,
+    max_format_attempts: u8,
     executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
     temp_dir_path: impl AsRef<Path>,
     timeout: u64,
+    custom_thumbnail_url: Option<&str>,
+    requested_format_id: Option<&str>,
+    transcode: Transcode,
+    limits: ProcessLimits,
 ) -> Result<VideoInFS, StreamErrorKind> {
+    let duration = video.duration;
+    let temp_dir_path = temp_dir_path.as_ref();
+
+    let (video_in_fs, has_incompatible_codec) = video_with_best_format(
+        video,
+        max_file_size,
+        fps_weight,
+        max_format_attempts,
+        executable_ytdl_path,
+        extra_args,
+        retries,
+        temp_dir_path,
+        timeout,
+        custom_thumbnail_url,
+        requested_format_id,
+        limits,
+    )?;
+
+    let video_in_fs = if transcode.enabled && has_incompatible_codec {
+        transcoded_or_original(
+            video_in_fs,
+            transcode.encoder,
+            transcode.crf,
+            max_file_size,
+            duration,
+            temp_dir_path,
+            limits,
+        )
+    } else {
+        video_in_fs
+    };
+
+    if let Ok(metadata) = fs::metadata(&video_in_fs.path) {
+        Span::current().record("file_size", metadata.len());
+    }
+
+    Ok(video_in_fs)
+}
+
+/// Re-encodes the video to H264 with the bitrate capped to fit the size.
+/// A hardware encoder that fails is replaced with the software one.
+/// The original video is returned if it fails, because it's still playable on desktop clients.
+#[cfg(target_family = "unix")]
+fn transcoded_or_original(
+    video_in_fs: VideoInFS,
+    encoder: H264Encoder,
+    crf: u8,
+    max_file_size: u64,
+    duration: Option<f64>,
+    temp_dir_path: &Path,
+    limits: ProcessLimits,
+) -> VideoInFS {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    let max_bitrate = duration
+        .filter(|duration| *duration > 0.0)
+        .map(|duration| (max_file_size as f64 * 8.0 / 1000.0 / duration) as u64)
+        .map(|bitrate| bitrate.saturating_sub(TRANSCODE_AUDIO_BITRATE))
+        .filter(|bitrate| *bitrate > 0);
+    let output_path = temp_dir_path.join("transcoded.mp4");
+
+    let result = transcode_to_h264(&video_in_fs.path, &output_path, encoder, crf, max_bitrate, limits).or_else(|err| {
+        if encoder == H264Encoder::Software {
+            return Err(err);
+        }
+
+        event!(Level::WARN, %err, encoder = encoder.as_str(), "Error transcoding video with hardware encoder, use software one");
+
+        transcode_to_h264(&video_in_fs.path, &output_path, H264Encoder::Software, crf, max_bitrate, limits)
+    });
+
+    match result {
+        Ok(()) => {
+            event!(Level::DEBUG, "Video transcoded to H264");
+
+            VideoInFS::new(output_path, video_in_fs.thumbnail_path)
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error transcoding video, send it as is");
+
+            video_in_fs
+        }
+    }
+}
+
+/// Removes the segments, like sponsored ones, from the downloaded video.
+/// Returns the video and whether the segments were removed, the original video is returned if it fails.
+#[instrument(skip_all, fields(segments_len = segments.len()))]
+pub fn without_segments(
+    video_in_fs: VideoInFS,
+    segments: &[(f64, f64)],
+    temp_dir_path: impl AsRef<Path>,
+    limits: ProcessLimits,
+) -> (VideoInFS, bool) {
+    if segments.is_empty() {
+        return (video_in_fs, false);
+    }
+
+    let temp_dir_path = temp_dir_path.as_ref();
+    let extension = video_in_fs
+        .path
+        .extension()
+        .map_or_else(|| "mp4".into(), |extension| extension.to_string_lossy());
+    let output_path = temp_dir_path.join(format!("without_segments.{extension}"));
+
+    match remove_segments(
+        &video_in_fs.path,
+        &output_path,
+        temp_dir_path.join("segments.ffconcat"),
+        segments,
+        limits,
+    ) {
+        Ok(()) => {
+            event!(Level::DEBUG, "Segments removed");
+
+            (VideoInFS::new(output_path, video_in_fs.thumbnail_path), true)
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error removing segments, send the video as is");
+
+            (video_in_fs, false)
+        }
+    }
+}
+
+/// Tries formats by priority until one is downloaded.
+/// A requested format bypasses the priority, it's only skipped if it exceeds the size.
+/// Returns the video and whether its codec is incompatible with Telegram mobile clients.
+#[cfg(target_family = "unix")]
+#[allow(clippy::too_many_arguments)]
+fn video_with_best_format(
+    video: VideoInYT,
+    max_file_size: u64,
+    fps_weight: f64,
+    max_format_attempts: u8,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: impl AsRef<Path>,
+    timeout: u64,
+    custom_thumbnail_url: Option<&str>,
+    requested_format_id: Option<&str>,
+    limits: ProcessLimits,
+) -> Result<(VideoInFS, bool), StreamErrorKind> {
     let mut combined_formats = video.get_combined_formats();
-    combined_formats.sort_by_priority_and_skip_by_size(max_file_size);
 
-    let Some(combined_format) = combined_formats.first().cloned() else {
+    match requested_format_id {
+        Some(format_id) => {
+            combined_formats.retain_requested(format_id);
+            combined_formats.skip_with_size_greater_than(max_file_size);
+        }
+        None => combined_formats.sort_by_priority_and_skip_by_size(max_file_size, fps_weight, &video.format_strategy),
+    }
+
+    if combined_formats.is_empty() {
         event!(Level::WARN, %combined_formats, "No video format found");
 
         return Err(StreamErrorKind::NoFormatFound {
             video_id: video.id.into_boxed_str(),
         });
-    };
+    }
 
-    drop(combined_formats);
+    let mut last_err = None;
+
+    for (attempt, combined_format) in combined_formats.iter().take(usize::from(max_format_attempts.max(1))).enumerate() {
+        let result = retry::blocking(&retries.yt_dlp_download, "video_download", || {
+            video_with_format(
+                &video,
+                combined_format,
+                max_file_size,
+                &executable_ytdl_path,
+                extra_args,
+                retries,
+                &temp_dir_path,
+                timeout,
+                custom_thumbnail_url,
+                limits,
+            )
+        });
 
+        match result {
+            Ok(video_in_fs) => {
+                event!(Level::INFO, format_id = %combined_format.format_id(), attempt, "Video downloaded");
+
+                return Ok((video_in_fs, combined_format.has_incompatible_video_codec()));
+            }
+            Err(err) => {
+                event!(
+                    Level::WARN,
+                    %err,
+                    format_id = %combined_format.format_id(),
+                    attempt,
+                    "Error downloading video with format, trying next one",
+                );
+
+                last_err = Some(err);
+            }
+        }
+    }
+
+    Err(last_err.expect("At least one format should be tried"))
+}
+
+#[cfg(target_family = "unix")]
+#[instrument(skip_all, fields(format_id = %combined_format.format_id(), file_path, extension))]
+#[allow(clippy::too_many_arguments)]
+fn video_with_format(
+    video: &VideoInYT,
+    combined_format: &combined_format::Format<'_>,
+    max_file_size: u64,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: impl AsRef<Path>,
+    timeout: u64,
+    custom_thumbnail_url: Option<&str>,
+    limits: ProcessLimits,
+) -> Result<VideoInFS, StreamErrorKind> {
     let extension = combined_format.get_extension();
 
-    Span::current().record("format_id", &combined_format.format_id());
     Span::current().record("extension", extension);
 
     event!(Level::DEBUG, %combined_format, "Got combined format");
@@ -138,11 +586,28 @@ pub fn video(
 
         Span::current().record("file_path", file_path.display().to_string());
 
-        download_video_to_path(&executable_ytdl_path, &video.original_url, extension, &temp_dir_path, timeout)?;
+        if video.is_direct {
+            direct_download::download_to_path(
+                combined_format.video_format.url,
+                combined_format.video_format.id,
+                &file_path,
+                max_file_size,
+                timeout,
+                limits,
+            )?;
+        } else {
+            download_video_to_path(
+                &executable_ytdl_path,
+                &video.original_url,
+                combined_format.video_format.id,
+                &temp_dir_path,
+                extra_args,
+                timeout,
+                limits,
+            )?;
+        }
 
-        let thumbnail_path = video
-            .thumbnail()
-            .and_then(|url| get_thumbnail_path(url, &video.id, &temp_dir_path))
+        let thumbnail_path = get_video_thumbnail_path(video, custom_thumbnail_url, &temp_dir_path, &retries.thumbnail, limits)
             .or_else(|| get_best_thumbnail_path_in_dir(&temp_dir_path).ok().flatten());
 
         return Ok(VideoInFS::new(file_path, thumbnail_path));
@@ -160,7 +625,9 @@ pub fn video(
 
     let output_path = temp_dir_path.as_ref().join(format!("merged.{extension}"));
 
-    let mut merge_child = merge_streams(video_read_fd, audio_read_fd, extension, &output_path)?;
+    Span::current().record("file_path", output_path.display().to_string());
+
+    let mut merge_child = merge_streams(video_read_fd, audio_read_fd, extension, &output_path, limits)?;
 
     let client = Client::new();
 
@@ -184,6 +651,8 @@ pub fn video(
             &executable_ytdl_path,
             &video.original_url,
             combined_format.video_format.id,
+            extra_args,
+            limits,
         )?;
     };
 
@@ -207,26 +676,280 @@ pub fn video(
             &executable_ytdl_path,
             &video.original_url,
             combined_format.audio_format.id,
+            extra_args,
+            limits,
         )?;
     };
 
-    let thumbnail_path = video.thumbnail().and_then(|url| get_thumbnail_path(url, &video.id, temp_dir_path));
+    let thumbnail_path = get_video_thumbnail_path(video, custom_thumbnail_url, temp_dir_path, &retries.thumbnail, limits);
 
-    let Some(exit_code) = merge_child.wait_timeout(Duration::from_secs(timeout))? else {
-        event!(Level::ERROR, "FFmpeg process timed out");
+    wait_merge(&mut merge_child, &output_path, timeout, limits.merge_stall_timeout)?;
 
-        return Err(io::Error::new(io::ErrorKind::TimedOut, "FFmpeg process timed out").into());
-    };
+    event!(Level::DEBUG, "Streams merged");
+
+    Ok(VideoInFS::new(output_path, thumbnail_path))
+}
+
+/// Waits for the merge process, killing it if it times out or stops writing to the output file.
+/// A stalled merge is killed early, so it doesn't waste the whole timeout.
+fn wait_merge(merge_child: &mut Child, output_path: &Path, timeout: u64, stall_timeout: Option<u64>) -> Result<(), StreamErrorKind> {
+    let started_at = Instant::now();
+    let mut last_growth_at = started_at;
+    let mut last_len = 0;
 
-    if !exit_code.success() {
-        event!(Level::ERROR, "FFmpeg exited with status `{exit_code}`");
+    loop {
+        if let Some(exit_code) = process::wait_timeout(merge_child, MERGE_POLL_INTERVAL)? {
+            if !exit_code.success() {
+                event!(Level::ERROR, "FFmpeg exited with status `{exit_code}`");
+
+                return Err(io::Error::new(io::ErrorKind::Other, format!("FFmpeg exited with status `{exit_code}`")).into());
+            }
+
+            return Ok(());
+        }
+
+        let len = fs::metadata(output_path).map_or(0, |metadata| metadata.len());
+
+        if len > last_len {
+            last_len = len;
+            last_growth_at = Instant::now();
+        }
+
+        let err = if started_at.elapsed() >= Duration::from_secs(timeout) {
+            event!(Level::ERROR, "FFmpeg process timed out");
+
+            io::Error::new(io::ErrorKind::TimedOut, "FFmpeg process timed out").into()
+        } else if let Some(stall_timeout) =
+            stall_timeout.filter(|stall_timeout| last_growth_at.elapsed() >= Duration::from_secs(*stall_timeout))
+        {
+            event!(Level::ERROR, stall_timeout, written_len = last_len, "FFmpeg process stalled");
+
+            StreamErrorKind::Stalled { stall_timeout }
+        } else {
+            continue;
+        };
+
+        // Kill the process, so the next format attempt doesn't compete with it for the output file
+        if let Err(err) = process::kill(merge_child) {
+            event!(Level::WARN, %err, "Error killing FFmpeg process");
+        }
 
-        return Err(io::Error::new(io::ErrorKind::Other, format!("FFmpeg exited with status `{exit_code}`")).into());
+        return Err(err);
     }
+}
 
-    event!(Level::DEBUG, "Streams merged");
+/// Downloads the video and converts it to a square video for a video note.
+/// Returns the path of the converted video.
+#[instrument(skip_all, fields(url = %video.original_url))]
+#[allow(clippy::too_many_arguments)]
+pub fn video_note(
+    video: VideoInYT,
+    max_file_size: u64,
+    fps_weight: f64,
+    max_format_attempts: u8,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: impl AsRef<Path>,
+    timeout: u64,
+    size: u32,
+    limits: ProcessLimits,
+) -> Result<PathBuf, StreamErrorKind> {
+    let temp_dir_path = temp_dir_path.as_ref();
+
+    // Video notes are re-encoded anyway, so incompatible codecs aren't transcoded
+    let (VideoInFS { path, .. }, _) = video_with_best_format(
+        video,
+        max_file_size,
+        fps_weight,
+        max_format_attempts,
+        executable_ytdl_path,
+        extra_args,
+        retries,
+        temp_dir_path,
+        timeout,
+        None,
+        None,
+        limits,
+    )?;
 
-    Ok(VideoInFS::new(output_path, thumbnail_path))
+    let output_path = temp_dir_path.join("video_note.mp4");
+    crop_to_square(path, &output_path, size, limits)?;
+
+    event!(Level::DEBUG, "Video cropped to a video note");
+
+    Ok(output_path)
+}
+
+/// Downloads the video and splits it into a file per chapter without re-encoding.
+/// Returns the chapters in the order of [`VideoInYT::chapters`], all of them have the thumbnail of the video.
+#[instrument(skip_all, fields(url = %video.original_url))]
+#[allow(clippy::too_many_arguments)]
+pub fn video_chapters(
+    video: VideoInYT,
+    max_file_size: u64,
+    fps_weight: f64,
+    max_format_attempts: u8,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: impl AsRef<Path>,
+    timeout: u64,
+    custom_thumbnail_url: Option<&str>,
+    transcode: Transcode,
+    limits: ProcessLimits,
+) -> Result<Vec<VideoInFS>, StreamErrorKind> {
+    let temp_dir_path = temp_dir_path.as_ref();
+    let chapters = video.chapters.clone().unwrap_or_default();
+
+    let VideoInFS { path, thumbnail_path } = self::video(
+        video,
+        max_file_size,
+        fps_weight,
+        max_format_attempts,
+        executable_ytdl_path,
+        extra_args,
+        retries,
+        temp_dir_path,
+        timeout,
+        custom_thumbnail_url,
+        None,
+        transcode,
+        limits,
+    )?;
+    let extension = path
+        .extension()
+        .map_or_else(|| "mp4".into(), |extension| extension.to_string_lossy());
+
+    let mut chapters_in_fs = Vec::with_capacity(chapters.len());
+
+    for (index, chapter) in chapters.iter().enumerate() {
+        let output_path = temp_dir_path.join(format!("chapter_{index}.{extension}"));
+        cut(
+            &path,
+            &output_path,
+            chapter.start_time,
+            chapter.end_time - chapter.start_time,
+            limits,
+        )?;
+
+        chapters_in_fs.push(VideoInFS::new(output_path, thumbnail_path.clone()));
+    }
+
+    event!(Level::DEBUG, chapters_len = chapters_in_fs.len(), "Video split into chapters");
+
+    Ok(chapters_in_fs)
+}
+
+/// Downloads the video and converts it to an MP4 without audio, so Telegram shows it as a looping animation.
+/// Silent videos don't have audio formats to merge with, so they're downloaded with a video-only format.
+/// Returns the path of the converted video.
+#[instrument(skip_all, fields(url = %video.original_url))]
+#[allow(clippy::too_many_arguments)]
+pub fn animation(
+    video: VideoInYT,
+    max_file_size: u64,
+    fps_weight: f64,
+    max_format_attempts: u8,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: impl AsRef<Path>,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<PathBuf, StreamErrorKind> {
+    let temp_dir_path = temp_dir_path.as_ref();
+
+    let path = if video.get_combined_formats().is_empty() {
+        video_without_audio(
+            &video,
+            max_file_size,
+            executable_ytdl_path,
+            extra_args,
+            retries,
+            temp_dir_path,
+            timeout,
+            limits,
+        )?
+    } else {
+        // Animations are re-encoded anyway, so incompatible codecs aren't transcoded
+        video_with_best_format(
+            video,
+            max_file_size,
+            fps_weight,
+            max_format_attempts,
+            executable_ytdl_path,
+            extra_args,
+            retries,
+            temp_dir_path,
+            timeout,
+            None,
+            None,
+            limits,
+        )?
+        .0
+        .path
+    };
+
+    let output_path = temp_dir_path.join("animation.mp4");
+    convert_to_animation(path, &output_path, limits)?;
+
+    event!(Level::DEBUG, "Video converted to an animation");
+
+    Ok(output_path)
+}
+
+/// Downloads the video-only format with the highest resolution that fits the size.
+/// Returns the path of the downloaded video.
+#[instrument(skip_all, fields(format_id = field::Empty))]
+#[allow(clippy::too_many_arguments)]
+fn video_without_audio(
+    video: &VideoInYT,
+    max_file_size: u64,
+    executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
+    temp_dir_path: &Path,
+    timeout: u64,
+    limits: ProcessLimits,
+) -> Result<PathBuf, StreamErrorKind> {
+    #[allow(clippy::cast_precision_loss)]
+    let max_file_size = max_file_size as f64;
+
+    let Some(video_format) = video
+        .get_video_formats()
+        .into_iter()
+        .filter(|format| format.filesize_or_approx().is_none_or(|filesize| filesize <= max_file_size))
+        .max_by(|a, b| a.height.unwrap_or_default().total_cmp(&b.height.unwrap_or_default()))
+    else {
+        event!(Level::WARN, "No video-only format found");
+
+        return Err(StreamErrorKind::NoFormatFound {
+            video_id: video.id.clone().into_boxed_str(),
+        });
+    };
+
+    Span::current().record("format_id", video_format.id);
+
+    event!(Level::DEBUG, %video_format, "Got video-only format");
+
+    retry::blocking(&retries.yt_dlp_download, "video_download", || {
+        download_video_to_path(
+            &executable_ytdl_path,
+            &video.original_url,
+            video_format.id,
+            temp_dir_path,
+            extra_args,
+            timeout,
+            limits,
+        )
+    })?;
+
+    Ok(temp_dir_path.join(format!(
+        "{video_id}.{extension}",
+        video_id = video.id,
+        extension = video_format.container.as_str(),
+    )))
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -237,16 +960,27 @@ pub enum ToTempDirErrorKind {
     Ytdl(#[from] ytdl::Error),
     #[error("Failed to get best thumbnail path in dir: {0}")]
     ThumbnailPathFailed(#[from] io::Error),
+    #[error("Failed to convert audio: {0}")]
+    ConvertFailed(io::Error),
+    #[error(transparent)]
+    DirectDownload(#[from] direct_download::ErrorKind),
 }
 
-#[instrument(skip_all, fields(video = video.id, format_id = field::Empty, file_path = field::Empty))]
+#[instrument(skip_all, fields(video = video.id, media_type = "audio", format_id = field::Empty, file_path = field::Empty, file_size = field::Empty))]
+#[allow(clippy::too_many_arguments)]
 pub fn audio_to_temp_dir(
     video: VideoInYT,
     video_id_or_url: impl AsRef<str>,
     max_file_size: u64,
     executable_ytdl_path: impl AsRef<str>,
+    extra_args: &[String],
+    retries: &Retries,
     temp_dir_path: impl AsRef<Path>,
     timeout: u64,
+    custom_thumbnail_url: Option<&str>,
+    conversion: AudioConversion,
+    normalize_target_lufs: f64,
+    limits: ProcessLimits,
 ) -> Result<AudioInFS, ToTempDirErrorKind> {
     let mut audio_formats = video.get_audio_formats();
     audio_formats.sort_by_priority_and_skip_by_size(max_file_size);
@@ -273,18 +1007,62 @@ pub fn audio_to_temp_dir(
 
     event!(Level::DEBUG, ?file_path, "Got file path");
 
-    download_audio_to_path(
-        executable_ytdl_path,
-        video_id_or_url,
-        audio_format.id,
-        extension,
-        &temp_dir_path,
-        timeout,
-    )?;
+    if video.is_direct {
+        retry::blocking(&retries.yt_dlp_download, "audio_download", || {
+            direct_download::download_to_path(audio_format.url, audio_format.id, &file_path, max_file_size, timeout, limits)
+        })?;
+    } else {
+        retry::blocking(&retries.yt_dlp_download, "audio_download", || {
+            download_audio_to_path(
+                &executable_ytdl_path,
+                &video_id_or_url,
+                audio_format.id,
+                extension,
+                &temp_dir_path,
+                video.playlist_index,
+                extra_args,
+                timeout,
+                limits,
+            )
+        })?;
+    }
 
     event!(Level::DEBUG, "Audio downloaded");
 
-    let thumbnail_path = get_best_thumbnail_path_in_dir(temp_dir_path)?;
+    let file_path = match conversion.target_extension(extension) {
+        Some(target_extension) => {
+            let output_path = temp_dir_path.as_ref().join(format!(
+                "{video_id}.converted.{extension}",
+                video_id = video.id,
+                extension = target_extension.as_str(),
+            ));
+
+            convert_audio(
+                &file_path,
+                &output_path,
+                target_extension.encoder(),
+                conversion.bitrate,
+                conversion.normalize.then_some(normalize_target_lufs),
+                limits,
+            )
+            .map_err(ToTempDirErrorKind::ConvertFailed)?;
+
+            event!(Level::DEBUG, ?output_path, "Audio converted");
+
+            output_path
+        }
+        None => file_path,
+    };
+
+    if let Ok(metadata) = fs::metadata(&file_path) {
+        Span::current().record("file_size", metadata.len());
+    }
+
+    let thumbnail_path =
+        match custom_thumbnail_url.and_then(|url| get_thumbnail_path(url, &video.id, &temp_dir_path, &retries.thumbnail, limits)) {
+            Some(thumbnail_path) => Some(thumbnail_path),
+            None => get_best_thumbnail_path_in_dir(temp_dir_path)?,
+        };
 
     Ok(AudioInFS::new(file_path, thumbnail_path))
 }
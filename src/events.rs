@@ -0,0 +1,125 @@
+use std::fmt::{self, Display, Formatter};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+use tracing::{event, Level};
+
+const CHANNEL_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Video,
+    Audio,
+}
+
+impl MediaKind {
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::Video => "video",
+            Self::Audio => "audio",
+        }
+    }
+}
+
+impl Display for MediaKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Domain events published by the download pipeline.
+/// `chat_id` is `None` for inline mode, because chosen inline results don't have a chat.
+#[derive(Debug, Clone)]
+pub enum Event {
+    DownloadStarted {
+        chat_id: Option<i64>,
+        url: Box<str>,
+        media_kind: MediaKind,
+    },
+    DownloadFinished {
+        chat_id: Option<i64>,
+        url: Box<str>,
+        media_kind: MediaKind,
+    },
+    DownloadFailed {
+        chat_id: Option<i64>,
+        url: Box<str>,
+        media_kind: MediaKind,
+        error: Box<str>,
+    },
+    SendFailed {
+        chat_id: Option<i64>,
+        media_kind: MediaKind,
+        error: Box<str>,
+    },
+}
+
+/// Outcome of playlist downloads aggregated from their events, so it can be reported to the user at once
+#[derive(Debug, Default)]
+pub struct PlaylistReport {
+    pub succeeded: usize,
+    /// Indexes in the playlist and titles of the failed media
+    pub failed: Vec<(usize, Option<String>)>,
+    /// Size of the sent files in bytes
+    pub total_size: u64,
+}
+
+impl PlaylistReport {
+    pub fn record(&mut self, index: usize, title: Option<&str>, event: &Event) {
+        match event {
+            Event::DownloadFinished { .. } => self.succeeded += 1,
+            Event::DownloadFailed { .. } => self.failed.push((index, title.map(ToOwned::to_owned))),
+            Event::DownloadStarted { .. } | Event::SendFailed { .. } => {}
+        }
+    }
+
+    pub fn add_size(&mut self, size: u64) {
+        self.total_size += size;
+    }
+}
+
+/// Broadcasts [`Event`] to all subscribers.
+/// Publishing doesn't wait for subscribers, and events are dropped if there are no subscribers,
+/// so cross-cutting features can't slow down or break the download pipeline.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: Sender<Event>,
+}
+
+impl EventBus {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: Event) {
+        // Error means that there are no subscribers, it's fine
+        let _ = self.sender.send(event);
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub async fn log_events(mut receiver: Receiver<Event>) {
+    loop {
+        match receiver.recv().await {
+            Ok(published_event) => {
+                event!(Level::DEBUG, event = ?published_event, "Event published");
+            }
+            Err(RecvError::Lagged(skipped_count)) => {
+                event!(Level::WARN, skipped_count, "Events subscriber lagged");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
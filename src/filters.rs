@@ -1,5 +1,23 @@
+mod audio_button;
+mod auto_download_enabled;
+mod bot_admin;
+mod chat_admin;
+mod default_media_type;
+mod domain_allowed;
+mod download_cancellation;
+mod playlist_selection;
+mod purge_confirmation;
 mod text_contains_url;
 mod via_bot;
 
+pub use audio_button::get_audio_callback;
+pub use auto_download_enabled::is_auto_download_enabled;
+pub use bot_admin::is_bot_admin;
+pub use chat_admin::{is_chat_admin, is_user_chat_admin};
+pub use default_media_type::is_default_media_audio;
+pub use domain_allowed::is_domain_allowed;
+pub use download_cancellation::download_cancellation_callback;
+pub use playlist_selection::playlist_selection_callback;
+pub use purge_confirmation::purge_confirmation_callback;
 pub use text_contains_url::{text_contains_url, text_contains_url_with_reply};
 pub use via_bot::is_via_bot;
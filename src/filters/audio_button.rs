@@ -0,0 +1,27 @@
+use crate::audio_buttons;
+
+use std::future::Future;
+use telers::{types::UpdateKind, Request};
+
+/// Checks that the callback query is from the audio button under a video.
+/// Inserts the token as `audio_button_token`.
+pub fn get_audio_callback(request: &mut Request) -> impl Future<Output = bool> {
+    let token = match request.update.kind() {
+        UpdateKind::CallbackQuery(callback_query) => callback_query
+            .data
+            .as_deref()
+            .and_then(audio_buttons::token_from_callback_data)
+            .map(|token| token.to_owned().into_boxed_str()),
+        _ => None,
+    };
+
+    let result = if let Some(token) = token {
+        request.context.insert("audio_button_token", token);
+
+        true
+    } else {
+        false
+    };
+
+    async move { result }
+}
@@ -0,0 +1,20 @@
+use crate::config::Bot as BotConfig;
+
+use std::future::Future;
+use telers::Request;
+
+/// Checks that the sender of the message is one of the bot admins from the config.
+/// Unlike [`super::is_chat_admin`], it's used for commands affecting the whole bot.
+#[allow(clippy::module_name_repetitions)]
+pub fn is_bot_admin(request: &mut Request) -> impl Future<Output = bool> {
+    let user_id = request
+        .update
+        .message()
+        .and_then(|message| message.from().as_ref().map(|user| user.id));
+    let result = match (user_id, request.extensions.get::<BotConfig>()) {
+        (Some(user_id), Some(bot_config)) => bot_config.admin_ids.contains(&user_id),
+        _ => false,
+    };
+
+    async move { result }
+}
@@ -0,0 +1,106 @@
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use telers::{
+    methods::GetChatMember,
+    types::{Chat, ChatIdKind, ChatMember},
+    Bot, Request,
+};
+use tracing::{event, Level};
+
+/// How long the admin status of the user is cached, so commands don't hit `getChatMember` every time
+const ADMIN_STATUS_CACHE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    static ref ADMIN_STATUS_CACHE: Mutex<HashMap<(ChatIdKind, i64), (bool, Instant)>> = Mutex::default();
+}
+
+fn get_cached_admin_status(key: &(ChatIdKind, i64)) -> Option<bool> {
+    let mut cache = ADMIN_STATUS_CACHE.lock().unwrap();
+
+    match cache.get(key) {
+        Some((is_admin, cached_at)) if cached_at.elapsed() < ADMIN_STATUS_CACHE_TTL => Some(*is_admin),
+        Some(_) => {
+            cache.remove(key);
+
+            None
+        }
+        None => None,
+    }
+}
+
+fn cache_admin_status(key: (ChatIdKind, i64), is_admin: bool) {
+    let mut cache = ADMIN_STATUS_CACHE.lock().unwrap();
+
+    cache.retain(|_, (_, cached_at)| cached_at.elapsed() < ADMIN_STATUS_CACHE_TTL);
+    cache.insert(key, (is_admin, Instant::now()));
+}
+
+/// Checks that the user is an admin of the chat, the chat can be set by username.
+/// The status is cached, so repeated checks don't hit `getChatMember` every time.
+pub async fn is_user_chat_admin(bot: &Bot, chat_id: impl Into<ChatIdKind>, user_id: i64) -> bool {
+    let key = (chat_id.into(), user_id);
+
+    if let Some(is_admin) = get_cached_admin_status(&key) {
+        return is_admin;
+    }
+
+    match bot.send(GetChatMember::new(key.0.clone(), user_id)).await {
+        Ok(member) => {
+            let is_admin = matches!(member, ChatMember::Owner(_) | ChatMember::Administrator(_));
+
+            cache_admin_status(key, is_admin);
+
+            is_admin
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, chat_id = %key.0, user_id, "Error while getting chat member");
+
+            false
+        }
+    }
+}
+
+/// Checks that the sender of the message is an admin of the chat.
+/// In private chats the user is always considered as an admin.
+#[allow(clippy::module_name_repetitions)]
+pub fn is_chat_admin(request: &mut Request) -> impl Future<Output = bool> {
+    enum Sender {
+        Admin,
+        User { chat_id: i64, user_id: i64 },
+        Unknown,
+    }
+
+    let sender = match request.update.message() {
+        Some(message) => {
+            let chat_id = message.chat().id();
+
+            if matches!(message.chat(), Chat::Private(_)) {
+                Sender::Admin
+            } else if message.sender_chat().is_some_and(|sender_chat| sender_chat.id() == chat_id) {
+                // Anonymous admins send messages on behalf of the chat
+                Sender::Admin
+            } else if let Some(user) = message.from() {
+                Sender::User { chat_id, user_id: user.id }
+            } else {
+                Sender::Unknown
+            }
+        }
+        None => Sender::Unknown,
+    };
+    let bot = request.bot.clone();
+
+    async move {
+        let (chat_id, user_id) = match sender {
+            Sender::Admin => return true,
+            Sender::Unknown => return false,
+            Sender::User { chat_id, user_id } => (chat_id, user_id),
+        };
+
+        is_user_chat_admin(&bot, chat_id, user_id).await
+    }
+}
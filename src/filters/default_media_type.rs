@@ -0,0 +1,16 @@
+use crate::chat_config::{ChatConfigStore, MediaType};
+
+use std::future::Future;
+use telers::Request;
+
+/// Checks that links in messages without commands are downloaded as audios in the chat
+#[allow(clippy::module_name_repetitions)]
+pub fn is_default_media_audio(request: &mut Request) -> impl Future<Output = bool> {
+    let chat_id = request.update.chat().map(|chat| chat.id());
+    let result = match (chat_id, request.extensions.get::<ChatConfigStore>()) {
+        (Some(chat_id), Some(chat_config_store)) => chat_config_store.get(chat_id).default_media_type == MediaType::Audio,
+        _ => false,
+    };
+
+    async move { result }
+}
@@ -0,0 +1,18 @@
+use crate::chat_config::ChatConfigStore;
+
+use std::future::Future;
+use telers::Request;
+
+/// Checks that the domain of the URL found by [`super::text_contains_url`] is included in the chat config.
+/// Chats without included domains accept all domains.
+#[allow(clippy::module_name_repetitions)]
+pub fn is_domain_allowed(request: &mut Request) -> impl Future<Output = bool> {
+    let chat_id = request.update.chat().map(|chat| chat.id());
+    let url = request.context.get::<Box<str>>("video_url");
+    let result = match (chat_id, url, request.extensions.get::<ChatConfigStore>()) {
+        (Some(chat_id), Some(url), Some(chat_config_store)) => chat_config_store.get(chat_id).is_domain_allowed(url),
+        _ => true,
+    };
+
+    async move { result }
+}
@@ -0,0 +1,27 @@
+use crate::cancellation;
+
+use std::future::Future;
+use telers::{types::UpdateKind, Request};
+
+/// Checks that the callback query is from the cancel button of a download.
+/// Inserts the key of the download as `cancel_key`.
+pub fn download_cancellation_callback(request: &mut Request) -> impl Future<Output = bool> {
+    let key = match request.update.kind() {
+        UpdateKind::CallbackQuery(callback_query) => callback_query
+            .data
+            .as_deref()
+            .and_then(cancellation::from_callback_data)
+            .map(|key| key.to_owned().into_boxed_str()),
+        _ => None,
+    };
+
+    let result = if let Some(key) = key {
+        request.context.insert("cancel_key", key);
+
+        true
+    } else {
+        false
+    };
+
+    async move { result }
+}
@@ -0,0 +1,28 @@
+use crate::selections::Action;
+
+use std::future::Future;
+use telers::{types::UpdateKind, Request};
+
+/// Checks that the callback query is from the playlist selection keyboard.
+/// Inserts the selection token as `selection_token` and the action as `selection_action`.
+pub fn playlist_selection_callback(request: &mut Request) -> impl Future<Output = bool> {
+    let parsed = match request.update.kind() {
+        UpdateKind::CallbackQuery(callback_query) => callback_query
+            .data
+            .as_deref()
+            .and_then(Action::from_callback_data)
+            .map(|(token, action)| (token.to_owned().into_boxed_str(), action)),
+        _ => None,
+    };
+
+    let result = if let Some((token, action)) = parsed {
+        request.context.insert("selection_token", token);
+        request.context.insert("selection_action", action);
+
+        true
+    } else {
+        false
+    };
+
+    async move { result }
+}
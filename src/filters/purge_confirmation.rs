@@ -0,0 +1,23 @@
+use crate::info_cache::PurgeAction;
+
+use std::future::Future;
+use telers::{types::UpdateKind, Request};
+
+/// Checks that the callback query is from the purge confirmation keyboard.
+/// Inserts the action as `purge_action`.
+pub fn purge_confirmation_callback(request: &mut Request) -> impl Future<Output = bool> {
+    let parsed = match request.update.kind() {
+        UpdateKind::CallbackQuery(callback_query) => callback_query.data.as_deref().and_then(PurgeAction::from_callback_data),
+        _ => None,
+    };
+
+    let result = if let Some(action) = parsed {
+        request.context.insert("purge_action", action);
+
+        true
+    } else {
+        false
+    };
+
+    async move { result }
+}
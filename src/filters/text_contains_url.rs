@@ -1,30 +1,44 @@
+use crate::config::Bot as BotConfig;
+
 use std::future::Future;
 use telers::{types::UpdateKind, Request};
 use url::Url;
 
-fn get_url_from_text(text: &str) -> Option<Url> {
-    for word in text.split_whitespace() {
-        match Url::parse(word) {
-            Ok(url) => {
-                return Some(url);
-            }
-            Err(_) => {
-                continue;
-            }
-        };
-    }
+/// Only HTTP(S) URLs are got, so words ending with a colon, like `Look:`, and other schemes aren't taken for URLs
+fn get_urls_from_text(text: &str, max_count: usize) -> Vec<Url> {
+    text.split_whitespace()
+        .filter_map(|word| Url::parse(word).ok())
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .take(max_count)
+        .collect()
+}
 
-    None
+fn get_max_urls_count(request: &Request) -> usize {
+    request
+        .extensions
+        .get::<BotConfig>()
+        .map_or(1, |bot_config| bot_config.max_urls_per_message.max(1))
+}
+
+/// Inserts the first URL as `video_url` and all found URLs as `video_urls`.
+/// Handlers that don't support batches use only the first one.
+fn insert_urls(request: &mut Request, urls: &[Url]) {
+    request.context.insert("video_url", urls[0].as_str().to_owned().into_boxed_str());
+    request.context.insert(
+        "video_urls",
+        urls.iter()
+            .map(|url| url.as_str().to_owned().into_boxed_str())
+            .collect::<Box<[Box<str>]>>(),
+    );
 }
 
 pub fn text_contains_url(request: &mut Request) -> impl Future<Output = bool> {
     let result = if let Some(text) = request.update.text() {
-        let mut url_found = false;
-
-        if let Some(url) = get_url_from_text(text) {
-            url_found = true;
+        let urls = get_urls_from_text(text, get_max_urls_count(request));
+        let url_found = !urls.is_empty();
 
-            request.context.insert("video_url", url.as_str().to_owned().into_boxed_str());
+        if url_found {
+            insert_urls(request, &urls);
         }
 
         url_found
@@ -35,31 +49,29 @@ pub fn text_contains_url(request: &mut Request) -> impl Future<Output = bool> {
     async move { result }
 }
 
+/// Same as [`text_contains_url`], but if the text doesn't contain URLs, they're got from the replied message text or caption.
+/// Params are still got by handlers from the text, so a command with params replying to a link uses both.
 #[allow(clippy::module_name_repetitions)]
 pub fn text_contains_url_with_reply(request: &mut Request) -> impl Future<Output = bool> {
     let result = if let Some(text) = request.update.text() {
-        let mut url_found = false;
-
-        match get_url_from_text(text) {
-            Some(url) => {
-                url_found = true;
+        let max_urls_count = get_max_urls_count(request);
+        let mut urls = get_urls_from_text(text, max_urls_count);
 
-                request.context.insert("video_url", url.as_str().to_owned().into_boxed_str());
-            }
-            None => match request.update.kind() {
-                UpdateKind::Message(message) | UpdateKind::EditedMessage(message) => {
-                    if let Some(message) = message.reply_to_message() {
-                        if let Some(text) = message.text() {
-                            if let Some(url) = get_url_from_text(text) {
-                                url_found = true;
-
-                                request.context.insert("video_url", url.as_str().to_owned().into_boxed_str());
-                            };
-                        };
-                    }
+        if urls.is_empty() {
+            if let UpdateKind::Message(message) | UpdateKind::EditedMessage(message) = request.update.kind() {
+                if let Some(text) = message
+                    .reply_to_message()
+                    .and_then(|message| message.text().or_else(|| message.caption()))
+                {
+                    urls = get_urls_from_text(text, max_urls_count);
                 }
-                _ => {}
-            },
+            }
+        }
+
+        let url_found = !urls.is_empty();
+
+        if url_found {
+            insert_urls(request, &urls);
         }
 
         url_found
@@ -69,3 +81,30 @@ pub fn text_contains_url_with_reply(request: &mut Request) -> impl Future<Output
 
     async move { result }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(text: &str, max_count: usize) -> Vec<String> {
+        get_urls_from_text(text, max_count).into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_get_urls_from_text() {
+        assert_eq!(
+            urls("Look: https://youtu.be/abc and http://example.com/video", 5),
+            ["https://youtu.be/abc", "http://example.com/video"]
+        );
+        assert_eq!(
+            urls("https://a.com https://b.com https://c.com", 2),
+            ["https://a.com/", "https://b.com/"]
+        );
+    }
+
+    #[test]
+    fn test_get_urls_from_text_skips_other_schemes() {
+        assert!(urls("source: mailto:user@example.com tg://resolve?domain=bot items=2:5", 5).is_empty());
+        assert!(urls("no links here", 5).is_empty());
+    }
+}
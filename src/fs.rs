@@ -1,3 +1,9 @@
+mod archive;
+mod json_file;
 mod thumbnail;
+mod work_dir;
 
+pub use archive::{sanitize_file_name, write_zip};
+pub use json_file::JsonFile;
 pub use thumbnail::get_best_thumbnail_path_in_dir;
+pub use work_dir::remove_stale_dirs;
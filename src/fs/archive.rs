@@ -0,0 +1,43 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+};
+use tracing::{event, instrument, Level};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+/// Replaces characters that aren't allowed in file names on common systems, so the archive can be extracted anywhere
+#[must_use]
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|char| {
+            if char.is_control() || matches!(char, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                char
+            }
+        })
+        .collect::<String>()
+        .trim()
+        .to_owned()
+}
+
+/// Writes files to a ZIP archive without compression, because media files are already compressed.
+/// Entries are `(name in the archive, path)`. Files are stored as is, so audios keep the tags embedded by `yt-dlp`:
+/// title, artist and track number of playlist entries.
+#[instrument(skip_all, fields(path = ?path.as_ref(), entries_len = entries.len()))]
+pub fn write_zip<P: AsRef<Path>>(path: impl AsRef<Path>, entries: &[(String, P)]) -> Result<(), io::Error> {
+    let mut writer = ZipWriter::new(BufWriter::new(File::create(path.as_ref())?));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for (name, entry_path) in entries {
+        writer.start_file(name.as_str(), options)?;
+        io::copy(&mut File::open(entry_path)?, &mut writer)?;
+    }
+
+    writer.finish()?;
+
+    event!(Level::DEBUG, "Archive written");
+
+    Ok(())
+}
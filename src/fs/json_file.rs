@@ -0,0 +1,85 @@
+use crate::telemetry::spawn_blocking;
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+use tracing::{event, Level};
+
+/// JSON file in the data dir with a value saved in the background on every change, so the value is kept across restarts.
+/// Clones save to the same file.
+#[derive(Debug, Clone)]
+pub struct JsonFile {
+    /// Number of the last change, it's increased with the value locked, so a greater number has a newer value
+    version: Arc<AtomicU64>,
+    /// Number of the change saved to the file, saves of older changes finished after newer ones are skipped
+    saved_version: Arc<Mutex<u64>>,
+    path: Arc<Path>,
+}
+
+impl JsonFile {
+    #[must_use]
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            version: Arc::new(AtomicU64::new(0)),
+            saved_version: Arc::new(Mutex::new(0)),
+            path: path.into(),
+        }
+    }
+
+    /// Reads the saved value, the default one is returned if the file doesn't exist
+    /// # Errors
+    /// Returns [`io::Error`] if the file can't be read or parsed
+    pub fn read<T: DeserializeOwned + Default>(&self) -> Result<T, io::Error> {
+        match fs::read(&self.path) {
+            Ok(content) => serde_json::from_slice(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Serializes the value and writes it on a blocking thread, so handlers don't wait for the disk.
+    /// The value should be locked by the caller until it returns, so changes are saved in order.
+    pub fn save_in_background<T: Serialize>(&self, value: &T) {
+        let version = self.version.fetch_add(1, Ordering::Relaxed) + 1;
+        let content = match serde_json::to_vec(value) {
+            Ok(content) => content,
+            Err(err) => {
+                event!(Level::ERROR, %err, path = %self.path.display(), "Error serializing JSON file");
+
+                return;
+            }
+        };
+        let path = self.path.clone();
+        let saved_version = self.saved_version.clone();
+
+        spawn_blocking(move || {
+            let mut saved_version = saved_version.lock().unwrap();
+            if *saved_version >= version {
+                return;
+            }
+
+            match save(&path, &content) {
+                Ok(()) => *saved_version = version,
+                Err(err) => event!(Level::ERROR, %err, path = %path.display(), "Error saving JSON file"),
+            }
+        });
+    }
+}
+
+/// Writes the content to a temporary file and renames it, so a crash mid-write doesn't leave a broken file
+fn save(path: &Path, content: &[u8]) -> Result<(), io::Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = path.with_extension("json.tmp");
+
+    fs::write(&temp_path, content)?;
+    fs::rename(temp_path, path)
+}
@@ -0,0 +1,45 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+use tracing::{event, instrument, Level};
+
+/// Removes subdirectories of the work directory not modified for `stale_after`.
+/// Creates the work directory if it doesn't exist.
+/// # Returns
+/// Number of removed subdirectories
+#[instrument(skip_all, fields(path = ?path.as_ref()))]
+pub fn remove_stale_dirs(path: impl AsRef<Path>, stale_after: Duration) -> Result<usize, io::Error> {
+    let path = path.as_ref();
+
+    fs::create_dir_all(path)?;
+
+    let now = SystemTime::now();
+    let mut removed_count = 0;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+
+        if !metadata.is_dir() {
+            continue;
+        }
+
+        // Modification time in the future is treated as fresh
+        let is_stale = now.duration_since(metadata.modified()?).is_ok_and(|elapsed| elapsed > stale_after);
+
+        if !is_stale {
+            continue;
+        }
+
+        match fs::remove_dir_all(entry.path()) {
+            Ok(()) => removed_count += 1,
+            Err(err) => {
+                event!(Level::WARN, %err, path = ?entry.path(), "Error removing stale dir");
+            }
+        }
+    }
+
+    Ok(removed_count)
+}
@@ -1,7 +1,42 @@
+mod audio_button;
+mod auto_download;
+mod broadcast;
+mod cancel;
+mod chat_migration;
+mod default_media_type;
+mod description;
+mod domains;
 mod download;
+mod formats;
+mod playlist;
+mod purge;
+mod show_link;
+mod source_button;
 mod start;
+mod stats;
+mod transcribe;
+mod video_note;
+mod yt_dlp;
 
 pub use self::download::{
-    audio_download, media_download_chosen_inline_result, media_select_inline_query, video_download, video_download_quite,
+    audio_download, audio_download_quite, media_download_chosen_inline_result, media_select_inline_query, resume_jobs, video_download,
+    video_download_quite,
 };
+pub use audio_button::{audio_button, audio_button_callback};
+pub use auto_download::auto_download;
+pub use broadcast::broadcast;
+pub use cancel::cancel_download_callback;
+pub use chat_migration::chat_migration;
+pub use default_media_type::default_media_type;
+pub use description::description;
+pub use domains::{allow_domain, deny_domain};
+pub use formats::formats;
+pub use playlist::{playlist_select, playlist_select_callback};
+pub use purge::{purge_domain, purge_domain_callback};
+pub use show_link::show_link;
+pub use source_button::source_button;
 pub use start::start;
+pub use stats::stats;
+pub use transcribe::transcribe;
+pub use video_note::video_note_download;
+pub use yt_dlp::{yt_dlp_update, yt_dlp_version};
@@ -0,0 +1,257 @@
+use crate::{
+    audio_buttons,
+    chat_config::ChatConfigStore,
+    config::{Bot as BotConfig, ProcessLimits, Retries, WorkDir, YtDlp},
+    download,
+    events::{Event, EventBus, MediaKind},
+    handlers_utils::{
+        chat_action::{ActionKind, ChatAction, Stage},
+        error, input_file, send,
+        setting::{self, Setting},
+        topic,
+    },
+    locale::Locale,
+    metrics::DownloadInProgress,
+    models::{AudioConversion, AudioInFS},
+    queue::DownloadQueue,
+    rate_limiter::RateLimiter,
+    telemetry::spawn_blocking,
+};
+
+use std::sync::Arc;
+use telers::{
+    errors::HandlerError,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::{AnswerCallbackQuery, SendAudio},
+    types::{CallbackQuery, InputFile, Message, ReplyParameters},
+    Bot, Context, Extension,
+};
+use tempfile::tempdir_in;
+use tracing::{event, instrument, Level, Span};
+
+const GET_INFO_TIMEOUT: u64 = 45;
+const DOWNLOAD_MEDIA_TIMEOUT: u64 = 180;
+const SEND_AUDIO_TIMEOUT: f32 = 60.0;
+
+/// Turns on or off a button to get the audio under videos sent to the chat
+#[instrument(skip_all, fields(chat_id))]
+pub async fn audio_button(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    setting::toggle_setting(
+        &bot,
+        &message,
+        &bot_config,
+        &chat_config_store,
+        Setting::on_off(
+            |chat_config| chat_config.audio_button_enabled,
+            ChatConfigStore::set_audio_button_enabled,
+            Locale::audio_button_toggled,
+            Locale::audio_button_usage,
+        ),
+    )
+    .await
+}
+
+/// Downloads the audio of the video with the pressed button and replies to the video with it.
+/// The media info is usually cached since the video was sent, so only the audio is downloaded.
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn audio_button_callback(
+    bot: Arc<Bot>,
+    mut context: Context,
+    callback_query: CallbackQuery,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+) -> HandlerResult {
+    let token = context
+        .remove::<Box<str>>("audio_button_token")
+        .expect("Token should be in context because `get_audio_callback` filter should do this");
+    let Some(message) = callback_query.message.as_deref() else {
+        bot.send(AnswerCallbackQuery::new(callback_query.id)).await?;
+
+        return Ok(EventReturn::Finish);
+    };
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(message);
+    let locale = bot_config.user_locale(chat_id, callback_query.from.language_code.as_deref());
+
+    let Some(url) = audio_buttons::url(&token) else {
+        bot.send(
+            AnswerCallbackQuery::new(callback_query.id)
+                .text(locale.audio_button_unavailable())
+                .show_alert(true),
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    };
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    bot.send(AnswerCallbackQuery::new(callback_query.id).text(locale.audio_downloading()))
+        .await?;
+
+    let video = match download::media_info(&yt_dlp_config, &url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(mut videos) => match videos.next() {
+            Some(video) => video,
+            None => {
+                event!(Level::WARN, "Video not found");
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_not_found(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        },
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting audio info error");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                error::ytdl_text(&err, locale, locale.audio_info_error()),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+    };
+
+    let video_url = video.original_url.clone().into_boxed_str();
+    let title = video.title.clone();
+    let performer = video.performer().map(ToOwned::to_owned);
+    let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+    let download_timeout = download::download_timeout(&video, DOWNLOAD_MEDIA_TIMEOUT, yt_dlp_config.max_download_timeout);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let duration = video.duration.map(|duration| duration as i64);
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Voice, Stage::Download);
+
+    event_bus.publish(Event::DownloadStarted {
+        chat_id: Some(chat_id),
+        url: video_url.clone(),
+        media_kind: MediaKind::Audio,
+    });
+
+    let _in_progress = DownloadInProgress::start();
+
+    let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+        chat_action.stop();
+
+        HandlerError::new(err)
+    })?;
+
+    let permit = download_queue
+        .acquire(video.estimated_audio_filesize(yt_dlp_config.max_file_size), Some(chat_id))
+        .await;
+
+    let result = spawn_blocking({
+        let temp_dir_path = temp_dir.path().to_owned();
+        let max_file_size = yt_dlp_config.max_file_size;
+        let normalize_target_lufs = yt_dlp_config.normalize_target_lufs;
+        let yt_dlp_full_path = yt_dlp_config.full_path.clone();
+        let id_or_url = video.original_url.clone();
+
+        move || {
+            download::audio_to_temp_dir(
+                video,
+                id_or_url,
+                max_file_size,
+                yt_dlp_full_path,
+                &extra_args,
+                &retries,
+                temp_dir_path,
+                download_timeout,
+                None,
+                AudioConversion::default(),
+                normalize_target_lufs,
+                process_limits,
+            )
+        }
+    })
+    .await;
+
+    drop(permit);
+
+    let AudioInFS { path, thumbnail_path } = match result {
+        Ok(Ok(audio)) => audio,
+        Ok(Err(err)) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while downloading audio");
+
+            event_bus.publish(Event::DownloadFailed {
+                chat_id: Some(chat_id),
+                url: video_url,
+                media_kind: MediaKind::Audio,
+                error: err.to_string().into_boxed_str(),
+            });
+
+            error::download_audios_in_message(&bot, locale, 1, chat_id, thread_id, message_id, None).await?;
+
+            return Ok(EventReturn::Finish);
+        }
+        Err(err) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while joining handle");
+
+            return Err(HandlerError::new(err));
+        }
+    };
+
+    event_bus.publish(Event::DownloadFinished {
+        chat_id: Some(chat_id),
+        url: video_url,
+        media_kind: MediaKind::Audio,
+    });
+
+    chat_action.set_stage(Stage::Upload);
+
+    let file_size = input_file::file_size(&path);
+    let result = send::upload_with_retries(
+        &bot,
+        &rate_limiter,
+        SendAudio::new(chat_id, input_file::from_work_dir(&work_dir, path))
+            .title_option(title)
+            .performer_option(performer)
+            .duration_option(duration)
+            .thumbnail_option(thumbnail_path.map(InputFile::fs))
+            .message_thread_id_option(thread_id)
+            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        file_size,
+        &retries.telegram_send,
+        SEND_AUDIO_TIMEOUT,
+    )
+    .await;
+
+    chat_action.stop();
+
+    if let Err(err) = result {
+        event_bus.publish(Event::SendFailed {
+            chat_id: Some(chat_id),
+            media_kind: MediaKind::Audio,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        return Err(err.into());
+    }
+
+    Ok(EventReturn::Finish)
+}
@@ -0,0 +1,32 @@
+use crate::{
+    chat_config::ChatConfigStore,
+    config::Bot as BotConfig,
+    handlers_utils::setting::{self, Setting},
+    locale::Locale,
+};
+
+use telers::{event::telegram::HandlerResult, types::Message, Bot, Extension};
+use tracing::instrument;
+
+/// Turns on or off downloading links in messages without commands in the chat, `/vd` and `/ad` work regardless of it
+#[instrument(skip_all, fields(chat_id))]
+pub async fn auto_download(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    setting::toggle_setting(
+        &bot,
+        &message,
+        &bot_config,
+        &chat_config_store,
+        Setting::on_off(
+            |chat_config| chat_config.auto_download_enabled,
+            ChatConfigStore::set_auto_download_enabled,
+            Locale::auto_download_toggled,
+            Locale::auto_download_usage,
+        ),
+    )
+    .await
+}
@@ -0,0 +1,115 @@
+use crate::{
+    config::{Bot as BotConfig, Retries},
+    handlers_utils::{send, topic},
+    known_chats::KnownChatsStore,
+    rate_limiter::RateLimiter,
+};
+
+use telers::{
+    errors::{SessionErrorKind, TelegramErrorKind},
+    event::{telegram::HandlerResult, EventReturn},
+    methods::{CopyMessage, EditMessageText, SendMessage},
+    types::{Message, ReplyParameters},
+    Bot, Extension,
+};
+use tracing::{event, instrument, Level, Span};
+
+/// The progress is edited once per this number of chats, so large broadcasts don't hit the edit limit
+const PROGRESS_EDIT_INTERVAL: usize = 25;
+
+/// Whether the message can't be sent to the chat anymore, because the bot was blocked or kicked, or the chat was deleted
+fn is_chat_dead(err: &SessionErrorKind) -> bool {
+    match err {
+        SessionErrorKind::Telegram(TelegramErrorKind::Forbidden { .. }) => true,
+        SessionErrorKind::Telegram(TelegramErrorKind::BadRequest { message }) => message.contains("chat not found"),
+        _ => false,
+    }
+}
+
+/// Copies the replied message to all known chats and edits a reply with the progress.
+/// Chats that blocked or removed the bot are soft-deleted, so next broadcasts skip them.
+#[instrument(skip_all, fields(total))]
+pub async fn broadcast(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(retries): Extension<Retries>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(known_chats_store): Extension<KnownChatsStore>,
+) -> HandlerResult {
+    let locale = bot_config.locale;
+    let chat_id = message.chat().id();
+    let reply_parameters = ReplyParameters::new(message.id()).allow_sending_without_reply(true);
+
+    let Some(broadcast_message) = message.reply_to_message() else {
+        bot.send(
+            SendMessage::new(chat_id, locale.broadcast_usage())
+                .message_thread_id_option(topic::thread_id(&message))
+                .reply_parameters(reply_parameters),
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    };
+
+    let chat_ids = known_chats_store.active();
+    let total = chat_ids.len();
+    let (mut sent, mut failed, mut removed) = (0, 0, 0);
+
+    Span::current().record("total", total);
+
+    event!(Level::INFO, "Broadcast started");
+
+    let progress_message = bot
+        .send(
+            SendMessage::new(chat_id, locale.broadcast_progress(sent, failed, removed, total))
+                .message_thread_id_option(topic::thread_id(&message))
+                .reply_parameters(reply_parameters),
+        )
+        .await?;
+
+    for (index, receiver_chat_id) in chat_ids.into_iter().enumerate() {
+        match send::with_retries(
+            &bot,
+            &rate_limiter,
+            CopyMessage::new(receiver_chat_id, chat_id, broadcast_message.id()),
+            &retries.telegram_send,
+            None,
+        )
+        .await
+        {
+            Ok(_) => sent += 1,
+            Err(err) => {
+                failed += 1;
+
+                if is_chat_dead(&err) {
+                    removed += 1;
+                    known_chats_store.mark_dead(receiver_chat_id);
+
+                    event!(Level::INFO, %err, receiver_chat_id, "Chat removed from known chats");
+                } else {
+                    event!(Level::WARN, %err, receiver_chat_id, "Error broadcasting to the chat");
+                }
+            }
+        }
+
+        if (index + 1) % PROGRESS_EDIT_INTERVAL != 0 && index + 1 != total {
+            continue;
+        }
+
+        if let Err(err) = bot
+            .send(
+                EditMessageText::new(locale.broadcast_progress(sent, failed, removed, total))
+                    .chat_id(chat_id)
+                    .message_id(progress_message.id()),
+            )
+            .await
+        {
+            event!(Level::WARN, %err, "Error editing broadcast progress");
+        }
+    }
+
+    event!(Level::INFO, sent, failed, removed, "Broadcast finished");
+
+    Ok(EventReturn::Finish)
+}
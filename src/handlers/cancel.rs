@@ -0,0 +1,43 @@
+use crate::{cancellation::CancelStore, config::Bot as BotConfig};
+
+use telers::{
+    event::{telegram::HandlerResult, EventReturn},
+    methods::AnswerCallbackQuery,
+    types::CallbackQuery,
+    Bot, Context, Extension,
+};
+use tracing::{event, instrument, Level, Span};
+
+/// Cancels the download with the pressed button, its processes are killed and the message is edited by the download handler
+#[instrument(skip_all, fields(key))]
+pub async fn cancel_download_callback(
+    bot: Bot,
+    mut context: Context,
+    callback_query: CallbackQuery,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(cancel_store): Extension<CancelStore>,
+) -> HandlerResult {
+    let key = context
+        .remove::<Box<str>>("cancel_key")
+        .expect("Key should be in context because `download_cancellation_callback` filter should do this");
+    let user_id = callback_query.from.id;
+    let locale = bot_config.user_locale(user_id, callback_query.from.language_code.as_deref());
+
+    Span::current().record("key", &*key);
+
+    if cancel_store.cancel(&key, user_id) {
+        event!(Level::INFO, "Download cancelled");
+
+        bot.send(AnswerCallbackQuery::new(callback_query.id).text(locale.download_cancelled()))
+            .await?;
+    } else {
+        bot.send(
+            AnswerCallbackQuery::new(callback_query.id)
+                .text(locale.download_cancel_unavailable())
+                .show_alert(true),
+        )
+        .await?;
+    }
+
+    Ok(EventReturn::Finish)
+}
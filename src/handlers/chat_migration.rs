@@ -0,0 +1,35 @@
+use crate::{chat_config::ChatConfigStore, known_chats::KnownChatsStore, stats::StatsStore};
+
+use telers::{
+    event::{telegram::HandlerResult, EventReturn},
+    types::Message,
+    Extension,
+};
+use tracing::{event, instrument, Level, Span};
+
+/// Moves settings, download counters and the known chat of a group upgraded to a supergroup to its new chat ID,
+/// because Telegram gives the supergroup another ID and the old one stops receiving messages
+#[instrument(skip_all, fields(from_chat_id, to_chat_id))]
+pub async fn chat_migration(
+    message: Message,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+    Extension(stats_store): Extension<StatsStore>,
+    Extension(known_chats_store): Extension<KnownChatsStore>,
+) -> HandlerResult {
+    let from_chat_id = message.chat().id();
+    let Some(to_chat_id) = message.migrate_to_chat_id() else {
+        return Ok(EventReturn::Finish);
+    };
+
+    Span::current()
+        .record("from_chat_id", from_chat_id)
+        .record("to_chat_id", to_chat_id);
+
+    chat_config_store.migrate(from_chat_id, to_chat_id);
+    stats_store.migrate(from_chat_id, to_chat_id);
+    known_chats_store.migrate(from_chat_id, to_chat_id);
+
+    event!(Level::INFO, "Chat migrated to a supergroup");
+
+    Ok(EventReturn::Finish)
+}
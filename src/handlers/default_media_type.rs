@@ -0,0 +1,33 @@
+use crate::{
+    chat_config::{ChatConfigStore, MediaType},
+    config::Bot as BotConfig,
+    handlers_utils::setting::{self, Setting},
+    locale::Locale,
+};
+
+use telers::{event::telegram::HandlerResult, types::Message, Bot, Extension};
+use tracing::instrument;
+
+/// Sets the media downloaded from links in messages without commands in the chat, `/vd` and `/ad` work regardless of it
+#[instrument(skip_all, fields(chat_id))]
+pub async fn default_media_type(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    setting::toggle_setting(
+        &bot,
+        &message,
+        &bot_config,
+        &chat_config_store,
+        Setting {
+            parse: MediaType::parse,
+            get: |chat_config| chat_config.default_media_type,
+            set: ChatConfigStore::set_default_media_type,
+            changed_text: Locale::default_media_type_set,
+            usage_text: Locale::default_media_type_usage,
+        },
+    )
+    .await
+}
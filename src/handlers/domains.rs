@@ -0,0 +1,117 @@
+use crate::{
+    chat_config::ChatConfigStore,
+    config::Bot as BotConfig,
+    handlers_utils::{locale, topic},
+    locale::Locale,
+};
+
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendMessage,
+    types::{Message, ReplyParameters},
+    utils::text::{html_code, html_quote},
+    Bot, Extension,
+};
+use tracing::{event, instrument, Level, Span};
+use url::Url;
+
+/// Host of the domain or URL after the command without the `www.` prefix, so `www.youtube.com` also includes `youtube.com`
+fn domain_from_message(message: &Message) -> Option<Box<str>> {
+    let value = message.text()?.split_whitespace().nth(1)?;
+    let url = Url::parse(value)
+        .ok()
+        .filter(Url::has_host)
+        .or_else(|| Url::parse(&format!("https://{value}")).ok())?;
+    let host = url.host_str()?;
+
+    Some(host.strip_prefix("www.").unwrap_or(host).into())
+}
+
+fn domains_text(domains: &[Box<str>]) -> String {
+    domains
+        .iter()
+        .map(|domain| html_code(html_quote(domain)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn usage_text(locale: Locale, chat_config_store: &ChatConfigStore, chat_id: i64) -> String {
+    locale.include_domains_usage(&domains_text(&chat_config_store.get(chat_id).include_domains))
+}
+
+async fn reply(bot: &Bot, message: &Message, text: String) -> HandlerResult {
+    bot.send(
+        SendMessage::new(message.chat().id(), text)
+            .message_thread_id_option(topic::thread_id(message))
+            .parse_mode(ParseMode::HTML)
+            .reply_parameters(ReplyParameters::new(message.id()).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
+
+/// Adds a domain to the ones downloaded without commands in the chat, links of other domains then need a command
+#[instrument(skip_all, fields(chat_id, domain))]
+pub async fn allow_domain(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    let chat_id = message.chat().id();
+    let locale = locale::from_message(&bot_config, &message);
+
+    Span::current().record("chat_id", chat_id);
+
+    let Some(domain) = domain_from_message(&message) else {
+        return reply(&bot, &message, usage_text(locale, &chat_config_store, chat_id)).await;
+    };
+
+    Span::current().record("domain", &*domain);
+
+    let text = if chat_config_store.include_domain(chat_id, &domain) {
+        event!(Level::INFO, "Domain included");
+
+        locale.domain_included(&html_code(html_quote(&domain)))
+    } else {
+        locale.domain_already_included(&html_code(html_quote(&domain)))
+    };
+
+    reply(&bot, &message, text).await
+}
+
+/// Removes a domain from the ones downloaded without commands in the chat.
+/// Links of all domains are downloaded again when the last one is removed.
+#[instrument(skip_all, fields(chat_id, domain))]
+pub async fn deny_domain(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    let chat_id = message.chat().id();
+    let locale = locale::from_message(&bot_config, &message);
+
+    Span::current().record("chat_id", chat_id);
+
+    let Some(domain) = domain_from_message(&message) else {
+        return reply(&bot, &message, usage_text(locale, &chat_config_store, chat_id)).await;
+    };
+
+    Span::current().record("domain", &*domain);
+
+    let text = if chat_config_store.exclude_domain(chat_id, &domain) {
+        event!(Level::INFO, "Domain excluded");
+
+        locale.domain_excluded(
+            &html_code(html_quote(&domain)),
+            &domains_text(&chat_config_store.get(chat_id).include_domains),
+        )
+    } else {
+        locale.domain_not_included(&html_code(html_quote(&domain)))
+    };
+
+    reply(&bot, &message, text).await
+}
@@ -1,38 +1,83 @@
+use super::playlist::format_duration;
 use crate::{
-    cmd::get_media_or_playlist_info,
-    config::{Bot as BotConfig, YtDlp},
+    audio_buttons,
+    cancellation::CancelStore,
+    chat_config::{ChatConfig, ChatConfigStore},
+    cmd::ytdl::Error as YtdlError,
+    config::{Bot as BotConfig, ProcessLimits, Retries, RetryPolicy, Summary as SummaryConfig, Transcode, WorkDir, YtDlp},
     download::{self, StreamErrorKind, ToTempDirErrorKind},
+    events::{Event, EventBus, MediaKind, PlaylistReport},
+    fs,
     handlers_utils::{
-        chat_action::{upload_video_action_in_loop, upload_voice_action_in_loop},
-        error, send,
+        caption::Caption,
+        chat_action::{ActionKind, ChatAction, Stage},
+        delivery::Delivery,
+        error,
+        inline_progress::InlineProgress,
+        input_file, locale, send, targets, thumbnail, topic,
+        upload_progress::UploadProgress,
     },
-    models::{AudioInFS, TgAudioInPlaylist, TgVideoInPlaylist, VideoInFS},
+    jobs::{Job, JobStore, RunningJob},
+    links::LinkStore,
+    locale::Locale,
+    metrics::DownloadInProgress,
+    models::{
+        AudioConversion, AudioInFS, DownloadOptions, PlaylistEntry, TgAudioInPlaylist, TgVideoInPlaylist, VideoInFS, VideoInYT, VideosInYT,
+    },
+    queue::DownloadQueue,
+    rate_limiter::RateLimiter,
+    sponsorblock, summary,
+    telemetry::spawn_blocking,
 };
 
-use std::sync::Arc;
+use futures_util::future::join_all;
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use telers::{
     enums::ParseMode,
     errors::{HandlerError, SessionErrorKind},
     event::{telegram::HandlerResult, EventReturn},
-    methods::{AnswerInlineQuery, DeleteMessage, EditMessageMedia, SendAudio, SendVideo},
+    methods::{
+        AnswerInlineQuery, DeleteMessage, EditMessageMedia, EditMessageText, SendAnimation, SendAudio, SendDocument, SendMessage, SendVideo,
+    },
     types::{
-        ChosenInlineResult, InlineKeyboardButton, InlineKeyboardMarkup, InlineQuery, InlineQueryResult, InlineQueryResultArticle,
-        InputFile, InputMediaVideo, InputTextMessageContent, Message,
+        ChatIdKind, ChosenInlineResult, InlineKeyboardButton, InlineKeyboardMarkup, InlineQuery, InlineQueryResult,
+        InlineQueryResultArticle, InputFile, InputMedia, InputMediaAudio, InputMediaDocument, InputMediaVideo, InputTextMessageContent,
+        Message, ReplyParameters,
     },
     utils::text::{html_code, html_quote},
     Bot, Context, Extension,
 };
-use tempfile::tempdir;
-use tokio::task::{spawn_blocking, JoinError, JoinHandle};
+use tempfile::{tempdir_in, TempDir};
+use tokio::{
+    task::{JoinError, JoinHandle},
+    time::{timeout, timeout_at, Instant},
+};
 use tracing::{event, instrument, Level, Span};
+use url::Url;
 use uuid::Uuid;
 
 const GET_INFO_TIMEOUT: u64 = 45;
+/// Time to get info for all URLs of a message, including retries
+const GET_INFO_BUDGET: Duration = Duration::from_secs(90);
 const DOWNLOAD_MEDIA_TIMEOUT: u64 = 180;
 const SEND_VIDEO_TIMEOUT: f32 = 60.0;
 const SEND_AUDIO_TIMEOUT: f32 = 60.0;
 const GET_MEDIA_OR_PLAYLIST_INFO_INLINE_QUERY_TIMEOUT: u64 = 12;
 const SELECT_INLINE_QUERY_CACHE_TIME: i64 = 86400; // 24 hours
+/// Telegram accepts up to 50 results per answer, and each video has a video and an audio result
+const SELECT_INLINE_QUERY_PAGE_SIZE: usize = 25;
+/// Time to retry a failed thumbnail download before sending the video without it
+const THUMBNAIL_RETRY_TIMEOUT: Duration = Duration::from_secs(10);
+const THUMBNAIL_RETRY_MAX_URLS: usize = 3;
+/// Silent videos up to this duration are sent as animations, longer ones are likely real videos without sound
+const MAX_ANIMATION_DURATION: f64 = 60.0;
+/// Headers of a ZIP entry without its name, they're counted to keep archive parts under the size limit
+const ARCHIVE_ENTRY_OVERHEAD: u64 = 128;
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(thiserror::Error, Debug)]
@@ -45,204 +90,1408 @@ pub enum DownloadErrorKind {
     Session(#[from] SessionErrorKind),
     #[error(transparent)]
     Join(#[from] JoinError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
 }
 
-#[instrument(skip_all, fields(message_id, chat_id, url))]
-pub async fn video_download(
-    bot: Arc<Bot>,
-    mut context: Context,
-    message: Message,
-    Extension(yt_dlp_config): Extension<YtDlp>,
-    Extension(bot_config): Extension<BotConfig>,
-) -> HandlerResult {
-    let url = context
-        .remove::<Box<str>>("video_url")
-        .expect("Url should be in context because `text_contains_url` filter should do this");
-    let message_id = message.id();
-    let chat_id = message.chat().id();
+/// Media uploaded to Telegram or served by a download link, if it exceeds the Telegram limits
+enum Uploaded {
+    File {
+        file_id: Box<str>,
+        caption: Caption,
+    },
+    Animation(Box<str>),
+    /// File IDs of the chapters with their captions
+    Chapters(Vec<(Box<str>, Caption)>),
+    Link(String),
+    /// Media uploaded right to the chat with its final caption, so it isn't sent to the chat again
+    Sent {
+        file_id: Box<str>,
+        caption: Option<String>,
+    },
+    /// Media kept on disk to be sent in an archive, the temp dir is removed when it's dropped
+    Local {
+        name: String,
+        path: PathBuf,
+        temp_dir: TempDir,
+    },
+}
 
-    Span::current()
-        .record("chat_id", chat_id)
-        .record("message_id", message_id)
-        .record("url", &*url);
+fn chapter_caption(locale: Locale, index: usize, title: Option<&str>) -> Caption {
+    Caption::new().title(match title {
+        Some(title) => format!("{}. {title}", index + 1),
+        None => locale.chapter(index + 1),
+    })
+}
 
-    event!(Level::DEBUG, "Got url");
+/// Header of a batch of videos with their playlist, uploader, count and total duration.
+/// The uploader is shown only if all videos have the same one.
+fn digest_header(locale: Locale, videos: &VideosInYT) -> String {
+    let mut lines = vec![];
 
-    let videos = match spawn_blocking({
-        let full_path = yt_dlp_config.full_path.clone();
-        let url = url.clone();
+    if let Some(playlist_title) = videos.iter().find_map(|video| video.playlist_title.as_deref()) {
+        lines.push(format!("<b>{}</b>", html_quote(playlist_title)));
+    }
 
-        move || get_media_or_playlist_info(full_path, url, true, GET_INFO_TIMEOUT)
-    })
+    if let Some(uploader) = videos
+        .front()
+        .and_then(|video| video.uploader.as_deref())
+        .filter(|uploader| videos.iter().all(|video| video.uploader.as_deref() == Some(uploader)))
+    {
+        lines.push(format!("{}: {}", locale.uploader(), html_quote(uploader)));
+    }
+
+    lines.push(locale.videos_count(videos.len()));
+
+    let durations: Vec<f64> = videos.iter().filter_map(|video| video.duration).collect();
+    if !durations.is_empty() {
+        lines.push(format!(
+            "{}: {}",
+            locale.total_duration(),
+            format_duration(durations.into_iter().sum())
+        ));
+    }
+
+    lines.join("\n")
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn playlist_summary(locale: Locale, report: &PlaylistReport, elapsed: Duration) -> String {
+    let mut lines = vec![
+        format!("<b>{}</b>", locale.playlist_summary()),
+        locale.succeeded_count(report.succeeded),
+    ];
+
+    if !report.failed.is_empty() {
+        lines.push(locale.failed_count(report.failed.len()));
+        lines.extend(
+            report
+                .failed
+                .iter()
+                .map(|(index, title)| format!("{}. {}", index + 1, html_quote(title.as_deref().unwrap_or(locale.untitled())))),
+        );
+    }
+
+    if report.total_size > 0 {
+        lines.push(format!(
+            "{}: {:.2} MB",
+            locale.total_size(),
+            report.total_size as f64 / 1024.0 / 1024.0
+        ));
+    }
+
+    lines.push(format!("{}: {}", locale.elapsed_time(), format_duration(elapsed.as_secs_f64())));
+
+    lines.join("\n")
+}
+
+fn download_link_text(locale: Locale, title: Option<&str>, link: &str, retention_in_secs: u64) -> String {
+    locale.download_link(
+        title.map(|title| format!("<b>{}</b>", html_quote(title))).as_deref(),
+        &html_quote(link),
+        retention_in_secs / 60,
+    )
+}
+
+/// Buttons under a video turned on in the chat: to the source and to get the audio of the source.
+/// Returns `None` if both are turned off.
+fn video_buttons(locale: Locale, chat_config: &ChatConfig, source_url: &str) -> Option<InlineKeyboardMarkup> {
+    let mut buttons = vec![];
+
+    if chat_config.source_button_enabled {
+        buttons.push(InlineKeyboardButton::new(locale.source()).url(source_url));
+    }
+    if chat_config.audio_button_enabled {
+        buttons.push(InlineKeyboardButton::new(locale.get_audio()).callback_data(audio_buttons::callback_data(source_url)));
+    }
+
+    (!buttons.is_empty()).then(|| InlineKeyboardMarkup::new([buttons]))
+}
+
+/// Sends videos one by one with buttons, because media groups can't have buttons, see [`video_buttons`]
+#[allow(clippy::too_many_arguments)]
+async fn send_with_buttons(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    locale: Locale,
+    chat_config: &ChatConfig,
+    chat_id: i64,
+    thread_id: Option<i64>,
+    message_id: i64,
+    videos: &[TgVideoInPlaylist],
+    retry_policy: &RetryPolicy,
+) -> Result<(), SessionErrorKind> {
+    for video in videos {
+        let reply_markup = video
+            .source_url
+            .as_deref()
+            .and_then(|source_url| video_buttons(locale, chat_config, source_url));
+
+        send::with_retries(
+            bot,
+            rate_limiter,
+            SendVideo::new(chat_id, InputFile::id(video.file_id.as_ref()))
+                .caption_option(video.caption.as_deref())
+                .parse_mode(ParseMode::HTML)
+                .message_thread_id_option(thread_id)
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true))
+                .reply_markup_option(reply_markup),
+            retry_policy,
+            Some(SEND_VIDEO_TIMEOUT),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Returns the estimated total size of the media if it exceeds the budget.
+/// Media of unknown size isn't counted, so the estimate is a lower bound.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn exceeded_total_size(videos: &VideosInYT, max_total_size: Option<u64>, estimate: impl Fn(&VideoInYT) -> Option<f64>) -> Option<u64> {
+    let max_total_size = max_total_size?;
+    let total_size = videos.iter().filter_map(estimate).sum::<f64>().round() as u64;
+
+    (total_size > max_total_size).then_some(total_size)
+}
+
+fn total_size_exceeded_text(locale: Locale, total_size: u64, max_total_size: u64) -> String {
+    locale.playlist_too_large(total_size / 1_000_000, max_total_size / 1_000_000)
+}
+
+/// Retries the thumbnail download once at send time if it failed during the download, so fewer videos are sent without a preview.
+/// The wait is bounded, so it doesn't delay the video much.
+async fn thumbnail_or_retry(
+    thumbnail_path: Option<PathBuf>,
+    mut thumbnail_urls: Vec<String>,
+    temp_dir_path: PathBuf,
+    process_limits: ProcessLimits,
+) -> Option<PathBuf> {
+    if thumbnail_path.is_some() || thumbnail_urls.is_empty() {
+        return thumbnail_path;
+    }
+
+    event!(Level::DEBUG, "Thumbnail is missing, retry its download");
+
+    thumbnail_urls.truncate(THUMBNAIL_RETRY_MAX_URLS);
+
+    match timeout(
+        THUMBNAIL_RETRY_TIMEOUT,
+        spawn_blocking(move || download::thumbnail_from_urls(&thumbnail_urls, temp_dir_path, process_limits)),
+    )
     .await
-    .map_err(|err| {
-        event!(Level::ERROR, %err, "Error while getting video/playlist info");
+    {
+        Ok(Ok(thumbnail_path)) => thumbnail_path,
+        Ok(Err(err)) => {
+            event!(Level::WARN, %err, "Error while joining handle");
 
-        HandlerError::new(err)
-    })? {
-        Ok(videos) => videos,
-        Err(err) => {
-            event!(Level::ERROR, %err, "Getting video/playlist info error");
+            None
+        }
+        Err(_) => {
+            event!(Level::WARN, "Thumbnail download retry timed out");
 
-            error::occured_in_message(
-                &bot,
-                chat_id,
-                message_id,
-                "Sorry, an error occurred while getting video/playlist info. Try again later.",
+            None
+        }
+    }
+}
+
+/// Sends animations one by one, they can't be in media groups with videos
+async fn send_animations(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    chat_id: &ChatIdKind,
+    file_ids: &[Box<str>],
+    retry_policy: &RetryPolicy,
+) -> Result<(), SessionErrorKind> {
+    for file_id in file_ids {
+        send::with_retries(
+            bot,
+            rate_limiter,
+            SendAnimation::new(chat_id.clone(), InputFile::id(file_id.as_ref())),
+            retry_policy,
+            Some(SEND_VIDEO_TIMEOUT),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Reposts downloaded media to the mirror chats of the chat.
+/// Errors are only logged, because the media is already sent to the chat.
+async fn send_to_mirrors<'a, T>(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    mirror_chat_ids: &[i64],
+    input_media_list: Vec<T>,
+    animations: &[Box<str>],
+    retry_policy: &RetryPolicy,
+) where
+    T: Into<InputMedia<'a>> + Clone,
+{
+    for &mirror_chat_id in mirror_chat_ids {
+        if !input_media_list.is_empty() {
+            if let Err(err) = send::media_groups(
+                bot,
+                rate_limiter,
+                mirror_chat_id,
+                None,
+                input_media_list.clone(),
                 None,
+                retry_policy,
+                Some(SEND_AUDIO_TIMEOUT),
             )
-            .await?;
+            .await
+            {
+                event!(Level::ERROR, %err, mirror_chat_id, "Error sending media to the mirror chat");
+            }
+        }
 
-            return Ok(EventReturn::Finish);
+        if let Err(err) = send_animations(bot, rate_limiter, &ChatIdKind::id(mirror_chat_id), animations, retry_policy).await {
+            event!(Level::ERROR, %err, mirror_chat_id, "Error sending animations to the mirror chat");
         }
-    };
+    }
+}
 
-    let videos_len = videos.len();
+/// Sends downloaded media to the chats of the `to=` parameter.
+/// Unlike mirrors, the chats are set by the user, so failed ones are reported to them.
+async fn send_to_targets<'a, T>(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    locale: Locale,
+    chat_id: i64,
+    thread_id: Option<i64>,
+    message_id: i64,
+    targets: &[ChatIdKind],
+    header: Option<&str>,
+    input_media_list: Vec<T>,
+    animations: &[Box<str>],
+    retry_policy: &RetryPolicy,
+) -> Result<(), SessionErrorKind>
+where
+    T: Into<InputMedia<'a>> + Clone,
+{
+    if input_media_list.is_empty() && animations.is_empty() {
+        return Ok(());
+    }
+
+    let mut failed_targets = vec![];
+
+    for target in targets {
+        if let Some(header) = header {
+            if let Err(err) = bot.send(SendMessage::new(target.clone(), header).parse_mode(ParseMode::HTML)).await {
+                event!(Level::ERROR, %err, %target, "Error sending header to the target chat");
+            }
+        }
+
+        let result = async {
+            if !input_media_list.is_empty() {
+                send::media_groups(
+                    bot,
+                    rate_limiter,
+                    target.clone(),
+                    None,
+                    input_media_list.clone(),
+                    None,
+                    retry_policy,
+                    Some(SEND_AUDIO_TIMEOUT),
+                )
+                .await?;
+            }
+
+            send_animations(bot, rate_limiter, target, animations, retry_policy).await
+        }
+        .await;
+
+        if let Err(err) = result {
+            event!(Level::ERROR, %err, %target, "Error sending media to the target chat");
+
+            failed_targets.push(format!(
+                "{}: {}",
+                html_code(html_quote(target.to_string())),
+                html_quote(err.to_string())
+            ));
+        }
+    }
+
+    if !failed_targets.is_empty() {
+        error::occured_in_message(
+            bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &locale.failed_targets(&failed_targets.join("\n")),
+            Some(ParseMode::HTML),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Removes ongoing live streams from the videos and tells about them, they can't be downloaded until they end.
+/// Returns `false` if no videos are left.
+async fn skip_ongoing_lives(
+    bot: &Bot,
+    chat_id: i64,
+    thread_id: Option<i64>,
+    message_id: i64,
+    locale: Locale,
+    videos: &mut VideosInYT,
+) -> Result<bool, SessionErrorKind> {
+    let lives_count = videos.remove_ongoing_lives();
+
+    if lives_count == 0 {
+        return Ok(true);
+    }
+
+    event!(Level::WARN, lives_count, "Ongoing live streams are skipped");
+
+    if videos.is_empty() {
+        error::occured_in_message(bot, chat_id, thread_id, message_id, locale.live_stream_not_supported(), None).await?;
+
+        return Ok(false);
+    }
+
+    error::occured_in_message(bot, chat_id, thread_id, message_id, &locale.live_streams_skipped(lives_count), None).await?;
+
+    Ok(true)
+}
+
+/// Deletes the message with the link after its media is sent, if the chat keeps only the media.
+/// Bots can't edit messages of users to remove the link preview instead, so the message is kept if the bot can't delete it.
+async fn delete_original_message(bot: &Bot, bot_config: &BotConfig, chat_id: i64, message_id: i64) {
+    if !bot_config.is_clean_chat(chat_id) {
+        return;
+    }
+
+    if let Err(err) = bot.send(DeleteMessage::new(chat_id, message_id)).await {
+        event!(Level::WARN, %err, "Error deleting the original message, the bot may not have the right to delete messages");
+    }
+}
+
+/// Downloads the videos and sends them to the chat in one media group as a reply to the message.
+/// Videos exceeding the Telegram limits are sent as download links if links are enabled.
+#[allow(clippy::too_many_arguments, clippy::too_many_lines)]
+pub async fn download_and_send_videos(
+    bot: Arc<Bot>,
+    rate_limiter: &RateLimiter,
+    chat_id: i64,
+    thread_id: Option<i64>,
+    message_id: i64,
+    locale: Locale,
+    videos: VideosInYT,
+    chat_action: ChatAction,
+    yt_dlp_config: &YtDlp,
+    retries: Retries,
+    bot_config: &BotConfig,
+    event_bus: &EventBus,
+    work_dir: &WorkDir,
+    link_store: &LinkStore,
+    download_queue: &DownloadQueue,
+    summary_config: &SummaryConfig,
+    custom_thumbnail_url: Option<String>,
+    target_chats: &[ChatIdKind],
+    chat_config: ChatConfig,
+    options: &DownloadOptions,
+    transcode: Transcode,
+    process_limits: ProcessLimits,
+    mut job: RunningJob,
+) -> HandlerResult {
+    let started_at = Instant::now();
+    // Videos of a resumed job include the ones uploaded before the restart
+    let videos_len = job.job().urls.len();
 
     if videos_len == 0 {
         event!(Level::WARN, "Playlist doesn't have videos");
 
-        error::occured_in_message(&bot, chat_id, message_id, "Playlist doesn't have videos.", None).await?;
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.playlist_without_videos(), None).await?;
 
         return Ok(EventReturn::Finish);
     }
 
     event!(Level::DEBUG, videos_len, "Got video/playlist info");
 
-    let upload_action_task = tokio::spawn({
-        let bot = bot.clone();
+    // A single video doesn't need a header, its caption has all info
+    let header = (options.with_header && videos_len > 1).then(|| digest_header(locale, &videos));
 
-        async move { upload_video_action_in_loop(&bot, chat_id).await }
-    });
+    chat_action.set_stage(Stage::Download);
 
-    let mut handles: Vec<JoinHandle<Result<_, DownloadErrorKind>>> = Vec::with_capacity(videos_len);
+    let mut handles: Vec<(Box<str>, Option<String>, JoinHandle<Result<_, DownloadErrorKind>>)> = Vec::with_capacity(videos_len);
+    let summary_enabled = summary_config.is_enabled_for(chat_id);
+    // Animations and chapters aren't sent as a single video, so they're always staged
+    let staged = Delivery::staged(bot_config);
+    let delivery = Delivery::new(bot_config, chat_id, thread_id, message_id, videos_len == 1);
+    let sponsorblock_categories = options.sponsorblock_categories(yt_dlp_config);
 
     for video in videos {
         let bot = bot.clone();
+        let chat_action = chat_action.clone();
+        let work_dir = work_dir.clone();
         let max_file_size = yt_dlp_config.max_file_size;
+        let fps_weight = yt_dlp_config.fps_weight;
+        let max_format_attempts = yt_dlp_config.max_format_attempts;
         let yt_dlp_full_path = yt_dlp_config.full_path.clone();
-        let receiver_video_chat_id = bot_config.receiver_video_chat_id;
+        let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+        let link_store = link_store.clone();
+        let download_queue = download_queue.clone();
+        let rate_limiter = rate_limiter.clone();
+        let estimated_size = video.estimated_video_filesize(max_file_size, fps_weight);
+        let download_timeout = download::download_timeout(&video, DOWNLOAD_MEDIA_TIMEOUT, yt_dlp_config.max_download_timeout);
+        let title = video.title.clone();
+        // A directly uploaded video isn't sent again, so it gets the source URL and button in the task
+        let visible_source_url = chat_config.link_is_visible.then(|| video.original_url.clone());
+        let description = chat_config.description_enabled.then(|| video.description.clone()).flatten();
+        let reply_markup = delivery
+            .is_direct()
+            .then(|| video_buttons(locale, &chat_config, &video.original_url))
+            .flatten();
+        // Clone only if it can be needed to download the video again for a download link
+        let video_for_link = link_store.is_enabled().then(|| video.clone());
+        let as_animation =
+            options.force_animation || video.is_silent() && video.duration.is_some_and(|duration| duration <= MAX_ANIMATION_DURATION);
+        // A video with a single chapter is sent as is
+        let chapters = if options.split_chapters && !as_animation {
+            video.chapters.clone().filter(|chapters| chapters.len() > 1)
+        } else {
+            None
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let make_summary = !as_animation
+            && chapters.is_none()
+            && summary_enabled
+            && video
+                .duration
+                .is_some_and(|duration| duration >= summary_config.min_duration as f64);
+        let summary_config = summary_config.clone();
+        let custom_thumbnail_url = custom_thumbnail_url.clone();
+        let requested_format_id = options.format_id.clone();
+        let sponsorblock_categories = sponsorblock_categories.clone();
+        let thumbnail_urls = video.thumbnail_urls();
 
         #[allow(clippy::cast_possible_truncation)]
         let (height, width, duration) = (video.height, video.width, video.duration.map(|duration| duration as i64));
 
-        let temp_dir = tempdir().map_err(|err| {
-            upload_action_task.abort();
+        let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+            chat_action.stop();
 
             HandlerError::new(err)
         })?;
 
-        handles.push(tokio::spawn(async move {
-            let VideoInFS { path, thumbnail_path } = spawn_blocking({
-                let temp_dir_path = temp_dir.path().to_owned();
-
-                move || download::video(video, max_file_size, yt_dlp_full_path, temp_dir_path, DOWNLOAD_MEDIA_TIMEOUT)
-            })
-            .await??;
+        let video_url = video.original_url.clone().into_boxed_str();
+
+        event_bus.publish(Event::DownloadStarted {
+            chat_id: Some(chat_id),
+            url: video_url.clone(),
+            media_kind: MediaKind::Video,
+        });
+
+        handles.push((
+            video_url,
+            title,
+            tokio::spawn(async move {
+                let _in_progress = DownloadInProgress::start();
+                let _permit = download_queue.acquire(estimated_size, Some(chat_id)).await;
+
+                if as_animation {
+                    let path = spawn_blocking({
+                        let temp_dir_path = temp_dir.path().to_owned();
+
+                        move || {
+                            download::animation(
+                                video,
+                                max_file_size,
+                                fps_weight,
+                                max_format_attempts,
+                                yt_dlp_full_path,
+                                &extra_args,
+                                &retries,
+                                temp_dir_path,
+                                download_timeout,
+                                process_limits,
+                            )
+                        }
+                    })
+                    .await??;
+
+                    chat_action.set_stage(Stage::Upload);
+
+                    event!(Level::TRACE, "Send animation");
+
+                    let file_size = input_file::file_size(&path);
+                    let message = send::upload_with_retries(
+                        &bot,
+                        &rate_limiter,
+                        SendAnimation::new(staged.chat_id(), input_file::from_work_dir(&work_dir, path))
+                            .disable_notification(true)
+                            .width_option(width)
+                            .height_option(height)
+                            .duration_option(duration),
+                        file_size,
+                        &retries.telegram_send,
+                        SEND_VIDEO_TIMEOUT,
+                    )
+                    .await?;
 
-            event!(Level::TRACE, "Send video");
+                    event!(Level::TRACE, "Animation sended");
 
-            let message = send::with_retries(
-                &bot,
-                SendVideo::new(receiver_video_chat_id, InputFile::fs(path))
-                    .disable_notification(true)
-                    .width_option(width)
-                    .height_option(height)
-                    .duration_option(duration)
-                    .thumbnail_option(thumbnail_path.map(InputFile::fs))
-                    .supports_streaming(true),
-                2,
-                Some(SEND_VIDEO_TIMEOUT),
-            )
-            .await?;
+                    staged.clean_up(&bot, message.id());
 
-            event!(Level::TRACE, "Video sended");
-
-            tokio::spawn({
-                let message_id = message.id();
+                    return Ok((Uploaded::Animation(message.animation().unwrap().file_id.clone()), file_size));
+                }
 
-                async move {
-                    let _ = bot.send(DeleteMessage::new(receiver_video_chat_id, message_id)).await;
+                if let Some(chapters) = chapters {
+                    let chapters_in_fs = spawn_blocking({
+                        let temp_dir_path = temp_dir.path().to_owned();
+
+                        move || {
+                            download::video_chapters(
+                                video,
+                                max_file_size,
+                                fps_weight,
+                                max_format_attempts,
+                                yt_dlp_full_path,
+                                &extra_args,
+                                &retries,
+                                temp_dir_path,
+                                download_timeout,
+                                custom_thumbnail_url.as_deref(),
+                                transcode,
+                                process_limits,
+                            )
+                        }
+                    })
+                    .await??;
+
+                    chat_action.set_stage(Stage::Upload);
+
+                    let mut uploaded_chapters = Vec::with_capacity(chapters_in_fs.len());
+                    let mut chapters_size = 0;
+
+                    for (index, (VideoInFS { path, thumbnail_path }, chapter)) in chapters_in_fs.into_iter().zip(chapters).enumerate() {
+                        event!(Level::TRACE, index, "Send chapter");
+
+                        #[allow(clippy::cast_possible_truncation)]
+                        let duration = (chapter.end_time - chapter.start_time) as i64;
+
+                        let file_size = input_file::file_size(&path);
+                        chapters_size += file_size;
+                        let message = send::upload_with_retries(
+                            &bot,
+                            &rate_limiter,
+                            staged.video(
+                                SendVideo::new(staged.chat_id(), input_file::from_work_dir(&work_dir, path))
+                                    .width_option(width)
+                                    .height_option(height)
+                                    .duration(duration)
+                                    .thumbnail_option(thumbnail_path.map(InputFile::fs))
+                                    .supports_streaming(true),
+                                None,
+                                None,
+                            ),
+                            file_size,
+                            &retries.telegram_send,
+                            SEND_VIDEO_TIMEOUT,
+                        )
+                        .await?;
+
+                        staged.clean_up(&bot, message.id());
+
+                        uploaded_chapters.push((
+                            message.video().unwrap().file_id.clone(),
+                            chapter_caption(locale, index, chapter.title.as_deref()),
+                        ));
+                    }
+
+                    event!(Level::TRACE, "Chapters sended");
+
+                    return Ok((Uploaded::Chapters(uploaded_chapters), chapters_size));
                 }
-            });
 
-            Ok(message.video().unwrap().file_id.clone())
-        }));
+                // Summary is made in parallel with the download, so it doesn't delay the video much
+                let summary_handle = make_summary.then(|| {
+                    spawn_blocking({
+                        let temp_dir_path = temp_dir.path().to_owned();
+                        let yt_dlp_full_path = yt_dlp_full_path.clone();
+                        let extra_args = extra_args.clone();
+                        let url = video.original_url.clone();
+
+                        move || summary::video(&summary_config, yt_dlp_full_path, url, &extra_args, temp_dir_path, process_limits)
+                    })
+                });
+
+                let segments = if sponsorblock_categories.is_empty() {
+                    vec![]
+                } else {
+                    sponsorblock::segments(&video.original_url, &sponsorblock_categories)
+                        .await
+                        .unwrap_or_else(|err| {
+                            event!(Level::WARN, %err, "Error getting SponsorBlock segments, download the video as is");
+
+                            vec![]
+                        })
+                };
+
+                let result = spawn_blocking({
+                    let temp_dir_path = temp_dir.path().to_owned();
+                    let yt_dlp_full_path = yt_dlp_full_path.clone();
+                    let extra_args = extra_args.clone();
+                    let requested_format_id = requested_format_id.clone();
+                    let segments = segments.clone();
+
+                    move || {
+                        download::video(
+                            video,
+                            max_file_size,
+                            fps_weight,
+                            max_format_attempts,
+                            yt_dlp_full_path,
+                            &extra_args,
+                            &retries,
+                            &temp_dir_path,
+                            download_timeout,
+                            custom_thumbnail_url.as_deref(),
+                            requested_format_id.as_deref(),
+                            transcode,
+                            process_limits,
+                        )
+                        .map(|video_in_fs| download::without_segments(video_in_fs, &segments, &temp_dir_path, process_limits))
+                    }
+                })
+                .await?;
+
+                let (VideoInFS { path, thumbnail_path }, segments_removed) = match (result, video_for_link) {
+                    (Err(StreamErrorKind::NoFormatFound { .. }), Some(video)) => {
+                        event!(Level::INFO, "Video exceeds the Telegram limits, download it for a link");
+
+                        let VideoInFS { path, .. } = spawn_blocking({
+                            let temp_dir_path = temp_dir.path().to_owned();
+                            let max_file_size = link_store.max_file_size();
+
+                            move || {
+                                download::video(
+                                    video,
+                                    max_file_size,
+                                    fps_weight,
+                                    max_format_attempts,
+                                    yt_dlp_full_path,
+                                    &extra_args,
+                                    &retries,
+                                    temp_dir_path,
+                                    download_timeout,
+                                    None,
+                                    requested_format_id.as_deref(),
+                                    transcode,
+                                    process_limits,
+                                )
+                            }
+                        })
+                        .await??;
+
+                        let file_size = input_file::file_size(&path);
+                        let link = link_store.insert(path, temp_dir).expect("Links should be enabled");
+
+                        return Ok((Uploaded::Link(link), file_size));
+                    }
+                    (result, _) => result?,
+                };
+
+                let thumbnail_path = thumbnail_or_retry(thumbnail_path, thumbnail_urls, temp_dir.path().to_owned(), process_limits).await;
+                let removed_duration = segments_removed.then(|| sponsorblock::removed_duration(&segments));
+                #[allow(clippy::cast_possible_truncation)]
+                let duration = duration.map(|duration| duration - removed_duration.unwrap_or_default() as i64);
+
+                // The summary is a part of the caption, which a directly uploaded video needs before the upload
+                let summary = match summary_handle {
+                    Some(handle) => match handle.await {
+                        Ok(Ok(summary)) => summary,
+                        Ok(Err(err)) => {
+                            event!(Level::WARN, %err, "Error making video summary");
+
+                            None
+                        }
+                        Err(err) => {
+                            event!(Level::WARN, %err, "Error while joining handle");
+
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                let caption = Caption::new()
+                    .note(removed_duration.map(|removed_duration| locale.segments_removed(&format_duration(removed_duration))))
+                    .summary(summary)
+                    .description(description);
+                let direct_caption = delivery
+                    .is_direct()
+                    .then(|| caption.clone().source_url(visible_source_url).build())
+                    .flatten();
+
+                chat_action.set_stage(Stage::Upload);
+
+                event!(Level::TRACE, direct = delivery.is_direct(), "Send video");
+
+                let file_size = input_file::file_size(&path);
+                let upload_progress = UploadProgress::start(
+                    bot.clone(),
+                    chat_id,
+                    thread_id,
+                    message_id,
+                    locale,
+                    input_file::upload_size(&work_dir, &path),
+                );
+                let result = send::upload_with_retries(
+                    &bot,
+                    &rate_limiter,
+                    send::Rebuildable::new({
+                        let work_dir = work_dir.clone();
+                        let uploaded = upload_progress.uploaded();
+                        let direct_caption = direct_caption.clone();
+
+                        move || {
+                            delivery.video(
+                                SendVideo::new(
+                                    delivery.chat_id(),
+                                    input_file::with_progress(&work_dir, path.clone(), uploaded.clone()),
+                                )
+                                .width_option(width)
+                                .height_option(height)
+                                .duration_option(duration)
+                                .thumbnail_option(thumbnail_path.clone().map(InputFile::fs))
+                                .supports_streaming(true),
+                                direct_caption.clone(),
+                                reply_markup.clone(),
+                            )
+                        }
+                    }),
+                    file_size,
+                    &retries.telegram_send,
+                    SEND_VIDEO_TIMEOUT,
+                )
+                .await;
+
+                upload_progress.stop();
+
+                let message = result?;
+
+                event!(Level::TRACE, "Video sended");
+
+                delivery.clean_up(&bot, message.id());
+
+                let file_id = message.video().unwrap().file_id.clone();
+                let uploaded = if delivery.is_direct() {
+                    Uploaded::Sent {
+                        file_id,
+                        caption: direct_caption,
+                    }
+                } else {
+                    Uploaded::File { file_id, caption }
+                };
+
+                Ok((uploaded, file_size))
+            }),
+        ));
     }
 
-    let mut videos_in_playlist = Vec::with_capacity(videos_len);
-    let mut failed_downloads_count = 0;
+    let mut videos_in_playlist = job.job().uploaded.clone();
+    let mut animations = vec![];
+    let mut report = PlaylistReport::default();
+    let mut sent_directly = false;
+
+    for (index, (video_url, title, handle)) in (job.job().next_index..).zip(handles) {
+        let uploaded_len = videos_in_playlist.len();
 
-    for (index, handle) in handles.into_iter().enumerate() {
         match handle.await {
-            Ok(Ok(file_id)) => videos_in_playlist.push(TgVideoInPlaylist::new(file_id, index)),
+            Ok(Ok((uploaded, file_size))) => {
+                let source_url = video_url.clone();
+                let visible_source_url = chat_config.link_is_visible.then(|| source_url.clone());
+                let finished = Event::DownloadFinished {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                };
+                report.record(index, title.as_deref(), &finished);
+                report.add_size(file_size);
+                event_bus.publish(finished);
+
+                match uploaded {
+                    Uploaded::File { file_id, caption } => videos_in_playlist.push(
+                        TgVideoInPlaylist::new(file_id, index)
+                            .caption(caption.source_url(visible_source_url).build())
+                            .source_url(source_url),
+                    ),
+                    // The sort by index is stable, so chapters stay in their order
+                    Uploaded::Chapters(chapters) => videos_in_playlist.extend(chapters.into_iter().map(|(file_id, caption)| {
+                        TgVideoInPlaylist::new(file_id, index)
+                            .caption(caption.source_url(visible_source_url.clone()).build())
+                            .source_url(source_url.clone())
+                    })),
+                    // Animations can't be in media groups, so they're sent separately
+                    Uploaded::Animation(file_id) => {
+                        bot.send(
+                            SendAnimation::new(chat_id, InputFile::id(file_id.as_ref()))
+                                .message_thread_id_option(thread_id)
+                                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+                        )
+                        .await?;
+
+                        animations.push(file_id);
+                    }
+                    Uploaded::Link(link) => {
+                        bot.send(
+                            SendMessage::new(
+                                chat_id,
+                                download_link_text(locale, title.as_deref(), &link, link_store.retention().as_secs()),
+                            )
+                            .parse_mode(ParseMode::HTML)
+                            .message_thread_id_option(thread_id)
+                            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+                        )
+                        .await?;
+                    }
+                    // Targets and mirrors still get the video by its file ID
+                    Uploaded::Sent { file_id, caption } => {
+                        sent_directly = true;
+                        videos_in_playlist.push(TgVideoInPlaylist::new(file_id, index).caption(caption).source_url(source_url));
+                    }
+                    Uploaded::Local { .. } => unreachable!("Videos aren't sent in archives"),
+                }
+            }
             Ok(Err(err)) => {
                 event!(Level::ERROR, %err, "Error while downloading video");
 
-                failed_downloads_count += 1;
+                let failed = Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                    error: err.to_string().into_boxed_str(),
+                };
+                report.record(index, title.as_deref(), &failed);
+                event_bus.publish(failed);
             }
             Err(err) => {
                 event!(Level::ERROR, %err, "Error while joining handle");
 
-                failed_downloads_count += 1;
+                let failed = Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                    error: err.to_string().into_boxed_str(),
+                };
+                report.record(index, title.as_deref(), &failed);
+                event_bus.publish(failed);
             }
         }
+
+        // A directly sent video is already in the chat, so it isn't sent again after a restart
+        job.finish_video(if sent_directly { &[] } else { &videos_in_playlist[uploaded_len..] });
     }
 
-    upload_action_task.abort();
+    chat_action.stop();
 
-    if failed_downloads_count > 0 {
+    let failed_downloads_count = report.failed.len();
+
+    // Failures of a playlist are reported in its summary after the videos
+    if failed_downloads_count > 0 && videos_len == 1 {
         event!(Level::ERROR, "Failed downloads count is {failed_downloads_count}");
 
-        error::download_videos_in_message(&bot, failed_downloads_count, chat_id, message_id, Some(ParseMode::HTML)).await?;
+        error::download_videos_in_message(
+            &bot,
+            locale,
+            failed_downloads_count,
+            chat_id,
+            thread_id,
+            message_id,
+            Some(ParseMode::HTML),
+        )
+        .await?;
     }
 
-    let input_media_list = {
-        videos_in_playlist.sort_by(|a, b| a.index.cmp(&b.index));
-        videos_in_playlist
-            .into_iter()
-            .map(|video| InputMediaVideo::new(InputFile::id(video.file_id.into_string())))
-            .collect()
+    videos_in_playlist.sort_by(|a, b| a.index.cmp(&b.index));
+
+    let input_media_list: Vec<_> = videos_in_playlist
+        .iter()
+        .map(|video| {
+            InputMediaVideo::new(InputFile::id(video.file_id.as_ref()))
+                .caption_option(video.caption.as_deref())
+                .parse_mode(ParseMode::HTML)
+        })
+        .collect();
+
+    if let Some(header) = header.as_deref().filter(|_| !input_media_list.is_empty()) {
+        bot.send(
+            SendMessage::new(chat_id, header)
+                .parse_mode(ParseMode::HTML)
+                .message_thread_id_option(thread_id)
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        )
+        .await?;
+    }
+
+    let result = if sent_directly {
+        Ok(())
+    } else if chat_config.video_buttons_enabled() {
+        send_with_buttons(
+            &bot,
+            rate_limiter,
+            locale,
+            &chat_config,
+            chat_id,
+            thread_id,
+            message_id,
+            &videos_in_playlist,
+            &retries.telegram_send,
+        )
+        .await
+    } else {
+        send::media_groups(
+            &bot,
+            rate_limiter,
+            chat_id,
+            thread_id,
+            input_media_list.clone(),
+            Some(message_id),
+            &retries.telegram_send_media_group,
+            Some(SEND_AUDIO_TIMEOUT),
+        )
+        .await
+        .map(|_| ())
     };
 
-    send::media_groups(&bot, chat_id, input_media_list, Some(message_id), Some(SEND_AUDIO_TIMEOUT)).await?;
+    result.map_err(|err| {
+        event_bus.publish(Event::SendFailed {
+            chat_id: Some(chat_id),
+            media_kind: MediaKind::Video,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        err
+    })?;
+
+    send_to_targets(
+        &bot,
+        rate_limiter,
+        locale,
+        chat_id,
+        thread_id,
+        message_id,
+        target_chats,
+        header.as_deref(),
+        input_media_list.clone(),
+        &animations,
+        &retries.telegram_send_media_group,
+    )
+    .await?;
+
+    send_to_mirrors(
+        &bot,
+        rate_limiter,
+        bot_config.get_mirror_chat_ids(chat_id),
+        input_media_list,
+        &animations,
+        &retries.telegram_send_media_group,
+    )
+    .await;
+
+    if videos_len > 1 {
+        event!(Level::DEBUG, ?report, "Playlist finished");
+
+        bot.send(
+            SendMessage::new(chat_id, playlist_summary(locale, &report, started_at.elapsed()))
+                .parse_mode(ParseMode::HTML)
+                .message_thread_id_option(thread_id)
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        )
+        .await?;
+    }
+
+    // The message is kept if some media failed, so the link isn't lost
+    if failed_downloads_count == 0 {
+        delete_original_message(&bot, bot_config, chat_id, message_id).await;
+    }
 
     Ok(EventReturn::Finish)
 }
 
 #[instrument(skip_all, fields(message_id, chat_id, url))]
-pub async fn video_download_quite(
+pub async fn video_download(
     bot: Arc<Bot>,
     mut context: Context,
     message: Message,
     Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
     Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(transcode): Extension<Transcode>,
+    Extension(summary_config): Extension<SummaryConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+    Extension(job_store): Extension<JobStore>,
 ) -> HandlerResult {
     let url = context
         .remove::<Box<str>>("video_url")
         .expect("Url should be in context because `text_contains_url` filter should do this");
+    let urls = context
+        .remove::<Box<[Box<str>]>>("video_urls")
+        .unwrap_or_else(|| Box::new([url.clone()]));
     let message_id = message.id();
     let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
 
     Span::current()
         .record("chat_id", chat_id)
         .record("message_id", message_id)
         .record("url", &*url);
 
-    event!(Level::DEBUG, "Got url");
+    event!(Level::DEBUG, urls_len = urls.len(), "Got urls");
 
-    let videos = match spawn_blocking({
-        let full_path = yt_dlp_config.full_path.clone();
-        let url = url.clone();
+    let target_chats = message.text().map(targets::from_text).unwrap_or_default();
 
-        move || get_media_or_playlist_info(full_path, url, true, GET_INFO_TIMEOUT)
-    })
+    if !targets::is_sender_allowed(&bot, &message, &target_chats).await {
+        event!(Level::WARN, "Sender isn't allowed to send media to other chats");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.only_admins_send_to_targets(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Video, Stage::Info);
+
+    // Info for all URLs is fetched concurrently, so one slow URL doesn't delay the others
+    let handles = urls
+        .iter()
+        .map(|url| {
+            let yt_dlp_config = yt_dlp_config.clone();
+            let url = url.clone();
+
+            tokio::spawn(async move {
+                download::media_info(&yt_dlp_config, &url, true, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await
+            })
+        })
+        .collect::<Vec<_>>();
+    let deadline = Instant::now() + GET_INFO_BUDGET;
+
+    let mut videos = VideosInYT::default();
+    let mut failed_urls = vec![];
+
+    for (url, mut handle) in urls.iter().zip(handles) {
+        match timeout_at(deadline, &mut handle).await {
+            Ok(Ok(Ok(url_videos))) => videos.extend(url_videos),
+            Ok(Ok(Err(err))) => {
+                event!(Level::ERROR, %err, %url, "Getting video/playlist info error");
+
+                failed_urls.push((url, Some(err)));
+            }
+            Ok(Err(err)) => {
+                event!(Level::ERROR, %err, "Error while getting video/playlist info");
+
+                chat_action.stop();
+
+                return Err(HandlerError::new(err));
+            }
+            // Aborting the task kills the process, so it doesn't run until its own timeout
+            Err(_) => {
+                event!(Level::ERROR, %url, "Getting video/playlist info exceeded the budget");
+
+                handle.abort();
+
+                failed_urls.push((url, None));
+            }
+        }
+    }
+
+    if failed_urls.len() == urls.len() {
+        chat_action.stop();
+
+        let default_text = locale.video_info_error();
+        let text = match &*failed_urls {
+            [(_, Some(err))] => error::ytdl_text(err, locale, default_text),
+            _ => default_text,
+        };
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, text, None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    // Other URLs are still downloaded, so failed ones are only reported
+    if !failed_urls.is_empty() {
+        let failed_urls_text = failed_urls
+            .iter()
+            .map(|(url, err)| match err {
+                Some(YtdlError::Failed(cause)) => format!("{} ({cause})", html_code(html_quote(url))),
+                _ => html_code(html_quote(url)),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        error::occured_in_message(
+            &bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &locale.skipped_urls(&failed_urls_text),
+            Some(ParseMode::HTML),
+        )
+        .await?;
+    }
+
+    if !skip_ongoing_lives(&bot, chat_id, thread_id, message_id, locale, &mut videos).await? {
+        chat_action.stop();
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let Some(options) = message.text().map_or(Some(DownloadOptions::default()), DownloadOptions::from_text) else {
+        event!(Level::WARN, "Invalid SponsorBlock categories");
+
+        chat_action.stop();
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.invalid_sponsorblock_categories(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    };
+
+    if let Some(format_id) = options
+        .format_id
+        .as_deref()
+        .filter(|format_id| !videos.iter().all(|video| video.has_requested_format(format_id)))
+    {
+        event!(Level::WARN, format_id, "Requested format isn't listed");
+
+        chat_action.stop();
+
+        error::occured_in_message(
+            &bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &locale.format_not_listed(&html_code(html_quote(format_id))),
+            Some(ParseMode::HTML),
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    if let Some(total_size) = exceeded_total_size(&videos, yt_dlp_config.max_total_size, |video| {
+        video.estimated_video_filesize(yt_dlp_config.max_file_size, yt_dlp_config.fps_weight)
+    }) {
+        event!(Level::WARN, total_size, "Playlist exceeds the total size budget");
+
+        chat_action.stop();
+
+        error::occured_in_message(
+            &bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &total_size_exceeded_text(locale, total_size, yt_dlp_config.max_total_size.unwrap_or_default()),
+            None,
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    // A photo the command replies to is used as the thumbnail of all videos
+    let custom_thumbnail_url = thumbnail::from_reply_photo(&bot, &bot_config, &message).await;
+    let job = job_store.start(Job::new(
+        chat_id,
+        thread_id,
+        message_id,
+        locale,
+        message.text().unwrap_or_default(),
+        videos.iter().map(|video| video.original_url.as_str()),
+    ));
+
+    download_and_send_videos(
+        bot,
+        &rate_limiter,
+        chat_id,
+        thread_id,
+        message_id,
+        locale,
+        videos,
+        chat_action,
+        &yt_dlp_config,
+        retries,
+        &bot_config,
+        &event_bus,
+        &work_dir,
+        &link_store,
+        &download_queue,
+        &summary_config,
+        custom_thumbnail_url,
+        &target_chats,
+        chat_config_store.get(chat_id),
+        &options,
+        transcode,
+        process_limits,
+        job,
+    )
     .await
-    .map_err(|err| {
-        event!(Level::ERROR, %err, "Error while getting video/playlist info");
+}
 
-        HandlerError::new(err)
-    })? {
+/// Resumes a download job interrupted by a restart, videos uploaded before it aren't downloaded again.
+/// Options and target chats are parsed from the command again, but a photo it replied to isn't used as the thumbnail.
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(chat_id = job.job().chat_id, message_id = job.job().message_id))]
+async fn resume_job(
+    bot: Arc<Bot>,
+    rate_limiter: &RateLimiter,
+    yt_dlp_config: &YtDlp,
+    retries: Retries,
+    bot_config: &BotConfig,
+    event_bus: &EventBus,
+    work_dir: &WorkDir,
+    link_store: &LinkStore,
+    download_queue: &DownloadQueue,
+    summary_config: &SummaryConfig,
+    chat_config_store: &ChatConfigStore,
+    transcode: Transcode,
+    process_limits: ProcessLimits,
+    job: RunningJob,
+) -> HandlerResult {
+    let Job {
+        chat_id,
+        thread_id,
+        message_id,
+        locale,
+        ..
+    } = *job.job();
+    let text = job.job().text.clone();
+
+    event!(Level::INFO, next_index = job.job().next_index, "Resume job");
+
+    let mut videos = VideosInYT::default();
+
+    // Indexes of the job are indexes of its URLs, so the job isn't resumed without any of them
+    for url in job.job().unfinished_urls() {
+        match download::media_info(yt_dlp_config, url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+            Ok(url_videos) => videos.extend(url_videos),
+            Err(err) => {
+                event!(Level::ERROR, %err, %url, "Getting video info error");
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_info_error(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        }
+    }
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Video, Stage::Download);
+
+    download_and_send_videos(
+        bot,
+        rate_limiter,
+        chat_id,
+        thread_id,
+        message_id,
+        locale,
+        videos,
+        chat_action,
+        yt_dlp_config,
+        retries,
+        bot_config,
+        event_bus,
+        work_dir,
+        link_store,
+        download_queue,
+        summary_config,
+        None,
+        &targets::from_text(&text),
+        chat_config_store.get(chat_id),
+        &DownloadOptions::from_text(&text).unwrap_or_default(),
+        transcode,
+        process_limits,
+        job,
+    )
+    .await
+}
+
+/// Resumes download jobs interrupted by the last shutdown, all of them are downloaded concurrently in the download queue
+#[allow(clippy::too_many_arguments)]
+pub async fn resume_jobs(
+    bot: Arc<Bot>,
+    rate_limiter: &RateLimiter,
+    yt_dlp_config: &YtDlp,
+    retries: Retries,
+    bot_config: &BotConfig,
+    event_bus: &EventBus,
+    work_dir: &WorkDir,
+    link_store: &LinkStore,
+    download_queue: &DownloadQueue,
+    summary_config: &SummaryConfig,
+    chat_config_store: &ChatConfigStore,
+    job_store: &JobStore,
+    transcode: Transcode,
+    process_limits: ProcessLimits,
+) {
+    let results = join_all(job_store.unfinished().into_iter().map(|job| {
+        resume_job(
+            bot.clone(),
+            rate_limiter,
+            yt_dlp_config,
+            retries,
+            bot_config,
+            event_bus,
+            work_dir,
+            link_store,
+            download_queue,
+            summary_config,
+            chat_config_store,
+            transcode,
+            process_limits,
+            job,
+        )
+    }))
+    .await;
+
+    for err in results.into_iter().filter_map(Result::err) {
+        event!(Level::ERROR, %err, "Error resuming job");
+    }
+}
+
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn video_download_quite(
+    bot: Arc<Bot>,
+    mut context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(transcode): Extension<Transcode>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    let url = context
+        .remove::<Box<str>>("video_url")
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+    let chat_config = chat_config_store.get(chat_id);
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    event!(Level::DEBUG, "Got url");
+
+    let mut videos = match download::media_info(&yt_dlp_config, &url, true, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
         Ok(videos) => videos,
         Err(err) => {
             event!(Level::ERROR, %err, "Getting video/playlist info error");
@@ -251,6 +1500,12 @@ pub async fn video_download_quite(
         }
     };
 
+    // Ongoing live streams are skipped silently like other errors in the quiet mode
+    let lives_count = videos.remove_ongoing_lives();
+    if lives_count > 0 {
+        event!(Level::WARN, lives_count, "Ongoing live streams are skipped");
+    }
+
     let videos_len = videos.len();
 
     if videos_len == 0 {
@@ -261,118 +1516,423 @@ pub async fn video_download_quite(
 
     event!(Level::DEBUG, videos_len, "Got video/playlist info");
 
-    let upload_action_task = tokio::spawn({
-        let bot = bot.clone();
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Video, Stage::Download);
 
-        async move { upload_video_action_in_loop(&bot, chat_id).await }
-    });
-
-    let mut handles: Vec<JoinHandle<Result<_, DownloadErrorKind>>> = Vec::with_capacity(videos_len);
+    let mut handles: Vec<(
+        Box<str>,
+        Option<String>,
+        Option<String>,
+        JoinHandle<Result<Uploaded, DownloadErrorKind>>,
+    )> = Vec::with_capacity(videos_len);
 
     for video in videos {
         let bot = bot.clone();
-        let max_file_size = yt_dlp_config.max_file_size;
-        let yt_dlp_full_path = yt_dlp_config.full_path.clone();
-        let receiver_video_chat_id = bot_config.receiver_video_chat_id;
-
-        #[allow(clippy::cast_possible_truncation)]
-        let (height, width, duration) = (video.height, video.width, video.duration.map(|duration| duration as i64));
-
-        let temp_dir = tempdir().map_err(|err| {
-            upload_action_task.abort();
-
-            HandlerError::new(err)
-        })?;
-
-        handles.push(tokio::spawn(async move {
-            let VideoInFS { path, thumbnail_path } = spawn_blocking({
-                let temp_dir_path = temp_dir.path().to_owned();
-
-                move || download::video(video, max_file_size, yt_dlp_full_path, temp_dir_path, DOWNLOAD_MEDIA_TIMEOUT)
-            })
-            .await??;
-
-            event!(Level::TRACE, "Send video");
-
-            let message = send::with_retries(
-                &bot,
-                SendVideo::new(receiver_video_chat_id, InputFile::fs(path))
-                    .disable_notification(true)
-                    .width_option(width)
-                    .height_option(height)
-                    .duration_option(duration)
-                    .thumbnail_option(thumbnail_path.map(InputFile::fs))
-                    .supports_streaming(true),
-                2,
-                Some(SEND_VIDEO_TIMEOUT),
-            )
-            .await?;
+        let chat_action = chat_action.clone();
+        let work_dir = work_dir.clone();
+        let max_file_size = yt_dlp_config.max_file_size;
+        let fps_weight = yt_dlp_config.fps_weight;
+        let max_format_attempts = yt_dlp_config.max_format_attempts;
+        let yt_dlp_full_path = yt_dlp_config.full_path.clone();
+        let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+        let link_store = link_store.clone();
+        let staged = Delivery::staged(&bot_config);
+        let thumbnail_urls = video.thumbnail_urls();
+        let download_queue = download_queue.clone();
+        let rate_limiter = rate_limiter.clone();
+        let estimated_size = video.estimated_video_filesize(max_file_size, fps_weight);
+        let download_timeout = download::download_timeout(&video, DOWNLOAD_MEDIA_TIMEOUT, yt_dlp_config.max_download_timeout);
+        // Clone only if it can be needed to download the video again for a download link
+        let video_for_link = link_store.is_enabled().then(|| video.clone());
 
-            event!(Level::TRACE, "Video sended");
+        #[allow(clippy::cast_possible_truncation)]
+        let (height, width, duration) = (video.height, video.width, video.duration.map(|duration| duration as i64));
 
-            tokio::spawn({
-                let message_id = message.id();
+        let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+            chat_action.stop();
 
-                async move {
-                    let _ = bot.send(DeleteMessage::new(receiver_video_chat_id, message_id)).await;
-                }
-            });
+            HandlerError::new(err)
+        })?;
 
-            Ok(message.video().unwrap().file_id.clone())
-        }));
+        let video_url = video.original_url.clone().into_boxed_str();
+
+        event_bus.publish(Event::DownloadStarted {
+            chat_id: Some(chat_id),
+            url: video_url.clone(),
+            media_kind: MediaKind::Video,
+        });
+
+        handles.push((
+            video_url,
+            video.title.clone(),
+            chat_config.description_enabled.then(|| video.description.clone()).flatten(),
+            tokio::spawn(async move {
+                let _in_progress = DownloadInProgress::start();
+                let _permit = download_queue.acquire(estimated_size, Some(chat_id)).await;
+
+                let result = spawn_blocking({
+                    let temp_dir_path = temp_dir.path().to_owned();
+                    let yt_dlp_full_path = yt_dlp_full_path.clone();
+                    let extra_args = extra_args.clone();
+
+                    move || {
+                        download::video(
+                            video,
+                            max_file_size,
+                            fps_weight,
+                            max_format_attempts,
+                            yt_dlp_full_path,
+                            &extra_args,
+                            &retries,
+                            temp_dir_path,
+                            download_timeout,
+                            None,
+                            None,
+                            transcode,
+                            process_limits,
+                        )
+                    }
+                })
+                .await?;
+
+                let VideoInFS { path, thumbnail_path } = match (result, video_for_link) {
+                    (Err(StreamErrorKind::NoFormatFound { .. }), Some(video)) => {
+                        event!(Level::INFO, "Video exceeds the Telegram limits, download it for a link");
+
+                        let VideoInFS { path, .. } = spawn_blocking({
+                            let temp_dir_path = temp_dir.path().to_owned();
+                            let max_file_size = link_store.max_file_size();
+
+                            move || {
+                                download::video(
+                                    video,
+                                    max_file_size,
+                                    fps_weight,
+                                    max_format_attempts,
+                                    yt_dlp_full_path,
+                                    &extra_args,
+                                    &retries,
+                                    temp_dir_path,
+                                    download_timeout,
+                                    None,
+                                    None,
+                                    transcode,
+                                    process_limits,
+                                )
+                            }
+                        })
+                        .await??;
+
+                        let link = link_store.insert(path, temp_dir).expect("Links should be enabled");
+
+                        return Ok(Uploaded::Link(link));
+                    }
+                    (result, _) => result?,
+                };
+
+                let thumbnail_path = thumbnail_or_retry(thumbnail_path, thumbnail_urls, temp_dir.path().to_owned(), process_limits).await;
+
+                chat_action.set_stage(Stage::Upload);
+
+                event!(Level::TRACE, "Send video");
+
+                let file_size = input_file::file_size(&path);
+                let message = send::upload_with_retries(
+                    &bot,
+                    &rate_limiter,
+                    SendVideo::new(staged.chat_id(), input_file::from_work_dir(&work_dir, path))
+                        .disable_notification(true)
+                        .width_option(width)
+                        .height_option(height)
+                        .duration_option(duration)
+                        .thumbnail_option(thumbnail_path.map(InputFile::fs))
+                        .supports_streaming(true),
+                    file_size,
+                    &retries.telegram_send,
+                    SEND_VIDEO_TIMEOUT,
+                )
+                .await?;
+
+                event!(Level::TRACE, "Video sended");
+
+                staged.clean_up(&bot, message.id());
+
+                Ok(Uploaded::File {
+                    file_id: message.video().unwrap().file_id.clone(),
+                    caption: Caption::new(),
+                })
+            }),
+        ));
     }
 
     let mut videos_in_playlist = Vec::with_capacity(videos_len);
     let mut failed_downloads_count = 0;
 
-    for (index, handle) in handles.into_iter().enumerate() {
+    for (index, (video_url, title, description, handle)) in handles.into_iter().enumerate() {
         match handle.await {
-            Ok(Ok(file_id)) => videos_in_playlist.push(TgVideoInPlaylist::new(file_id, index)),
+            Ok(Ok(uploaded)) => {
+                match uploaded {
+                    Uploaded::File { file_id, caption } => videos_in_playlist.push(
+                        TgVideoInPlaylist::new(file_id, index)
+                            .caption(
+                                caption
+                                    .description(description)
+                                    .source_url(chat_config.link_is_visible.then(|| video_url.clone()))
+                                    .build(),
+                            )
+                            .source_url(video_url.clone()),
+                    ),
+                    Uploaded::Link(link) => {
+                        bot.send(
+                            SendMessage::new(
+                                chat_id,
+                                download_link_text(locale, title.as_deref(), &link, link_store.retention().as_secs()),
+                            )
+                            .parse_mode(ParseMode::HTML)
+                            .disable_notification(true)
+                            .message_thread_id_option(thread_id)
+                            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+                        )
+                        .await?;
+                    }
+                    Uploaded::Animation(_) | Uploaded::Chapters(_) | Uploaded::Sent { .. } | Uploaded::Local { .. } => {
+                        unreachable!("Videos are sent only as files or links in the quiet mode")
+                    }
+                }
+
+                event_bus.publish(Event::DownloadFinished {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                });
+            }
             Ok(Err(err)) => {
                 event!(Level::ERROR, %err, "Error while downloading video");
 
+                event_bus.publish(Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                    error: err.to_string().into_boxed_str(),
+                });
+
                 failed_downloads_count += 1;
             }
             Err(err) => {
                 event!(Level::ERROR, %err, "Error while joining handle");
 
+                event_bus.publish(Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Video,
+                    error: err.to_string().into_boxed_str(),
+                });
+
                 failed_downloads_count += 1;
             }
         }
     }
 
-    upload_action_task.abort();
+    chat_action.stop();
 
     if failed_downloads_count > 0 {
         event!(Level::ERROR, "Failed downloads count is {failed_downloads_count}");
     }
 
-    let input_media_list = {
-        videos_in_playlist.sort_by(|a, b| a.index.cmp(&b.index));
-        videos_in_playlist
-            .into_iter()
-            .map(|video| InputMediaVideo::new(InputFile::id(video.file_id.into_string())))
-            .collect()
+    videos_in_playlist.sort_by(|a, b| a.index.cmp(&b.index));
+
+    let input_media_list: Vec<_> = videos_in_playlist
+        .iter()
+        .map(|video| {
+            InputMediaVideo::new(InputFile::id(video.file_id.as_ref()))
+                .caption_option(video.caption.as_deref())
+                .parse_mode(ParseMode::HTML)
+        })
+        .collect();
+
+    let result = if chat_config.video_buttons_enabled() {
+        send_with_buttons(
+            &bot,
+            &rate_limiter,
+            locale,
+            &chat_config,
+            chat_id,
+            thread_id,
+            message_id,
+            &videos_in_playlist,
+            &retries.telegram_send,
+        )
+        .await
+    } else {
+        send::media_groups(
+            &bot,
+            &rate_limiter,
+            chat_id,
+            thread_id,
+            input_media_list.clone(),
+            Some(message_id),
+            &retries.telegram_send_media_group,
+            Some(SEND_AUDIO_TIMEOUT),
+        )
+        .await
+        .map(|_| ())
     };
 
-    send::media_groups(&bot, chat_id, input_media_list, Some(message_id), Some(SEND_AUDIO_TIMEOUT)).await?;
+    result.map_err(|err| {
+        event_bus.publish(Event::SendFailed {
+            chat_id: Some(chat_id),
+            media_kind: MediaKind::Video,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        err
+    })?;
+
+    send_to_mirrors(
+        &bot,
+        &rate_limiter,
+        bot_config.get_mirror_chat_ids(chat_id),
+        input_media_list,
+        &[],
+        &retries.telegram_send_media_group,
+    )
+    .await;
+
+    // The message is kept if some media failed, so the link isn't lost
+    if failed_downloads_count == 0 {
+        delete_original_message(&bot, &bot_config, chat_id, message_id).await;
+    }
 
     Ok(EventReturn::Finish)
 }
 
+fn album_summary_text(locale: Locale, title: &str, performer: Option<&str>, tracks_count: usize) -> String {
+    match performer {
+        Some(performer) => format!(
+            "<b>{title}</b> — {performer}\n{tracks}: {tracks_count}",
+            title = html_quote(title),
+            performer = html_quote(performer),
+            tracks = locale.tracks(),
+        ),
+        None => format!(
+            "<b>{title}</b>\n{tracks}: {tracks_count}",
+            title = html_quote(title),
+            tracks = locale.tracks()
+        ),
+    }
+}
+
+/// Name of the audio in an archive, the index keeps the playlist order in file managers
+fn archive_entry_name(index: usize, title: Option<&str>, performer: Option<&str>, path: &Path) -> String {
+    let name = match (performer, title) {
+        (Some(performer), Some(title)) => format!("{index:02}. {performer} - {title}"),
+        (None, Some(title)) => format!("{index:02}. {title}"),
+        (_, None) => format!("{index:02}"),
+    };
+
+    match path.extension() {
+        Some(extension) => format!("{}.{}", fs::sanitize_file_name(&name), extension.to_string_lossy()),
+        None => fs::sanitize_file_name(&name),
+    }
+}
+
+/// Packs the audios in ZIP archives and uploads them as documents.
+/// Audios are split between several archives if they don't fit the size limit together.
+#[allow(clippy::too_many_arguments)]
+async fn upload_archives(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    bot_config: &BotConfig,
+    work_dir: &WorkDir,
+    name: &str,
+    entries: Vec<(String, PathBuf)>,
+    max_file_size: u64,
+    retry_policy: &RetryPolicy,
+) -> Result<Vec<InputMediaDocument<'static>>, DownloadErrorKind> {
+    let mut parts: Vec<Vec<(String, PathBuf)>> = vec![];
+    let mut part_size = 0;
+
+    for (entry_name, path) in entries {
+        let entry_size = input_file::file_size(&path) + ARCHIVE_ENTRY_OVERHEAD + entry_name.len() as u64 * 2;
+
+        match parts.last_mut() {
+            Some(part) if part_size + entry_size <= max_file_size => {
+                part.push((entry_name, path));
+                part_size += entry_size;
+            }
+            _ => {
+                parts.push(vec![(entry_name, path)]);
+                part_size = entry_size;
+            }
+        }
+    }
+
+    let parts_len = parts.len();
+    let staged = Delivery::staged(bot_config);
+    let mut documents = Vec::with_capacity(parts_len);
+
+    for (index, part) in parts.into_iter().enumerate() {
+        let temp_dir = tempdir_in(&work_dir.path)?;
+        let file_name = if parts_len > 1 {
+            format!("{name} ({}).zip", index + 1)
+        } else {
+            format!("{name}.zip")
+        };
+        let path = temp_dir.path().join(file_name);
+
+        spawn_blocking({
+            let path = path.clone();
+
+            move || fs::write_zip(path, &part)
+        })
+        .await??;
+
+        event!(Level::TRACE, index, "Send archive");
+
+        let file_size = input_file::file_size(&path);
+        let message = send::upload_with_retries(
+            bot,
+            rate_limiter,
+            SendDocument::new(staged.chat_id(), input_file::from_work_dir(work_dir, path)).disable_notification(true),
+            file_size,
+            retry_policy,
+            SEND_AUDIO_TIMEOUT,
+        )
+        .await?;
+
+        staged.clean_up(bot, message.id());
+
+        documents.push(InputMediaDocument::new(InputFile::id(
+            message.document().unwrap().file_id.clone().into_string(),
+        )));
+    }
+
+    Ok(documents)
+}
+
 #[instrument(skip_all, fields(message_id, chat_id, url))]
 pub async fn audio_download(
     bot: Arc<Bot>,
     mut context: Context,
     message: Message,
     Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
     Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
 ) -> HandlerResult {
     let url = context
         .remove::<Box<str>>("video_url")
         .expect("Url should be in context because `text_contains_url` filter should do this");
     let message_id = message.id();
     let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+    let chat_config = chat_config_store.get(chat_id);
 
     Span::current()
         .record("url", &*url)
@@ -381,15 +1941,25 @@ pub async fn audio_download(
 
     event!(Level::DEBUG, "Got url");
 
-    let videos = match spawn_blocking({
-        let full_path = yt_dlp_config.full_path.clone();
-        let url = url.clone();
+    let target_chats = message.text().map(targets::from_text).unwrap_or_default();
 
-        move || get_media_or_playlist_info(full_path, url, true, GET_INFO_TIMEOUT)
-    })
-    .await
-    .map_err(HandlerError::new)?
-    {
+    if !targets::is_sender_allowed(&bot, &message, &target_chats).await {
+        event!(Level::WARN, "Sender isn't allowed to send media to other chats");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.only_admins_send_to_targets(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let Some(conversion) = message.text().map_or(Some(AudioConversion::default()), AudioConversion::from_text) else {
+        event!(Level::WARN, "Invalid audio conversion parameters");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.invalid_audio_parameters(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    };
+
+    let mut videos = match download::media_info(&yt_dlp_config, &url, true, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
         Ok(videos) => videos,
         Err(err) => {
             event!(Level::ERROR, %err, "Getting audio/playlist info error");
@@ -397,8 +1967,9 @@ pub async fn audio_download(
             error::occured_in_message(
                 &bot,
                 chat_id,
+                thread_id,
                 message_id,
-                "Sorry, an error occurred while getting audio/playlist info. Try again later.",
+                error::ytdl_text(&err, locale, locale.audio_info_error()),
                 None,
             )
             .await?;
@@ -412,27 +1983,83 @@ pub async fn audio_download(
     if videos_len == 0 {
         event!(Level::WARN, "Playlist doesn't have audios");
 
-        error::occured_in_message(&bot, chat_id, message_id, "Playlist doesn't have audios.", None).await?;
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.playlist_without_audios(), None).await?;
 
         return Ok(EventReturn::Finish);
     }
 
     event!(Level::DEBUG, videos_len, "Got video/playlist info");
 
-    let upload_action_task = tokio::spawn({
-        let bot = bot.clone();
+    if !skip_ongoing_lives(&bot, chat_id, thread_id, message_id, locale, &mut videos).await? {
+        return Ok(EventReturn::Finish);
+    }
 
-        async move { upload_voice_action_in_loop(&bot, chat_id).await }
-    });
+    let videos_len = videos.len();
+    // A single audio is sent as is, an archive wouldn't save messages
+    let as_archive = videos_len > 1
+        && message
+            .text()
+            .and_then(DownloadOptions::from_text)
+            .is_some_and(|options| options.as_archive);
+
+    if let Some(total_size) = exceeded_total_size(&videos, yt_dlp_config.max_total_size, |video| {
+        video.estimated_audio_filesize(yt_dlp_config.max_file_size)
+    }) {
+        event!(Level::WARN, total_size, "Playlist exceeds the total size budget");
+
+        error::occured_in_message(
+            &bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &total_size_exceeded_text(locale, total_size, yt_dlp_config.max_total_size.unwrap_or_default()),
+            None,
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    }
 
-    let mut handles: Vec<JoinHandle<Result<Box<str>, DownloadErrorKind>>> = Vec::with_capacity(videos_len);
+    // Playlists like SoundCloud sets and Bandcamp albums are sent with a summary, so the tracks don't lose the album context
+    let album = if videos_len > 1 {
+        videos.front().and_then(|video| {
+            video
+                .playlist_title
+                .clone()
+                .map(|title| (title, video.performer().map(ToOwned::to_owned)))
+        })
+    } else {
+        None
+    };
 
-    for video in videos {
+    // A photo the command replies to is used as the thumbnail of all audios
+    let custom_thumbnail_url = thumbnail::from_reply_photo(&bot, &bot_config, &message).await;
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Voice, Stage::Download);
+
+    let mut handles: Vec<(usize, Box<str>, Option<String>, JoinHandle<Result<Uploaded, DownloadErrorKind>>)> =
+        Vec::with_capacity(videos_len);
+
+    for (index, video) in videos.enumerate() {
         let bot = bot.clone();
+        let chat_action = chat_action.clone();
+        let work_dir = work_dir.clone();
         let max_file_size = yt_dlp_config.max_file_size;
         let yt_dlp_full_path = yt_dlp_config.full_path.clone();
-        let receiver_video_chat_id = bot_config.receiver_video_chat_id;
+        let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+        let staged = Delivery::staged(&bot_config);
         let title = video.title.clone();
+        let performer = video.performer().map(ToOwned::to_owned);
+        let index = video.playlist_index.unwrap_or(index + 1);
+        let link_store = link_store.clone();
+        let download_queue = download_queue.clone();
+        let rate_limiter = rate_limiter.clone();
+        let estimated_size = video.estimated_audio_filesize(max_file_size);
+        let normalize_target_lufs = yt_dlp_config.normalize_target_lufs;
+        let download_timeout = download::download_timeout(&video, DOWNLOAD_MEDIA_TIMEOUT, yt_dlp_config.max_download_timeout);
+        // Clone only if it can be needed to download the audio again for a download link
+        let video_for_link = link_store.is_enabled().then(|| video.clone());
+        let custom_thumbnail_url = custom_thumbnail_url.clone();
 
         // This hack is needed because `ytdl` doesn't support downloading videos by ID from other sources, for example `coub.com `.
         // It also doesn't support uploading videos by direct URL, so we can only transmit the passeds URL.
@@ -447,97 +2074,437 @@ pub async fn audio_download(
         #[allow(clippy::cast_possible_truncation)]
         let duration = video.duration.map(|duration| duration as i64);
 
-        let temp_dir = tempdir().map_err(|err| {
-            upload_action_task.abort();
+        let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+            chat_action.stop();
 
             HandlerError::new(err)
         })?;
 
-        handles.push(tokio::spawn(async move {
-            let AudioInFS { path, thumbnail_path } = spawn_blocking({
-                let temp_dir_path = temp_dir.path().to_owned();
-
-                move || {
-                    download::audio_to_temp_dir(
-                        video,
-                        id_or_url,
-                        max_file_size,
-                        yt_dlp_full_path,
-                        temp_dir_path,
-                        DOWNLOAD_MEDIA_TIMEOUT,
-                    )
-                }
-            })
-            .await??;
-
-            let message = send::with_retries(
-                &bot,
-                SendAudio::new(receiver_video_chat_id, InputFile::fs(path))
-                    .disable_notification(true)
-                    .title_option(title)
-                    .duration_option(duration)
-                    .thumbnail_option(thumbnail_path.map(InputFile::fs)),
-                2,
-                Some(SEND_AUDIO_TIMEOUT),
-            )
-            .await?;
-
-            tokio::spawn({
-                let message_id = message.id();
-
-                async move {
-                    let _ = bot.send(DeleteMessage::new(receiver_video_chat_id, message_id)).await;
+        let video_url = video.original_url.clone().into_boxed_str();
+
+        event_bus.publish(Event::DownloadStarted {
+            chat_id: Some(chat_id),
+            url: video_url.clone(),
+            media_kind: MediaKind::Audio,
+        });
+
+        handles.push((
+            index,
+            video_url,
+            title.clone(),
+            tokio::spawn(async move {
+                let _in_progress = DownloadInProgress::start();
+                let _permit = download_queue.acquire(estimated_size, Some(chat_id)).await;
+
+                let result = spawn_blocking({
+                    let temp_dir_path = temp_dir.path().to_owned();
+                    let id_or_url = id_or_url.clone();
+                    let yt_dlp_full_path = yt_dlp_full_path.clone();
+                    let extra_args = extra_args.clone();
+
+                    move || {
+                        download::audio_to_temp_dir(
+                            video,
+                            id_or_url,
+                            max_file_size,
+                            yt_dlp_full_path,
+                            &extra_args,
+                            &retries,
+                            temp_dir_path,
+                            download_timeout,
+                            custom_thumbnail_url.as_deref(),
+                            conversion,
+                            normalize_target_lufs,
+                            process_limits,
+                        )
+                    }
+                })
+                .await?;
+
+                let AudioInFS { path, thumbnail_path } = match (result, video_for_link) {
+                    (Err(ToTempDirErrorKind::NoFormatFound { .. }), Some(video)) => {
+                        event!(Level::INFO, "Audio exceeds the Telegram limits, download it for a link");
+
+                        let AudioInFS { path, .. } = spawn_blocking({
+                            let temp_dir_path = temp_dir.path().to_owned();
+                            let max_file_size = link_store.max_file_size();
+
+                            move || {
+                                download::audio_to_temp_dir(
+                                    video,
+                                    id_or_url,
+                                    max_file_size,
+                                    yt_dlp_full_path,
+                                    &extra_args,
+                                    &retries,
+                                    temp_dir_path,
+                                    download_timeout,
+                                    None,
+                                    conversion,
+                                    normalize_target_lufs,
+                                    process_limits,
+                                )
+                            }
+                        })
+                        .await??;
+
+                        let link = link_store.insert(path, temp_dir).expect("Links should be enabled");
+
+                        return Ok(Uploaded::Link(link));
+                    }
+                    (result, _) => result?,
+                };
+
+                if as_archive {
+                    return Ok(Uploaded::Local {
+                        name: archive_entry_name(index, title.as_deref(), performer.as_deref(), &path),
+                        path,
+                        temp_dir,
+                    });
                 }
-            });
-
-            let file_id = if let Some(audio) = message.audio() {
-                audio.file_id.as_ref()
-            } else if let Some(voice) = message.voice() {
-                voice.file_id.as_ref()
-            } else {
-                unreachable!("Message should have audio or voice")
-            };
 
-            Ok(file_id.to_owned().into_boxed_str())
-        }));
+                chat_action.set_stage(Stage::Upload);
+
+                let file_size = input_file::file_size(&path);
+                let upload_progress = UploadProgress::start(
+                    bot.clone(),
+                    chat_id,
+                    thread_id,
+                    message_id,
+                    locale,
+                    input_file::upload_size(&work_dir, &path),
+                );
+                let result = send::upload_with_retries(
+                    &bot,
+                    &rate_limiter,
+                    send::Rebuildable::new({
+                        let work_dir = work_dir.clone();
+                        let uploaded = upload_progress.uploaded();
+
+                        move || {
+                            SendAudio::new(
+                                staged.chat_id(),
+                                input_file::with_progress(&work_dir, path.clone(), uploaded.clone()),
+                            )
+                            .disable_notification(true)
+                            .title_option(title.clone())
+                            .performer_option(performer.clone())
+                            .duration_option(duration)
+                            .thumbnail_option(thumbnail_path.clone().map(InputFile::fs))
+                        }
+                    }),
+                    file_size,
+                    &retries.telegram_send,
+                    SEND_AUDIO_TIMEOUT,
+                )
+                .await;
+
+                upload_progress.stop();
+
+                let message = result?;
+
+                staged.clean_up(&bot, message.id());
+
+                let file_id = if let Some(audio) = message.audio() {
+                    audio.file_id.as_ref()
+                } else if let Some(voice) = message.voice() {
+                    voice.file_id.as_ref()
+                } else {
+                    unreachable!("Message should have audio or voice")
+                };
+
+                Ok(Uploaded::File {
+                    file_id: file_id.to_owned().into_boxed_str(),
+                    caption: Caption::new(),
+                })
+            }),
+        ));
     }
 
     let mut audios_in_playlist = Vec::with_capacity(videos_len);
+    let mut archive_entries = vec![];
     let mut failed_downloads_count = 0;
 
-    for (index, handle) in handles.into_iter().enumerate() {
+    for (index, video_url, title, handle) in handles {
         match handle.await {
-            Ok(Ok(file_id)) => audios_in_playlist.push(TgAudioInPlaylist::new(file_id, index)),
+            Ok(Ok(uploaded)) => {
+                let visible_source_url = chat_config.link_is_visible.then(|| video_url.clone());
+
+                event_bus.publish(Event::DownloadFinished {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Audio,
+                });
+
+                match uploaded {
+                    Uploaded::File { file_id, caption } => audios_in_playlist
+                        .push(TgAudioInPlaylist::new(file_id, index).caption(caption.source_url(visible_source_url).build())),
+                    Uploaded::Local { name, path, temp_dir } => archive_entries.push((index, name, path, temp_dir)),
+                    Uploaded::Animation(_) | Uploaded::Chapters(_) | Uploaded::Sent { .. } => {
+                        unreachable!("Audios are sent only as files, links or in archives")
+                    }
+                    Uploaded::Link(link) => {
+                        bot.send(
+                            SendMessage::new(
+                                chat_id,
+                                download_link_text(locale, title.as_deref(), &link, link_store.retention().as_secs()),
+                            )
+                            .parse_mode(ParseMode::HTML)
+                            .message_thread_id_option(thread_id)
+                            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+                        )
+                        .await?;
+                    }
+                }
+            }
             Ok(Err(err)) => {
                 event!(Level::ERROR, %err, "Error while downloading audio");
 
+                event_bus.publish(Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Audio,
+                    error: err.to_string().into_boxed_str(),
+                });
+
                 failed_downloads_count += 1;
             }
             Err(err) => {
                 event!(Level::ERROR, %err, "Error while joining handle");
 
+                event_bus.publish(Event::DownloadFailed {
+                    chat_id: Some(chat_id),
+                    url: video_url,
+                    media_kind: MediaKind::Audio,
+                    error: err.to_string().into_boxed_str(),
+                });
+
                 failed_downloads_count += 1;
             }
         }
     }
 
-    upload_action_task.abort();
+    chat_action.stop();
 
     if failed_downloads_count > 0 {
         event!(Level::ERROR, "Failed downloads count is {failed_downloads_count}");
 
-        error::download_audios_in_message(&bot, failed_downloads_count, chat_id, message_id, Some(ParseMode::HTML)).await?;
+        error::download_audios_in_message(
+            &bot,
+            locale,
+            failed_downloads_count,
+            chat_id,
+            thread_id,
+            message_id,
+            Some(ParseMode::HTML),
+        )
+        .await?;
+    }
+
+    if let Some((title, performer)) = &album {
+        let tracks_count = audios_in_playlist.len() + archive_entries.len();
+
+        if tracks_count > 0 {
+            bot.send(
+                SendMessage::new(chat_id, album_summary_text(locale, title, performer.as_deref(), tracks_count))
+                    .parse_mode(ParseMode::HTML)
+                    .message_thread_id_option(thread_id)
+                    .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+            )
+            .await?;
+        }
     }
 
-    let input_media_list = {
+    let input_media_list: Vec<InputMedia> = if archive_entries.is_empty() {
         audios_in_playlist.sort_by(|a, b| a.index.cmp(&b.index));
         audios_in_playlist
             .into_iter()
-            .map(|video| InputMediaVideo::new(InputFile::id(video.file_id.into_string())))
+            .map(|audio| {
+                InputMediaAudio::new(InputFile::id(audio.file_id.into_string()))
+                    .caption_option(audio.caption)
+                    .parse_mode(ParseMode::HTML)
+                    .into()
+            })
             .collect()
+    } else {
+        archive_entries.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+
+        let name = album
+            .as_ref()
+            .map(|(title, _)| fs::sanitize_file_name(title))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| locale.tracks().to_owned());
+        // Temp dirs of the audios are kept until the archives are written
+        let (entries, _temp_dirs): (Vec<_>, Vec<_>) = archive_entries
+            .into_iter()
+            .map(|(_, entry_name, path, temp_dir)| ((entry_name, path), temp_dir))
+            .unzip();
+
+        match upload_archives(
+            &bot,
+            &rate_limiter,
+            &bot_config,
+            &work_dir,
+            &name,
+            entries,
+            yt_dlp_config.max_file_size,
+            &retries.telegram_send,
+        )
+        .await
+        {
+            Ok(documents) => documents.into_iter().map(Into::into).collect(),
+            Err(err) => {
+                event!(Level::ERROR, %err, "Error sending audios in an archive");
+
+                event_bus.publish(Event::SendFailed {
+                    chat_id: Some(chat_id),
+                    media_kind: MediaKind::Audio,
+                    error: err.to_string().into_boxed_str(),
+                });
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.archive_error(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        }
     };
 
-    send::media_groups(&bot, chat_id, input_media_list, Some(message_id), Some(SEND_AUDIO_TIMEOUT)).await?;
+    send::media_groups(
+        &bot,
+        &rate_limiter,
+        chat_id,
+        thread_id,
+        input_media_list.clone(),
+        Some(message_id),
+        &retries.telegram_send_media_group,
+        Some(SEND_AUDIO_TIMEOUT),
+    )
+    .await
+    .map_err(|err| {
+        event_bus.publish(Event::SendFailed {
+            chat_id: Some(chat_id),
+            media_kind: MediaKind::Audio,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        err
+    })?;
+
+    send_to_targets(
+        &bot,
+        &rate_limiter,
+        locale,
+        chat_id,
+        thread_id,
+        message_id,
+        &target_chats,
+        None,
+        input_media_list.clone(),
+        &[],
+        &retries.telegram_send_media_group,
+    )
+    .await?;
+
+    send_to_mirrors(
+        &bot,
+        &rate_limiter,
+        bot_config.get_mirror_chat_ids(chat_id),
+        input_media_list,
+        &[],
+        &retries.telegram_send_media_group,
+    )
+    .await;
+
+    // The message is kept if some media failed, so the link isn't lost
+    if failed_downloads_count == 0 {
+        delete_original_message(&bot, &bot_config, chat_id, message_id).await;
+    }
+
+    Ok(EventReturn::Finish)
+}
+
+/// Downloads audios of links in messages without commands in chats where audio is the default media type.
+/// Links without media are skipped silently like in [`video_download_quite`], the info is cached, so [`audio_download`] reuses it.
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn audio_download_quite(
+    bot: Arc<Bot>,
+    context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    let url = context
+        .get::<Box<str>>("video_url")
+        .cloned()
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+
+    Span::current()
+        .record("chat_id", message.chat().id())
+        .record("message_id", message.id())
+        .record("url", &*url);
+
+    match download::media_info(&yt_dlp_config, &url, true, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(videos) if videos.iter().any(|video| !video.is_ongoing_live()) => {}
+        Ok(_) => {
+            event!(Level::WARN, "Playlist doesn't have audios");
+
+            return Ok(EventReturn::Finish);
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting audio/playlist info error");
+
+            return Ok(EventReturn::Finish);
+        }
+    }
+
+    audio_download(
+        bot,
+        context,
+        message,
+        Extension(yt_dlp_config),
+        Extension(retries),
+        Extension(bot_config),
+        Extension(event_bus),
+        Extension(work_dir),
+        Extension(process_limits),
+        Extension(link_store),
+        Extension(download_queue),
+        Extension(rate_limiter),
+        Extension(chat_config_store),
+    )
+    .await
+}
+
+/// Replaces the inline message with a download link to media exceeding the Telegram limits
+async fn edit_with_link(bot: &Bot, text: String, inline_message_id: &str, progress: &InlineProgress) -> Result<(), DownloadErrorKind> {
+    progress.stop();
+
+    bot.send(
+        EditMessageText::new(text)
+            .inline_message_id(inline_message_id)
+            .reply_markup(InlineKeyboardMarkup::new([[]]))
+            .parse_mode(ParseMode::HTML),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Replaces the inline message with the cancellation notice, processes of the download are already killed by the cancel token
+async fn edit_cancelled(bot: &Bot, locale: Locale, inline_message_id: &str, progress: &InlineProgress) -> HandlerResult {
+    event!(Level::INFO, "Download cancelled");
+
+    progress.stop();
+
+    error::occured_in_chosen_inline_result(bot, locale.download_cancelled(), inline_message_id, None).await?;
 
     Ok(EventReturn::Finish)
 }
@@ -547,12 +2514,22 @@ pub async fn media_download_chosen_inline_result(
     bot: Arc<Bot>,
     ChosenInlineResult {
         result_id,
+        from,
         inline_message_id,
         query: url,
         ..
     }: ChosenInlineResult,
     Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
     Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(transcode): Extension<Transcode>,
+    Extension(cancel_store): Extension<CancelStore>,
 ) -> HandlerResult {
     Span::current().record("result_id", result_id.as_ref());
     Span::current().record("inline_message_id", inline_message_id.as_deref());
@@ -560,26 +2537,47 @@ pub async fn media_download_chosen_inline_result(
 
     // If `result_id` starts with `audio_` then it's audio, else it's video
     let download_video = result_id.starts_with("video_");
+    // Results of playlist entries have the position of the entry after the kind, see `media_select_inline_query`
+    let playlist_index = result_id.split('_').nth(1).and_then(|playlist_index| playlist_index.parse().ok());
     let inline_message_id = inline_message_id.as_deref().unwrap();
+    let locale = bot_config.user_locale(from.id, from.language_code.as_deref());
 
     event!(Level::DEBUG, "Got url");
 
-    let videos = match spawn_blocking({
-        let full_path = yt_dlp_config.full_path.clone();
-        let url = url.clone();
-
-        move || get_media_or_playlist_info(full_path, url, false, GET_INFO_TIMEOUT)
-    })
-    .await
-    .map_err(HandlerError::new)?
-    {
+    let cancellable = cancel_store.insert(from.id);
+    let progress = InlineProgress::start(bot.clone(), inline_message_id.into(), locale, Stage::Info, &cancellable.key);
+
+    let get_info = async {
+        match playlist_index {
+            Some(playlist_index) => {
+                download::playlist_entry_info(
+                    &yt_dlp_config,
+                    &url,
+                    playlist_index,
+                    &retries.yt_dlp_info,
+                    GET_INFO_TIMEOUT,
+                    process_limits,
+                )
+                .await
+            }
+            None => download::media_info(&yt_dlp_config, &url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await,
+        }
+    };
+    let videos = tokio::select! {
+        biased;
+        () = cancellable.token.cancelled() => return edit_cancelled(&bot, locale, inline_message_id, &progress).await,
+        videos = cancellable.token.scope(get_info) => videos,
+    };
+    let videos = match videos {
         Ok(videos) => videos,
         Err(err) => {
             event!(Level::ERROR, %err, "Getting video/playlist info error");
 
+            progress.stop();
+
             error::occured_in_chosen_inline_result(
                 &bot,
-                "Sorry, an error occurred while getting video/playlist info. Try again later.",
+                error::ytdl_text(&err, locale, locale.video_info_error()),
                 inline_message_id,
                 None,
             )
@@ -592,79 +2590,182 @@ pub async fn media_download_chosen_inline_result(
     let Some(video) = videos.front().cloned() else {
         event!(Level::ERROR, "Video not found");
 
-        error::occured_in_chosen_inline_result(&bot, "Sorry, video not found.", inline_message_id, None).await?;
+        progress.stop();
+
+        error::occured_in_chosen_inline_result(&bot, locale.video_not_found(), inline_message_id, None).await?;
 
         return Ok(EventReturn::Finish);
     };
 
+    if video.is_ongoing_live() {
+        event!(Level::WARN, "Ongoing live stream is skipped");
+
+        progress.stop();
+
+        error::occured_in_chosen_inline_result(&bot, locale.live_stream_not_supported(), inline_message_id, None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
     event!(Level::DEBUG, "Got video/audio info");
 
     drop(videos);
 
-    let temp_dir = tempdir().map_err(HandlerError::new)?;
+    progress.set_stage(Stage::Download);
+
+    let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+        progress.stop();
+
+        HandlerError::new(err)
+    })?;
+
+    let media_kind = if download_video { MediaKind::Video } else { MediaKind::Audio };
+    let video_url = url.clone();
+    let extra_args = yt_dlp_config.get_media_extra_args(&url, video.requires_cookies);
+
+    event_bus.publish(Event::DownloadStarted {
+        chat_id: None,
+        url: video_url.clone(),
+        media_kind,
+    });
+
+    let _in_progress = DownloadInProgress::start();
+
+    let download_timeout = download::download_timeout(&video, DOWNLOAD_MEDIA_TIMEOUT, yt_dlp_config.max_download_timeout);
+    let estimated_size = if download_video {
+        video.estimated_video_filesize(yt_dlp_config.max_file_size, yt_dlp_config.fps_weight)
+    } else {
+        video.estimated_audio_filesize(yt_dlp_config.max_file_size)
+    };
+    let title = video.title.clone();
+    // Clone only if it can be needed to download the media again for a download link
+    let video_for_link = link_store.is_enabled().then(|| video.clone());
+
+    let download = async {
+        let _permit = download_queue.acquire(estimated_size, None).await;
 
-    let handle: Result<(), DownloadErrorKind> = async {
         if download_video {
             #[allow(clippy::cast_possible_truncation)]
             let (height, width, duration) = (video.height, video.width, video.duration.map(|duration| duration as i64));
+            let thumbnail_urls = video.thumbnail_urls();
 
-            let VideoInFS { path, thumbnail_path } = spawn_blocking({
+            let result = spawn_blocking({
                 let temp_dir_path = temp_dir.path().to_owned();
+                let yt_dlp_config = yt_dlp_config.clone();
+                let extra_args = extra_args.clone();
 
                 move || {
                     download::video(
                         video,
                         yt_dlp_config.max_file_size,
+                        yt_dlp_config.fps_weight,
+                        yt_dlp_config.max_format_attempts,
                         &yt_dlp_config.full_path,
+                        &extra_args,
+                        &retries,
                         temp_dir_path,
-                        DOWNLOAD_MEDIA_TIMEOUT,
+                        download_timeout,
+                        None,
+                        None,
+                        transcode,
+                        process_limits,
                     )
                 }
             })
-            .await??;
+            .await?;
 
-            let message = send::with_retries(
+            let VideoInFS { path, thumbnail_path } = match (result, video_for_link) {
+                (Err(StreamErrorKind::NoFormatFound { .. }), Some(video)) => {
+                    event!(Level::INFO, "Video exceeds the Telegram limits, download it for a link");
+
+                    let VideoInFS { path, .. } = spawn_blocking({
+                        let temp_dir_path = temp_dir.path().to_owned();
+                        let max_file_size = link_store.max_file_size();
+
+                        move || {
+                            download::video(
+                                video,
+                                max_file_size,
+                                yt_dlp_config.fps_weight,
+                                yt_dlp_config.max_format_attempts,
+                                &yt_dlp_config.full_path,
+                                &extra_args,
+                                &retries,
+                                temp_dir_path,
+                                download_timeout,
+                                None,
+                                None,
+                                transcode,
+                                process_limits,
+                            )
+                        }
+                    })
+                    .await??;
+
+                    let link = link_store.insert(path, temp_dir).expect("Links should be enabled");
+
+                    let text = download_link_text(locale, title.as_deref(), &link, link_store.retention().as_secs());
+
+                    return edit_with_link(&bot, text, inline_message_id, &progress).await;
+                }
+                (result, _) => result?,
+            };
+
+            let thumbnail_path = thumbnail_or_retry(thumbnail_path, thumbnail_urls, temp_dir.path().to_owned(), process_limits).await;
+
+            let file_size = input_file::file_size(&path);
+            let uploaded = progress.track_upload(input_file::upload_size(&work_dir, &path));
+            let message = send::upload_with_retries(
                 &bot,
-                SendVideo::new(bot_config.receiver_video_chat_id, InputFile::fs(path))
-                    .disable_notification(true)
-                    .width_option(width)
-                    .height_option(height)
-                    .duration_option(duration)
-                    .thumbnail_option(thumbnail_path.map(InputFile::fs))
-                    .supports_streaming(true),
-                2,
-                Some(SEND_VIDEO_TIMEOUT),
+                &rate_limiter,
+                send::Rebuildable::new({
+                    let receiver_chat_id = Delivery::staged(&bot_config).chat_id();
+                    let work_dir = work_dir.clone();
+
+                    move || {
+                        SendVideo::new(
+                            receiver_chat_id,
+                            input_file::with_progress(&work_dir, path.clone(), uploaded.clone()),
+                        )
+                        .disable_notification(true)
+                        .width_option(width)
+                        .height_option(height)
+                        .duration_option(duration)
+                        .thumbnail_option(thumbnail_path.clone().map(InputFile::fs))
+                        .supports_streaming(true)
+                    }
+                }),
+                file_size,
+                &retries.telegram_send,
+                SEND_VIDEO_TIMEOUT,
             )
             .await?;
 
             drop(temp_dir);
 
-            tokio::spawn({
-                let message_id = message.id();
-                let bot = bot.clone();
+            Delivery::staged(&bot_config).clean_up(&bot, message.id());
 
-                async move {
-                    let _ = bot.send(DeleteMessage::new(bot_config.receiver_video_chat_id, message_id)).await;
-                }
-            });
+            progress.stop();
 
             send::with_retries(
                 &bot,
+                &rate_limiter,
                 EditMessageMedia::new(InputMediaVideo::new(InputFile::id(message.video().unwrap().file_id.as_ref())))
                     .inline_message_id(inline_message_id)
                     .reply_markup(InlineKeyboardMarkup::new([[]])),
-                2,
+                &retries.telegram_send,
                 Some(SEND_VIDEO_TIMEOUT),
             )
             .await?;
         } else {
-            let title = video.title.clone();
-
             #[allow(clippy::cast_possible_truncation)]
             let duration = video.duration.map(|duration| duration as i64);
 
-            let AudioInFS { path, thumbnail_path } = spawn_blocking({
+            let result = spawn_blocking({
                 let temp_dir_path = temp_dir.path().to_owned();
+                let yt_dlp_config = yt_dlp_config.clone();
+                let extra_args = extra_args.clone();
+                let url = url.clone();
 
                 move || {
                     download::audio_to_temp_dir(
@@ -672,35 +2773,84 @@ pub async fn media_download_chosen_inline_result(
                         url,
                         yt_dlp_config.max_file_size,
                         &yt_dlp_config.full_path,
+                        &extra_args,
+                        &retries,
                         temp_dir_path,
-                        DOWNLOAD_MEDIA_TIMEOUT,
+                        download_timeout,
+                        None,
+                        AudioConversion::default(),
+                        yt_dlp_config.normalize_target_lufs,
+                        process_limits,
                     )
                 }
             })
-            .await??;
+            .await?;
+
+            let AudioInFS { path, thumbnail_path } = match (result, video_for_link) {
+                (Err(ToTempDirErrorKind::NoFormatFound { .. }), Some(video)) => {
+                    event!(Level::INFO, "Audio exceeds the Telegram limits, download it for a link");
+
+                    let AudioInFS { path, .. } = spawn_blocking({
+                        let temp_dir_path = temp_dir.path().to_owned();
+                        let max_file_size = link_store.max_file_size();
+
+                        move || {
+                            download::audio_to_temp_dir(
+                                video,
+                                url,
+                                max_file_size,
+                                &yt_dlp_config.full_path,
+                                &extra_args,
+                                &retries,
+                                temp_dir_path,
+                                download_timeout,
+                                None,
+                                AudioConversion::default(),
+                                yt_dlp_config.normalize_target_lufs,
+                                process_limits,
+                            )
+                        }
+                    })
+                    .await??;
+
+                    let link = link_store.insert(path, temp_dir).expect("Links should be enabled");
+
+                    let text = download_link_text(locale, title.as_deref(), &link, link_store.retention().as_secs());
+
+                    return edit_with_link(&bot, text, inline_message_id, &progress).await;
+                }
+                (result, _) => result?,
+            };
 
-            let message = send::with_retries(
+            let file_size = input_file::file_size(&path);
+            let uploaded = progress.track_upload(input_file::upload_size(&work_dir, &path));
+            let message = send::upload_with_retries(
                 &bot,
-                SendAudio::new(bot_config.receiver_video_chat_id, InputFile::fs(path))
-                    .disable_notification(true)
-                    .title_option(title)
-                    .duration_option(duration)
-                    .thumbnail_option(thumbnail_path.map(InputFile::fs)),
-                2,
-                Some(SEND_AUDIO_TIMEOUT),
+                &rate_limiter,
+                send::Rebuildable::new({
+                    let receiver_chat_id = Delivery::staged(&bot_config).chat_id();
+                    let work_dir = work_dir.clone();
+
+                    move || {
+                        SendAudio::new(
+                            receiver_chat_id,
+                            input_file::with_progress(&work_dir, path.clone(), uploaded.clone()),
+                        )
+                        .disable_notification(true)
+                        .title_option(title.clone())
+                        .duration_option(duration)
+                        .thumbnail_option(thumbnail_path.clone().map(InputFile::fs))
+                    }
+                }),
+                file_size,
+                &retries.telegram_send,
+                SEND_AUDIO_TIMEOUT,
             )
             .await?;
 
             drop(temp_dir);
 
-            tokio::spawn({
-                let message_id = message.id();
-                let bot = bot.clone();
-
-                async move {
-                    let _ = bot.send(DeleteMessage::new(bot_config.receiver_video_chat_id, message_id)).await;
-                }
-            });
+            Delivery::staged(&bot_config).clean_up(&bot, message.id());
 
             let file_id = if let Some(audio) = message.audio() {
                 audio.file_id.as_ref()
@@ -710,60 +2860,118 @@ pub async fn media_download_chosen_inline_result(
                 unreachable!("Message should have audio or voice")
             };
 
+            progress.stop();
+
             send::with_retries(
                 &bot,
+                &rate_limiter,
                 EditMessageMedia::new(InputMediaVideo::new(InputFile::id(file_id))).inline_message_id(inline_message_id),
-                2,
+                &retries.telegram_send,
                 Some(SEND_AUDIO_TIMEOUT),
             )
             .await?;
         }
 
         Ok(())
-    }
-    .await;
+    };
+    // Processes of the download are killed by the token, and its temp dir is removed when the future is dropped
+    let handle: Result<(), DownloadErrorKind> = tokio::select! {
+        biased;
+        () = cancellable.token.cancelled() => {
+            event_bus.publish(Event::DownloadFailed {
+                chat_id: None,
+                url: video_url,
+                media_kind,
+                error: "Cancelled".into(),
+            });
+
+            return edit_cancelled(&bot, locale, inline_message_id, &progress).await;
+        }
+        handle = cancellable.token.scope(download) => handle,
+    };
 
     if let Err(err) = handle {
         event!(Level::ERROR, %err, "Error while downloading media");
 
-        error::occured_in_chosen_inline_result(
-            &bot,
-            "Sorry, an error occurred while downloading media. Try again later.",
-            inline_message_id,
-            None,
-        )
-        .await?;
+        progress.stop();
+
+        event_bus.publish(Event::DownloadFailed {
+            chat_id: None,
+            url: video_url,
+            media_kind,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        error::occured_in_chosen_inline_result(&bot, locale.download_media_error(), inline_message_id, None).await?;
+    } else {
+        event_bus.publish(Event::DownloadFinished {
+            chat_id: None,
+            url: video_url,
+            media_kind,
+        });
     }
 
     Ok(EventReturn::Finish)
 }
 
+/// Telegram shows only JPEG thumbnails of inline query results,
+/// so thumbnails in other formats (`webp`, `png`, etc.) are converted by the HTTP server if links are enabled
+fn inline_thumbnail_url(entry: &PlaylistEntry, link_store: &LinkStore) -> Option<String> {
+    let url = entry.thumbnail()?;
+    let is_jpeg = Url::parse(url).is_ok_and(|parsed_url| {
+        let path = parsed_url.path().to_ascii_lowercase();
+
+        path.ends_with(".jpg") || path.ends_with(".jpeg")
+    });
+
+    if is_jpeg {
+        Some(url.to_owned())
+    } else {
+        link_store.insert_thumbnail(url, Duration::from_secs(SELECT_INLINE_QUERY_CACHE_TIME.unsigned_abs()))
+    }
+}
+
 #[instrument(skip_all, fields(query_id, url))]
 pub async fn media_select_inline_query(
     bot: Arc<Bot>,
     InlineQuery {
-        id: query_id, query: url, ..
+        id: query_id,
+        from,
+        query: url,
+        offset,
+        ..
     }: InlineQuery,
     Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(link_store): Extension<LinkStore>,
 ) -> HandlerResult {
     Span::current().record("query_id", query_id.as_ref());
     Span::current().record("url", url.as_ref());
 
+    // Inline queries aren't bound to a chat, so only the language of the user is used
+    let locale = bot_config.user_locale(from.id, from.language_code.as_deref());
+
     event!(Level::DEBUG, "Got url");
 
-    let videos = match spawn_blocking(move || {
-        get_media_or_playlist_info(&yt_dlp_config.full_path, url, true, GET_MEDIA_OR_PLAYLIST_INFO_INLINE_QUERY_TIMEOUT)
-    })
+    // Only titles and thumbnails are needed for results, formats are got for the chosen entry
+    let entries = match download::playlist_entries(
+        &yt_dlp_config,
+        &url,
+        &retries.yt_dlp_info,
+        GET_MEDIA_OR_PLAYLIST_INFO_INLINE_QUERY_TIMEOUT,
+        process_limits,
+    )
     .await
-    .map_err(HandlerError::new)?
     {
-        Ok(videos) => videos,
+        Ok(entries) => entries,
         Err(err) => {
             event!(Level::ERROR, %err, "Getting media/playlist info error");
 
             error::occured_in_chosen_inline_result(
                 &bot,
-                "Sorry, an error occurred while getting media/playlist info.",
+                error::ytdl_text(&err, locale, locale.media_info_error()),
                 query_id.as_ref(),
                 None,
             )
@@ -773,25 +2981,34 @@ pub async fn media_select_inline_query(
         }
     };
 
-    let videos_len = videos.len();
+    let entries_len = entries.len();
 
-    if videos_len == 0 {
+    if entries_len == 0 {
         event!(Level::WARN, "Playlist doesn't have videos");
 
-        error::occured_in_inline_query_occured(&bot, query_id.as_ref(), "Playlist doesn't have videos.").await?;
+        error::occured_in_inline_query_occured(&bot, query_id.as_ref(), locale.playlist_without_videos()).await?;
 
         return Ok(EventReturn::Finish);
     }
 
-    event!(Level::DEBUG, videos_len, "Got video/playlist info");
+    // The offset is the index of the first video of the page, it's empty for the first page
+    let start = offset.parse().unwrap_or(0);
+    let next_start = start + SELECT_INLINE_QUERY_PAGE_SIZE;
 
-    let mut results: Vec<InlineQueryResult> = Vec::with_capacity(videos_len);
+    event!(Level::DEBUG, entries_len, start, "Got video/playlist entries");
 
-    for video in videos {
-        let title = video.title.as_deref().unwrap_or("Untitled");
+    let mut results: Vec<InlineQueryResult> = Vec::with_capacity(SELECT_INLINE_QUERY_PAGE_SIZE * 2);
+
+    for entry in entries.iter().skip(start).take(SELECT_INLINE_QUERY_PAGE_SIZE) {
+        let title = entry.title.as_deref().unwrap_or(locale.untitled());
         let title_html = html_code(html_quote(title));
+        let thumbnail_url = inline_thumbnail_url(entry, &link_store);
 
-        let result_id = Uuid::new_v4();
+        // The position of the entry is passed to the chosen result to get only its info, a single video doesn't need it
+        let result_id = match entry.playlist_index.filter(|_| entries_len > 1) {
+            Some(playlist_index) => format!("{playlist_index}_{}", Uuid::new_v4()),
+            None => Uuid::new_v4().to_string(),
+        };
 
         results.push(
             InlineQueryResultArticle::new(
@@ -800,9 +3017,10 @@ pub async fn media_select_inline_query(
                 InputTextMessageContent::new(&title_html).parse_mode(ParseMode::HTML),
             )
             .title(title)
-            .description("Click to download video")
+            .description(locale.click_to_download_video())
+            .thumbnail_url_option(thumbnail_url.clone())
             .reply_markup(InlineKeyboardMarkup::new([[
-                InlineKeyboardButton::new("Video downloading...").callback_data("video_download")
+                InlineKeyboardButton::new(locale.video_downloading()).callback_data("video_download")
             ]]))
             .into(),
         );
@@ -813,9 +3031,10 @@ pub async fn media_select_inline_query(
                 InputTextMessageContent::new(&title_html).parse_mode(ParseMode::HTML),
             )
             .title(title)
-            .description("Click to download audio")
+            .description(locale.click_to_download_audio())
+            .thumbnail_url_option(thumbnail_url)
             .reply_markup(InlineKeyboardMarkup::new([[
-                InlineKeyboardButton::new("Audio downloading...").callback_data("audio_download")
+                InlineKeyboardButton::new(locale.audio_downloading()).callback_data("audio_download")
             ]]))
             .into(),
         );
@@ -824,7 +3043,12 @@ pub async fn media_select_inline_query(
     bot.send(
         AnswerInlineQuery::new(query_id, results)
             .is_personal(false)
-            .cache_time(SELECT_INLINE_QUERY_CACHE_TIME),
+            .cache_time(SELECT_INLINE_QUERY_CACHE_TIME)
+            .next_offset(if next_start < entries_len {
+                next_start.to_string()
+            } else {
+                String::new()
+            }),
     )
     .await?;
 
@@ -0,0 +1,165 @@
+use crate::{
+    config::{Bot as BotConfig, ProcessLimits, Retries, YtDlp},
+    download,
+    handlers_utils::{error, locale, topic},
+    models::format::{self, Kind},
+};
+
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendMessage,
+    types::{Message, ReplyParameters},
+    utils::text::html_quote,
+    Bot, Context, Extension,
+};
+use tracing::{event, instrument, Level, Span};
+
+const GET_INFO_TIMEOUT: u64 = 45;
+/// Telegram limits a message to 4096 characters, some are left for the title
+const MAX_TABLE_LEN: usize = 3800;
+
+/// Codecs like `avc1.64001F` are cut to their family, the profile doesn't matter for picking a format
+fn short_codec(codec: &str) -> &str {
+    codec.split('.').next().unwrap_or(codec)
+}
+
+fn format_row(any: &format::Any, kind: &Kind<'_>) -> String {
+    let (container, codec, resolution) = match kind {
+        Kind::Combined(audio, video) => (
+            video.container.as_str(),
+            format!(
+                "{}+{}",
+                video.codec.as_ref().map_or("unknown", |codec| short_codec(codec.as_str())),
+                short_codec(audio.codec.as_raw_str())
+            ),
+            video.resolution(),
+        ),
+        Kind::Video(video) => (
+            video.container.as_str(),
+            video
+                .codec
+                .as_ref()
+                .map_or("unknown", |codec| short_codec(codec.as_str()))
+                .to_owned(),
+            video.resolution(),
+        ),
+        Kind::Audio(audio) => (
+            any.ext.as_str(),
+            short_codec(audio.codec.as_raw_str()).to_owned(),
+            "audio only".to_owned(),
+        ),
+    };
+
+    let size = match (any.filesize, any.filesize_approx) {
+        (Some(filesize), _) => format!("{:.1}MB", filesize / 1024.0 / 1024.0),
+        (None, Some(filesize_approx)) => format!("~{:.1}MB", filesize_approx / 1024.0 / 1024.0),
+        (None, None) => "?".to_owned(),
+    };
+
+    format!(
+        "{id:<8} {container:<4} {codec:<10} {resolution:<10} {size:>9} {language}",
+        id = any.id,
+        language = any.language.as_deref().unwrap_or_default(),
+    )
+    .trim_end()
+    .to_owned()
+}
+
+/// Replies with the formats of the media that the bot can download, so users can pick an exact one
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn formats(
+    bot: Bot,
+    mut context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+) -> HandlerResult {
+    let url = context
+        .remove::<Box<str>>("video_url")
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    let video = match download::media_info(&yt_dlp_config, &url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(mut videos) => match videos.next() {
+            Some(video) => video,
+            None => {
+                event!(Level::WARN, "Video not found");
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_not_found(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        },
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting video info error");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                error::ytdl_text(&err, locale, locale.video_info_error_single()),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+    };
+
+    let rows: Vec<String> = video
+        .formats()
+        .iter()
+        .filter_map(|any| any.kind().ok().map(|kind| format_row(any, &kind)))
+        .collect();
+
+    event!(Level::DEBUG, formats_count = rows.len(), "Got formats");
+
+    if rows.is_empty() {
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.formats_not_found(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let mut table = String::new();
+    let mut skipped_count = 0;
+
+    for row in &rows {
+        if table.len() + row.len() > MAX_TABLE_LEN {
+            skipped_count += 1;
+            continue;
+        }
+
+        table.push_str(row);
+        table.push('\n');
+    }
+
+    let title = video.title.as_deref().unwrap_or(locale.untitled());
+    let mut text = format!("<b>{}</b>\n<pre>{}</pre>", html_quote(title), html_quote(table.trim_end()));
+
+    if skipped_count > 0 {
+        text.push('\n');
+        text.push_str(&locale.formats_skipped(skipped_count));
+    }
+
+    bot.send(
+        SendMessage::new(chat_id, text)
+            .message_thread_id_option(thread_id)
+            .parse_mode(ParseMode::HTML)
+            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
@@ -0,0 +1,373 @@
+use super::download::download_and_send_videos;
+use crate::{
+    chat_config::ChatConfigStore,
+    config::{Bot as BotConfig, ProcessLimits, Retries, Summary as SummaryConfig, Transcode, WorkDir, YtDlp},
+    download,
+    events::EventBus,
+    handlers_utils::{
+        chat_action::{ActionKind, ChatAction, Stage},
+        error, locale, topic,
+    },
+    jobs::{Job, JobStore},
+    links::LinkStore,
+    locale::Locale,
+    models::{DownloadOptions, VideoInYT, VideosInYT},
+    queue::DownloadQueue,
+    rate_limiter::RateLimiter,
+    selections::{Action, Selection, SelectionStore},
+};
+
+use std::sync::Arc;
+use telers::{
+    errors::SessionErrorKind,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::{AnswerCallbackQuery, DeleteMessage, EditMessageReplyMarkup, SendMessage},
+    types::{CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, Message, ReplyParameters},
+    Bot, Context, Extension,
+};
+use tracing::{event, instrument, Level, Span};
+
+const GET_INFO_TIMEOUT: u64 = 45;
+const PAGE_SIZE: usize = 8;
+const MAX_TITLE_LEN: usize = 40;
+
+pub fn format_duration(duration: f64) -> String {
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let duration = duration as u64;
+    let (hours, minutes, seconds) = (duration / 3600, duration / 60 % 60, duration % 60);
+
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
+
+fn entry_button_text(locale: Locale, index: usize, video: &VideoInYT, selected: bool) -> String {
+    let mark = if selected { "✅" } else { "▫️" };
+    let title = video.title.as_deref().unwrap_or(locale.untitled());
+    let title = match title.char_indices().nth(MAX_TITLE_LEN) {
+        Some((end, _)) => format!("{}...", &title[..end]),
+        None => title.to_owned(),
+    };
+
+    match video.duration {
+        Some(duration) => format!("{mark} {}. {title} ({})", index + 1, format_duration(duration)),
+        None => format!("{mark} {}. {title}", index + 1),
+    }
+}
+
+fn selection_keyboard(locale: Locale, token: &str, selection: &Selection, page: usize) -> InlineKeyboardMarkup {
+    let pages_count = selection.videos.len().div_ceil(PAGE_SIZE);
+    let page = page.min(pages_count.saturating_sub(1));
+    let start = page * PAGE_SIZE;
+
+    let mut rows: Vec<Vec<InlineKeyboardButton>> = selection
+        .videos
+        .iter()
+        .zip(&selection.selected)
+        .enumerate()
+        .skip(start)
+        .take(PAGE_SIZE)
+        .map(|(index, (video, selected))| {
+            vec![InlineKeyboardButton::new(entry_button_text(locale, index, video, *selected))
+                .callback_data(Action::Toggle(index).to_callback_data(token))]
+        })
+        .collect();
+
+    if pages_count > 1 {
+        let mut navigation_row = vec![];
+
+        if page > 0 {
+            navigation_row.push(InlineKeyboardButton::new(locale.back()).callback_data(Action::Page(page - 1).to_callback_data(token)));
+        }
+        if page + 1 < pages_count {
+            navigation_row.push(
+                InlineKeyboardButton::new(locale.next_page(page + 2, pages_count))
+                    .callback_data(Action::Page(page + 1).to_callback_data(token)),
+            );
+        }
+
+        rows.push(navigation_row);
+    }
+
+    rows.push(vec![
+        InlineKeyboardButton::new(locale.download_selected(selection.selected_count()))
+            .callback_data(Action::Download.to_callback_data(token)),
+        InlineKeyboardButton::new(locale.cancel()).callback_data(Action::Cancel.to_callback_data(token)),
+    ]);
+
+    InlineKeyboardMarkup::new(rows)
+}
+
+/// Answers the callback query and edits the selection keyboard.
+/// `keyboard` is `None` if the selection is expired or belongs to another user.
+async fn update_keyboard(
+    bot: &Bot,
+    locale: Locale,
+    callback_query_id: Box<str>,
+    keyboard: Option<(i64, Option<i64>, InlineKeyboardMarkup)>,
+) -> Result<(), SessionErrorKind> {
+    let Some((chat_id, keyboard_message_id, keyboard)) = keyboard else {
+        bot.send(
+            AnswerCallbackQuery::new(callback_query_id)
+                .text(locale.selection_unavailable())
+                .show_alert(true),
+        )
+        .await?;
+
+        return Ok(());
+    };
+
+    bot.send(AnswerCallbackQuery::new(callback_query_id)).await?;
+
+    if let Some(keyboard_message_id) = keyboard_message_id {
+        bot.send(
+            EditMessageReplyMarkup::new()
+                .chat_id(chat_id)
+                .message_id(keyboard_message_id)
+                .reply_markup(keyboard),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Replies with a keyboard of playlist entries, so the user can pick specific videos to download
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn playlist_select(
+    bot: Bot,
+    mut context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(selection_store): Extension<SelectionStore>,
+) -> HandlerResult {
+    let url = context
+        .remove::<Box<str>>("video_url")
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    let Some(user_id) = message.from().as_ref().map(|user| user.id) else {
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.selection_anonymous_sender(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    };
+
+    let videos = match download::media_info(&yt_dlp_config, &url, true, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(videos) => videos.collect::<Vec<_>>(),
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting video/playlist info error");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                error::ytdl_text(&err, locale, locale.video_info_error()),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+    };
+
+    if videos.is_empty() {
+        event!(Level::WARN, "Playlist doesn't have videos");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.playlist_without_videos(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    event!(Level::DEBUG, videos_len = videos.len(), "Got video/playlist info");
+
+    let token = selection_store.insert(user_id, chat_id, thread_id, message_id, videos);
+    let keyboard = selection_store
+        .with(&token, user_id, |selection| selection_keyboard(locale, &token, selection, 0))
+        .expect("Selection should be in the store because it was just inserted");
+
+    let keyboard_message = bot
+        .send(
+            SendMessage::new(chat_id, locale.selection_prompt())
+                .reply_markup(keyboard)
+                .message_thread_id_option(thread_id)
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        )
+        .await?;
+
+    selection_store.with(&token, user_id, |selection| {
+        selection.keyboard_message_id = Some(keyboard_message.id());
+    });
+
+    Ok(EventReturn::Finish)
+}
+
+#[allow(clippy::too_many_arguments)]
+#[instrument(skip_all, fields(token))]
+pub async fn playlist_select_callback(
+    bot: Arc<Bot>,
+    mut context: Context,
+    callback_query: CallbackQuery,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+    Extension(transcode): Extension<Transcode>,
+    Extension(summary_config): Extension<SummaryConfig>,
+    Extension(selection_store): Extension<SelectionStore>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+    Extension(job_store): Extension<JobStore>,
+) -> HandlerResult {
+    let token = context
+        .remove::<Box<str>>("selection_token")
+        .expect("Token should be in context because `playlist_selection_callback` filter should do this");
+    let action = context
+        .remove::<Action>("selection_action")
+        .expect("Action should be in context because `playlist_selection_callback` filter should do this");
+    let user_id = callback_query.from.id;
+    let locale = bot_config.user_locale(
+        callback_query.message.as_deref().map_or(user_id, |message| message.chat().id()),
+        callback_query.from.language_code.as_deref(),
+    );
+
+    Span::current().record("token", &*token);
+
+    event!(Level::DEBUG, ?action, "Got playlist selection action");
+
+    match action {
+        Action::Toggle(index) => {
+            let keyboard = selection_store.with(&token, user_id, |selection| {
+                if let Some(selected) = selection.selected.get_mut(index) {
+                    *selected = !*selected;
+                }
+
+                (
+                    selection.chat_id,
+                    selection.keyboard_message_id,
+                    selection_keyboard(locale, &token, selection, index / PAGE_SIZE),
+                )
+            });
+
+            update_keyboard(&bot, locale, callback_query.id, keyboard).await?;
+        }
+        Action::Page(page) => {
+            let keyboard = selection_store.with(&token, user_id, |selection| {
+                (
+                    selection.chat_id,
+                    selection.keyboard_message_id,
+                    selection_keyboard(locale, &token, selection, page),
+                )
+            });
+
+            update_keyboard(&bot, locale, callback_query.id, keyboard).await?;
+        }
+        Action::Download | Action::Cancel => {
+            if action == Action::Download && selection_store.with(&token, user_id, |selection| selection.selected_count()) == Some(0) {
+                bot.send(
+                    AnswerCallbackQuery::new(callback_query.id)
+                        .text(locale.selection_empty())
+                        .show_alert(true),
+                )
+                .await?;
+
+                return Ok(EventReturn::Finish);
+            }
+
+            let Some(selection) = selection_store.remove(&token, user_id) else {
+                bot.send(
+                    AnswerCallbackQuery::new(callback_query.id)
+                        .text(locale.selection_unavailable())
+                        .show_alert(true),
+                )
+                .await?;
+
+                return Ok(EventReturn::Finish);
+            };
+
+            bot.send(AnswerCallbackQuery::new(callback_query.id)).await?;
+
+            if let Some(keyboard_message_id) = selection.keyboard_message_id {
+                let _ = bot.send(DeleteMessage::new(selection.chat_id, keyboard_message_id)).await;
+            }
+
+            if action == Action::Cancel {
+                return Ok(EventReturn::Finish);
+            }
+
+            let Selection {
+                chat_id,
+                thread_id,
+                message_id,
+                videos,
+                selected,
+                ..
+            } = selection;
+            let videos = VideosInYT::new(
+                videos
+                    .into_iter()
+                    .zip(selected)
+                    .filter_map(|(video, selected)| selected.then_some(video))
+                    .collect::<Vec<_>>(),
+            );
+
+            event!(Level::DEBUG, videos_len = videos.len(), "Download selected videos");
+
+            let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Video, Stage::Download);
+            let job = job_store.start(Job::new(
+                chat_id,
+                thread_id,
+                message_id,
+                locale,
+                "",
+                videos.iter().map(|video| video.original_url.as_str()),
+            ));
+
+            return download_and_send_videos(
+                bot,
+                &rate_limiter,
+                chat_id,
+                thread_id,
+                message_id,
+                locale,
+                videos,
+                chat_action,
+                &yt_dlp_config,
+                retries,
+                &bot_config,
+                &event_bus,
+                &work_dir,
+                &link_store,
+                &download_queue,
+                &summary_config,
+                None,
+                &[],
+                chat_config_store.get(chat_id),
+                &DownloadOptions::default(),
+                transcode,
+                process_limits,
+                job,
+            )
+            .await;
+        }
+    }
+
+    Ok(EventReturn::Finish)
+}
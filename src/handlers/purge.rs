@@ -0,0 +1,180 @@
+use crate::{
+    config::Bot as BotConfig,
+    handlers_utils::topic,
+    info_cache::{Purge, PurgeAction},
+};
+
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::{AnswerCallbackQuery, EditMessageText, SendMessage},
+    types::{CallbackQuery, InlineKeyboardButton, InlineKeyboardMarkup, Message, ReplyParameters},
+    utils::text::{html_code, html_quote},
+    Bot, Context, Extension,
+};
+use tracing::{event, instrument, Level};
+
+/// Telegram doesn't accept callback data longer than 64 bytes
+const MAX_CALLBACK_DATA_LEN: usize = 64;
+
+/// Parses a `YYYY-MM-DD` date to the Unix timestamp of its start in UTC
+fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days since the Unix epoch, see http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    u64::try_from(days * 86400).ok()
+}
+
+/// Parses `<domain> [--before YYYY-MM-DD]` arguments of the command
+fn parse_args(text: &str) -> Option<Purge> {
+    let mut args = text.split_whitespace().skip(1);
+    let domain = args.next()?.trim_end_matches('.').to_lowercase();
+
+    if domain.is_empty() || domain.contains([':', '/']) {
+        return None;
+    }
+
+    let cached_before = match (args.next(), args.next()) {
+        (Some("--before"), Some(date)) => Some(parse_date(date)?),
+        (None, None) => None,
+        _ => return None,
+    };
+
+    if args.next().is_some() {
+        return None;
+    }
+
+    Some(Purge {
+        domain: domain.into_boxed_str(),
+        cached_before,
+    })
+}
+
+async fn reply(bot: &Bot, message: &Message, text: impl Into<String>, keyboard: Option<InlineKeyboardMarkup>) -> HandlerResult {
+    bot.send(
+        SendMessage::new(message.chat().id(), text.into())
+            .message_thread_id_option(topic::thread_id(message))
+            .parse_mode(ParseMode::HTML)
+            .reply_markup_option(keyboard)
+            .reply_parameters(ReplyParameters::new(message.id()).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
+
+/// Asks to confirm removal of cached media info of the domain, so operators can force fresh info after extractor fixes
+#[instrument(skip_all)]
+pub async fn purge_domain(bot: Bot, message: Message, Extension(bot_config): Extension<BotConfig>) -> HandlerResult {
+    let locale = bot_config.locale;
+
+    let Some(purge) = message.text().and_then(parse_args) else {
+        return reply(&bot, &message, locale.purge_domain_usage(), None).await;
+    };
+    let confirm_callback_data = PurgeAction::Confirm(purge.clone()).to_callback_data();
+
+    if confirm_callback_data.len() > MAX_CALLBACK_DATA_LEN {
+        return reply(&bot, &message, locale.purge_domain_usage(), None).await;
+    }
+
+    let count = purge.count();
+
+    event!(
+        Level::INFO,
+        domain = &*purge.domain,
+        cached_before = purge.cached_before,
+        count,
+        "Purge requested"
+    );
+
+    if count == 0 {
+        return reply(
+            &bot,
+            &message,
+            format!("{}: {}", locale.purge_domain_nothing(), html_code(html_quote(&purge.domain))),
+            None,
+        )
+        .await;
+    }
+
+    let keyboard = InlineKeyboardMarkup::new([[
+        InlineKeyboardButton::new(locale.confirm()).callback_data(confirm_callback_data),
+        InlineKeyboardButton::new(locale.cancel()).callback_data(PurgeAction::Cancel.to_callback_data()),
+    ]]);
+
+    reply(
+        &bot,
+        &message,
+        format!(
+            "{} ({}): {count}",
+            locale.purge_domain_confirm(),
+            html_code(html_quote(&purge.domain))
+        ),
+        Some(keyboard),
+    )
+    .await
+}
+
+#[instrument(skip_all)]
+pub async fn purge_domain_callback(
+    bot: Bot,
+    mut context: Context,
+    callback_query: CallbackQuery,
+    Extension(bot_config): Extension<BotConfig>,
+) -> HandlerResult {
+    let action = context
+        .remove::<PurgeAction>("purge_action")
+        .expect("Purge should be in context because `purge_confirmation_callback` filter should do this");
+    let locale = bot_config.locale;
+
+    if !bot_config.admin_ids.contains(&callback_query.from.id) {
+        bot.send(AnswerCallbackQuery::new(callback_query.id)).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let text = match action {
+        PurgeAction::Confirm(purge) => {
+            let count = purge.run();
+
+            event!(
+                Level::INFO,
+                domain = &*purge.domain,
+                cached_before = purge.cached_before,
+                count,
+                "Purged"
+            );
+
+            format!("{} ({}): {count}", locale.purge_domain_done(), html_code(html_quote(&purge.domain)))
+        }
+        PurgeAction::Cancel => locale.purge_domain_cancelled().to_owned(),
+    };
+
+    bot.send(AnswerCallbackQuery::new(callback_query.id)).await?;
+
+    if let Some(message) = callback_query.message.as_deref() {
+        bot.send(
+            EditMessageText::new(text)
+                .chat_id(message.chat().id())
+                .message_id(message.id())
+                .parse_mode(ParseMode::HTML),
+        )
+        .await?;
+    }
+
+    Ok(EventReturn::Finish)
+}
@@ -0,0 +1,32 @@
+use crate::{
+    chat_config::ChatConfigStore,
+    config::Bot as BotConfig,
+    handlers_utils::setting::{self, Setting},
+    locale::Locale,
+};
+
+use telers::{event::telegram::HandlerResult, types::Message, Bot, Extension};
+use tracing::instrument;
+
+/// Turns on or off a link to the source in captions of media sent to the chat
+#[instrument(skip_all, fields(chat_id))]
+pub async fn show_link(
+    bot: Bot,
+    message: Message,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
+) -> HandlerResult {
+    setting::toggle_setting(
+        &bot,
+        &message,
+        &bot_config,
+        &chat_config_store,
+        Setting::on_off(
+            |chat_config| chat_config.link_is_visible,
+            ChatConfigStore::set_link_is_visible,
+            Locale::show_link_toggled,
+            Locale::show_link_usage,
+        ),
+    )
+    .await
+}
@@ -1,42 +1,108 @@
-use crate::config::{Bot as BotConfig, YtDlp};
+use crate::{
+    chat_config::ChatConfigStore,
+    config::{Bot as BotConfig, Summary as SummaryConfig, Transcription as TranscriptionConfig, YtDlp},
+    handlers_utils::{locale, topic},
+    links::LinkStore,
+    locale::Locale,
+};
 
 use telers::{
     enums::ParseMode,
     event::{telegram::HandlerResult, EventReturn},
     methods::{GetMe, SendMessage},
     types::{LinkPreviewOptions, Message, ReplyParameters},
-    utils::text::{html_quote, html_text_link},
+    utils::text::{html_code, html_quote, html_text_link},
     Bot, Extension,
 };
 
+/// Lists limits and optional features as they're configured, so the help doesn't promise what's disabled.
+/// Per-chat settings are shown only for the chat the help is requested in.
+fn capabilities_text(
+    locale: Locale,
+    message: &Message,
+    yt_dlp_config: &YtDlp,
+    bot_config: &BotConfig,
+    link_store: &LinkStore,
+    summary_config: &SummaryConfig,
+    transcription_config: &TranscriptionConfig,
+    chat_config_store: &ChatConfigStore,
+) -> String {
+    let chat_id = message.chat().id();
+
+    let mut lines = vec![locale.capability_max_file_size(yt_dlp_config.max_file_size / 1000 / 1000)];
+
+    if link_store.is_enabled() {
+        lines.push(locale.capability_download_links(link_store.max_file_size() / 1000 / 1000, link_store.retention().as_secs() / 60));
+    }
+    if let Some(max_total_size) = yt_dlp_config.max_total_size {
+        lines.push(locale.capability_max_total_size(max_total_size / 1000 / 1000));
+    }
+    if bot_config.max_urls_per_message > 1 {
+        lines.push(locale.capability_max_urls_per_message(bot_config.max_urls_per_message));
+    }
+    if summary_config.is_enabled_for(chat_id) {
+        lines.push(locale.capability_summary(summary_config.min_duration / 60));
+    }
+    if transcription_config.is_enabled() {
+        lines.push(locale.capability_transcription(transcription_config.max_duration / 60));
+    }
+    let include_domains = chat_config_store.get(chat_id).include_domains;
+    if !include_domains.is_empty() {
+        lines.push(
+            locale.capability_allowed_domains(
+                &include_domains
+                    .iter()
+                    .map(|domain| html_code(html_quote(domain)))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        );
+    }
+    if !bot_config.get_mirror_chat_ids(chat_id).is_empty() {
+        lines.push(locale.capability_mirrors().to_owned());
+    }
+
+    lines.push(locale.capability_targets().to_owned());
+
+    lines.push(locale.capability_no_inline_playlists().to_owned());
+
+    lines.join("\n")
+}
+
 pub async fn start(
     bot: Bot,
     message: Message,
     Extension(yt_dlp_config): Extension<YtDlp>,
     Extension(bot_config): Extension<BotConfig>,
+    Extension(link_store): Extension<LinkStore>,
+    Extension(summary_config): Extension<SummaryConfig>,
+    Extension(transcription_config): Extension<TranscriptionConfig>,
+    Extension(chat_config_store): Extension<ChatConfigStore>,
 ) -> HandlerResult {
     let bot_info = bot.send(GetMe {}).await?;
-    let text = format!(
-        "Hi, {first_name}. I'm a bot that can help you download videos from YouTube.\n\n\
-        In a private chat, send me a video link and I will reply with a video or playlist.\n\
-        In a group chat, send <code>/vd</code> (<code>/video_download</code>) with a link or reply to the message with a link.\n\n\
-        If you want to download an audio, send <code>/ad</code> (<code>/audio_download</code>) instead of <code>/vd</code>. \
-        This command works the same way as previous.\n\n\
-        You can use me in inline mode in any chat by typing <code>@{bot_username} </code><code>&lt;url&gt;</code>.\n\n\
-        * You can't download playlists in inline mode.\n\
-        * I'm download videos and audios in the best quality that less than {max_file_size_in_mb}MB.\n\
-        * The bot is open source, and you can find the source code {source_code_href}.",
-        first_name = message
+    let locale = locale::from_message(&bot_config, &message);
+    let text = locale.help(
+        &message
             .from()
             .as_ref()
-            .map_or("Anonymous".to_owned(), |user| html_quote(user.first_name.as_ref())),
-        bot_username = bot_info.username.expect("Bots always have a username"),
-        max_file_size_in_mb = yt_dlp_config.max_file_size / 1000 / 1000,
-        source_code_href = html_text_link("here", html_quote(bot_config.source_code_url.as_str())),
+            .map_or(locale.anonymous().to_owned(), |user| html_quote(user.first_name.as_ref())),
+        &bot_info.username.expect("Bots always have a username"),
+        &capabilities_text(
+            locale,
+            &message,
+            &yt_dlp_config,
+            &bot_config,
+            &link_store,
+            &summary_config,
+            &transcription_config,
+            &chat_config_store,
+        ),
+        &html_text_link(locale.source_code_link(), html_quote(bot_config.source_code_url.as_str())),
     );
 
     bot.send(
         SendMessage::new(message.chat().id(), text)
+            .message_thread_id_option(topic::thread_id(&message))
             .parse_mode(ParseMode::HTML)
             .link_preview_options(LinkPreviewOptions::new().is_disabled(true))
             .reply_parameters_option(
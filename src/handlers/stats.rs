@@ -0,0 +1,95 @@
+use crate::{
+    config::Bot as BotConfig,
+    handlers_utils::topic,
+    metrics::{self, SizeBucket},
+    stats::StatsStore,
+};
+
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendMessage,
+    types::{Message, ReplyParameters},
+    utils::text::html_quote,
+    Bot, Extension,
+};
+use tracing::instrument;
+
+const TOP_DOMAINS_COUNT: usize = 5;
+
+/// Average upload durations by file size, so operators can notice a degraded Bot API server
+fn upload_durations_text() -> Option<String> {
+    let lines: Vec<String> = SizeBucket::ALL
+        .into_iter()
+        .filter_map(|size_bucket| {
+            let (count, average_duration) = metrics::upload_durations(size_bucket);
+
+            (count > 0).then(|| format!("{}: {average_duration:.1}s avg ({count})", size_bucket.as_str()))
+        })
+        .collect();
+
+    if lines.is_empty() {
+        return None;
+    }
+
+    Some(format!("<b>Upload durations since the bot start</b>\n{}", lines.join("\n")))
+}
+
+/// Replies with download counters of the chat since the bot start.
+/// Bot admins also get upload durations of all chats.
+#[instrument(skip_all, fields(chat_id = message.chat().id()))]
+pub async fn stats(
+    bot: Bot,
+    message: Message,
+    Extension(stats_store): Extension<StatsStore>,
+    Extension(bot_config): Extension<BotConfig>,
+) -> HandlerResult {
+    let chat_id = message.chat().id();
+
+    let mut text = match stats_store
+        .get(chat_id)
+        .filter(|chat_stats| chat_stats.downloads() + chat_stats.failed > 0)
+    {
+        Some(chat_stats) => {
+            let mut text = format!(
+                "<b>Downloads in this chat since the bot start</b>\n\
+                Videos: {videos}\n\
+                Audios: {audios}\n\
+                Failed: {failed}",
+                videos = chat_stats.videos,
+                audios = chat_stats.audios,
+                failed = chat_stats.failed,
+            );
+
+            let top_domains = chat_stats.top_domains(TOP_DOMAINS_COUNT);
+
+            if !top_domains.is_empty() {
+                text.push_str("\n\n<b>Top domains</b>");
+
+                for (index, (domain, count)) in top_domains.into_iter().enumerate() {
+                    text.push_str(&format!("\n{}. {} — {count}", index + 1, html_quote(domain)));
+                }
+            }
+
+            text
+        }
+        None => "There were no downloads in this chat since the bot start.".to_owned(),
+    };
+
+    let is_admin = message.from().as_ref().is_some_and(|user| bot_config.admin_ids.contains(&user.id));
+
+    if let Some(upload_durations_text) = is_admin.then(upload_durations_text).flatten() {
+        text.push_str("\n\n");
+        text.push_str(&upload_durations_text);
+    }
+
+    bot.send(
+        SendMessage::new(chat_id, text)
+            .message_thread_id_option(topic::thread_id(&message))
+            .parse_mode(ParseMode::HTML)
+            .reply_parameters(ReplyParameters::new(message.id()).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
@@ -0,0 +1,321 @@
+use crate::{
+    config::{Bot as BotConfig, ProcessLimits, Retries, Transcription as TranscriptionConfig, WorkDir, YtDlp},
+    download,
+    events::{Event, EventBus, MediaKind},
+    fs,
+    handlers_utils::{
+        chat_action::{ActionKind, ChatAction, Stage},
+        error, input_file, locale, send, topic,
+    },
+    metrics::DownloadInProgress,
+    models::AudioConversion,
+    queue::DownloadQueue,
+    rate_limiter::RateLimiter,
+    telemetry::spawn_blocking,
+    transcription,
+};
+
+use std::sync::Arc;
+use telers::{
+    errors::HandlerError,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::{SendDocument, SendMessage},
+    types::{LinkPreviewOptions, Message, ReplyParameters},
+    Bot, Context, Extension,
+};
+use tempfile::tempdir_in;
+use tracing::{event, instrument, Level, Span};
+
+const GET_INFO_TIMEOUT: u64 = 45;
+const DOWNLOAD_MEDIA_TIMEOUT: u64 = 180;
+const SEND_DOCUMENT_TIMEOUT: f32 = 60.0;
+/// Telegram doesn't accept longer text messages, longer transcripts are sent as a file
+const MAX_MESSAGE_LEN: usize = 4096;
+
+/// Whether the message has the `srt=1` parameter to get the transcript as subtitles with timestamps
+fn srt_requested(text: &str) -> bool {
+    text.split_whitespace().any(|word| word == "srt=1")
+}
+
+/// Downloads the audio of the media, transcribes it and replies with the transcript.
+/// A short transcript is sent as a text message, a long one or subtitles requested by `srt=1` as a file.
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn transcribe(
+    bot: Arc<Bot>,
+    mut context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(transcription_config): Extension<TranscriptionConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+) -> HandlerResult {
+    let url = context
+        .remove::<Box<str>>("video_url")
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+    let as_srt = message.text().is_some_and(srt_requested);
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    event!(Level::DEBUG, "Got url");
+
+    if !transcription_config.is_enabled() {
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.transcription_disabled(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let video = match download::media_info(&yt_dlp_config, &url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(mut videos) => match videos.next() {
+            Some(video) => video,
+            None => {
+                event!(Level::WARN, "Video not found");
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_not_found(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        },
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting video info error");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                error::ytdl_text(&err, locale, locale.video_info_error_single()),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+    };
+
+    if video.is_ongoing_live() {
+        event!(Level::WARN, "Ongoing live stream is skipped");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.live_stream_not_supported(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let max_duration = transcription_config.max_duration as f64;
+
+    if video.duration.is_some_and(|duration| duration > max_duration) {
+        event!(Level::INFO, duration = video.duration, "Media is too long to transcribe");
+
+        error::occured_in_message(
+            &bot,
+            chat_id,
+            thread_id,
+            message_id,
+            &locale.transcription_too_long(transcription_config.max_duration / 60),
+            None,
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let video_url = video.original_url.clone().into_boxed_str();
+    let title = video.title.clone();
+    let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+    let max_file_size = transcription_config.max_audio_file_size(yt_dlp_config.max_file_size);
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::Voice, Stage::Download);
+
+    event_bus.publish(Event::DownloadStarted {
+        chat_id: Some(chat_id),
+        url: video_url.clone(),
+        media_kind: MediaKind::Audio,
+    });
+
+    let _in_progress = DownloadInProgress::start();
+
+    let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+        chat_action.stop();
+
+        HandlerError::new(err)
+    })?;
+
+    let permit = download_queue
+        .acquire(video.estimated_audio_filesize(max_file_size), Some(chat_id))
+        .await;
+
+    let result = spawn_blocking({
+        let temp_dir_path = temp_dir.path().to_owned();
+        let normalize_target_lufs = yt_dlp_config.normalize_target_lufs;
+        let yt_dlp_full_path = yt_dlp_config.full_path.clone();
+        let id_or_url = video.original_url.clone();
+
+        move || {
+            download::audio_to_temp_dir(
+                video,
+                id_or_url,
+                max_file_size,
+                yt_dlp_full_path,
+                &extra_args,
+                &retries,
+                temp_dir_path,
+                DOWNLOAD_MEDIA_TIMEOUT,
+                None,
+                AudioConversion::default(),
+                normalize_target_lufs,
+                process_limits,
+            )
+        }
+    })
+    .await;
+
+    drop(permit);
+
+    let audio = match result {
+        Ok(Ok(audio)) => audio,
+        Ok(Err(err)) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while downloading audio");
+
+            event_bus.publish(Event::DownloadFailed {
+                chat_id: Some(chat_id),
+                url: video_url,
+                media_kind: MediaKind::Audio,
+                error: err.to_string().into_boxed_str(),
+            });
+
+            error::download_audios_in_message(&bot, locale, 1, chat_id, thread_id, message_id, None).await?;
+
+            return Ok(EventReturn::Finish);
+        }
+        Err(err) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while joining handle");
+
+            return Err(HandlerError::new(err));
+        }
+    };
+
+    event_bus.publish(Event::DownloadFinished {
+        chat_id: Some(chat_id),
+        url: video_url,
+        media_kind: MediaKind::Audio,
+    });
+
+    chat_action.set_stage(Stage::Info);
+
+    let result = spawn_blocking({
+        let temp_dir_path = temp_dir.path().to_owned();
+
+        move || transcription::audio(&transcription_config, audio.path, temp_dir_path, process_limits)
+    })
+    .await;
+
+    let srt = match result {
+        Ok(Ok(srt)) => srt,
+        Ok(Err(err @ transcription::ErrorKind::TooLarge { max_size, .. })) => {
+            chat_action.stop();
+
+            event!(Level::WARN, %err, "Audio is too large to transcribe");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                &locale.transcription_too_large(max_size / 1000 / 1000),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+        Ok(Err(err)) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while transcribing audio");
+
+            error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.transcription_error(), None).await?;
+
+            return Ok(EventReturn::Finish);
+        }
+        Err(err) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while joining handle");
+
+            return Err(HandlerError::new(err));
+        }
+    };
+
+    let text = transcription::text_from_srt(&srt);
+
+    if text.is_empty() {
+        chat_action.stop();
+
+        event!(Level::INFO, "Transcript is empty");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.transcript_empty(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    if !as_srt && text.chars().count() <= MAX_MESSAGE_LEN {
+        chat_action.stop();
+
+        bot.send(
+            SendMessage::new(chat_id, text)
+                .message_thread_id_option(thread_id)
+                .link_preview_options(LinkPreviewOptions::new().is_disabled(true))
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        )
+        .await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    let (content, extension) = if as_srt { (srt, "srt") } else { (text, "txt") };
+    let name = title.as_deref().map(fs::sanitize_file_name).filter(|name| !name.is_empty());
+    let path = temp_dir
+        .path()
+        .join(format!("{}.{extension}", name.as_deref().unwrap_or(locale.transcript())));
+
+    if let Err(err) = tokio::fs::write(&path, content).await {
+        chat_action.stop();
+
+        return Err(HandlerError::new(err));
+    }
+
+    let file_size = input_file::file_size(&path);
+    let result = send::upload_with_retries(
+        &bot,
+        &rate_limiter,
+        SendDocument::new(chat_id, input_file::from_work_dir(&work_dir, path))
+            .message_thread_id_option(thread_id)
+            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        file_size,
+        &retries.telegram_send,
+        SEND_DOCUMENT_TIMEOUT,
+    )
+    .await;
+
+    chat_action.stop();
+
+    result?;
+
+    Ok(EventReturn::Finish)
+}
@@ -0,0 +1,226 @@
+use crate::{
+    config::{Bot as BotConfig, ProcessLimits, Retries, WorkDir, YtDlp},
+    download,
+    events::{Event, EventBus, MediaKind},
+    handlers_utils::{
+        chat_action::{ActionKind, ChatAction, Stage},
+        error, input_file, locale, send, topic,
+    },
+    metrics::DownloadInProgress,
+    queue::DownloadQueue,
+    rate_limiter::RateLimiter,
+    telemetry::spawn_blocking,
+};
+
+use std::sync::Arc;
+use telers::{
+    errors::HandlerError,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendVideoNote,
+    types::{Message, ReplyParameters},
+    Bot, Context, Extension,
+};
+use tempfile::tempdir_in;
+use tracing::{event, instrument, Level, Span};
+
+const GET_INFO_TIMEOUT: u64 = 45;
+const DOWNLOAD_MEDIA_TIMEOUT: u64 = 180;
+const SEND_VIDEO_NOTE_TIMEOUT: f32 = 60.0;
+/// Telegram doesn't accept video notes longer than a minute
+const MAX_VIDEO_NOTE_DURATION: f64 = 60.0;
+const VIDEO_NOTE_SIZE: u32 = 640;
+
+/// Downloads a short video and sends it as a video note (round video)
+#[instrument(skip_all, fields(message_id, chat_id, url))]
+pub async fn video_note_download(
+    bot: Arc<Bot>,
+    mut context: Context,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(retries): Extension<Retries>,
+    Extension(bot_config): Extension<BotConfig>,
+    Extension(event_bus): Extension<EventBus>,
+    Extension(work_dir): Extension<WorkDir>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(download_queue): Extension<DownloadQueue>,
+    Extension(rate_limiter): Extension<RateLimiter>,
+) -> HandlerResult {
+    let url = context
+        .remove::<Box<str>>("video_url")
+        .expect("Url should be in context because `text_contains_url` filter should do this");
+    let message_id = message.id();
+    let chat_id = message.chat().id();
+    let thread_id = topic::thread_id(&message);
+    let locale = locale::from_message(&bot_config, &message);
+
+    Span::current()
+        .record("chat_id", chat_id)
+        .record("message_id", message_id)
+        .record("url", &*url);
+
+    event!(Level::DEBUG, "Got url");
+
+    let video = match download::media_info(&yt_dlp_config, &url, false, &retries.yt_dlp_info, GET_INFO_TIMEOUT, process_limits).await {
+        Ok(mut videos) => match videos.next() {
+            Some(video) => video,
+            None => {
+                event!(Level::WARN, "Video not found");
+
+                error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_not_found(), None).await?;
+
+                return Ok(EventReturn::Finish);
+            }
+        },
+        Err(err) => {
+            event!(Level::ERROR, %err, "Getting video info error");
+
+            error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                error::ytdl_text(&err, locale, locale.video_info_error_single()),
+                None,
+            )
+            .await?;
+
+            return Ok(EventReturn::Finish);
+        }
+    };
+
+    // Live streams don't have a duration, so they'd be reported as too long
+    if video.is_ongoing_live() {
+        event!(Level::WARN, "Ongoing live stream is skipped");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.live_stream_not_supported(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    if !video.duration.is_some_and(|duration| duration <= MAX_VIDEO_NOTE_DURATION) {
+        event!(Level::INFO, duration = video.duration, "Video is too long for a video note");
+
+        error::occured_in_message(&bot, chat_id, thread_id, message_id, locale.video_note_too_long(), None).await?;
+
+        return Ok(EventReturn::Finish);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let duration = video.duration.map(|duration| duration as i64);
+    let video_url = video.original_url.clone().into_boxed_str();
+    let extra_args = yt_dlp_config.get_media_extra_args(&video.original_url, video.requires_cookies);
+
+    let chat_action = ChatAction::start(bot.clone(), chat_id, thread_id, ActionKind::VideoNote, Stage::Download);
+
+    event_bus.publish(Event::DownloadStarted {
+        chat_id: Some(chat_id),
+        url: video_url.clone(),
+        media_kind: MediaKind::Video,
+    });
+
+    let _in_progress = DownloadInProgress::start();
+
+    let temp_dir = tempdir_in(&work_dir.path).map_err(|err| {
+        chat_action.stop();
+
+        HandlerError::new(err)
+    })?;
+
+    let permit = download_queue
+        .acquire(
+            video.estimated_video_filesize(yt_dlp_config.max_file_size, yt_dlp_config.fps_weight),
+            Some(chat_id),
+        )
+        .await;
+
+    let result = spawn_blocking({
+        let temp_dir_path = temp_dir.path().to_owned();
+        let max_file_size = yt_dlp_config.max_file_size;
+        let fps_weight = yt_dlp_config.fps_weight;
+        let max_format_attempts = yt_dlp_config.max_format_attempts;
+        let yt_dlp_full_path = yt_dlp_config.full_path.clone();
+
+        move || {
+            download::video_note(
+                video,
+                max_file_size,
+                fps_weight,
+                max_format_attempts,
+                yt_dlp_full_path,
+                &extra_args,
+                &retries,
+                temp_dir_path,
+                DOWNLOAD_MEDIA_TIMEOUT,
+                VIDEO_NOTE_SIZE,
+                process_limits,
+            )
+        }
+    })
+    .await;
+
+    drop(permit);
+
+    let path = match result {
+        Ok(Ok(path)) => path,
+        Ok(Err(err)) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while downloading video note");
+
+            event_bus.publish(Event::DownloadFailed {
+                chat_id: Some(chat_id),
+                url: video_url,
+                media_kind: MediaKind::Video,
+                error: err.to_string().into_boxed_str(),
+            });
+
+            error::download_videos_in_message(&bot, locale, 1, chat_id, thread_id, message_id, None).await?;
+
+            return Ok(EventReturn::Finish);
+        }
+        Err(err) => {
+            chat_action.stop();
+
+            event!(Level::ERROR, %err, "Error while joining handle");
+
+            return Err(HandlerError::new(err));
+        }
+    };
+
+    event_bus.publish(Event::DownloadFinished {
+        chat_id: Some(chat_id),
+        url: video_url,
+        media_kind: MediaKind::Video,
+    });
+
+    chat_action.set_stage(Stage::Upload);
+
+    let file_size = input_file::file_size(&path);
+    let result = send::upload_with_retries(
+        &bot,
+        &rate_limiter,
+        SendVideoNote::new(chat_id, input_file::from_work_dir(&work_dir, path))
+            .length(i64::from(VIDEO_NOTE_SIZE))
+            .duration_option(duration)
+            .message_thread_id_option(thread_id)
+            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true)),
+        file_size,
+        &retries.telegram_send,
+        SEND_VIDEO_NOTE_TIMEOUT,
+    )
+    .await;
+
+    chat_action.stop();
+
+    if let Err(err) = result {
+        event_bus.publish(Event::SendFailed {
+            chat_id: Some(chat_id),
+            media_kind: MediaKind::Video,
+            error: err.to_string().into_boxed_str(),
+        });
+
+        return Err(err.into());
+    }
+
+    Ok(EventReturn::Finish)
+}
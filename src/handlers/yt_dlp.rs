@@ -0,0 +1,112 @@
+use crate::{
+    cmd::{get_version, run_update},
+    config::{Bot as BotConfig, ProcessLimits, YtDlp},
+    handlers_utils::topic,
+};
+
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendMessage,
+    types::{Message, ReplyParameters},
+    utils::text::{html_code, html_quote},
+    Bot, Extension,
+};
+use tracing::{event, instrument, Level};
+
+const GET_VERSION_TIMEOUT: u64 = 10;
+const UPDATE_TIMEOUT: u64 = 300;
+
+async fn reply(bot: &Bot, message: &Message, text: impl Into<String>) -> HandlerResult {
+    bot.send(
+        SendMessage::new(message.chat().id(), text.into())
+            .message_thread_id_option(topic::thread_id(message))
+            .parse_mode(ParseMode::HTML)
+            .reply_parameters(ReplyParameters::new(message.id()).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
+
+#[instrument(skip_all)]
+pub async fn yt_dlp_version(
+    bot: Bot,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(bot_config): Extension<BotConfig>,
+) -> HandlerResult {
+    let locale = bot_config.locale;
+    let full_path = yt_dlp_config.full_path;
+
+    match get_version(full_path, GET_VERSION_TIMEOUT, process_limits).await {
+        Ok(version) => {
+            reply(
+                &bot,
+                &message,
+                format!("{}: {}", locale.yt_dlp_version(), html_code(html_quote(version))),
+            )
+            .await
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error getting yt-dlp version");
+
+            reply(
+                &bot,
+                &message,
+                format!("{}: {}", locale.yt_dlp_version_error(), html_code(html_quote(err.to_string()))),
+            )
+            .await
+        }
+    }
+}
+
+/// Runs the configured update command, so operators don't need shell access when extractors break
+#[instrument(skip_all)]
+pub async fn yt_dlp_update(
+    bot: Bot,
+    message: Message,
+    Extension(yt_dlp_config): Extension<YtDlp>,
+    Extension(process_limits): Extension<ProcessLimits>,
+    Extension(bot_config): Extension<BotConfig>,
+) -> HandlerResult {
+    let locale = bot_config.locale;
+
+    event!(Level::INFO, command = ?yt_dlp_config.update_command, "Update yt-dlp");
+
+    reply(&bot, &message, locale.yt_dlp_updating()).await?;
+
+    let YtDlp {
+        full_path, update_command, ..
+    } = yt_dlp_config;
+
+    let result = async {
+        run_update(&update_command, UPDATE_TIMEOUT, process_limits).await?;
+        get_version(full_path, GET_VERSION_TIMEOUT, process_limits).await
+    }
+    .await;
+
+    match result {
+        Ok(version) => {
+            event!(Level::INFO, %version, "yt-dlp updated");
+
+            reply(
+                &bot,
+                &message,
+                format!("{}: {}", locale.yt_dlp_updated(), html_code(html_quote(version))),
+            )
+            .await
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error updating yt-dlp");
+
+            reply(
+                &bot,
+                &message,
+                format!("{}: {}", locale.yt_dlp_update_error(), html_code(html_quote(err.to_string()))),
+            )
+            .await
+        }
+    }
+}
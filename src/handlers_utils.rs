@@ -1,3 +1,13 @@
+pub mod caption;
 pub mod chat_action;
+pub mod delivery;
 pub mod error;
+pub mod inline_progress;
+pub mod input_file;
+pub mod locale;
 pub mod send;
+pub mod setting;
+pub mod targets;
+pub mod thumbnail;
+pub mod topic;
+pub mod upload_progress;
@@ -0,0 +1,105 @@
+use telers::utils::text::html_quote;
+
+/// Telegram limits a caption to 1024 characters after entities parsing
+const MAX_CAPTION_LEN: usize = 1024;
+const PARTS_SEPARATOR: &str = "\n\n";
+const TRUNCATION_MARK: char = '…';
+
+/// Caption of sent media with parts in the same order in all send paths.
+/// Parts are plain text, they're quoted to HTML on build, so captions should be sent with [`telers::enums::ParseMode::HTML`].
+#[derive(Debug, Clone, Default)]
+pub struct Caption {
+    title: Option<String>,
+    note: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    source_url: Option<String>,
+}
+
+impl Caption {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn title(self, title: impl Into<String>) -> Self {
+        Self {
+            title: Some(title.into()),
+            ..self
+        }
+    }
+
+    /// Short note about changes of the media, like removed segments, it's shown after the title
+    #[must_use]
+    pub fn note(self, note: Option<impl Into<String>>) -> Self {
+        Self {
+            note: note.map(Into::into),
+            ..self
+        }
+    }
+
+    /// Summary is shown in an expandable blockquote, it's truncated if the caption is too long
+    #[must_use]
+    pub fn summary(self, summary: Option<impl Into<String>>) -> Self {
+        Self {
+            summary: summary.map(Into::into),
+            ..self
+        }
+    }
+
+    /// Description of the source is shown in an expandable blockquote after the summary, it gets the rest of the limit
+    #[must_use]
+    pub fn description(self, description: Option<impl Into<String>>) -> Self {
+        Self {
+            description: description.map(Into::into).filter(|description| !description.trim().is_empty()),
+            ..self
+        }
+    }
+
+    /// Link to the source, it should be set only if the chat config allows it
+    #[must_use]
+    pub fn source_url(self, source_url: Option<impl Into<String>>) -> Self {
+        Self {
+            source_url: source_url.map(Into::into),
+            ..self
+        }
+    }
+
+    /// Returns `None` if the caption doesn't have parts
+    #[must_use]
+    pub fn build(&self) -> Option<String> {
+        let title = self.title.as_deref().map(html_quote);
+        let note = self.note.as_deref().map(html_quote);
+        let source_url = self.source_url.as_deref().map(html_quote);
+
+        // Tags aren't counted by Telegram, so quoted parts get the rest of the limit by plain text in their order
+        let fixed_len = [self.title.as_deref(), self.note.as_deref(), self.source_url.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(|part| part.chars().count() + PARTS_SEPARATOR.len())
+            .sum::<usize>();
+        let mut rest_len = MAX_CAPTION_LEN.saturating_sub(fixed_len);
+        let mut quote = |text: Option<&str>| {
+            let text = text?;
+            let max_len = rest_len.checked_sub(PARTS_SEPARATOR.len()).filter(|max_len| *max_len > 1)?;
+            let text = match text.char_indices().nth(max_len - 1) {
+                Some((end, _)) => format!("{}{TRUNCATION_MARK}", &text[..end]),
+                None => text.to_owned(),
+            };
+            rest_len -= text.chars().count() + PARTS_SEPARATOR.len();
+
+            Some(format!("<blockquote expandable>{}</blockquote>", html_quote(text)))
+        };
+        let summary = quote(self.summary.as_deref());
+        let description = quote(self.description.as_deref().map(str::trim));
+
+        let parts: Vec<String> = [title, note, summary, description, source_url].into_iter().flatten().collect();
+
+        if parts.is_empty() {
+            return None;
+        }
+
+        Some(parts.join(PARTS_SEPARATOR))
+    }
+}
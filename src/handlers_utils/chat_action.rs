@@ -1,29 +1,88 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use telers::{methods::SendChatAction, Bot};
+use tokio::{sync::watch, task::AbortHandle, time::timeout};
 use tracing::{event, Level};
 
 const TIME_SLEEP_BETWEEN_SEND_ACTION_IN_MILLIS: u64 = 5000;
 
-pub async fn upload_video_action_in_loop(bot: &Bot, chat_id: i64) {
-    loop {
-        if let Err(err) = bot.send(SendChatAction::new(chat_id, "upload_video")).await {
-            event!(Level::ERROR, %err, "Error while sending upload action");
+/// Stage of the download pipeline shown to the user by the chat action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Info,
+    Download,
+    Upload,
+}
 
-            break;
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Video,
+    Voice,
+    VideoNote,
+}
 
-        tokio::time::sleep(Duration::from_millis(TIME_SLEEP_BETWEEN_SEND_ACTION_IN_MILLIS)).await;
+impl ActionKind {
+    const fn action(self, stage: Stage) -> &'static str {
+        match (stage, self) {
+            (Stage::Info, _) => "typing",
+            (Stage::Download, Self::Video) => "record_video",
+            (Stage::Download, Self::Voice) => "record_voice",
+            (Stage::Download, Self::VideoNote) => "record_video_note",
+            (Stage::Upload, Self::Video) => "upload_video",
+            (Stage::Upload, Self::Voice) => "upload_voice",
+            (Stage::Upload, Self::VideoNote) => "upload_video_note",
+        }
     }
 }
 
-pub async fn upload_voice_action_in_loop(bot: &Bot, chat_id: i64) {
-    loop {
-        if let Err(err) = bot.send(SendChatAction::new(chat_id, "upload_voice")).await {
-            event!(Level::ERROR, %err, "Error while sending upload action");
+/// Keeps sending the chat action of the current stage until it's stopped.
+/// Clones share the stage, so download tasks can switch it to the upload stage.
+#[derive(Debug, Clone)]
+pub struct ChatAction {
+    stage: Arc<watch::Sender<Stage>>,
+    abort_handle: AbortHandle,
+}
+
+impl ChatAction {
+    #[must_use]
+    pub fn start(bot: Arc<Bot>, chat_id: i64, thread_id: Option<i64>, action_kind: ActionKind, stage: Stage) -> Self {
+        let (sender, mut receiver) = watch::channel(stage);
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let action = action_kind.action(*receiver.borrow_and_update());
 
-            break;
+                if let Err(err) = bot
+                    .send(SendChatAction::new(chat_id, action).message_thread_id_option(thread_id))
+                    .await
+                {
+                    event!(Level::ERROR, %err, "Error while sending chat action");
+
+                    break;
+                }
+
+                // The action of a new stage is sent right away instead of waiting for the previous one to expire
+                match timeout(Duration::from_millis(TIME_SLEEP_BETWEEN_SEND_ACTION_IN_MILLIS), receiver.changed()).await {
+                    Ok(Ok(())) | Err(_) => {}
+                    Ok(Err(_)) => break,
+                }
+            }
+        });
+
+        Self {
+            stage: Arc::new(sender),
+            abort_handle: handle.abort_handle(),
         }
+    }
+
+    pub fn set_stage(&self, stage: Stage) {
+        self.stage.send_if_modified(|current| {
+            let modified = *current != stage;
+            *current = stage;
+            modified
+        });
+    }
 
-        tokio::time::sleep(Duration::from_millis(TIME_SLEEP_BETWEEN_SEND_ACTION_IN_MILLIS)).await;
+    pub fn stop(&self) {
+        self.abort_handle.abort();
     }
 }
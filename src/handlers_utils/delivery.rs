@@ -0,0 +1,94 @@
+use crate::config::{Bot as BotConfig, MediaDelivery};
+
+use telers::{
+    enums::ParseMode,
+    methods::{DeleteMessage, SendVideo},
+    types::{InlineKeyboardMarkup, ReplyParameters},
+    Bot,
+};
+
+/// Chat where media is uploaded first, see [`MediaDelivery`]
+#[derive(Debug, Clone, Copy)]
+pub enum Delivery {
+    /// Media is uploaded silently to the receiver chat, the message there is deleted after its file ID is got
+    Staged { receiver_chat_id: i64 },
+    /// Media is uploaded to the chat as a reply to the message, so it doesn't need to be sent again
+    Direct {
+        chat_id: i64,
+        thread_id: Option<i64>,
+        message_id: i64,
+    },
+}
+
+impl Delivery {
+    /// Media is uploaded directly only if the chat config allows it and the media is sent alone,
+    /// because media of playlists is sent in media groups by file IDs.
+    #[must_use]
+    pub fn new(bot_config: &BotConfig, chat_id: i64, thread_id: Option<i64>, message_id: i64, alone: bool) -> Self {
+        if alone && bot_config.media_delivery(chat_id) == MediaDelivery::Direct {
+            Self::Direct {
+                chat_id,
+                thread_id,
+                message_id,
+            }
+        } else {
+            Self::staged(bot_config)
+        }
+    }
+
+    /// Delivery for media that can't be uploaded directly, like media sent in media groups or inline messages
+    #[must_use]
+    pub const fn staged(bot_config: &BotConfig) -> Self {
+        Self::Staged {
+            receiver_chat_id: bot_config.receiver_video_chat_id,
+        }
+    }
+
+    #[must_use]
+    pub const fn chat_id(&self) -> i64 {
+        match self {
+            Self::Staged { receiver_chat_id } => *receiver_chat_id,
+            Self::Direct { chat_id, .. } => *chat_id,
+        }
+    }
+
+    #[must_use]
+    pub const fn is_direct(&self) -> bool {
+        matches!(self, Self::Direct { .. })
+    }
+
+    /// Staged video is sent without a notification, direct one gets the HTML caption and the reply markup,
+    /// because it isn't sent to the chat again.
+    #[must_use]
+    pub fn video<'a>(
+        &self,
+        send_video: SendVideo<'a>,
+        caption: Option<String>,
+        reply_markup: Option<InlineKeyboardMarkup>,
+    ) -> SendVideo<'a> {
+        match *self {
+            Self::Staged { .. } => send_video.disable_notification(true),
+            Self::Direct { thread_id, message_id, .. } => send_video
+                .caption_option(caption)
+                .parse_mode(ParseMode::HTML)
+                .message_thread_id_option(thread_id)
+                .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply(true))
+                .reply_markup_option(reply_markup),
+        }
+    }
+
+    /// Deletes the staged message in the background, direct one is kept
+    pub fn clean_up(&self, bot: &Bot, message_id: i64) {
+        let Self::Staged { receiver_chat_id } = *self else {
+            return;
+        };
+
+        tokio::spawn({
+            let bot = bot.clone();
+
+            async move {
+                let _ = bot.send(DeleteMessage::new(receiver_chat_id, message_id)).await;
+            }
+        });
+    }
+}
@@ -1,3 +1,8 @@
+use crate::{
+    cmd::ytdl::{Error as YtdlError, FailureCause},
+    locale::Locale,
+};
+
 use telers::{
     enums::ParseMode,
     errors::SessionErrorKind,
@@ -9,12 +14,14 @@ use telers::{
 pub async fn occured_in_message(
     bot: &Bot,
     chat_id: i64,
+    thread_id: Option<i64>,
     reply_to_message_id: i64,
     text: &str,
     parse_mode: Option<ParseMode>,
 ) -> Result<Message, SessionErrorKind> {
     bot.send(
         SendMessage::new(chat_id, text)
+            .message_thread_id_option(thread_id)
             .link_preview_options(LinkPreviewOptions::new().is_disabled(true))
             .reply_parameters(ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true))
             .parse_mode_option(parse_mode),
@@ -47,36 +54,49 @@ pub async fn occured_in_inline_query_occured(bot: &Bot, query_id: &str, text: &s
 
 pub async fn download_videos_in_message(
     bot: &Bot,
+    locale: Locale,
     count: usize,
     chat_id: i64,
+    thread_id: Option<i64>,
     reply_to_message_id: i64,
     parse_mode: Option<ParseMode>,
 ) -> Result<(), SessionErrorKind> {
-    let text = if count == 1 {
-        "Sorry, an error occurred while downloading the video. Try again later.".to_owned()
-    } else {
-        format!("Sorry, an error occurred while downloading {count} videos from the playlist. Try again later.")
-    };
+    let text = locale.download_videos_error(count);
 
-    occured_in_message(bot, chat_id, reply_to_message_id, &text, parse_mode)
+    occured_in_message(bot, chat_id, thread_id, reply_to_message_id, &text, parse_mode)
         .await
         .map(|_| ())
 }
 
 pub async fn download_audios_in_message(
     bot: &Bot,
+    locale: Locale,
     count: usize,
     chat_id: i64,
+    thread_id: Option<i64>,
     reply_to_message_id: i64,
     parse_mode: Option<ParseMode>,
 ) -> Result<(), SessionErrorKind> {
-    let text = if count == 1 {
-        "Sorry, an error occurred while downloading the audio. Try again later.".to_owned()
-    } else {
-        format!("Sorry, an error occurred while downloading {count} audios from the playlist. Try again later.")
-    };
+    let text = locale.download_audios_error(count);
 
-    occured_in_message(bot, chat_id, reply_to_message_id, &text, parse_mode)
+    occured_in_message(bot, chat_id, thread_id, reply_to_message_id, &text, parse_mode)
         .await
         .map(|_| ())
 }
+
+/// Text for the known causes of `yt-dlp` failures, so the user knows that retrying won't help.
+/// `default` is used for other errors.
+pub fn ytdl_text<'a>(err: &YtdlError, locale: Locale, default: &'a str) -> &'a str {
+    let YtdlError::Failed(cause) = err else {
+        return default;
+    };
+
+    match cause {
+        FailureCause::GeoRestricted => locale.geo_restricted(),
+        FailureCause::AgeRestricted => locale.age_restricted(),
+        FailureCause::LoginRequired => locale.login_required(),
+        FailureCause::Private => locale.private_media(),
+        FailureCause::NotFound => locale.media_not_found(),
+        FailureCause::Drm => locale.drm_protected(),
+    }
+}
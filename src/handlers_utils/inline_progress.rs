@@ -0,0 +1,134 @@
+use super::chat_action::Stage;
+use crate::{cancellation, locale::Locale};
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use telers::{
+    methods::EditMessageText,
+    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    Bot,
+};
+use tokio::{
+    sync::watch,
+    task::AbortHandle,
+    time::{timeout, Instant},
+};
+use tracing::{event, Level};
+
+/// Telegram limits edits of a message, so the elapsed time is updated rarely
+const EDIT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Bytes of the file read for the upload and its size, the size is zero if the upload isn't tracked
+#[derive(Debug, Default)]
+struct Upload {
+    uploaded: Arc<AtomicU64>,
+    size: AtomicU64,
+}
+
+impl Upload {
+    fn percent(&self) -> Option<u64> {
+        let size = self.size.load(Ordering::Relaxed);
+
+        if size == 0 {
+            return None;
+        }
+
+        Some((self.uploaded.load(Ordering::Relaxed) * 100 / size).min(100))
+    }
+}
+
+fn progress_text(locale: Locale, stage: Stage, elapsed: Duration, upload_percent: Option<u64>) -> String {
+    let stage_text = match stage {
+        Stage::Info => locale.getting_info(),
+        Stage::Download => locale.downloading(),
+        Stage::Upload => locale.uploading(),
+    };
+    let elapsed = elapsed.as_secs();
+
+    match upload_percent.filter(|_| stage == Stage::Upload) {
+        Some(percent) => format!("{stage_text} {percent}% {}:{:02}", elapsed / 60, elapsed % 60),
+        None => format!("{stage_text} {}:{:02}", elapsed / 60, elapsed % 60),
+    }
+}
+
+/// Keeps editing the placeholder of the chosen inline result with the current stage and the elapsed time until it's stopped,
+/// so the user sees that the media is being processed. It should be stopped before the final edit of the placeholder.
+/// The placeholder has a button to cancel the download by its key in [`crate::cancellation::CancelStore`].
+#[derive(Debug, Clone)]
+pub struct InlineProgress {
+    stage: Arc<watch::Sender<Stage>>,
+    upload: Arc<Upload>,
+    abort_handle: AbortHandle,
+}
+
+impl InlineProgress {
+    #[must_use]
+    pub fn start(bot: Arc<Bot>, inline_message_id: Box<str>, locale: Locale, stage: Stage, cancel_key: &str) -> Self {
+        let (sender, mut receiver) = watch::channel(stage);
+        let upload = Arc::new(Upload::default());
+        let started_at = Instant::now();
+        let reply_markup = InlineKeyboardMarkup::new([[
+            InlineKeyboardButton::new(locale.cancel()).callback_data(cancellation::to_callback_data(cancel_key))
+        ]]);
+
+        let handle = tokio::spawn({
+            let upload = upload.clone();
+
+            async move {
+                loop {
+                    let text = progress_text(locale, *receiver.borrow_and_update(), started_at.elapsed(), upload.percent());
+
+                    if let Err(err) = bot
+                        .send(
+                            EditMessageText::new(text)
+                                .inline_message_id(&*inline_message_id)
+                                .reply_markup(reply_markup.clone()),
+                        )
+                        .await
+                    {
+                        event!(Level::WARN, %err, "Error while editing inline progress");
+                    }
+
+                    match timeout(EDIT_INTERVAL, receiver.changed()).await {
+                        Ok(Ok(())) | Err(_) => {}
+                        Ok(Err(_)) => break,
+                    }
+                }
+            }
+        });
+
+        Self {
+            stage: Arc::new(sender),
+            upload,
+            abort_handle: handle.abort_handle(),
+        }
+    }
+
+    /// Switches to the upload stage and shows the percentage of the file of the size read for the upload.
+    /// Returns the counter of read bytes, see [`super::input_file::with_progress`].
+    #[must_use]
+    pub fn track_upload(&self, file_size: u64) -> Arc<AtomicU64> {
+        self.upload.uploaded.store(0, Ordering::Relaxed);
+        self.upload.size.store(file_size, Ordering::Relaxed);
+        self.set_stage(Stage::Upload);
+
+        self.upload.uploaded.clone()
+    }
+
+    pub fn set_stage(&self, stage: Stage) {
+        self.stage.send_if_modified(|current| {
+            let modified = *current != stage;
+            *current = stage;
+            modified
+        });
+    }
+
+    pub fn stop(&self) {
+        self.abort_handle.abort();
+    }
+}
@@ -0,0 +1,63 @@
+use crate::config::WorkDir;
+
+use futures_util::{future::Either, stream, TryStreamExt as _};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use telers::types::InputFile;
+use tokio_util::io::ReaderStream;
+
+/// Files are sent by a local file URI if the work dir is shared with a local Bot API server, otherwise they're uploaded.
+/// It saves copying multi-GB files over HTTP to the server, which would copy them once more.
+pub fn from_work_dir(work_dir: &WorkDir, path: PathBuf) -> InputFile<'static> {
+    match work_dir.local_file_uri(&path) {
+        Some(uri) => InputFile::url(uri),
+        None => InputFile::fs(path),
+    }
+}
+
+/// Like [`from_work_dir`], but the file is streamed and read bytes are counted in `uploaded`, so the upload progress can be shown.
+/// Files sent by a local file URI aren't read by the bot, so they aren't counted.
+/// A stream can be read only once, so the file should be got again for each attempt of the upload, see [`super::send::Rebuildable`].
+pub fn with_progress(work_dir: &WorkDir, path: PathBuf, uploaded: Arc<AtomicU64>) -> InputFile<'static> {
+    if let Some(uri) = work_dir.local_file_uri(&path) {
+        return InputFile::url(uri);
+    }
+
+    let file_name = path.file_name().map(|file_name| file_name.to_string_lossy().into_owned());
+
+    uploaded.store(0, Ordering::Relaxed);
+
+    // An error of opening the file is returned by the stream, so it fails the upload like an error of reading it
+    let stream = match fs::File::open(&path) {
+        Ok(file) => Either::Left(ReaderStream::new(tokio::fs::File::from_std(file)).inspect_ok(move |bytes| {
+            uploaded.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        })),
+        Err(err) => Either::Right(stream::iter([Err(err)])),
+    };
+
+    match file_name {
+        Some(file_name) => InputFile::stream_with_name(stream, file_name),
+        None => InputFile::stream(stream),
+    }
+}
+
+/// Size of the file read by the bot for the upload, it's zero if the file is sent by a local file URI,
+/// so the upload progress isn't shown for it
+pub fn upload_size(work_dir: &WorkDir, path: &Path) -> u64 {
+    if work_dir.local_file_uri(path).is_some() {
+        return 0;
+    }
+
+    file_size(path)
+}
+
+/// Size of the file in bytes, it's zero if the file doesn't exist
+pub fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map_or(0, |metadata| metadata.len())
+}
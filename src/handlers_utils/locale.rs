@@ -0,0 +1,12 @@
+use crate::{config::Bot as BotConfig, locale::Locale};
+
+use telers::types::Message;
+
+/// Language of replies to the sender of the message
+#[must_use]
+pub fn from_message(bot_config: &BotConfig, message: &Message) -> Locale {
+    bot_config.user_locale(
+        message.chat().id(),
+        message.from().as_ref().and_then(|user| user.language_code.as_deref()),
+    )
+}
@@ -1,5 +1,15 @@
-use backoff::{backoff::Backoff as _, ExponentialBackoff};
-use std::{mem, time::Duration};
+use crate::{
+    config::RetryPolicy,
+    metrics::{self, SizeBucket, TELEGRAM_SEND_RETRIES, TELEGRAM_UPLOAD_DURATION},
+    rate_limiter::RateLimiter,
+};
+
+use backoff::backoff::Backoff as _;
+use std::{
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use telers::{
     errors::{SessionErrorKind, TelegramErrorKind},
     methods::{SendMediaGroup, TelegramMethod},
@@ -8,10 +18,16 @@ use telers::{
 };
 use tracing::{event, instrument, Level};
 
+/// Uploads of a size bucket needed to rely on their average duration
+const UPLOAD_TIMEOUT_MIN_SAMPLES: u64 = 5;
+const UPLOAD_TIMEOUT_AVERAGE_FACTOR: f64 = 3.0;
+
 /// Sends a request to the Telegram Bot API with limited retries.
 /// # Arguments
 /// * `bot` - Bot instance
+/// * `rate_limiter` - Limiter of requests shared by all chats
 /// * `method` - Method to send
+/// * `policy` - Retry policy, see [`RetryPolicy`]
 /// * `request_timeout` - Request timeout
 /// # Notes
 /// This function will retry the request if the following serrors occur:
@@ -20,6 +36,8 @@ use tracing::{event, instrument, Level};
 /// - [`TelegramErrorKind::RetryAfter`]
 /// - [`TelegramErrorKind::ServerError`]
 /// - [`TelegramErrorKind::RestartingTelegram`]
+///
+/// Requests wait for the rate limiter, and a flood-wait pauses requests of all chats.
 /// # Returns
 /// - `Ok(T::Return)` - If the request was successful
 /// - `Err(SessionErrorKind)` - If the request was unsuccessful and the maximum number of retries was exceeded
@@ -27,8 +45,9 @@ use tracing::{event, instrument, Level};
 #[allow(clippy::cast_sign_loss)]
 pub async fn with_retries<T, TRef>(
     bot: &Bot,
+    rate_limiter: &RateLimiter,
     method: TRef,
-    max_retries: u8,
+    policy: &RetryPolicy,
     request_timeout: Option<f32>,
 ) -> Result<T::Return, SessionErrorKind>
 where
@@ -36,10 +55,12 @@ where
     T::Method: Send + Sync,
     TRef: AsRef<T> + Clone,
 {
-    let mut backoff = ExponentialBackoff::default();
+    let mut backoff = policy.to_backoff();
     let mut cur_retry_count = 0;
 
     loop {
+        rate_limiter.acquire().await;
+
         match if let Some(request_timeout) = request_timeout {
             bot.send_with_timeout(method.clone(), request_timeout).await
         } else {
@@ -52,9 +73,12 @@ where
                         if retry_after > 0 {
                             event!(Level::DEBUG, "Sleeping for {retry_after:?} seconds");
 
+                            TELEGRAM_SEND_RETRIES.with_label_values(&["retry_after"]).inc();
+
                             backoff.reset();
 
-                            tokio::time::sleep(Duration::from_secs(retry_after as u64)).await;
+                            // Other requests would get the flood-wait too, so they're paused until it ends
+                            rate_limiter.pause(Duration::from_secs(retry_after as u64));
 
                             // Don't use retry count limiter
                             continue;
@@ -76,28 +100,112 @@ where
 
                 cur_retry_count += 1;
 
-                if cur_retry_count > max_retries {
+                if cur_retry_count > policy.attempts {
                     event!(Level::ERROR, "Max retries exceeded");
 
                     break Err(err);
                 }
 
-                if let Some(duration) = backoff.next_backoff() {
-                    event!(Level::DEBUG, "Sleeping for {duration:?} seconds");
+                TELEGRAM_SEND_RETRIES.with_label_values(&["error"]).inc();
 
-                    tokio::time::sleep(duration).await;
-                }
+                let Some(duration) = backoff.next_backoff() else {
+                    event!(Level::ERROR, "Max retries elapsed time exceeded");
+
+                    break Err(err);
+                };
+
+                event!(Level::DEBUG, "Sleeping for {duration:?} seconds");
+
+                tokio::time::sleep(duration).await;
             }
         }
     }
 }
 
+/// Request timeout of an upload by the average upload duration of files of the same size,
+/// so uploads don't fail when a local Bot API server is slower than usual.
+/// `min_timeout` is used until there are enough uploads of the size to rely on.
+#[must_use]
+pub fn upload_timeout(file_size: u64, min_timeout: f32) -> f32 {
+    let (count, average_duration) = metrics::upload_durations(SizeBucket::from_file_size(file_size));
+
+    if count < UPLOAD_TIMEOUT_MIN_SAMPLES {
+        return min_timeout;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    min_timeout.max((average_duration * UPLOAD_TIMEOUT_AVERAGE_FACTOR) as f32)
+}
+
+/// Method that is built again when it's cloned for an attempt of [`with_retries`].
+/// It's needed for methods with streamed files, because a stream can be read only once.
+pub struct Rebuildable<T> {
+    method: T,
+    build: Arc<dyn Fn() -> T + Send + Sync>,
+}
+
+impl<T> Rebuildable<T> {
+    pub fn new(build: impl Fn() -> T + Send + Sync + 'static) -> Self {
+        Self {
+            method: build(),
+            build: Arc::new(build),
+        }
+    }
+}
+
+impl<T> Clone for Rebuildable<T> {
+    fn clone(&self) -> Self {
+        Self {
+            method: (self.build)(),
+            build: self.build.clone(),
+        }
+    }
+}
+
+impl<T> AsRef<T> for Rebuildable<T> {
+    fn as_ref(&self) -> &T {
+        &self.method
+    }
+}
+
+/// Sends a request with a file to the Telegram Bot API with limited retries, see [`with_retries`] for more info.
+/// The request timeout is got by [`upload_timeout`], and the upload duration is recorded by the file size.
+#[instrument(skip_all, fields(%file_size))]
+pub async fn upload_with_retries<T, TRef>(
+    bot: &Bot,
+    rate_limiter: &RateLimiter,
+    method: TRef,
+    file_size: u64,
+    policy: &RetryPolicy,
+    min_timeout: f32,
+) -> Result<T::Return, SessionErrorKind>
+where
+    T: TelegramMethod + Send + Sync,
+    T::Method: Send + Sync,
+    TRef: AsRef<T> + Clone,
+{
+    let started_at = Instant::now();
+    let result = with_retries(bot, rate_limiter, method, policy, Some(upload_timeout(file_size, min_timeout))).await;
+
+    // Failed uploads aren't recorded, so timeouts don't inflate the average
+    if result.is_ok() {
+        TELEGRAM_UPLOAD_DURATION
+            .with_label_values(&[SizeBucket::from_file_size(file_size).as_str()])
+            .observe(started_at.elapsed().as_secs_f64());
+    }
+
+    result
+}
+
 /// Sends a media groups to the Telegram Bot API with limited retries for each media group.
 /// # Arguments
 /// * `bot` - Bot instance
+/// * `rate_limiter` - Limiter of requests shared by all chats
 /// * `chat_id` - Chat ID
+/// * `thread_id` - ID of the forum topic to send the media to
 /// * `input_media_list` - List of input media
 /// * `reply_to_message_id` - If the message is a reply, ID of the original message
+/// * `policy` - Retry policy for each media group, see [`RetryPolicy`]
 /// * `request_timeout` - Request timeout
 /// # Notess
 /// If the number of input media is greater than 10, the function will split the input media into groups,
@@ -107,9 +215,12 @@ where
 #[instrument(skip_all)]
 pub async fn media_groups(
     bot: &Bot,
+    rate_limiter: &RateLimiter,
     chat_id: impl Into<ChatIdKind>,
+    thread_id: Option<i64>,
     input_media_list: Vec<impl Into<InputMedia<'_>>>,
     reply_to_message_id: Option<i64>,
+    policy: &RetryPolicy,
     request_timeout: Option<f32>,
 ) -> Result<Box<[Message]>, SessionErrorKind> {
     let chat_id = chat_id.into();
@@ -138,11 +249,14 @@ pub async fn media_groups(
             messages.extend(
                 with_retries(
                     bot,
-                    SendMediaGroup::new(chat_id.clone(), media_group).reply_parameters_option(
-                        reply_to_message_id
-                            .map(|reply_to_message_id| ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true)),
-                    ),
-                    4,
+                    rate_limiter,
+                    SendMediaGroup::new(chat_id.clone(), media_group)
+                        .message_thread_id_option(thread_id)
+                        .reply_parameters_option(
+                            reply_to_message_id
+                                .map(|reply_to_message_id| ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true)),
+                        ),
+                    policy,
                     request_timeout,
                 )
                 .await?,
@@ -156,11 +270,14 @@ pub async fn media_groups(
         messages.extend(
             with_retries(
                 bot,
-                SendMediaGroup::new(chat_id.clone(), cur_media_group).reply_parameters_option(
-                    reply_to_message_id
-                        .map(|reply_to_message_id| ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true)),
-                ),
-                4,
+                rate_limiter,
+                SendMediaGroup::new(chat_id.clone(), cur_media_group)
+                    .message_thread_id_option(thread_id)
+                    .reply_parameters_option(
+                        reply_to_message_id
+                            .map(|reply_to_message_id| ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true)),
+                    ),
+                policy,
                 request_timeout,
             )
             .await?,
@@ -0,0 +1,108 @@
+use super::{locale, topic};
+use crate::{
+    chat_config::{ChatConfig, ChatConfigStore},
+    config::Bot as BotConfig,
+    locale::Locale,
+};
+
+use std::fmt::Debug;
+use telers::{
+    enums::ParseMode,
+    event::{telegram::HandlerResult, EventReturn},
+    methods::SendMessage,
+    types::{Message, ReplyParameters},
+    Bot,
+};
+use tracing::{event, Level, Span};
+
+/// Chat setting changed by a command with a value, like `/source_button on`
+pub struct Setting<T> {
+    pub parse: fn(&str) -> Option<T>,
+    pub get: fn(&ChatConfig) -> T,
+    pub set: fn(&ChatConfigStore, i64, T),
+    /// Reply after the setting is changed
+    pub changed_text: fn(Locale, T) -> String,
+    /// Reply with the current value if the command has no valid value
+    pub usage_text: fn(Locale, T) -> String,
+}
+
+impl Setting<bool> {
+    /// Setting turned on or off by `on|off`
+    #[must_use]
+    pub fn on_off(
+        get: fn(&ChatConfig) -> bool,
+        set: fn(&ChatConfigStore, i64, bool),
+        changed_text: fn(Locale, bool) -> String,
+        usage_text: fn(Locale, bool) -> String,
+    ) -> Self {
+        Self {
+            parse: parse_on_off,
+            get,
+            set,
+            changed_text,
+            usage_text,
+        }
+    }
+}
+
+#[must_use]
+pub fn parse_on_off(value: &str) -> Option<bool> {
+    match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// Changes the setting of the chat to the value after the command and replies with the change,
+/// or replies with the current value and usage if the value is missing or invalid
+pub async fn toggle_setting<T: Copy + Debug>(
+    bot: &Bot,
+    message: &Message,
+    bot_config: &BotConfig,
+    chat_config_store: &ChatConfigStore,
+    setting: Setting<T>,
+) -> HandlerResult {
+    let chat_id = message.chat().id();
+    let locale = locale::from_message(bot_config, message);
+
+    Span::current().record("chat_id", chat_id);
+
+    let value = message
+        .text()
+        .and_then(|text| text.split_whitespace().nth(1))
+        .and_then(setting.parse);
+
+    let text = match value {
+        Some(value) => {
+            (setting.set)(chat_config_store, chat_id, value);
+
+            event!(Level::INFO, ?value, "Chat setting changed");
+
+            (setting.changed_text)(locale, value)
+        }
+        None => (setting.usage_text)(locale, (setting.get)(&chat_config_store.get(chat_id))),
+    };
+
+    bot.send(
+        SendMessage::new(chat_id, text)
+            .message_thread_id_option(topic::thread_id(message))
+            .parse_mode(ParseMode::HTML)
+            .reply_parameters(ReplyParameters::new(message.id()).allow_sending_without_reply(true)),
+    )
+    .await?;
+
+    Ok(EventReturn::Finish)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_on_off() {
+        assert_eq!(parse_on_off("on"), Some(true));
+        assert_eq!(parse_on_off("off"), Some(false));
+        assert_eq!(parse_on_off("yes"), None);
+    }
+}
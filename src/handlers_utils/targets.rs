@@ -0,0 +1,74 @@
+use crate::filters::is_user_chat_admin;
+
+use telers::{
+    types::{ChatIdKind, Message},
+    Bot,
+};
+
+const PARAM_PREFIX: &str = "to=";
+
+/// Parses target chats of the `to=@channel1,@channel2` parameter of the message text.
+/// Chats can be set by username or ID, invalid ones are skipped.
+#[must_use]
+pub fn from_text(text: &str) -> Vec<ChatIdKind> {
+    let mut targets: Vec<ChatIdKind> = vec![];
+
+    for target in text
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix(PARAM_PREFIX))
+        .flat_map(|value| value.split(','))
+    {
+        let target = if target.len() > 1 && target.starts_with('@') {
+            ChatIdKind::username(target)
+        } else if let Ok(chat_id) = target.parse() {
+            ChatIdKind::id(chat_id)
+        } else {
+            continue;
+        };
+
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+
+    targets
+}
+
+/// Only admins of all target chats can send media to them, because the bot can post in any chat it's a member of
+pub async fn is_sender_allowed(bot: &Bot, message: &Message, targets: &[ChatIdKind]) -> bool {
+    let Some(user) = message.from() else {
+        return false;
+    };
+
+    for target in targets {
+        if !is_user_chat_admin(bot, target.clone(), user.id).await {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            from_text("/vd https://example.com to=@channel,-1001234567890"),
+            [ChatIdKind::username("@channel"), ChatIdKind::id(-1_001_234_567_890)]
+        );
+        assert_eq!(
+            from_text("/vd https://example.com to=@channel to=@channel,@other"),
+            [ChatIdKind::username("@channel"), ChatIdKind::username("@other")]
+        );
+    }
+
+    #[test]
+    fn test_from_text_skips_invalid_targets() {
+        assert!(from_text("/vd https://example.com").is_empty());
+        assert!(from_text("/vd https://example.com to=").is_empty());
+        assert_eq!(from_text("/vd https://example.com to=@,channel,,42"), [ChatIdKind::id(42)]);
+    }
+}
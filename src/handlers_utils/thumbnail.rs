@@ -0,0 +1,39 @@
+use crate::config::Bot as BotConfig;
+
+use telers::{methods::GetFile, types::Message, Bot};
+use tracing::{event, Level};
+
+/// Telegram ignores thumbnails with a side greater than this
+const MAX_THUMBNAIL_SIDE: i64 = 320;
+
+/// Returns the URL of the photo the message replies to, so it can be used as a custom thumbnail instead of the media one.
+/// Errors are only logged, because the media thumbnail is used in this case.
+pub async fn from_reply_photo(bot: &Bot, bot_config: &BotConfig, message: &Message) -> Option<String> {
+    let photo_sizes = message.reply_to_message()?.photo()?;
+
+    // The largest size accepted as a thumbnail or the smallest one, if all of them are too large
+    let photo_size = photo_sizes
+        .iter()
+        .filter(|photo_size| photo_size.width.max(photo_size.height) <= MAX_THUMBNAIL_SIDE)
+        .max_by_key(|photo_size| photo_size.width * photo_size.height)
+        .or_else(|| photo_sizes.iter().min_by_key(|photo_size| photo_size.width * photo_size.height))?;
+
+    let file = match bot.send(GetFile::new(photo_size.file_id.clone())).await {
+        Ok(file) => file,
+        Err(err) => {
+            event!(Level::WARN, %err, "Error getting custom thumbnail file");
+
+            return None;
+        }
+    };
+
+    match file.file_path {
+        // A local Bot API server returns an absolute path on its file system, it isn't available over HTTP
+        Some(file_path) if !file_path.starts_with('/') => Some(bot_config.file_url(&file_path)),
+        _ => {
+            event!(Level::WARN, "Custom thumbnail file isn't available for download");
+
+            None
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use telers::types::Message;
+
+/// ID of the forum topic of the message, so replies are posted in the topic where the link was shared.
+/// Threads of other chats, like comments of channel posts, aren't topics and `None` is returned for them.
+#[must_use]
+pub fn thread_id(message: &Message) -> Option<i64> {
+    if message.is_topic_message().unwrap_or_default() {
+        message.thread_id()
+    } else {
+        None
+    }
+}
@@ -0,0 +1,118 @@
+use crate::locale::Locale;
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+use telers::{
+    methods::{DeleteMessage, EditMessageText, SendMessage},
+    types::ReplyParameters,
+    Bot,
+};
+use tokio::{task::AbortHandle, time::interval};
+use tracing::{event, Level};
+
+/// Smaller files are uploaded fast, so a status message would be sent and removed right away
+const MIN_FILE_SIZE: u64 = 50 * 1024 * 1024;
+/// Telegram limits edits of a message, so the percentage is updated rarely
+const EDIT_INTERVAL: Duration = Duration::from_secs(10);
+
+fn progress_text(locale: Locale, uploaded: u64, file_size: u64) -> String {
+    format!("{} {}%", locale.uploading(), (uploaded * 100 / file_size).min(100))
+}
+
+/// Status message in the chat with the percentage of a large file read for the upload, it's updated until the progress is stopped.
+/// Chat actions don't show how much is left, while multi-GB files are uploaded for minutes.
+#[derive(Debug)]
+pub struct UploadProgress {
+    uploaded: Arc<AtomicU64>,
+    message_id: Arc<OnceLock<i64>>,
+    abort_handle: Option<AbortHandle>,
+    bot: Arc<Bot>,
+    chat_id: i64,
+}
+
+impl UploadProgress {
+    /// Sends the status message replying to the message with the link, if the file is large enough to show the progress.
+    /// `upload_size` is the size of the file read for the upload, see [`super::input_file::upload_size`].
+    #[must_use]
+    pub fn start(bot: Arc<Bot>, chat_id: i64, thread_id: Option<i64>, reply_to_message_id: i64, locale: Locale, upload_size: u64) -> Self {
+        let uploaded = Arc::new(AtomicU64::new(0));
+        let message_id = Arc::new(OnceLock::new());
+
+        let abort_handle = (upload_size >= MIN_FILE_SIZE).then(|| {
+            let bot = bot.clone();
+            let uploaded = uploaded.clone();
+            let message_id = message_id.clone();
+
+            tokio::spawn(async move {
+                let message = match bot
+                    .send(
+                        SendMessage::new(chat_id, progress_text(locale, 0, upload_size))
+                            .disable_notification(true)
+                            .message_thread_id_option(thread_id)
+                            .reply_parameters(ReplyParameters::new(reply_to_message_id).allow_sending_without_reply(true)),
+                    )
+                    .await
+                {
+                    Ok(message) => message,
+                    Err(err) => {
+                        event!(Level::WARN, %err, "Error while sending upload progress");
+
+                        return;
+                    }
+                };
+                let _ = message_id.set(message.id());
+
+                let mut interval = interval(EDIT_INTERVAL);
+                // The first tick is immediate, the message already has the initial percentage
+                interval.tick().await;
+
+                loop {
+                    interval.tick().await;
+
+                    let text = progress_text(locale, uploaded.load(Ordering::Relaxed), upload_size);
+
+                    if let Err(err) = bot.send(EditMessageText::new(text).chat_id(chat_id).message_id(message.id())).await {
+                        event!(Level::WARN, %err, "Error while editing upload progress");
+                    }
+                }
+            })
+            .abort_handle()
+        });
+
+        Self {
+            uploaded,
+            message_id,
+            abort_handle,
+            bot,
+            chat_id,
+        }
+    }
+
+    /// Counter of read bytes, see [`super::input_file::with_progress`]
+    #[must_use]
+    pub fn uploaded(&self) -> Arc<AtomicU64> {
+        self.uploaded.clone()
+    }
+
+    /// Stops updating the status message and deletes it in the background
+    pub fn stop(self) {
+        let Some(abort_handle) = self.abort_handle else {
+            return;
+        };
+
+        abort_handle.abort();
+
+        if let Some(&message_id) = self.message_id.get() {
+            let (bot, chat_id) = (self.bot, self.chat_id);
+
+            tokio::spawn(async move {
+                let _ = bot.send(DeleteMessage::new(chat_id, message_id)).await;
+            });
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{models::VideosInYT, youtube_fallback};
+
+use lazy_static::lazy_static;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use url::Url;
+
+/// Max number of cached URLs, so a burst of unique URLs doesn't grow the cache unbounded
+const MAX_ENTRIES: usize = 512;
+const PURGE_CALLBACK_DATA_PREFIX: &str = "pdc";
+
+lazy_static! {
+    static ref INFO_CACHE: Mutex<HashMap<(Box<str>, bool), (VideosInYT, Instant)>> = Mutex::default();
+}
+
+/// URLs that differ only in the fragment point to the same media.
+/// Alternate URLs of a video from youtube.com, like `youtu.be` and `shorts` links, are keyed by the video ID,
+/// URLs with a playlist are kept as is because they may point to the whole playlist.
+fn normalize_url(url: &str) -> Box<str> {
+    match Url::parse(url.trim()) {
+        Ok(mut url) => {
+            let has_playlist = url.query_pairs().any(|(key, _)| key == "list");
+
+            if let Some(video_id) = youtube_fallback::video_id(url.as_str()).filter(|_| !has_playlist) {
+                return format!("https://www.youtube.com/watch?v={video_id}").into();
+            }
+
+            url.set_fragment(None);
+            url.as_str().into()
+        }
+        Err(_) => url.trim().into(),
+    }
+}
+
+/// Returns media info of the URL got less than `ttl` ago
+pub fn get(url: &str, allow_playlist: bool, ttl: Duration) -> Option<VideosInYT> {
+    let key = (normalize_url(url), allow_playlist);
+    let mut cache = INFO_CACHE.lock().unwrap();
+
+    match cache.get(&key) {
+        Some((videos, cached_at)) if cached_at.elapsed() < ttl => Some(videos.clone()),
+        Some(_) => {
+            cache.remove(&key);
+
+            None
+        }
+        None => None,
+    }
+}
+
+pub fn insert(url: &str, allow_playlist: bool, videos: VideosInYT, ttl: Duration) {
+    let mut cache = INFO_CACHE.lock().unwrap();
+
+    cache.retain(|_, (_, cached_at)| cached_at.elapsed() < ttl);
+
+    if cache.len() >= MAX_ENTRIES {
+        let oldest_key = cache
+            .iter()
+            .min_by_key(|(_, (_, cached_at))| *cached_at)
+            .map(|(key, _)| key.clone());
+
+        if let Some(oldest_key) = oldest_key {
+            cache.remove(&oldest_key);
+        }
+    }
+
+    cache.insert((normalize_url(url), allow_playlist), (videos, Instant::now()));
+}
+
+/// Removal of cached entries of a domain, for example, after an extractor fix when cached info of the site is wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Purge {
+    pub domain: Box<str>,
+    /// Unix timestamp, only entries cached before it are removed
+    pub cached_before: Option<u64>,
+}
+
+/// Action of the purge confirmation keyboard button
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PurgeAction {
+    Confirm(Purge),
+    Cancel,
+}
+
+impl PurgeAction {
+    #[must_use]
+    pub fn to_callback_data(&self) -> String {
+        match self {
+            Self::Confirm(Purge {
+                domain,
+                cached_before: Some(cached_before),
+            }) => format!("{PURGE_CALLBACK_DATA_PREFIX}:{domain}:{cached_before}"),
+            Self::Confirm(Purge {
+                domain,
+                cached_before: None,
+            }) => format!("{PURGE_CALLBACK_DATA_PREFIX}:{domain}:"),
+            Self::Cancel => format!("{PURGE_CALLBACK_DATA_PREFIX}:"),
+        }
+    }
+
+    /// Returns `None` if the callback data isn't from the confirmation keyboard
+    #[must_use]
+    pub fn from_callback_data(data: &str) -> Option<Self> {
+        let mut parts = data.split(':');
+
+        if parts.next()? != PURGE_CALLBACK_DATA_PREFIX {
+            return None;
+        }
+
+        let domain = parts.next()?;
+
+        if domain.is_empty() {
+            return Some(Self::Cancel);
+        }
+
+        let cached_before = match parts.next()? {
+            "" => None,
+            cached_before => Some(cached_before.parse().ok()?),
+        };
+
+        Some(Self::Confirm(Purge {
+            domain: domain.into(),
+            cached_before,
+        }))
+    }
+}
+
+impl Purge {
+    fn matches(&self, url: &str, cached_at: Instant) -> bool {
+        let is_domain = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_lowercase))
+            .is_some_and(|host| host == *self.domain || host.ends_with(&format!(".{}", self.domain)));
+
+        is_domain
+            && self.cached_before.is_none_or(|cached_before| {
+                // Entries keep the monotonic time, so the timestamp is converted to the age of the entry
+                let cached_before = UNIX_EPOCH + Duration::from_secs(cached_before);
+
+                match SystemTime::now().duration_since(cached_before) {
+                    Ok(age) => cached_at.elapsed() > age,
+                    Err(_) => true,
+                }
+            })
+    }
+
+    /// Number of cached entries that would be removed
+    #[must_use]
+    pub fn count(&self) -> usize {
+        INFO_CACHE
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((url, _), (_, cached_at))| self.matches(url, *cached_at))
+            .count()
+    }
+
+    /// Removes the cached entries and returns their number
+    pub fn run(&self) -> usize {
+        let mut cache = INFO_CACHE.lock().unwrap();
+        let len = cache.len();
+
+        cache.retain(|(url, _), (_, cached_at)| !self.matches(url, *cached_at));
+
+        len - cache.len()
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{fs::JsonFile, locale::Locale, models::TgVideoInPlaylist};
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use uuid::Uuid;
+
+/// Download of videos requested by a message, it's kept until all its videos are sent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub chat_id: i64,
+    pub thread_id: Option<i64>,
+    pub message_id: i64,
+    pub locale: Locale,
+    /// Text of the command, download options and target chats are parsed from it again to resume the job
+    pub text: Box<str>,
+    /// URLs of the videos in the order they're sent, selected videos of a playlist have their own URLs
+    pub urls: Vec<Box<str>>,
+    /// Index of the first video whose download isn't finished
+    pub next_index: usize,
+    /// Videos uploaded before `next_index`, they're sent with the rest of the videos without downloading them again
+    pub uploaded: Vec<TgVideoInPlaylist>,
+}
+
+impl Job {
+    #[must_use]
+    pub fn new(
+        chat_id: i64,
+        thread_id: Option<i64>,
+        message_id: i64,
+        locale: Locale,
+        text: impl Into<Box<str>>,
+        urls: impl IntoIterator<Item = impl Into<Box<str>>>,
+    ) -> Self {
+        Self {
+            chat_id,
+            thread_id,
+            message_id,
+            locale,
+            text: text.into(),
+            urls: urls.into_iter().map(Into::into).collect(),
+            next_index: 0,
+            uploaded: vec![],
+        }
+    }
+
+    /// URLs of the videos that aren't downloaded yet
+    #[must_use]
+    pub fn unfinished_urls(&self) -> &[Box<str>] {
+        self.urls.get(self.next_index..).unwrap_or_default()
+    }
+}
+
+/// Download jobs in progress.
+/// Jobs are saved to the file in the background on every change and the ones left after a restart are resumed on startup.
+#[derive(Debug, Clone)]
+pub struct JobStore {
+    jobs: Arc<Mutex<HashMap<Box<str>, Job>>>,
+    file: JsonFile,
+    /// Whether the bot is shutting down, jobs dropped after it are kept to resume them
+    closed: Arc<AtomicBool>,
+}
+
+impl JobStore {
+    /// Loads jobs saved to the file, there are no jobs if it doesn't exist
+    /// # Errors
+    /// Returns [`io::Error`] if the file can't be read or parsed
+    pub fn load(path: PathBuf) -> Result<Self, io::Error> {
+        let file = JsonFile::new(path);
+
+        Ok(Self {
+            jobs: Arc::new(Mutex::new(file.read()?)),
+            file,
+            closed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    fn update(&self, f: impl FnOnce(&mut HashMap<Box<str>, Job>)) {
+        let mut jobs = self.jobs.lock().unwrap();
+
+        f(&mut jobs);
+
+        self.file.save_in_background(&*jobs);
+    }
+
+    /// Saves the job, it's removed from the store when the returned value is dropped
+    #[must_use]
+    pub fn start(&self, job: Job) -> RunningJob {
+        let id: Box<str> = Uuid::new_v4().simple().to_string().into();
+
+        self.update(|jobs| {
+            jobs.insert(id.clone(), job.clone());
+        });
+
+        RunningJob {
+            id,
+            job,
+            store: self.clone(),
+        }
+    }
+
+    /// Jobs interrupted by the last shutdown, they're kept in the store until they're resumed and finished
+    #[must_use]
+    pub fn unfinished(&self) -> Vec<RunningJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, job)| RunningJob {
+                id: id.clone(),
+                job: job.clone(),
+                store: self.clone(),
+            })
+            .collect()
+    }
+
+    /// Marks the bot as shutting down, so jobs aborted by the shutdown are resumed after the restart
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Job saved in [`JobStore`].
+/// It's removed from the store when dropped, unless the bot is shutting down.
+#[derive(Debug)]
+pub struct RunningJob {
+    id: Box<str>,
+    job: Job,
+    store: JobStore,
+}
+
+impl RunningJob {
+    #[must_use]
+    pub const fn job(&self) -> &Job {
+        &self.job
+    }
+
+    /// Moves the job to the next video and saves the uploaded media of the finished one
+    pub fn finish_video(&mut self, uploaded: &[TgVideoInPlaylist]) {
+        self.job.next_index += 1;
+        self.job.uploaded.extend_from_slice(uploaded);
+
+        self.store.update(|jobs| {
+            jobs.insert(self.id.clone(), self.job.clone());
+        });
+    }
+}
+
+impl Drop for RunningJob {
+    fn drop(&mut self) {
+        if self.store.closed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        self.store.update(|jobs| {
+            jobs.remove(&self.id);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_jobs_dropped_on_shutdown_are_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("jobs.json");
+        let store = JobStore::load(path.clone()).unwrap();
+        let new_job = || Job::new(1, None, 2, Locale::En, "/vd gif=1", ["https://a.com", "https://b.com"]);
+
+        drop(store.start(new_job()));
+        assert!(store.unfinished().is_empty());
+
+        let mut running_job = store.start(new_job());
+        running_job.finish_video(&[TgVideoInPlaylist::new("file_id", 0)]);
+        store.close();
+        drop(running_job);
+
+        let [job] = &*store.unfinished() else {
+            panic!("Job isn't kept");
+        };
+        assert_eq!(job.job().unfinished_urls(), [Box::from("https://b.com")]);
+
+        // Jobs are saved in the background, so the file is checked until the last change is saved
+        for _ in 0..100 {
+            let saved_jobs = JobStore::load(path.clone()).unwrap().jobs.lock().unwrap().clone();
+
+            if saved_jobs.values().any(|job| job.next_index == 1) {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        panic!("Jobs aren't saved");
+    }
+}
@@ -0,0 +1,119 @@
+use crate::fs::JsonFile;
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatStatus {
+    Active,
+    /// The bot was blocked or kicked from the chat, it's active again after a new update from the chat
+    Dead,
+}
+
+/// Chats the bot got updates from, they're the receivers of `/broadcast`.
+/// Chats are saved to the file in the background when they're added or their status changes, so they're kept across restarts.
+#[derive(Debug, Clone)]
+pub struct KnownChatsStore {
+    chats: Arc<Mutex<HashMap<i64, ChatStatus>>>,
+    file: JsonFile,
+}
+
+impl KnownChatsStore {
+    /// Loads chats saved to the file, there are no known chats if it doesn't exist
+    /// # Errors
+    /// Returns [`io::Error`] if the file can't be read or parsed
+    pub fn load(path: PathBuf) -> Result<Self, io::Error> {
+        let file = JsonFile::new(path);
+
+        Ok(Self {
+            chats: Arc::new(Mutex::new(file.read()?)),
+            file,
+        })
+    }
+
+    fn set_status(&self, chat_id: i64, status: ChatStatus) {
+        let mut chats = self.chats.lock().unwrap();
+
+        if chats.insert(chat_id, status) != Some(status) {
+            self.file.save_in_background(&*chats);
+        }
+    }
+
+    /// Adds the chat or marks a dead one as active, the file isn't saved if the chat is already active
+    pub fn insert(&self, chat_id: i64) {
+        self.set_status(chat_id, ChatStatus::Active);
+    }
+
+    /// Soft-deletes the chat, it's kept in the file but doesn't receive broadcasts
+    pub fn mark_dead(&self, chat_id: i64) {
+        self.set_status(chat_id, ChatStatus::Dead);
+    }
+
+    /// Active chats sorted by ID
+    #[must_use]
+    pub fn active(&self) -> Vec<i64> {
+        let mut chat_ids: Vec<i64> = self
+            .chats
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(chat_id, status)| (*status == ChatStatus::Active).then_some(*chat_id))
+            .collect();
+
+        chat_ids.sort_unstable();
+        chat_ids
+    }
+
+    /// Replaces a group upgraded to a supergroup with the new chat ID
+    pub fn migrate(&self, from_chat_id: i64, to_chat_id: i64) {
+        let mut chats = self.chats.lock().unwrap();
+        let Some(status) = chats.remove(&from_chat_id) else {
+            return;
+        };
+
+        chats.insert(to_chat_id, status);
+
+        self.file.save_in_background(&*chats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_dead_chats_are_not_active() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("known_chats.json");
+        let store = KnownChatsStore::load(path.clone()).unwrap();
+
+        store.insert(2);
+        store.insert(1);
+        store.insert(3);
+        store.mark_dead(2);
+        assert_eq!(store.active(), [1, 3]);
+
+        store.insert(2);
+        store.migrate(3, 4);
+        assert_eq!(store.active(), [1, 2, 4]);
+
+        // Chats are saved in the background, so the file is checked until the last change is saved
+        for _ in 0..100 {
+            if KnownChatsStore::load(path.clone()).unwrap().active() == [1, 2, 4] {
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        panic!("Chats aren't saved");
+    }
+}
@@ -0,0 +1,332 @@
+use bytes::Bytes;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash as _, Hasher as _},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tempfile::TempDir;
+use tracing::{event, Level};
+use uuid::Uuid;
+
+const REMOVE_EXPIRED_INTERVAL: Duration = Duration::from_secs(60);
+/// Max number of thumbnail links, the ones expiring first are removed first
+const MAX_THUMBNAILS: usize = 4096;
+
+#[derive(Debug)]
+struct Link {
+    path: PathBuf,
+    expires_at: Instant,
+    // Keep the directory alive until the link expires, the file is removed with it
+    _temp_dir: TempDir,
+}
+
+#[derive(Debug)]
+struct ThumbnailLink {
+    source_url: Box<str>,
+    expires_at: Instant,
+    /// JPEG converted on the first request, so the same thumbnail isn't converted on each request
+    jpeg: Option<Bytes>,
+}
+
+/// Thumbnail links by token with tokens by source URL, so the same thumbnail in many inline queries has one link
+#[derive(Debug, Default)]
+struct Thumbnails {
+    links: HashMap<Box<str>, ThumbnailLink>,
+    tokens: HashMap<Box<str>, Box<str>>,
+}
+
+impl Thumbnails {
+    fn retain(&mut self, mut f: impl FnMut(&ThumbnailLink) -> bool) {
+        let tokens = &mut self.tokens;
+
+        self.links.retain(|_, thumbnail| {
+            let keep = f(thumbnail);
+            if !keep {
+                tokens.remove(&thumbnail.source_url);
+            }
+
+            keep
+        });
+    }
+
+    fn remove_first_expiring(&mut self) {
+        let token = self
+            .links
+            .iter()
+            .min_by_key(|(_, thumbnail)| thumbnail.expires_at)
+            .map(|(token, _)| token.clone());
+
+        if let Some(link) = token.and_then(|token| self.links.remove(&token)) {
+            self.tokens.remove(&link.source_url);
+        }
+    }
+}
+
+/// Temporary download links for media exceeding the Telegram limits.
+/// Files are served by the HTTP server by an unguessable token until the link expires.
+///
+/// Also keeps links to thumbnails in formats unsupported by Telegram, the HTTP server converts them to JPEG.
+/// Source URLs are kept by token, so the server can't be used as an open proxy.
+#[derive(Debug, Clone)]
+pub struct LinkStore {
+    links: Arc<Mutex<HashMap<Box<str>, Link>>>,
+    thumbnails: Arc<Mutex<Thumbnails>>,
+    public_url: Option<Box<str>>,
+    max_file_size: u64,
+    retention: Duration,
+}
+
+impl LinkStore {
+    #[must_use]
+    pub fn new(public_url: Option<impl Into<Box<str>>>, max_file_size: u64, retention: Duration) -> Self {
+        Self {
+            links: Arc::default(),
+            thumbnails: Arc::default(),
+            public_url: public_url.map(Into::into),
+            max_file_size,
+            retention,
+        }
+    }
+
+    /// Links are disabled if the public URL of the HTTP server isn't set
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.public_url.is_some()
+    }
+
+    #[must_use]
+    pub const fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    #[must_use]
+    pub const fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Takes ownership of the temp dir, so the file lives until the link expires.
+    /// Returns the public URL of the file or `None` if links are disabled.
+    pub fn insert(&self, path: PathBuf, temp_dir: TempDir) -> Option<String> {
+        let public_url = self.public_url.as_deref()?;
+        let token = Uuid::new_v4().simple().to_string();
+
+        self.links.lock().unwrap().insert(
+            token.clone().into_boxed_str(),
+            Link {
+                path,
+                expires_at: Instant::now() + self.retention,
+                _temp_dir: temp_dir,
+            },
+        );
+
+        Some(format!("{}/download/{token}", public_url.trim_end_matches('/')))
+    }
+
+    /// Returns the public URL of the thumbnail converted to JPEG or `None` if links are disabled.
+    /// The link of a source URL is reused and kept for the retention since the last insert,
+    /// it should be close to the cache time of the inline query results with it.
+    pub fn insert_thumbnail(&self, source_url: &str, retention: Duration) -> Option<String> {
+        let public_url = self.public_url.as_deref()?;
+        let expires_at = Instant::now() + retention;
+        let mut thumbnails = self.thumbnails.lock().unwrap();
+
+        let token = if let Some(token) = thumbnails.tokens.get(source_url).cloned() {
+            if let Some(thumbnail) = thumbnails.links.get_mut(&token) {
+                thumbnail.expires_at = thumbnail.expires_at.max(expires_at);
+            }
+
+            token
+        } else {
+            if thumbnails.links.len() >= MAX_THUMBNAILS {
+                thumbnails.remove_first_expiring();
+            }
+
+            let token: Box<str> = Uuid::new_v4().simple().to_string().into();
+            thumbnails.tokens.insert(source_url.into(), token.clone());
+            thumbnails.links.insert(
+                token.clone(),
+                ThumbnailLink {
+                    source_url: source_url.into(),
+                    expires_at,
+                    jpeg: None,
+                },
+            );
+
+            token
+        };
+
+        Some(format!("{}/thumbnail/{token}.jpg", public_url.trim_end_matches('/')))
+    }
+
+    /// Returns the source URL of the thumbnail with its JPEG if it's already converted
+    #[must_use]
+    pub fn get_thumbnail(&self, token: &str) -> Option<(Box<str>, Option<Bytes>)> {
+        self.thumbnails
+            .lock()
+            .unwrap()
+            .links
+            .get(token)
+            .filter(|thumbnail| thumbnail.expires_at > Instant::now())
+            .map(|thumbnail| (thumbnail.source_url.clone(), thumbnail.jpeg.clone()))
+    }
+
+    /// Keeps the converted JPEG of the thumbnail until its link expires
+    pub fn set_thumbnail_jpeg(&self, token: &str, jpeg: Bytes) {
+        if let Some(thumbnail) = self.thumbnails.lock().unwrap().links.get_mut(token) {
+            thumbnail.jpeg = Some(jpeg);
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, token: &str) -> Option<PathBuf> {
+        self.links
+            .lock()
+            .unwrap()
+            .get(token)
+            .filter(|link| link.expires_at > Instant::now())
+            .map(|link| link.path.clone())
+    }
+
+    fn remove_expired(&self) -> usize {
+        let now = Instant::now();
+
+        let mut links = self.links.lock().unwrap();
+        let len_before = links.len();
+        links.retain(|_, link| link.expires_at > now);
+
+        self.thumbnails.lock().unwrap().retain(|thumbnail| thumbnail.expires_at > now);
+
+        len_before - links.len()
+    }
+}
+
+/// Short hash of the token to tell requests apart in logs, the token itself gives access to the file
+#[must_use]
+pub fn token_hash(token: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    token.hash(&mut hasher);
+
+    format!("{:08x}", hasher.finish() >> 32)
+}
+
+pub async fn remove_expired_in_loop(link_store: LinkStore) {
+    let mut interval = tokio::time::interval(REMOVE_EXPIRED_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let removed_count = link_store.remove_expired();
+
+        if removed_count > 0 {
+            event!(Level::DEBUG, removed_count, "Expired download links removed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_link_store(retention: Duration) -> LinkStore {
+        LinkStore::new(Some("https://bot.example.com/"), 1024, retention)
+    }
+
+    fn token(url: &str) -> &str {
+        url.rsplit('/').next().unwrap().trim_end_matches(".jpg")
+    }
+
+    fn thumbnail_source_url(link_store: &LinkStore, token: &str) -> Option<Box<str>> {
+        link_store.get_thumbnail(token).map(|(source_url, _)| source_url)
+    }
+
+    #[test]
+    fn test_links_are_disabled_without_public_url() {
+        let link_store = LinkStore::new(None::<&str>, 1024, Duration::from_secs(90));
+
+        assert!(!link_store.is_enabled());
+        assert_eq!(link_store.insert(PathBuf::from("video.mp4"), TempDir::new().unwrap()), None);
+        assert_eq!(
+            link_store.insert_thumbnail("https://example.com/thumbnail.webp", Duration::from_secs(90)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_link_expiry() {
+        let link_store = new_link_store(Duration::from_secs(90));
+        let url = link_store.insert(PathBuf::from("video.mp4"), TempDir::new().unwrap()).unwrap();
+
+        assert!(url.starts_with("https://bot.example.com/download/"));
+        assert_eq!(link_store.get(token(&url)), Some(PathBuf::from("video.mp4")));
+        assert_eq!(link_store.get("unknown"), None);
+        assert_eq!(link_store.remove_expired(), 0);
+
+        let link_store = new_link_store(Duration::ZERO);
+        let temp_dir = TempDir::new().unwrap();
+        let temp_dir_path = temp_dir.path().to_owned();
+        let url = link_store.insert(PathBuf::from("video.mp4"), temp_dir).unwrap();
+
+        assert_eq!(link_store.get(token(&url)), None);
+        assert_eq!(link_store.remove_expired(), 1);
+        // The file is removed with its directory when the link is removed
+        assert!(!temp_dir_path.exists());
+    }
+
+    #[test]
+    fn test_thumbnail_link_is_reused() {
+        let link_store = new_link_store(Duration::from_secs(90));
+        let source_url = "https://example.com/thumbnail.webp";
+
+        let url = link_store.insert_thumbnail(source_url, Duration::ZERO).unwrap();
+
+        assert!(url.starts_with("https://bot.example.com/thumbnail/"));
+        assert_eq!(thumbnail_source_url(&link_store, token(&url)), None);
+
+        // The expired link is extended instead of creating a new one
+        assert_eq!(link_store.insert_thumbnail(source_url, Duration::from_secs(90)).unwrap(), url);
+        assert_eq!(thumbnail_source_url(&link_store, token(&url)).as_deref(), Some(source_url));
+        // A shorter retention doesn't shorten the link
+        link_store.insert_thumbnail(source_url, Duration::ZERO);
+        assert_eq!(thumbnail_source_url(&link_store, token(&url)).as_deref(), Some(source_url));
+    }
+
+    #[test]
+    fn test_thumbnail_jpeg_is_cached() {
+        let link_store = new_link_store(Duration::from_secs(90));
+        let url = link_store
+            .insert_thumbnail("https://example.com/thumbnail.webp", Duration::from_secs(90))
+            .unwrap();
+
+        assert_eq!(link_store.get_thumbnail(token(&url)).unwrap().1, None);
+
+        link_store.set_thumbnail_jpeg(token(&url), Bytes::from_static(b"jpeg"));
+
+        assert_eq!(link_store.get_thumbnail(token(&url)).unwrap().1.as_deref(), Some(&b"jpeg"[..]));
+    }
+
+    #[test]
+    fn test_thumbnail_links_are_limited() {
+        let link_store = new_link_store(Duration::from_secs(90));
+        let first_url = link_store
+            .insert_thumbnail("https://example.com/0.webp", Duration::from_secs(1))
+            .unwrap();
+
+        for i in 1..=MAX_THUMBNAILS {
+            link_store.insert_thumbnail(&format!("https://example.com/{i}.webp"), Duration::from_secs(90));
+        }
+
+        // The first expiring link is removed to make room for the new one
+        assert_eq!(thumbnail_source_url(&link_store, token(&first_url)), None);
+        assert_eq!(link_store.thumbnails.lock().unwrap().links.len(), MAX_THUMBNAILS);
+        assert_eq!(link_store.thumbnails.lock().unwrap().tokens.len(), MAX_THUMBNAILS);
+    }
+
+    #[test]
+    fn test_token_hash() {
+        assert_eq!(token_hash("token"), token_hash("token"));
+        assert_ne!(token_hash("token"), token_hash("other"));
+        assert_eq!(token_hash("token").len(), 8);
+    }
+}
@@ -0,0 +1,1234 @@
+use crate::chat_config::MediaType;
+
+use serde::{Deserialize, Serialize};
+
+/// Interface language.
+/// The language of the deployment is used for system texts that aren't bound to a chat: startup messages, command descriptions
+/// and operator replies. Replies to users are in the language selected by [`crate::config::Bot::user_locale`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Ru,
+}
+
+impl Locale {
+    /// Parses a language code, for example `ru` or `en-US`. Regional variants use their base language.
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+
+        match code.split(['-', '_']).next().unwrap_or_default() {
+            "en" => Some(Self::En),
+            "ru" => Some(Self::Ru),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn receiver_chat_self_test(self) -> &'static str {
+        match self {
+            Self::En => "Self-test: the bot can post messages in this chat.",
+            Self::Ru => "Самопроверка: бот может отправлять сообщения в этот чат.",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_start(self) -> &'static str {
+        match self {
+            Self::En => "Start the bot",
+            Self::Ru => "Запустить бота",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_video_download(self) -> &'static str {
+        match self {
+            Self::En => "Download a video",
+            Self::Ru => "Скачать видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_audio_download(self) -> &'static str {
+        match self {
+            Self::En => "Download an audio",
+            Self::Ru => "Скачать аудио",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_video_select(self) -> &'static str {
+        match self {
+            Self::En => "Select videos of a playlist to download",
+            Self::Ru => "Выбрать видео из плейлиста для скачивания",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_video_note(self) -> &'static str {
+        match self {
+            Self::En => "Download a short video as a round video",
+            Self::Ru => "Скачать короткое видео как кружок",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_formats(self) -> &'static str {
+        match self {
+            Self::En => "Show available formats of a video",
+            Self::Ru => "Показать доступные форматы видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_stats(self) -> &'static str {
+        match self {
+            Self::En => "Show download statistics of the chat",
+            Self::Ru => "Показать статистику скачиваний чата",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_auto_download(self) -> &'static str {
+        match self {
+            Self::En => "Turn on or off downloading links in messages",
+            Self::Ru => "Включить или выключить скачивание ссылок в сообщениях",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_source_button(self) -> &'static str {
+        match self {
+            Self::En => "Turn on or off a button to the source under videos",
+            Self::Ru => "Включить или выключить кнопку с источником под видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_audio_button(self) -> &'static str {
+        match self {
+            Self::En => "Turn on or off a button to get the audio under videos",
+            Self::Ru => "Включить или выключить кнопку получения аудио под видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_show_link(self) -> &'static str {
+        match self {
+            Self::En => "Turn on or off a link to the source in captions",
+            Self::Ru => "Включить или выключить ссылку на источник в подписях",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_description(self) -> &'static str {
+        match self {
+            Self::En => "Turn on or off the description of the source in captions",
+            Self::Ru => "Включить или выключить описание источника в подписях",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_default_media_type(self) -> &'static str {
+        match self {
+            Self::En => "Choose whether links in messages are downloaded as videos or audios",
+            Self::Ru => "Выбрать, скачивать ссылки в сообщениях как видео или аудио",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_allow_domain(self) -> &'static str {
+        match self {
+            Self::En => "Download links in messages only from this domain and other allowed ones",
+            Self::Ru => "Скачивать ссылки в сообщениях только с этого и других разрешённых доменов",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_deny_domain(self) -> &'static str {
+        match self {
+            Self::En => "Stop downloading links in messages from an allowed domain",
+            Self::Ru => "Перестать скачивать ссылки в сообщениях с разрешённого домена",
+        }
+    }
+
+    #[must_use]
+    pub const fn command_transcribe(self) -> &'static str {
+        match self {
+            Self::En => "Transcribe speech of a video or audio",
+            Self::Ru => "Расшифровать речь видео или аудио",
+        }
+    }
+
+    #[must_use]
+    pub const fn yt_dlp_version(self) -> &'static str {
+        match self {
+            Self::En => "yt-dlp version",
+            Self::Ru => "Версия yt-dlp",
+        }
+    }
+
+    #[must_use]
+    pub const fn yt_dlp_version_error(self) -> &'static str {
+        match self {
+            Self::En => "Error getting yt-dlp version",
+            Self::Ru => "Ошибка получения версии yt-dlp",
+        }
+    }
+
+    #[must_use]
+    pub const fn yt_dlp_updating(self) -> &'static str {
+        match self {
+            Self::En => "Updating yt-dlp...",
+            Self::Ru => "Обновление yt-dlp...",
+        }
+    }
+
+    #[must_use]
+    pub const fn yt_dlp_updated(self) -> &'static str {
+        match self {
+            Self::En => "yt-dlp updated, version",
+            Self::Ru => "yt-dlp обновлён, версия",
+        }
+    }
+
+    #[must_use]
+    pub const fn yt_dlp_update_error(self) -> &'static str {
+        match self {
+            Self::En => "Error updating yt-dlp",
+            Self::Ru => "Ошибка обновления yt-dlp",
+        }
+    }
+
+    #[must_use]
+    pub fn auto_download_usage(self, enabled: bool) -> String {
+        match self {
+            Self::En => format!(
+                "Links in messages are downloaded automatically: {}.\nUsage: /autodownload on|off",
+                if enabled { "on" } else { "off" }
+            ),
+            Self::Ru => format!(
+                "Ссылки в сообщениях скачиваются автоматически: {}.\nИспользование: /autodownload on|off",
+                if enabled { "да" } else { "нет" }
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn auto_download_toggled(self, enabled: bool) -> String {
+        match (self, enabled) {
+            (Self::En, true) => "Links in messages will be downloaded automatically.".to_owned(),
+            (Self::En, false) => "Links in messages won't be downloaded automatically, use /vd and /ad instead.".to_owned(),
+            (Self::Ru, true) => "Ссылки в сообщениях будут скачиваться автоматически.".to_owned(),
+            (Self::Ru, false) => "Ссылки в сообщениях не будут скачиваться автоматически, используй /vd и /ad.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn source_button_usage(self, enabled: bool) -> String {
+        match self {
+            Self::En => format!(
+                "Videos are sent with a button to their source: {}.\nUsage: /source_button on|off",
+                if enabled { "on" } else { "off" }
+            ),
+            Self::Ru => format!(
+                "Видео отправляются с кнопкой на источник: {}.\nИспользование: /source_button on|off",
+                if enabled { "да" } else { "нет" }
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn source_button_toggled(self, enabled: bool) -> String {
+        match (self, enabled) {
+            (Self::En, true) => "Videos will be sent with a button to their source, one by one instead of albums.".to_owned(),
+            (Self::En, false) => "Videos will be sent without a button to their source.".to_owned(),
+            (Self::Ru, true) => "Видео будут отправляться с кнопкой на источник, по одному вместо альбомов.".to_owned(),
+            (Self::Ru, false) => "Видео будут отправляться без кнопки на источник.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn show_link_usage(self, visible: bool) -> String {
+        match self {
+            Self::En => format!(
+                "Captions of videos and audios have a link to their source: {}.\nUsage: /show_link on|off",
+                if visible { "on" } else { "off" }
+            ),
+            Self::Ru => format!(
+                "В подписях видео и аудио есть ссылка на источник: {}.\nИспользование: /show_link on|off",
+                if visible { "да" } else { "нет" }
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn show_link_toggled(self, visible: bool) -> String {
+        match (self, visible) {
+            (Self::En, true) => "Captions of videos and audios will have a link to their source.".to_owned(),
+            (Self::En, false) => "Captions of videos and audios won't have a link to their source.".to_owned(),
+            (Self::Ru, true) => "В подписях видео и аудио будет ссылка на источник.".to_owned(),
+            (Self::Ru, false) => "В подписях видео и аудио не будет ссылки на источник.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn description_usage(self, enabled: bool) -> String {
+        match self {
+            Self::En => format!(
+                "Captions of videos have the description of their source: {}.\nUsage: /description on|off",
+                if enabled { "on" } else { "off" }
+            ),
+            Self::Ru => format!(
+                "В подписях видео есть описание источника: {}.\nИспользование: /description on|off",
+                if enabled { "да" } else { "нет" }
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn description_toggled(self, enabled: bool) -> String {
+        match (self, enabled) {
+            (Self::En, true) => "Captions of videos will have the description of their source.".to_owned(),
+            (Self::En, false) => "Captions of videos won't have the description of their source.".to_owned(),
+            (Self::Ru, true) => "В подписях видео будет описание источника.".to_owned(),
+            (Self::Ru, false) => "В подписях видео не будет описания источника.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn default_media_type_usage(self, media_type: MediaType) -> String {
+        match (self, media_type) {
+            (Self::En, MediaType::Video) => "Links in messages are downloaded as videos.\nUsage: /default audio|video".to_owned(),
+            (Self::En, MediaType::Audio) => "Links in messages are downloaded as audios.\nUsage: /default audio|video".to_owned(),
+            (Self::Ru, MediaType::Video) => "Ссылки в сообщениях скачиваются как видео.\nИспользование: /default audio|video".to_owned(),
+            (Self::Ru, MediaType::Audio) => "Ссылки в сообщениях скачиваются как аудио.\nИспользование: /default audio|video".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn default_media_type_set(self, media_type: MediaType) -> String {
+        match (self, media_type) {
+            (Self::En, MediaType::Video) => "Links in messages will be downloaded as videos, use /ad for audios.".to_owned(),
+            (Self::En, MediaType::Audio) => "Links in messages will be downloaded as audios, use /vd for videos.".to_owned(),
+            (Self::Ru, MediaType::Video) => "Ссылки в сообщениях будут скачиваться как видео, используй /ad для аудио.".to_owned(),
+            (Self::Ru, MediaType::Audio) => "Ссылки в сообщениях будут скачиваться как аудио, используй /vd для видео.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub fn include_domains_usage(self, domains: &str) -> String {
+        match (self, domains.is_empty()) {
+            (Self::En, true) => {
+                "Links in messages are downloaded from all domains.\nUsage: /allow_domain youtube.com, /deny_domain youtube.com".to_owned()
+            }
+            (Self::En, false) => format!(
+                "Links in messages are downloaded only from: {domains}.\nUsage: /allow_domain youtube.com, /deny_domain youtube.com"
+            ),
+            (Self::Ru, true) => {
+                "Ссылки в сообщениях скачиваются со всех доменов.\nИспользование: /allow_domain youtube.com, /deny_domain youtube.com"
+                    .to_owned()
+            }
+            (Self::Ru, false) => format!(
+                "Ссылки в сообщениях скачиваются только с: {domains}.\nИспользование: /allow_domain youtube.com, /deny_domain youtube.com"
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn domain_included(self, domain: &str) -> String {
+        match self {
+            Self::En => {
+                format!("Links in messages will be downloaded from {domain}, links of other domains that aren't allowed need /vd or /ad.")
+            }
+            Self::Ru => {
+                format!("Ссылки в сообщениях будут скачиваться с {domain}, для ссылок других неразрешённых доменов нужны /vd или /ad.")
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn domain_already_included(self, domain: &str) -> String {
+        match self {
+            Self::En => format!("Links in messages are already downloaded from {domain}."),
+            Self::Ru => format!("Ссылки в сообщениях уже скачиваются с {domain}."),
+        }
+    }
+
+    #[must_use]
+    pub fn domain_excluded(self, domain: &str, domains_left: &str) -> String {
+        match (self, domains_left.is_empty()) {
+            (Self::En, true) => {
+                format!("{domain} is removed, no domains are left, so links in messages will be downloaded from all domains.")
+            }
+            (Self::En, false) => format!("Links in messages won't be downloaded from {domain}, only from: {domains_left}."),
+            (Self::Ru, true) => {
+                format!("{domain} удалён, доменов не осталось, поэтому ссылки в сообщениях будут скачиваться со всех доменов.")
+            }
+            (Self::Ru, false) => format!("Ссылки в сообщениях не будут скачиваться с {domain}, только с: {domains_left}."),
+        }
+    }
+
+    #[must_use]
+    pub fn domain_not_included(self, domain: &str) -> String {
+        match self {
+            Self::En => format!("{domain} isn't among the allowed domains."),
+            Self::Ru => format!("{domain} нет среди разрешённых доменов."),
+        }
+    }
+
+    #[must_use]
+    pub fn audio_button_usage(self, enabled: bool) -> String {
+        match self {
+            Self::En => format!(
+                "Videos are sent with a button to get their audio: {}.\nUsage: /audio_button on|off",
+                if enabled { "on" } else { "off" }
+            ),
+            Self::Ru => format!(
+                "Видео отправляются с кнопкой получения аудио: {}.\nИспользование: /audio_button on|off",
+                if enabled { "да" } else { "нет" }
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn audio_button_toggled(self, enabled: bool) -> String {
+        match (self, enabled) {
+            (Self::En, true) => "Videos will be sent with a button to get their audio, one by one instead of albums.".to_owned(),
+            (Self::En, false) => "Videos will be sent without a button to get their audio.".to_owned(),
+            (Self::Ru, true) => "Видео будут отправляться с кнопкой получения аудио, по одному вместо альбомов.".to_owned(),
+            (Self::Ru, false) => "Видео будут отправляться без кнопки получения аудио.".to_owned(),
+        }
+    }
+
+    #[must_use]
+    pub const fn get_audio(self) -> &'static str {
+        match self {
+            Self::En => "🎵 Get audio",
+            Self::Ru => "🎵 Получить аудио",
+        }
+    }
+
+    #[must_use]
+    pub const fn audio_button_unavailable(self) -> &'static str {
+        match self {
+            Self::En => "This button is expired, send the link with /ad",
+            Self::Ru => "Эта кнопка устарела, отправь ссылку с /ad",
+        }
+    }
+
+    #[must_use]
+    pub const fn source(self) -> &'static str {
+        match self {
+            Self::En => "Source",
+            Self::Ru => "Источник",
+        }
+    }
+
+    #[must_use]
+    pub const fn purge_domain_usage(self) -> &'static str {
+        match self {
+            Self::En => "Usage: /purge_domain &lt;domain&gt; [--before YYYY-MM-DD]",
+            Self::Ru => "Использование: /purge_domain &lt;домен&gt; [--before ГГГГ-ММ-ДД]",
+        }
+    }
+
+    #[must_use]
+    pub const fn purge_domain_nothing(self) -> &'static str {
+        match self {
+            Self::En => "There are no cached entries of the domain",
+            Self::Ru => "Нет кэшированных записей домена",
+        }
+    }
+
+    #[must_use]
+    pub const fn purge_domain_confirm(self) -> &'static str {
+        match self {
+            Self::En => "Cached entries to remove",
+            Self::Ru => "Кэшированных записей будет удалено",
+        }
+    }
+
+    #[must_use]
+    pub const fn purge_domain_done(self) -> &'static str {
+        match self {
+            Self::En => "Removed cached entries",
+            Self::Ru => "Удалено кэшированных записей",
+        }
+    }
+
+    #[must_use]
+    pub const fn purge_domain_cancelled(self) -> &'static str {
+        match self {
+            Self::En => "Purge cancelled",
+            Self::Ru => "Удаление отменено",
+        }
+    }
+
+    #[must_use]
+    pub const fn broadcast_usage(self) -> &'static str {
+        match self {
+            Self::En => "Reply with /broadcast to the message to send to all chats of the bot",
+            Self::Ru => "Ответь /broadcast на сообщение, чтобы отправить его во все чаты бота",
+        }
+    }
+
+    #[must_use]
+    pub fn broadcast_progress(self, sent: usize, failed: usize, removed: usize, total: usize) -> String {
+        let done = if sent + failed == total { "✓ " } else { "" };
+
+        match self {
+            Self::En => format!("{done}Broadcast: {sent}/{total} sent, {failed} failed, {removed} of them blocked the bot or removed it"),
+            Self::Ru => {
+                format!("{done}Рассылка: отправлено {sent}/{total}, ошибок {failed}, из них {removed} заблокировали или удалили бота")
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn confirm(self) -> &'static str {
+        match self {
+            Self::En => "Confirm",
+            Self::Ru => "Подтвердить",
+        }
+    }
+
+    #[must_use]
+    pub const fn cancel(self) -> &'static str {
+        match self {
+            Self::En => "Cancel",
+            Self::Ru => "Отмена",
+        }
+    }
+
+    #[must_use]
+    pub fn help(self, first_name: &str, bot_username: &str, capabilities: &str, source_code_href: &str) -> String {
+        match self {
+            Self::En => format!(
+                "Hi, {first_name}. I'm a bot that can help you download videos from YouTube.\n\n\
+                In a private chat, send me a video link and I will reply with a video or playlist.\n\
+                In a group chat, send <code>/vd</code> (<code>/video_download</code>) with a link or reply to the message with a link.\n\n\
+                If you want to download an audio, send <code>/ad</code> (<code>/audio_download</code>) instead of <code>/vd</code>. \
+                This command works the same way as previous.\n\
+                Add <code>abr=128</code> (bitrate in kbps) or <code>aext=mp3</code> (<code>mp3</code> or <code>m4a</code>) to <code>/ad</code> \
+                to convert the audio, or <code>normalize=1</code> to even out its loudness.\n\
+                Add <code>archive=1</code> to <code>/ad</code> with a playlist to get all audios in a single ZIP archive.\n\
+                Short videos without sound are sent as GIFs, add <code>gif=1</code> to <code>/vd</code> to send any video this way.\n\
+                Add <code>chapters=1</code> to <code>/vd</code> to get a video with chapters as a file per chapter.\n\
+                Add <code>sb=sponsor,intro</code> to <code>/vd</code> to choose SponsorBlock segments removed from YouTube videos, \
+                or <code>sb=0</code> to keep them, if the removal is enabled.\n\
+                Add <code>header=1</code> to <code>/vd</code> with a playlist to send its uploader, title and total duration before the videos.\n\
+                To use your own thumbnail, reply to a photo with <code>/vd</code> or <code>/ad</code> and a link.\n\n\
+                To pick specific videos of a playlist, send <code>/vs</code> (<code>/video_select</code>) with a link.\n\
+                To get a short video (up to 60 seconds) as a round video, send <code>/round</code> with a link.\n\
+                To see formats of a video with their IDs, sizes and codecs, send <code>/formats</code> with a link. \
+                Add <code>format=137+140</code> (video and audio IDs) or <code>format=22</code> to <code>/vd</code> to download an exact format.\n\
+                To see download statistics of the chat, send <code>/stats</code>.\n\
+                Chat admins can turn off downloading links in messages without commands by <code>/autodownload off</code>, \
+                add a button to the source under videos by <code>/source_button on</code>, \
+                a button to get the audio under videos by <code>/audio_button on</code>, \
+                a link to the source in captions by <code>/show_link on</code>, \
+                the description of the source in captions of videos by <code>/description on</code>, \
+                download links in messages as audios by <code>/default audio</code>, \
+                and download links in messages only from some domains by <code>/allow_domain youtube.com</code>.\n\n\
+                You can use me in inline mode in any chat by typing <code>@{bot_username} </code><code>&lt;url&gt;</code>.\n\n\
+                {capabilities}\n\
+                * The bot is open source, and you can find the source code {source_code_href}.",
+            ),
+            Self::Ru => format!(
+                "Привет, {first_name}. Я бот, который поможет скачать видео с YouTube.\n\n\
+                В личном чате отправь мне ссылку на видео, и я отвечу видео или плейлистом.\n\
+                В группе отправь <code>/vd</code> (<code>/video_download</code>) со ссылкой или ответь им на сообщение со ссылкой.\n\n\
+                Чтобы скачать аудио, отправь <code>/ad</code> (<code>/audio_download</code>) вместо <code>/vd</code>. \
+                Эта команда работает так же, как предыдущая.\n\
+                Добавь <code>abr=128</code> (битрейт в кбит/с) или <code>aext=mp3</code> (<code>mp3</code> или <code>m4a</code>) к <code>/ad</code>, \
+                чтобы сконвертировать аудио, или <code>normalize=1</code>, чтобы выровнять громкость.\n\
+                Добавь <code>archive=1</code> к <code>/ad</code> с плейлистом, чтобы получить все аудио одним ZIP-архивом.\n\
+                Короткие видео без звука отправляются как GIF, добавь <code>gif=1</code> к <code>/vd</code>, чтобы так отправить любое видео.\n\
+                Добавь <code>chapters=1</code> к <code>/vd</code>, чтобы получить видео с главами отдельным файлом на каждую главу.\n\
+                Добавь <code>sb=sponsor,intro</code> к <code>/vd</code>, чтобы выбрать фрагменты SponsorBlock, удаляемые из видео YouTube, \
+                или <code>sb=0</code>, чтобы оставить их, если удаление включено.\n\
+                Добавь <code>header=1</code> к <code>/vd</code> с плейлистом, чтобы перед видео отправить автора, название и общую длительность.\n\
+                Чтобы использовать свою обложку, ответь на фото командой <code>/vd</code> или <code>/ad</code> со ссылкой.\n\n\
+                Чтобы выбрать отдельные видео из плейлиста, отправь <code>/vs</code> (<code>/video_select</code>) со ссылкой.\n\
+                Чтобы получить короткое видео (до 60 секунд) кружком, отправь <code>/round</code> со ссылкой.\n\
+                Чтобы посмотреть форматы видео с их ID, размерами и кодеками, отправь <code>/formats</code> со ссылкой. \
+                Добавь <code>format=137+140</code> (ID видео и аудио) или <code>format=22</code> к <code>/vd</code>, чтобы скачать конкретный формат.\n\
+                Чтобы посмотреть статистику скачиваний чата, отправь <code>/stats</code>.\n\
+                Админы чата могут выключить скачивание ссылок в сообщениях без команд через <code>/autodownload off</code>, \
+                добавить кнопку на источник под видео через <code>/source_button on</code>, \
+                кнопку получения аудио под видео через <code>/audio_button on</code>, \
+                ссылку на источник в подписи через <code>/show_link on</code>, \
+                описание источника в подписи видео через <code>/description on</code>, \
+                скачивать ссылки в сообщениях как аудио через <code>/default audio</code>, \
+                и скачивать ссылки в сообщениях только с некоторых доменов через <code>/allow_domain youtube.com</code>.\n\n\
+                Меня можно использовать в инлайн-режиме в любом чате, набрав <code>@{bot_username} </code><code>&lt;ссылка&gt;</code>.\n\n\
+                {capabilities}\n\
+                * Бот с открытым исходным кодом, исходный код можно найти {source_code_href}.",
+            ),
+        }
+    }
+
+    #[must_use]
+    pub const fn source_code_link(self) -> &'static str {
+        match self {
+            Self::En => "here",
+            Self::Ru => "здесь",
+        }
+    }
+
+    #[must_use]
+    pub const fn anonymous(self) -> &'static str {
+        match self {
+            Self::En => "Anonymous",
+            Self::Ru => "Аноним",
+        }
+    }
+
+    #[must_use]
+    pub fn capability_max_file_size(self, max_file_size_in_mb: u64) -> String {
+        match self {
+            Self::En => format!("* I download videos and audios in the best quality that is less than {max_file_size_in_mb}MB."),
+            Self::Ru => format!("* Я скачиваю видео и аудио в лучшем качестве размером меньше {max_file_size_in_mb}МБ."),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_download_links(self, max_file_size_in_mb: u64, retention_in_minutes: u64) -> String {
+        match self {
+            Self::En => format!(
+                "* Larger media up to {max_file_size_in_mb}MB is sent as a download link, which expires in {retention_in_minutes} minutes."
+            ),
+            Self::Ru => format!(
+                "* Медиа большего размера до {max_file_size_in_mb}МБ отправляется ссылкой на скачивание, \
+                которая действует {retention_in_minutes} минут."
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_max_total_size(self, max_total_size_in_mb: u64) -> String {
+        match self {
+            Self::En => format!("* Playlists larger than {max_total_size_in_mb}MB in total aren't downloaded."),
+            Self::Ru => format!("* Плейлисты общим размером больше {max_total_size_in_mb}МБ не скачиваются."),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_max_urls_per_message(self, max_urls_per_message: usize) -> String {
+        match self {
+            Self::En => format!("* Up to {max_urls_per_message} links from one message are downloaded at once."),
+            Self::Ru => format!("* Из одного сообщения скачивается до {max_urls_per_message} ссылок сразу."),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_summary(self, min_duration_in_minutes: u64) -> String {
+        match self {
+            Self::En => format!("* Videos longer than {min_duration_in_minutes} minutes get a short summary in this chat."),
+            Self::Ru => format!("* Видео длиннее {min_duration_in_minutes} минут получают краткое содержание в этом чате."),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_transcription(self, max_duration_in_minutes: u64) -> String {
+        match self {
+            Self::En => format!(
+                "* Send <code>/transcribe</code> with a link to get the transcript of media up to {max_duration_in_minutes} minutes long, \
+                add <code>srt=1</code> to get it as subtitles."
+            ),
+            Self::Ru => format!(
+                "* Отправь <code>/transcribe</code> со ссылкой, чтобы получить расшифровку медиа длиной до {max_duration_in_minutes} минут, \
+                добавь <code>srt=1</code>, чтобы получить её субтитрами."
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn capability_allowed_domains(self, domains: &str) -> String {
+        match self {
+            Self::En => format!("* In this chat, links are downloaded without a command only from: {domains}."),
+            Self::Ru => format!("* В этом чате без команды скачиваются только ссылки с: {domains}."),
+        }
+    }
+
+    #[must_use]
+    pub const fn capability_mirrors(self) -> &'static str {
+        match self {
+            Self::En => "* Media downloaded in this chat is also reposted to other chats.",
+            Self::Ru => "* Медиа, скачанные в этом чате, также пересылаются в другие чаты.",
+        }
+    }
+
+    #[must_use]
+    pub const fn capability_targets(self) -> &'static str {
+        match self {
+            Self::En => {
+                "* As an admin of a chat, you can add <code>to=@channel</code> to <code>/vd</code> and <code>/ad</code> \
+                to send media to the chat too."
+            }
+            Self::Ru => {
+                "* Как администратор чата, ты можешь добавить <code>to=@channel</code> к <code>/vd</code> и <code>/ad</code>, \
+                чтобы отправить медиа и в этот чат."
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn capability_no_inline_playlists(self) -> &'static str {
+        match self {
+            Self::En => "* You can't download playlists in inline mode.",
+            Self::Ru => "* В инлайн-режиме нельзя скачивать плейлисты.",
+        }
+    }
+
+    #[must_use]
+    pub fn download_videos_error(self, count: usize) -> String {
+        match (self, count) {
+            (Self::En, 1) => "Sorry, an error occurred while downloading the video. Try again later.".to_owned(),
+            (Self::En, _) => format!("Sorry, an error occurred while downloading {count} videos from the playlist. Try again later."),
+            (Self::Ru, 1) => "Извините, при скачивании видео произошла ошибка. Попробуйте позже.".to_owned(),
+            (Self::Ru, _) => format!("Извините, при скачивании видео из плейлиста произошла ошибка ({count} шт.). Попробуйте позже."),
+        }
+    }
+
+    #[must_use]
+    pub fn download_audios_error(self, count: usize) -> String {
+        match (self, count) {
+            (Self::En, 1) => "Sorry, an error occurred while downloading the audio. Try again later.".to_owned(),
+            (Self::En, _) => format!("Sorry, an error occurred while downloading {count} audios from the playlist. Try again later."),
+            (Self::Ru, 1) => "Извините, при скачивании аудио произошла ошибка. Попробуйте позже.".to_owned(),
+            (Self::Ru, _) => format!("Извините, при скачивании аудио из плейлиста произошла ошибка ({count} шт.). Попробуйте позже."),
+        }
+    }
+
+    #[must_use]
+    pub const fn download_media_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while downloading media. Try again later.",
+            Self::Ru => "Извините, при скачивании медиа произошла ошибка. Попробуйте позже.",
+        }
+    }
+
+    #[must_use]
+    pub const fn geo_restricted(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, this media isn't available in the bot's country.",
+            Self::Ru => "Извините, это медиа недоступно в стране бота.",
+        }
+    }
+
+    #[must_use]
+    pub const fn age_restricted(self) -> &'static str {
+        match self {
+            Self::En => {
+                "Sorry, this media is age-restricted and can't be downloaded without signing in. \
+                The bot owner can add cookies of an account for this site to download it."
+            }
+            Self::Ru => {
+                "Извините, у этого медиа возрастное ограничение, и его нельзя скачать без входа в аккаунт. \
+                Владелец бота может добавить cookies аккаунта этого сайта, чтобы его скачать."
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn login_required(self) -> &'static str {
+        match self {
+            Self::En => {
+                "Sorry, this site requires signing in to download this media. \
+                The bot owner can add cookies of an account for this site to download it."
+            }
+            Self::Ru => {
+                "Извините, для скачивания этого медиа сайт требует вход в аккаунт. \
+                Владелец бота может добавить cookies аккаунта этого сайта, чтобы его скачать."
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn private_media(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, this media is private.",
+            Self::Ru => "Извините, это медиа приватное.",
+        }
+    }
+
+    #[must_use]
+    pub const fn media_not_found(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, this media isn't found. Check the link, maybe it's removed.",
+            Self::Ru => "Извините, это медиа не найдено. Проверьте ссылку, возможно, оно удалено.",
+        }
+    }
+
+    #[must_use]
+    pub const fn drm_protected(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, this media is DRM protected and can't be downloaded.",
+            Self::Ru => "Извините, это медиа защищено DRM и не может быть скачано.",
+        }
+    }
+
+    #[must_use]
+    pub const fn video_info_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while getting video/playlist info. Try again later.",
+            Self::Ru => "Извините, при получении информации о видео/плейлисте произошла ошибка. Попробуйте позже.",
+        }
+    }
+
+    #[must_use]
+    pub const fn audio_info_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while getting audio/playlist info. Try again later.",
+            Self::Ru => "Извините, при получении информации об аудио/плейлисте произошла ошибка. Попробуйте позже.",
+        }
+    }
+
+    #[must_use]
+    pub const fn archive_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while sending the audios in an archive. Try again later or without archive=1.",
+            Self::Ru => "Извините, при отправке аудио архивом произошла ошибка. Попробуйте позже или без archive=1.",
+        }
+    }
+
+    #[must_use]
+    pub const fn media_info_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while getting media/playlist info.",
+            Self::Ru => "Извините, при получении информации о медиа/плейлисте произошла ошибка.",
+        }
+    }
+
+    #[must_use]
+    pub fn skipped_urls(self, urls: &str) -> String {
+        match self {
+            Self::En => format!("Sorry, an error occurred while getting info for these links, they're skipped:\n{urls}"),
+            Self::Ru => format!("Извините, при получении информации по этим ссылкам произошла ошибка, они пропущены:\n{urls}"),
+        }
+    }
+
+    #[must_use]
+    pub fn segments_removed(self, duration: &str) -> String {
+        match self {
+            Self::En => format!("SponsorBlock segments removed: {duration}"),
+            Self::Ru => format!("Удалены фрагменты SponsorBlock: {duration}"),
+        }
+    }
+
+    #[must_use]
+    pub const fn invalid_sponsorblock_categories(self) -> &'static str {
+        match self {
+            Self::En => {
+                "Sorry, SponsorBlock categories are invalid. Use sb= with comma-separated sponsor, intro, outro, selfpromo, \
+                preview, filler, interaction, music_offtopic or all, or sb=0 to keep all segments."
+            }
+            Self::Ru => {
+                "Извините, категории SponsorBlock неверны. Используйте sb= с перечисленными через запятую sponsor, intro, outro, \
+                selfpromo, preview, filler, interaction, music_offtopic или all, или sb=0, чтобы оставить все фрагменты."
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn format_not_listed(self, format_id: &str) -> String {
+        match self {
+            Self::En => {
+                format!("Sorry, the format {format_id} isn't listed for the video. Send /formats with the link to see available formats.")
+            }
+            Self::Ru => {
+                format!("Извините, формата {format_id} нет у видео. Отправь /formats со ссылкой, чтобы посмотреть доступные форматы.")
+            }
+        }
+    }
+
+    #[must_use]
+    pub const fn formats_not_found(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, the video doesn't have formats that I can download.",
+            Self::Ru => "Извините, у видео нет форматов, которые я могу скачать.",
+        }
+    }
+
+    #[must_use]
+    pub fn formats_skipped(self, count: usize) -> String {
+        match self {
+            Self::En => format!("{count} more formats don't fit in the message."),
+            Self::Ru => format!("Ещё {count} форматов не поместились в сообщение."),
+        }
+    }
+
+    #[must_use]
+    pub const fn video_not_found(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, video not found.",
+            Self::Ru => "Извините, видео не найдено.",
+        }
+    }
+
+    #[must_use]
+    pub const fn live_stream_not_supported(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, the live stream is still going on. Send the link again after it ends.",
+            Self::Ru => "Извините, трансляция ещё идёт. Отправь ссылку снова, когда она закончится.",
+        }
+    }
+
+    #[must_use]
+    pub fn live_streams_skipped(self, count: usize) -> String {
+        match self {
+            Self::En => format!("{count} live streams are still going on, they're skipped."),
+            Self::Ru => format!("{count} трансляций ещё идут, они пропущены."),
+        }
+    }
+
+    #[must_use]
+    pub const fn playlist_without_videos(self) -> &'static str {
+        match self {
+            Self::En => "Playlist doesn't have videos.",
+            Self::Ru => "В плейлисте нет видео.",
+        }
+    }
+
+    #[must_use]
+    pub const fn playlist_without_audios(self) -> &'static str {
+        match self {
+            Self::En => "Playlist doesn't have audios.",
+            Self::Ru => "В плейлисте нет аудио.",
+        }
+    }
+
+    #[must_use]
+    pub fn playlist_too_large(self, total_size_in_mb: u64, max_total_size_in_mb: u64) -> String {
+        match self {
+            Self::En => format!(
+                "Sorry, the playlist is too large to download: about {total_size_in_mb} MB, while the limit is {max_total_size_in_mb} MB."
+            ),
+            Self::Ru => format!(
+                "Извините, плейлист слишком большой для скачивания: около {total_size_in_mb} МБ при ограничении {max_total_size_in_mb} МБ."
+            ),
+        }
+    }
+
+    #[must_use]
+    pub const fn only_admins_send_to_targets(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, you can send media only to chats you're an admin of.",
+            Self::Ru => "Извините, отправлять медиа можно только в чаты, где вы администратор.",
+        }
+    }
+
+    #[must_use]
+    pub fn failed_targets(self, targets: &str) -> String {
+        match self {
+            Self::En => format!("Sorry, the media wasn't sent to these chats:\n{targets}"),
+            Self::Ru => format!("Извините, медиа не отправлено в эти чаты:\n{targets}"),
+        }
+    }
+
+    #[must_use]
+    pub const fn invalid_audio_parameters(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, audio parameters are invalid. Use abr= with a bitrate from 32 to 320 kbps and aext= with mp3 or m4a, normalize= with 1 or 0.",
+            Self::Ru => "Извините, параметры аудио неверны. Используйте abr= с битрейтом от 32 до 320 кбит/с и aext= с mp3 или m4a, normalize= с 1 или 0.",
+        }
+    }
+
+    #[must_use]
+    pub fn download_link(self, title: Option<&str>, link: &str, retention_in_minutes: u64) -> String {
+        match self {
+            Self::En => format!(
+                "{title} is too large for Telegram, download it by the link: {link}\n\nThe link expires in {retention_in_minutes} minutes.",
+                title = title.unwrap_or("Media"),
+            ),
+            Self::Ru => format!(
+                "{title} слишком большое для Telegram, скачайте его по ссылке: {link}\n\nСсылка действует {retention_in_minutes} минут.",
+                title = title.unwrap_or("Медиа"),
+            ),
+        }
+    }
+
+    #[must_use]
+    pub fn chapter(self, number: usize) -> String {
+        match self {
+            Self::En => format!("Chapter {number}"),
+            Self::Ru => format!("Глава {number}"),
+        }
+    }
+
+    #[must_use]
+    pub const fn tracks(self) -> &'static str {
+        match self {
+            Self::En => "Tracks",
+            Self::Ru => "Треки",
+        }
+    }
+
+    #[must_use]
+    pub const fn untitled(self) -> &'static str {
+        match self {
+            Self::En => "Untitled",
+            Self::Ru => "Без названия",
+        }
+    }
+
+    #[must_use]
+    pub const fn click_to_download_video(self) -> &'static str {
+        match self {
+            Self::En => "Click to download video",
+            Self::Ru => "Нажмите, чтобы скачать видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn click_to_download_audio(self) -> &'static str {
+        match self {
+            Self::En => "Click to download audio",
+            Self::Ru => "Нажмите, чтобы скачать аудио",
+        }
+    }
+
+    #[must_use]
+    pub const fn video_downloading(self) -> &'static str {
+        match self {
+            Self::En => "Video downloading...",
+            Self::Ru => "Видео скачивается...",
+        }
+    }
+
+    #[must_use]
+    pub const fn audio_downloading(self) -> &'static str {
+        match self {
+            Self::En => "Audio downloading...",
+            Self::Ru => "Аудио скачивается...",
+        }
+    }
+
+    #[must_use]
+    pub const fn video_info_error_single(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while getting video info. Try again later.",
+            Self::Ru => "Извините, при получении информации о видео произошла ошибка. Попробуйте позже.",
+        }
+    }
+
+    #[must_use]
+    pub const fn transcription_disabled(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, transcription isn't available in this bot.",
+            Self::Ru => "Извините, расшифровка недоступна в этом боте.",
+        }
+    }
+
+    #[must_use]
+    pub fn transcription_too_long(self, max_duration_in_minutes: u64) -> String {
+        match self {
+            Self::En => format!("Sorry, only media up to {max_duration_in_minutes} minutes long can be transcribed."),
+            Self::Ru => format!("Извините, расшифровать можно только медиа длиной до {max_duration_in_minutes} минут."),
+        }
+    }
+
+    #[must_use]
+    pub fn transcription_too_large(self, max_file_size_in_mb: u64) -> String {
+        match self {
+            Self::En => format!("Sorry, the audio of this media is larger than {max_file_size_in_mb} MB, so it can't be transcribed."),
+            Self::Ru => format!("Извините, аудио этого медиа больше {max_file_size_in_mb} МБ, поэтому его нельзя расшифровать."),
+        }
+    }
+
+    #[must_use]
+    pub const fn transcription_error(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, an error occurred while transcribing the media. Try again later.",
+            Self::Ru => "Извините, при расшифровке медиа произошла ошибка. Попробуйте позже.",
+        }
+    }
+
+    #[must_use]
+    pub const fn transcript_empty(self) -> &'static str {
+        match self {
+            Self::En => "No speech was found in the media.",
+            Self::Ru => "В медиа не найдено речи.",
+        }
+    }
+
+    #[must_use]
+    pub const fn transcript(self) -> &'static str {
+        match self {
+            Self::En => "Transcript",
+            Self::Ru => "Расшифровка",
+        }
+    }
+
+    #[must_use]
+    pub const fn video_note_too_long(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, only videos up to 60 seconds long can be sent as a video note.",
+            Self::Ru => "Извините, кружком можно отправить только видео длиной до 60 секунд.",
+        }
+    }
+
+    #[must_use]
+    pub const fn selection_anonymous_sender(self) -> &'static str {
+        match self {
+            Self::En => "Sorry, playlist selection isn't available for anonymous senders.",
+            Self::Ru => "Извините, выбор видео из плейлиста недоступен анонимным отправителям.",
+        }
+    }
+
+    #[must_use]
+    pub const fn selection_prompt(self) -> &'static str {
+        match self {
+            Self::En => "Select videos to download:",
+            Self::Ru => "Выберите видео для скачивания:",
+        }
+    }
+
+    #[must_use]
+    pub const fn selection_unavailable(self) -> &'static str {
+        match self {
+            Self::En => "This selection is expired or isn't yours",
+            Self::Ru => "Этот выбор устарел или принадлежит не вам",
+        }
+    }
+
+    #[must_use]
+    pub const fn download_cancelled(self) -> &'static str {
+        match self {
+            Self::En => "Download is cancelled.",
+            Self::Ru => "Скачивание отменено.",
+        }
+    }
+
+    #[must_use]
+    pub const fn download_cancel_unavailable(self) -> &'static str {
+        match self {
+            Self::En => "This download is finished or isn't yours",
+            Self::Ru => "Это скачивание завершено или принадлежит не вам",
+        }
+    }
+
+    #[must_use]
+    pub const fn selection_empty(self) -> &'static str {
+        match self {
+            Self::En => "Select at least one video",
+            Self::Ru => "Выберите хотя бы одно видео",
+        }
+    }
+
+    #[must_use]
+    pub const fn back(self) -> &'static str {
+        match self {
+            Self::En => "« Back",
+            Self::Ru => "« Назад",
+        }
+    }
+
+    #[must_use]
+    pub fn next_page(self, page: usize, pages_count: usize) -> String {
+        match self {
+            Self::En => format!("Next ({page}/{pages_count}) »"),
+            Self::Ru => format!("Далее ({page}/{pages_count}) »"),
+        }
+    }
+
+    #[must_use]
+    pub fn download_selected(self, selected_count: usize) -> String {
+        match self {
+            Self::En => format!("Download ({selected_count})"),
+            Self::Ru => format!("Скачать ({selected_count})"),
+        }
+    }
+
+    #[must_use]
+    pub const fn getting_info(self) -> &'static str {
+        match self {
+            Self::En => "Getting info...",
+            Self::Ru => "Получение информации...",
+        }
+    }
+
+    #[must_use]
+    pub const fn downloading(self) -> &'static str {
+        match self {
+            Self::En => "Downloading...",
+            Self::Ru => "Скачивание...",
+        }
+    }
+
+    #[must_use]
+    pub const fn uploading(self) -> &'static str {
+        match self {
+            Self::En => "Uploading...",
+            Self::Ru => "Загрузка в Telegram...",
+        }
+    }
+
+    #[must_use]
+    pub const fn uploader(self) -> &'static str {
+        match self {
+            Self::En => "Uploader",
+            Self::Ru => "Автор",
+        }
+    }
+
+    #[must_use]
+    pub fn videos_count(self, count: usize) -> String {
+        match self {
+            Self::En => format!("Videos: {count}"),
+            Self::Ru => format!("Видео: {count}"),
+        }
+    }
+
+    #[must_use]
+    pub const fn total_duration(self) -> &'static str {
+        match self {
+            Self::En => "Total duration",
+            Self::Ru => "Общая длительность",
+        }
+    }
+
+    #[must_use]
+    pub const fn playlist_summary(self) -> &'static str {
+        match self {
+            Self::En => "Playlist is done",
+            Self::Ru => "Плейлист готов",
+        }
+    }
+
+    #[must_use]
+    pub fn succeeded_count(self, count: usize) -> String {
+        match self {
+            Self::En => format!("Succeeded: {count}"),
+            Self::Ru => format!("Успешно: {count}"),
+        }
+    }
+
+    #[must_use]
+    pub fn failed_count(self, count: usize) -> String {
+        match self {
+            Self::En => format!("Failed: {count}"),
+            Self::Ru => format!("С ошибкой: {count}"),
+        }
+    }
+
+    #[must_use]
+    pub const fn total_size(self) -> &'static str {
+        match self {
+            Self::En => "Total size",
+            Self::Ru => "Общий размер",
+        }
+    }
+
+    #[must_use]
+    pub const fn elapsed_time(self) -> &'static str {
+        match self {
+            Self::En => "Elapsed time",
+            Self::Ru => "Затраченное время",
+        }
+    }
+}
@@ -1,23 +1,72 @@
+mod audio_buttons;
+mod cancellation;
+mod chat_config;
 mod cmd;
 mod config;
+mod direct_download;
 mod download;
 mod errors;
+mod events;
 mod filters;
 mod fs;
 mod handlers;
 mod handlers_utils;
+mod info_cache;
+mod jobs;
+mod known_chats;
+mod links;
+mod locale;
+mod metrics;
 mod middlewares;
 mod models;
+mod queue;
+mod rate_limiter;
+mod retry;
+mod scheduler;
+mod selections;
+mod server;
+mod sponsorblock;
+mod stats;
+mod summary;
+mod telemetry;
+mod transcription;
 mod utils;
+mod youtube_fallback;
 
-use config::read_config_from_env;
-use filters::{is_via_bot, text_contains_url, text_contains_url_with_reply};
+use cancellation::CancelStore;
+use chat_config::ChatConfigStore;
+use cmd::H264Encoder;
+use config::{read_config_from_env, Transcode};
+use events::{log_events, EventBus};
+use filters::{
+    download_cancellation_callback, get_audio_callback, is_auto_download_enabled, is_bot_admin, is_chat_admin, is_default_media_audio,
+    is_domain_allowed, is_via_bot, playlist_selection_callback, purge_confirmation_callback, text_contains_url,
+    text_contains_url_with_reply,
+};
 use handlers::{
-    audio_download, media_download_chosen_inline_result, media_select_inline_query, start, video_download, video_download_quite,
+    allow_domain, audio_button, audio_button_callback, audio_download, audio_download_quite, auto_download, broadcast,
+    cancel_download_callback, chat_migration, default_media_type, deny_domain, description, formats, media_download_chosen_inline_result,
+    media_select_inline_query, playlist_select, playlist_select_callback, purge_domain, purge_domain_callback, resume_jobs, show_link,
+    source_button, start, stats, transcribe, video_download, video_download_quite, video_note_download, yt_dlp_update, yt_dlp_version,
+};
+use jobs::JobStore;
+use known_chats::KnownChatsStore;
+use links::LinkStore;
+use middlewares::{
+    Cancellations as CancellationsMiddleware, ChatConfig as ChatConfigMiddleware, Config as ConfigMiddleware, Events as EventsMiddleware,
+    Jobs as JobsMiddleware, KnownChats as KnownChatsMiddleware, Links as LinksMiddleware, Panics as PanicsMiddleware,
+    Queue as QueueMiddleware, RateLimiter as RateLimiterMiddleware, Selections as SelectionsMiddleware, Stats as StatsMiddleware,
 };
-use middlewares::Config as ConfigMiddleware;
-use std::process;
+use queue::DownloadQueue;
+use rate_limiter::RateLimiter;
+use selections::SelectionStore;
+use stats::StatsStore;
+use std::{borrow::Cow, process, sync::Arc, time::Duration};
 use telers::{
+    client::{
+        telegram::{APIServer, BareFilesPathWrapper},
+        Reqwest,
+    },
     enums::{ChatType as ChatTypeEnum, ContentType as ContentTypeEnum},
     event::ToServiceProvider as _,
     filters::{ChatType, Command, ContentType, Filter as _},
@@ -38,11 +87,19 @@ fn main() {
 #[cfg(target_family = "unix")]
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
-    let config = match read_config_from_env() {
+    let mut config = match read_config_from_env() {
         Ok(config) => {
+            // Logging isn't set up yet, so the error is printed
+            let otlp_layer = config.telemetry.otlp_endpoint.as_deref().and_then(|endpoint| {
+                telemetry::otlp_layer(endpoint, &config.telemetry.service_name)
+                    .map_err(|err| eprintln!("Error building span exporter, spans aren't exported: {err}"))
+                    .ok()
+            });
+
             tracing_subscriber::registry()
                 .with(fmt::layer())
                 .with(EnvFilter::from_env("LOGGING_LEVEL"))
+                .with(otlp_layer)
                 .init();
 
             event!(Level::DEBUG, "Config loaded from env");
@@ -56,10 +113,217 @@ async fn main() {
         }
     };
 
-    let bot = Bot::new(config.bot.token.clone());
+    let transcode = Transcode {
+        encoder: if config.transcode.enabled && config.transcode.hw_accel {
+            match cmd::detect_hw_encoder(config.process_limits) {
+                Some(encoder) => {
+                    event!(Level::INFO, encoder = encoder.as_str(), "Hardware encoder found");
+
+                    encoder
+                }
+                None => {
+                    event!(Level::WARN, "No working hardware encoder found, the software one is used");
+
+                    H264Encoder::Software
+                }
+            }
+        } else {
+            H264Encoder::Software
+        },
+        ..config.transcode
+    };
+
+    let bot = match config.bot.api_url.as_deref() {
+        Some(api_url) => {
+            let api_url = api_url.trim_end_matches('/');
+
+            Bot::with_client(
+                config.bot.token.clone(),
+                Reqwest::default().with_api_server(Cow::Owned(APIServer::new(
+                    &format!("{api_url}/bot{{token}}/{{method_name}}"),
+                    &format!("{api_url}/file/bot{{token}}/{{path}}"),
+                    config.work_dir.server_path.is_some(),
+                    BareFilesPathWrapper,
+                ))),
+            )
+        }
+        None => {
+            if config.work_dir.server_path.is_some() {
+                event!(Level::WARN, "`WORK_DIR_SERVER_PATH` is ignored, because `BOT_API_URL` isn't set");
+
+                config.work_dir.server_path = None;
+            }
+
+            Bot::new(config.bot.token.clone())
+        }
+    };
+    let receiver_video_chat_id = config.bot.receiver_video_chat_id;
+    let locale = config.bot.locale;
+    let transcription_enabled = config.transcription.is_enabled();
+
+    let event_bus = EventBus::new();
+    tokio::spawn(log_events(event_bus.subscribe()));
+    tokio::spawn(metrics::record_events(event_bus.subscribe(), config.yt_dlp.configured_domains()));
+
+    let stats_store = StatsStore::new();
+    tokio::spawn(stats::record_events(stats_store.clone(), event_bus.subscribe()));
+
+    let chat_config_store = match ChatConfigStore::load(config.data_dir.chat_config_path()) {
+        Ok(chat_config_store) => chat_config_store,
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error loading chat config");
+
+            process::exit(1);
+        }
+    };
+    let known_chats_store = match KnownChatsStore::load(config.data_dir.known_chats_path()) {
+        Ok(known_chats_store) => known_chats_store,
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error loading known chats");
+
+            process::exit(1);
+        }
+    };
+    let job_store = match JobStore::load(config.data_dir.jobs_path()) {
+        Ok(job_store) => job_store,
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error loading jobs");
+
+            process::exit(1);
+        }
+    };
+
+    // Download links are served by the HTTP server, so they're disabled without it
+    let link_store = LinkStore::new(
+        config.http.address.and(config.http.public_url.clone()),
+        config.http.link_max_file_size,
+        Duration::from_secs(config.http.link_retention),
+    );
+    tokio::spawn(links::remove_expired_in_loop(link_store.clone()));
+
+    if let Some(address) = config.http.address {
+        let link_store = link_store.clone();
+        let process_limits = config.process_limits;
+        let work_dir = config.work_dir.clone();
+
+        tokio::spawn(async move {
+            if let Err(err) = server::run(address, link_store, process_limits, work_dir).await {
+                event!(Level::ERROR, %err, "HTTP server stopped");
+            }
+        });
+    }
+
+    let selection_store = SelectionStore::new();
+    tokio::spawn(selections::remove_expired_in_loop(selection_store.clone()));
+
+    let cancel_store = CancelStore::new();
+
+    let download_queue = DownloadQueue::new(config.queue);
+    let rate_limiter = RateLimiter::new(config.send_rate.per_second, config.send_rate.burst);
+
+    tokio::spawn({
+        let bot = Arc::new(bot.clone());
+        let rate_limiter = rate_limiter.clone();
+        let yt_dlp_config = config.yt_dlp.clone();
+        let bot_config = config.bot.clone();
+        let event_bus = event_bus.clone();
+        let work_dir = config.work_dir.clone();
+        let link_store = link_store.clone();
+        let download_queue = download_queue.clone();
+        let summary_config = config.summary.clone();
+        let chat_config_store = chat_config_store.clone();
+        let job_store = job_store.clone();
+        let retries = config.retries;
+        let process_limits = config.process_limits;
+
+        async move {
+            resume_jobs(
+                bot,
+                &rate_limiter,
+                &yt_dlp_config,
+                retries,
+                &bot_config,
+                &event_bus,
+                &work_dir,
+                &link_store,
+                &download_queue,
+                &summary_config,
+                &chat_config_store,
+                &job_store,
+                transcode,
+                process_limits,
+            )
+            .await;
+        }
+    });
 
     let mut router = Router::new("main");
     router.message.register(start).filter(Command::many(["start", "help"]));
+    router.message.register(stats).filter(Command::many(["stats"]));
+    router
+        .message
+        .register(chat_migration)
+        .filter(ContentType::one(ContentTypeEnum::MigrateToChatId));
+    router
+        .message
+        .register(yt_dlp_version)
+        .filter(Command::many(["ytdlp_version"]))
+        .filter(is_bot_admin);
+    router
+        .message
+        .register(yt_dlp_update)
+        .filter(Command::many(["ytdlp_update"]))
+        .filter(is_bot_admin);
+    router
+        .message
+        .register(purge_domain)
+        .filter(Command::many(["purge_domain"]))
+        .filter(is_bot_admin);
+    router
+        .message
+        .register(broadcast)
+        .filter(Command::many(["broadcast"]))
+        .filter(is_bot_admin);
+    router
+        .message
+        .register(auto_download)
+        .filter(Command::many(["autodownload", "auto_download"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(source_button)
+        .filter(Command::many(["source_button"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(audio_button)
+        .filter(Command::many(["audio_button"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(show_link)
+        .filter(Command::many(["show_link"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(description)
+        .filter(Command::many(["description"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(default_media_type)
+        .filter(Command::many(["default"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(allow_domain)
+        .filter(Command::many(["allow_domain"]))
+        .filter(is_chat_admin);
+    router
+        .message
+        .register(deny_domain)
+        .filter(Command::many(["deny_domain"]))
+        .filter(is_chat_admin);
     router
         .message
         .register(video_download)
@@ -72,30 +336,114 @@ async fn main() {
         .filter(ContentType::one(ContentTypeEnum::Text))
         .filter(Command::many(["ad", "audio_download"]))
         .filter(text_contains_url_with_reply);
+    router
+        .message
+        .register(playlist_select)
+        .filter(ContentType::one(ContentTypeEnum::Text))
+        .filter(Command::many(["vs", "video_select"]))
+        .filter(text_contains_url_with_reply);
+    router
+        .message
+        .register(video_note_download)
+        .filter(ContentType::one(ContentTypeEnum::Text))
+        .filter(Command::many(["round", "video_note"]))
+        .filter(text_contains_url_with_reply);
+    router
+        .message
+        .register(formats)
+        .filter(ContentType::one(ContentTypeEnum::Text))
+        .filter(Command::many(["formats"]))
+        .filter(text_contains_url_with_reply);
+    router
+        .message
+        .register(transcribe)
+        .filter(ContentType::one(ContentTypeEnum::Text))
+        .filter(Command::many(["transcribe"]))
+        .filter(text_contains_url_with_reply);
+    router
+        .message
+        .register(audio_download)
+        .filter(ChatType::one(ChatTypeEnum::Private))
+        .filter(is_default_media_audio)
+        .filter(text_contains_url_with_reply)
+        .filter(is_via_bot.invert());
     router
         .message
         .register(video_download)
         .filter(ChatType::one(ChatTypeEnum::Private))
         .filter(text_contains_url_with_reply)
         .filter(is_via_bot.invert());
+    router
+        .message
+        .register(audio_download_quite)
+        .filter(is_auto_download_enabled)
+        .filter(is_default_media_audio)
+        .filter(text_contains_url)
+        .filter(is_domain_allowed)
+        .filter(is_via_bot.invert());
     router
         .message
         .register(video_download_quite)
+        .filter(is_auto_download_enabled)
         .filter(text_contains_url)
+        .filter(is_domain_allowed)
         .filter(is_via_bot.invert());
+    router
+        .callback_query
+        .register(playlist_select_callback)
+        .filter(playlist_selection_callback);
+    router
+        .callback_query
+        .register(purge_domain_callback)
+        .filter(purge_confirmation_callback);
+    router.callback_query.register(audio_button_callback).filter(get_audio_callback);
+    router
+        .callback_query
+        .register(cancel_download_callback)
+        .filter(download_cancellation_callback);
     router.inline_query.register(media_select_inline_query).filter(text_contains_url);
     router
         .chosen_inline_result
         .register(media_download_chosen_inline_result)
         .filter(text_contains_url);
 
+    router.update.outer_middlewares.register(ConfigMiddleware::new(
+        config.yt_dlp.clone(),
+        config.bot,
+        config.retries,
+        config.work_dir.clone(),
+        config.summary,
+        config.transcription,
+        transcode,
+        config.process_limits,
+    ));
+    router.update.outer_middlewares.register(EventsMiddleware::new(event_bus));
+    router.update.outer_middlewares.register(LinksMiddleware::new(link_store));
+    router.update.outer_middlewares.register(QueueMiddleware::new(download_queue));
+    router.update.outer_middlewares.register(RateLimiterMiddleware::new(rate_limiter));
+    router.update.outer_middlewares.register(SelectionsMiddleware::new(selection_store));
+    router.update.outer_middlewares.register(CancellationsMiddleware::new(cancel_store));
+    router.update.outer_middlewares.register(StatsMiddleware::new(stats_store));
+    router
+        .update
+        .outer_middlewares
+        .register(ChatConfigMiddleware::new(chat_config_store));
     router
         .update
         .outer_middlewares
-        .register(ConfigMiddleware::new(config.yt_dlp.clone(), config.bot));
+        .register(KnownChatsMiddleware::new(known_chats_store));
+    router.update.outer_middlewares.register(JobsMiddleware::new(job_store.clone()));
+
+    router.message.inner_middlewares.register(PanicsMiddleware);
+    router.callback_query.inner_middlewares.register(PanicsMiddleware);
+    router.inline_query.inner_middlewares.register(PanicsMiddleware);
+    router.chosen_inline_result.inner_middlewares.register(PanicsMiddleware);
 
-    router.startup.register(on_startup, (bot.clone(),));
-    router.shutdown.register(on_shutdown, ());
+    router.startup.register(
+        on_startup,
+        (bot.clone(), receiver_video_chat_id, config.work_dir, locale, transcription_enabled),
+    );
+    router.shutdown.register(on_shutdown, (job_store,));
 
     let dispatcher = Dispatcher::builder()
         .allowed_updates(router.resolve_used_update_types())
@@ -0,0 +1,245 @@
+use crate::{config::host_matches_domain, events::Event};
+
+use lazy_static::lazy_static;
+use prometheus::{
+    histogram_opts, opts, register_histogram_vec, register_int_counter, register_int_counter_vec, register_int_gauge, Encoder as _,
+    HistogramVec, IntCounter, IntCounterVec, IntGauge, TextEncoder,
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{event, Level};
+use url::Url;
+
+const UNKNOWN_DOMAIN: &str = "unknown";
+/// Label of domains that aren't known or configured, so the number of series stays bounded
+const OTHER_DOMAIN: &str = "other";
+/// Domains counted under their own label in addition to configured domains
+const KNOWN_DOMAINS: &[&str] = &[
+    "youtube.com",
+    "youtu.be",
+    "tiktok.com",
+    "instagram.com",
+    "twitter.com",
+    "x.com",
+    "reddit.com",
+    "vk.com",
+    "twitch.tv",
+    "soundcloud.com",
+    "vimeo.com",
+    "facebook.com",
+];
+
+lazy_static! {
+    pub static ref DOWNLOADS_STARTED: IntCounterVec = register_int_counter_vec!(
+        opts!("downloads_started_total", "Number of started downloads"),
+        &["media_kind", "domain"]
+    )
+    .unwrap();
+    pub static ref DOWNLOADS_SUCCEEDED: IntCounterVec = register_int_counter_vec!(
+        opts!("downloads_succeeded_total", "Number of succeeded downloads"),
+        &["media_kind", "domain"]
+    )
+    .unwrap();
+    pub static ref DOWNLOADS_FAILED: IntCounterVec = register_int_counter_vec!(
+        opts!("downloads_failed_total", "Number of failed downloads"),
+        &["media_kind", "domain"]
+    )
+    .unwrap();
+    pub static ref SENDS_FAILED: IntCounterVec =
+        register_int_counter_vec!(opts!("sends_failed_total", "Number of failed sends to users"), &["media_kind"]).unwrap();
+    pub static ref DOWNLOADS_IN_PROGRESS: IntGauge =
+        register_int_gauge!(opts!("downloads_in_progress", "Number of downloads in progress")).unwrap();
+    pub static ref DOWNLOADS_WAITING: IntGauge = register_int_gauge!(opts!(
+        "downloads_waiting",
+        "Number of downloads waiting for a slot in the download queue"
+    ))
+    .unwrap();
+    pub static ref YT_DLP_PROCESS_DURATION: HistogramVec = register_histogram_vec!(
+        histogram_opts!(
+            "yt_dlp_process_duration_seconds",
+            "Duration of yt-dlp processes",
+            vec![0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 45.0, 90.0, 180.0]
+        ),
+        &["operation"]
+    )
+    .unwrap();
+    pub static ref TELEGRAM_SEND_RETRIES: IntCounterVec = register_int_counter_vec!(
+        opts!("telegram_send_retries_total", "Number of retried requests to the Telegram Bot API"),
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref TELEGRAM_UPLOAD_DURATION: HistogramVec = register_histogram_vec!(
+        histogram_opts!(
+            "telegram_upload_duration_seconds",
+            "Duration of media uploads to the Telegram Bot API, including retries",
+            vec![1.0, 2.5, 5.0, 10.0, 20.0, 45.0, 90.0, 180.0, 300.0, 600.0]
+        ),
+        &["size_bucket"]
+    )
+    .unwrap();
+    pub static ref HANDLER_PANICS: IntCounter =
+        register_int_counter!(opts!("handler_panics_total", "Number of panics caught in handlers")).unwrap();
+}
+
+/// File size range of uploaded media, upload durations are recorded by it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeBucket {
+    UpTo10MB,
+    UpTo50MB,
+    UpTo200MB,
+    UpTo1GB,
+    Over1GB,
+}
+
+impl SizeBucket {
+    pub const ALL: [Self; 5] = [Self::UpTo10MB, Self::UpTo50MB, Self::UpTo200MB, Self::UpTo1GB, Self::Over1GB];
+
+    #[must_use]
+    pub const fn from_file_size(file_size: u64) -> Self {
+        match file_size {
+            0..=10_000_000 => Self::UpTo10MB,
+            10_000_001..=50_000_000 => Self::UpTo50MB,
+            50_000_001..=200_000_000 => Self::UpTo200MB,
+            200_000_001..=1_000_000_000 => Self::UpTo1GB,
+            _ => Self::Over1GB,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::UpTo10MB => "0-10MB",
+            Self::UpTo50MB => "10-50MB",
+            Self::UpTo200MB => "50-200MB",
+            Self::UpTo1GB => "200MB-1GB",
+            Self::Over1GB => "1GB+",
+        }
+    }
+}
+
+/// Number of uploads of the size bucket since the bot start and their average duration in seconds
+#[must_use]
+pub fn upload_durations(size_bucket: SizeBucket) -> (u64, f64) {
+    let histogram = TELEGRAM_UPLOAD_DURATION.with_label_values(&[size_bucket.as_str()]);
+    let count = histogram.get_sample_count();
+
+    if count == 0 {
+        return (0, 0.0);
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    (count, histogram.get_sample_sum() / count as f64)
+}
+
+/// Counts a download in [`DOWNLOADS_IN_PROGRESS`] until it's dropped.
+/// Unlike events, it's dropped if the handler panics or the task is cancelled, and it isn't lost if the events subscriber lags.
+#[must_use]
+pub struct DownloadInProgress(());
+
+impl DownloadInProgress {
+    pub fn start() -> Self {
+        DOWNLOADS_IN_PROGRESS.inc();
+
+        Self(())
+    }
+}
+
+impl Drop for DownloadInProgress {
+    fn drop(&mut self) {
+        DOWNLOADS_IN_PROGRESS.dec();
+    }
+}
+
+/// Counts a download in [`DOWNLOADS_WAITING`] until it's dropped, so cancelled waiting isn't counted
+#[must_use]
+pub struct DownloadWaiting(());
+
+impl DownloadWaiting {
+    pub fn start() -> Self {
+        DOWNLOADS_WAITING.inc();
+
+        Self(())
+    }
+}
+
+impl Drop for DownloadWaiting {
+    fn drop(&mut self) {
+        DOWNLOADS_WAITING.dec();
+    }
+}
+
+/// Label of the domain of the URL, it's one of the known and configured domains or [`OTHER_DOMAIN`]
+fn get_domain<'a>(url: &str, domains: &'a [String]) -> &'a str {
+    let Some(host) = Url::parse(url).ok().and_then(|url| url.host_str().map(ToOwned::to_owned)) else {
+        return UNKNOWN_DOMAIN;
+    };
+
+    KNOWN_DOMAINS
+        .iter()
+        .copied()
+        .chain(domains.iter().map(String::as_str))
+        .find(|domain| host_matches_domain(&host, domain))
+        .unwrap_or(OTHER_DOMAIN)
+}
+
+fn record_event(event: &Event, domains: &[String]) {
+    match event {
+        Event::DownloadStarted { url, media_kind, .. } => {
+            DOWNLOADS_STARTED
+                .with_label_values(&[media_kind.as_str(), get_domain(url, domains)])
+                .inc();
+        }
+        Event::DownloadFinished { url, media_kind, .. } => {
+            DOWNLOADS_SUCCEEDED
+                .with_label_values(&[media_kind.as_str(), get_domain(url, domains)])
+                .inc();
+        }
+        Event::DownloadFailed { url, media_kind, .. } => {
+            DOWNLOADS_FAILED
+                .with_label_values(&[media_kind.as_str(), get_domain(url, domains)])
+                .inc();
+        }
+        Event::SendFailed { media_kind, .. } => {
+            SENDS_FAILED.with_label_values(&[media_kind.as_str()]).inc();
+        }
+    }
+}
+
+/// Records events in metrics, the `domain` label is bounded by the known domains and `domains` from the config
+pub async fn record_events(mut receiver: Receiver<Event>, domains: Vec<String>) {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => record_event(&event, &domains),
+            Err(RecvError::Lagged(skipped_count)) => {
+                event!(Level::WARN, skipped_count, "Metrics subscriber lagged");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Encodes all registered metrics in the Prometheus text format
+#[must_use]
+pub fn gather() -> Vec<u8> {
+    let mut buffer = vec![];
+
+    if let Err(err) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        event!(Level::ERROR, %err, "Error encoding metrics");
+    }
+
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_domain() {
+        let domains = ["example.com".to_owned()];
+
+        assert_eq!(get_domain("https://m.youtube.com/watch?v=id", &domains), "youtube.com");
+        assert_eq!(get_domain("https://cdn.example.com/video.mp4", &domains), "example.com");
+        assert_eq!(get_domain("https://random.org/video.mp4", &domains), OTHER_DOMAIN);
+        assert_eq!(get_domain("not a url", &domains), UNKNOWN_DOMAIN);
+    }
+}
@@ -1,3 +1,25 @@
+mod cancellations;
+mod chat_config;
 mod config;
+mod events;
+mod jobs;
+mod known_chats;
+mod links;
+mod panics;
+mod queue;
+mod rate_limiter;
+mod selections;
+mod stats;
 
+pub use cancellations::Cancellations;
+pub use chat_config::ChatConfig;
 pub use config::Config;
+pub use events::Events;
+pub use jobs::Jobs;
+pub use known_chats::KnownChats;
+pub use links::Links;
+pub use panics::Panics;
+pub use queue::Queue;
+pub use rate_limiter::RateLimiter;
+pub use selections::Selections;
+pub use stats::Stats;
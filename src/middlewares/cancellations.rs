@@ -0,0 +1,32 @@
+use crate::cancellation::CancelStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Cancellations {
+    cancel_store: CancelStore,
+}
+
+impl Cancellations {
+    pub fn new(cancel_store: CancelStore) -> Self {
+        Self { cancel_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Cancellations
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.cancel_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -0,0 +1,32 @@
+use crate::chat_config::ChatConfigStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct ChatConfig {
+    chat_config_store: ChatConfigStore,
+}
+
+impl ChatConfig {
+    pub fn new(chat_config_store: ChatConfigStore) -> Self {
+        Self { chat_config_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for ChatConfig
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.chat_config_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::config::{Bot as BotConfig, YtDlp};
+use crate::config::{Bot as BotConfig, ProcessLimits, Retries, Summary, Transcode, Transcription, WorkDir, YtDlp};
 
 use async_trait::async_trait;
 use telers::{
@@ -12,11 +12,35 @@ use telers::{
 pub struct Config {
     yt_dlp: YtDlp,
     bot: BotConfig,
+    retries: Retries,
+    work_dir: WorkDir,
+    summary: Summary,
+    transcription: Transcription,
+    transcode: Transcode,
+    process_limits: ProcessLimits,
 }
 
 impl Config {
-    pub fn new(yt_dlp: YtDlp, bot: BotConfig) -> Self {
-        Self { yt_dlp, bot }
+    pub fn new(
+        yt_dlp: YtDlp,
+        bot: BotConfig,
+        retries: Retries,
+        work_dir: WorkDir,
+        summary: Summary,
+        transcription: Transcription,
+        transcode: Transcode,
+        process_limits: ProcessLimits,
+    ) -> Self {
+        Self {
+            yt_dlp,
+            bot,
+            retries,
+            work_dir,
+            summary,
+            transcription,
+            transcode,
+            process_limits,
+        }
     }
 }
 
@@ -28,6 +52,12 @@ where
     async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
         request.extensions.insert(self.yt_dlp.clone());
         request.extensions.insert(self.bot.clone());
+        request.extensions.insert(self.retries);
+        request.extensions.insert(self.work_dir.clone());
+        request.extensions.insert(self.summary.clone());
+        request.extensions.insert(self.transcription.clone());
+        request.extensions.insert(self.transcode);
+        request.extensions.insert(self.process_limits);
 
         Ok((request, EventReturn::Finish))
     }
@@ -0,0 +1,32 @@
+use crate::events::EventBus;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Events {
+    event_bus: EventBus,
+}
+
+impl Events {
+    pub fn new(event_bus: EventBus) -> Self {
+        Self { event_bus }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Events
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.event_bus.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -0,0 +1,32 @@
+use crate::jobs::JobStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Jobs {
+    job_store: JobStore,
+}
+
+impl Jobs {
+    pub fn new(job_store: JobStore) -> Self {
+        Self { job_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Jobs
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.job_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -0,0 +1,37 @@
+use crate::known_chats::KnownChatsStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+/// Remembers chats of updates, so `/broadcast` reaches every chat the bot is used in
+#[derive(Clone, Debug)]
+pub struct KnownChats {
+    known_chats_store: KnownChatsStore,
+}
+
+impl KnownChats {
+    pub fn new(known_chats_store: KnownChatsStore) -> Self {
+        Self { known_chats_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for KnownChats
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        if let Some(chat) = request.update.chat() {
+            self.known_chats_store.insert(chat.id());
+        }
+
+        request.extensions.insert(self.known_chats_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
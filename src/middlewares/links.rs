@@ -0,0 +1,32 @@
+use crate::links::LinkStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Links {
+    link_store: LinkStore,
+}
+
+impl Links {
+    pub fn new(link_store: LinkStore) -> Self {
+        Self { link_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Links
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.link_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
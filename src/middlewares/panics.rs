@@ -0,0 +1,78 @@
+use crate::{
+    handlers_utils::{error, topic},
+    metrics::HANDLER_PANICS,
+};
+
+use async_trait::async_trait;
+use futures_util::FutureExt as _;
+use std::{any::Any, panic::AssertUnwindSafe};
+use telers::{
+    errors::{EventErrorKind, HandlerError},
+    event::telegram::HandlerResponse,
+    middlewares::{inner::Next, InnerMiddleware},
+    Request,
+};
+use tracing::{event, Level};
+use uuid::Uuid;
+
+#[derive(thiserror::Error, Debug)]
+#[error("Handler panicked (id {id}): {message}")]
+struct HandlerPanicked {
+    id: Box<str>,
+    message: Box<str>,
+}
+
+fn get_panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Unknown panic"
+    }
+}
+
+/// Catches panics in handlers, so the user gets an error message instead of silence.
+/// The error id is logged with the panic, so a user report can be matched with the logs.
+#[derive(Clone, Debug, Default)]
+pub struct Panics;
+
+#[async_trait]
+impl InnerMiddleware for Panics {
+    async fn call(&self, request: Request, next: Next) -> Result<HandlerResponse, EventErrorKind> {
+        let bot = request.bot.clone();
+        let chat_and_message_ids = request
+            .update
+            .message()
+            .map(|message| (message.chat().id(), topic::thread_id(message), message.id()));
+
+        let payload = match AssertUnwindSafe(next(request)).catch_unwind().await {
+            Ok(result) => return result,
+            Err(payload) => payload,
+        };
+
+        let id: Box<str> = Uuid::new_v4().simple().to_string()[..8].into();
+        let message: Box<str> = get_panic_message(payload.as_ref()).into();
+
+        HANDLER_PANICS.inc();
+
+        event!(Level::ERROR, %id, %message, "Handler panicked");
+
+        if let Some((chat_id, thread_id, message_id)) = chat_and_message_ids {
+            if let Err(err) = error::occured_in_message(
+                &bot,
+                chat_id,
+                thread_id,
+                message_id,
+                &format!("Sorry, an internal error occurred (id {id}). Try again later."),
+                None,
+            )
+            .await
+            {
+                event!(Level::ERROR, %err, "Error sending internal error message");
+            }
+        }
+
+        Err(EventErrorKind::Handler(HandlerError::new(HandlerPanicked { id, message })))
+    }
+}
@@ -0,0 +1,32 @@
+use crate::queue::DownloadQueue;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Queue {
+    download_queue: DownloadQueue,
+}
+
+impl Queue {
+    pub fn new(download_queue: DownloadQueue) -> Self {
+        Self { download_queue }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Queue
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.download_queue.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
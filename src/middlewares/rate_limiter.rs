@@ -0,0 +1,32 @@
+use crate::rate_limiter::RateLimiter as SendRateLimiter;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    rate_limiter: SendRateLimiter,
+}
+
+impl RateLimiter {
+    pub fn new(rate_limiter: SendRateLimiter) -> Self {
+        Self { rate_limiter }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for RateLimiter
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.rate_limiter.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -0,0 +1,32 @@
+use crate::selections::SelectionStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Selections {
+    selection_store: SelectionStore,
+}
+
+impl Selections {
+    pub fn new(selection_store: SelectionStore) -> Self {
+        Self { selection_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Selections
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.selection_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
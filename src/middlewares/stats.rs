@@ -0,0 +1,32 @@
+use crate::stats::StatsStore;
+
+use async_trait::async_trait;
+use telers::{
+    errors::EventErrorKind,
+    event::EventReturn,
+    middlewares::{outer::MiddlewareResponse, OuterMiddleware},
+    Request,
+};
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    stats_store: StatsStore,
+}
+
+impl Stats {
+    pub fn new(stats_store: StatsStore) -> Self {
+        Self { stats_store }
+    }
+}
+
+#[async_trait]
+impl<Client> OuterMiddleware<Client> for Stats
+where
+    Client: Send + Sync + 'static,
+{
+    async fn call(&self, mut request: Request<Client>) -> Result<MiddlewareResponse<Client>, EventErrorKind> {
+        request.extensions.insert(self.stats_store.clone());
+
+        Ok((request, EventReturn::Finish))
+    }
+}
@@ -1,7 +1,11 @@
 pub mod audio;
 pub mod combined_format;
+pub mod download_options;
 pub mod format;
+pub mod format_strategy;
 pub mod video;
 
-pub use audio::{AudioInFS, TgAudioInPlaylist};
-pub use video::{TgVideoInPlaylist, VideoInFS, VideoInYT, VideosInYT};
+pub use audio::{AudioConversion, AudioInFS, TgAudioInPlaylist};
+pub use download_options::DownloadOptions;
+pub use format_strategy::FormatStrategy;
+pub use video::{PlaylistEntry, TgVideoInPlaylist, VideoInFS, VideoInYT, VideosInYT};
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 pub struct TgAudioInPlaylist {
     pub file_id: Box<str>,
     pub index: usize,
+    pub caption: Option<String>,
 }
 
 impl TgAudioInPlaylist {
@@ -11,8 +12,14 @@ impl TgAudioInPlaylist {
         Self {
             file_id: file_id.into(),
             index,
+            caption: None,
         }
     }
+
+    #[must_use]
+    pub fn caption(self, caption: Option<String>) -> Self {
+        Self { caption, ..self }
+    }
 }
 
 #[allow(clippy::module_name_repetitions)]
@@ -30,3 +37,153 @@ impl AudioInFS {
         }
     }
 }
+
+const MIN_BITRATE: u16 = 32;
+const MAX_BITRATE: u16 = 320;
+
+/// Extension of the converted audio, Telegram plays only these in its audio player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioExtension {
+    Mp3,
+    M4a,
+}
+
+impl AudioExtension {
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "mp3" => Some(Self::Mp3),
+            "m4a" => Some(Self::M4a),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub const fn as_str(&self) -> &str {
+        match self {
+            Self::Mp3 => "mp3",
+            Self::M4a => "m4a",
+        }
+    }
+
+    /// `FFmpeg` encoder of the extension
+    #[must_use]
+    pub const fn encoder(&self) -> &str {
+        match self {
+            Self::Mp3 => "libmp3lame",
+            Self::M4a => "aac",
+        }
+    }
+}
+
+/// Conversion of the downloaded audio set by `abr=128` (bitrate in kbps), `aext=mp3` and `normalize=1` parameters of the message text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AudioConversion {
+    pub extension: Option<AudioExtension>,
+    pub bitrate: Option<u16>,
+    /// Whether the loudness is normalized, so talks and podcasts have consistent volume
+    pub normalize: bool,
+}
+
+impl AudioConversion {
+    /// Returns `None` if a parameter has an invalid value
+    #[must_use]
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut conversion = Self::default();
+
+        for word in text.split_whitespace() {
+            if let Some(value) = word.strip_prefix("abr=") {
+                conversion.bitrate = Some(
+                    value
+                        .trim_end_matches(['k', 'K'])
+                        .parse()
+                        .ok()
+                        .filter(|bitrate| (MIN_BITRATE..=MAX_BITRATE).contains(bitrate))?,
+                );
+            } else if let Some(value) = word.strip_prefix("aext=") {
+                conversion.extension = Some(AudioExtension::parse(value)?);
+            } else if let Some(value) = word.strip_prefix("normalize=") {
+                conversion.normalize = match value {
+                    "1" => true,
+                    "0" => false,
+                    _ => return None,
+                };
+            }
+        }
+
+        Some(conversion)
+    }
+
+    /// Returns the extension to convert the audio with `extension` to or `None` if it doesn't need a conversion.
+    /// Only the bitrate or loudness is changed if the extension isn't set and the audio is already playable by Telegram.
+    #[must_use]
+    pub fn target_extension(self, extension: &str) -> Option<AudioExtension> {
+        let current_extension = AudioExtension::parse(extension);
+        let reencode = self.bitrate.is_some() || self.normalize;
+
+        match (self.extension, reencode) {
+            (Some(target_extension), false) if Some(target_extension) == current_extension => None,
+            (Some(target_extension), _) => Some(target_extension),
+            (None, true) => Some(current_extension.unwrap_or(AudioExtension::M4a)),
+            (None, false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_from_text() {
+        assert_eq!(
+            AudioConversion::from_text("/ad https://example.com"),
+            Some(AudioConversion::default())
+        );
+        assert_eq!(
+            AudioConversion::from_text("/ad https://example.com abr=128k aext=MP3 normalize=1"),
+            Some(AudioConversion {
+                extension: Some(AudioExtension::Mp3),
+                bitrate: Some(128),
+                normalize: true,
+            })
+        );
+        assert_eq!(
+            AudioConversion::from_text("/ad https://example.com normalize=0 abr=320"),
+            Some(AudioConversion {
+                extension: None,
+                bitrate: Some(320),
+                normalize: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_conversion_from_text_with_invalid_values() {
+        assert_eq!(AudioConversion::from_text("/ad https://example.com abr=16"), None);
+        assert_eq!(AudioConversion::from_text("/ad https://example.com abr=1000"), None);
+        assert_eq!(AudioConversion::from_text("/ad https://example.com abr=high"), None);
+        assert_eq!(AudioConversion::from_text("/ad https://example.com aext=ogg"), None);
+        assert_eq!(AudioConversion::from_text("/ad https://example.com normalize=yes"), None);
+    }
+
+    #[test]
+    fn test_target_extension() {
+        let default = AudioConversion::default();
+        let mp3 = AudioConversion {
+            extension: Some(AudioExtension::Mp3),
+            ..default
+        };
+        let normalize = AudioConversion {
+            normalize: true,
+            ..default
+        };
+
+        assert_eq!(default.target_extension("opus"), None);
+        assert_eq!(mp3.target_extension("mp3"), None);
+        assert_eq!(mp3.target_extension("m4a"), Some(AudioExtension::Mp3));
+        assert_eq!(normalize.target_extension("mp3"), Some(AudioExtension::Mp3));
+        // Audio unplayable by Telegram is re-encoded to M4A
+        assert_eq!(normalize.target_extension("opus"), Some(AudioExtension::M4a));
+    }
+}
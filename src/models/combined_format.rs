@@ -1,4 +1,4 @@
-use super::format;
+use super::{format, FormatStrategy};
 
 use std::cmp::Reverse;
 use std::{
@@ -6,6 +6,12 @@ use std::{
     ops::Deref,
 };
 
+/// Frame rate of formats without it and the one the score is relative to
+const BASE_FPS: f64 = 30.0;
+
+/// Min height of a passthrough format relative to the best format to be preferred over it
+const PASSTHROUGH_MIN_HEIGHT_RATIO: f64 = 0.9;
+
 #[derive(Clone, Debug)]
 pub struct Format<'a> {
     pub video_format: format::Video<'a>,
@@ -23,6 +29,11 @@ impl<'a> Format<'a> {
 
     #[must_use]
     pub fn filesize(&self) -> Option<f64> {
+        // Audio and video of a single format are in the same file
+        if self.format_ids_are_equal() {
+            return self.video_format.filesize;
+        }
+
         let video_filesize = self.video_format.filesize;
         let audio_filesize = self.audio_format.filesize;
 
@@ -36,6 +47,10 @@ impl<'a> Format<'a> {
 
     #[must_use]
     pub fn filesize_approx(&self) -> Option<f64> {
+        if self.format_ids_are_equal() {
+            return self.video_format.filesize_approx;
+        }
+
         let video_filesize_approx = self.video_format.filesize_approx;
         let audio_filesize_approx = self.audio_format.filesize_approx;
 
@@ -65,6 +80,24 @@ impl<'a> Format<'a> {
         self.video_format.id == self.audio_format.id
     }
 
+    /// Checks that it's a single H264/AAC MP4 format, which Telegram plays as is and which is downloaded without merging streams
+    #[must_use]
+    pub fn is_passthrough(&self) -> bool {
+        self.format_ids_are_equal()
+            && matches!(self.video_format.container, format::Container::MP4)
+            && matches!(self.video_format.codec, Some(format::VideoCodec::H264(_)))
+            && matches!(&self.audio_format.codec, format::AudioCodec::AAC_OR_ALAC(codec) if !codec.to_lowercase().starts_with("alac"))
+    }
+
+    /// Checks that the video codec is known to play badly on Telegram mobile clients, which decode only H264 and H265 in hardware
+    #[must_use]
+    pub const fn has_incompatible_video_codec(&self) -> bool {
+        matches!(
+            self.video_format.codec,
+            Some(format::VideoCodec::VP9(_) | format::VideoCodec::ProRes(_))
+        )
+    }
+
     #[must_use]
     pub const fn get_extension(&self) -> &str {
         self.video_format.container.as_str()
@@ -78,6 +111,15 @@ impl<'a> Format<'a> {
     pub fn get_vbr_plus_abr(&self) -> f64 {
         self.video_format.vbr.unwrap_or(0.0) + self.audio_format.abr.unwrap_or(0.0)
     }
+
+    /// Video quality by height and frame rate, the weight is how much frame rate counts against height
+    #[must_use]
+    pub fn quality_score(&self, fps_weight: f64) -> f64 {
+        let height = self.video_format.height.unwrap_or(0.0);
+        let fps = self.video_format.fps.filter(|fps| *fps > 0.0).unwrap_or(BASE_FPS);
+
+        height * (fps / BASE_FPS).powf(fps_weight)
+    }
 }
 
 impl Display for Format<'_> {
@@ -108,6 +150,18 @@ impl<'a> Formats<'a> {
         });
     }
 
+    /// Keeps only the format requested by a yt-dlp style expression,
+    /// like `137+140` for separate video and audio or `22` for a format with both
+    pub fn retain_requested(&mut self, expression: &str) {
+        let format_id = if expression.contains('+') {
+            expression.to_owned()
+        } else {
+            format!("{expression}+{expression}")
+        };
+
+        self.0.retain(|combined_format| *combined_format.format_id() == *format_id);
+    }
+
     pub fn skip_with_priority_greater_than(&mut self, priority: u8) {
         self.0.retain(|combined_format| combined_format.get_priority() <= priority);
     }
@@ -125,8 +179,63 @@ impl<'a> Formats<'a> {
         self.0.sort_by_key(|format| Reverse(format.get_vbr_plus_abr() as i64));
     }
 
-    pub fn sort_by_priority_and_skip_by_size(&mut self, size: u64) {
+    pub fn sort_by_quality_score(&mut self, fps_weight: f64) {
+        self.0
+            .sort_by(|a, b| b.quality_score(fps_weight).total_cmp(&a.quality_score(fps_weight)));
+    }
+
+    /// Moves the passthrough format with the highest resolution to the front if it's close in quality to the best format,
+    /// so `FFmpeg` doesn't merge streams for a barely better video
+    fn prefer_passthrough(&mut self, passthrough_formats: Vec<Format<'a>>) {
+        let Some(best_format) = self.0.first() else {
+            return;
+        };
+        if best_format.is_passthrough() {
+            return;
+        }
+        let Some(best_height) = best_format.video_format.height else {
+            return;
+        };
+
+        let Some(passthrough_format) = passthrough_formats
+            .into_iter()
+            .filter(|format| {
+                format
+                    .video_format
+                    .height
+                    .is_some_and(|height| height >= best_height * PASSTHROUGH_MIN_HEIGHT_RATIO)
+            })
+            .max_by(|a, b| {
+                a.video_format
+                    .height
+                    .unwrap_or(0.0)
+                    .total_cmp(&b.video_format.height.unwrap_or(0.0))
+            })
+        else {
+            return;
+        };
+
+        let format_id = passthrough_format.format_id();
+        self.0.retain(|format| format.format_id() != format_id);
+        self.0.insert(0, passthrough_format);
+    }
+
+    /// Moves formats preferred by the strategy to the front and avoided ones to the back, the order is kept otherwise
+    pub fn sort_by_strategy(&mut self, strategy: &FormatStrategy) {
+        if strategy.is_empty() {
+            return;
+        }
+
+        self.0
+            .sort_by_cached_key(|format| strategy.rank(&[format.video_format.id, format.audio_format.id]));
+    }
+
+    pub fn sort_by_priority_and_skip_by_size(&mut self, size: u64, fps_weight: f64, strategy: &FormatStrategy) {
         self.skip_with_size_greater_than(size);
+
+        // Passthrough formats are picked before skipping by priority, because their priority is usually lower
+        let passthrough_formats = self.0.iter().filter(|format| format.is_passthrough()).cloned().collect();
+
         self.sort_by_priority();
 
         match self.0.first() {
@@ -136,7 +245,11 @@ impl<'a> Formats<'a> {
 
         self.sort_by_filesize();
         self.sort_by_vbr_plus_abr();
+        self.sort_by_quality_score(fps_weight);
         self.sort_by_priority();
+        self.prefer_passthrough(passthrough_formats);
+        // Site preferences are applied last, so they choose between formats compatible with Telegram
+        self.sort_by_strategy(strategy);
     }
 }
 
@@ -215,3 +328,122 @@ impl Display for Formats<'_> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use format::{AudioCodec, Container, VideoCodec};
+
+    fn video(id: &'static str, height: f64, fps: Option<f64>, vbr: f64, filesize: Option<f64>) -> format::Video<'static> {
+        format::Video::new(
+            id,
+            "https://example.com",
+            Some(VideoCodec::H264("avc1.640028")),
+            Container::MP4,
+            Some(vbr),
+            Some(height),
+            Some(height * 16.0 / 9.0),
+            fps,
+            filesize,
+            None,
+        )
+    }
+
+    fn audio(id: &'static str) -> format::Audio<'static> {
+        format::Audio::new(
+            id,
+            "https://example.com",
+            AudioCodec::AAC_OR_ALAC("mp4a.40.2"),
+            Some(128.0),
+            Some(1_000_000.0),
+            None,
+        )
+    }
+
+    fn format_ids(formats: &Formats) -> Vec<Box<str>> {
+        formats.iter().map(Format::format_id).collect()
+    }
+
+    #[test]
+    fn test_quality_score() {
+        let format_30 = Format::new(video("hls-1080p30", 1080.0, Some(30.0), 4000.0, None), audio("audio"));
+        let format_60 = Format::new(video("hls-1080p60", 1080.0, Some(60.0), 4000.0, None), audio("audio"));
+        let format_without_fps = Format::new(video("hls-1080p", 1080.0, None, 4000.0, None), audio("audio"));
+
+        assert!(format_60.quality_score(0.25) > format_30.quality_score(0.25));
+        assert!((format_60.quality_score(0.0) - format_30.quality_score(0.0)).abs() < f64::EPSILON);
+        assert!((format_without_fps.quality_score(0.25) - format_30.quality_score(0.25)).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_sort_prefers_higher_fps_of_same_height() {
+        let mut formats = Formats(vec![
+            Format::new(video("hls-1080p30", 1080.0, Some(30.0), 5000.0, None), audio("audio")),
+            Format::new(video("hls-1080p60", 1080.0, Some(60.0), 4000.0, None), audio("audio")),
+            Format::new(video("hls-720p60", 720.0, Some(60.0), 3000.0, None), audio("audio")),
+        ]);
+
+        formats.sort_by_priority_and_skip_by_size(u64::MAX, 0.25, &FormatStrategy::default());
+
+        assert_eq!(
+            format_ids(&formats),
+            ["hls-1080p60+audio", "hls-1080p30+audio", "hls-720p60+audio"].map(Box::from)
+        );
+    }
+
+    #[test]
+    fn test_sort_ignores_fps_without_weight() {
+        let mut formats = Formats(vec![
+            Format::new(video("hls-1080p60", 1080.0, Some(60.0), 4000.0, None), audio("audio")),
+            Format::new(video("hls-1080p30", 1080.0, Some(30.0), 5000.0, None), audio("audio")),
+        ]);
+
+        formats.sort_by_priority_and_skip_by_size(u64::MAX, 0.0, &FormatStrategy::default());
+
+        // Scores are equal, so the order by bitrate is kept
+        assert_eq!(format_ids(&formats), ["hls-1080p30+audio", "hls-1080p60+audio"].map(Box::from));
+    }
+
+    #[test]
+    fn test_sort_skips_by_size() {
+        let mut formats = Formats(vec![
+            Format::new(video("hls-1080p", 1080.0, None, 5000.0, Some(100_000_000.0)), audio("audio")),
+            Format::new(video("hls-720p", 720.0, None, 3000.0, Some(10_000_000.0)), audio("audio")),
+            Format::new(video("hls-480p", 480.0, None, 1000.0, None), audio("audio")),
+        ]);
+
+        formats.sort_by_priority_and_skip_by_size(50_000_000, 0.25, &FormatStrategy::default());
+
+        // Formats of unknown size are kept, because their size is checked after downloading
+        assert_eq!(format_ids(&formats), ["hls-720p+audio", "hls-480p+audio"].map(Box::from));
+    }
+
+    #[test]
+    fn test_sort_prefers_close_passthrough() {
+        let passthrough = || Format::new(video("hls-1000p", 1000.0, None, 3000.0, None), audio("hls-1000p"));
+        let mut formats = Formats(vec![
+            Format::new(video("hls-1080p", 1080.0, None, 5000.0, None), audio("audio")),
+            passthrough(),
+        ]);
+
+        formats.sort_by_priority_and_skip_by_size(u64::MAX, 0.25, &FormatStrategy::default());
+
+        assert_eq!(format_ids(&formats), ["hls-1000p+hls-1000p", "hls-1080p+audio"].map(Box::from));
+    }
+
+    #[test]
+    fn test_sort_by_strategy() {
+        let mut formats = Formats(vec![
+            Format::new(video("hls-1080p", 1080.0, None, 5000.0, None), audio("audio")),
+            Format::new(video("download-720p", 720.0, None, 3000.0, None), audio("audio")),
+        ]);
+        let strategy = FormatStrategy {
+            prefer: vec!["download".to_owned()],
+            avoid: vec![],
+        };
+
+        formats.sort_by_priority_and_skip_by_size(u64::MAX, 0.25, &strategy);
+
+        assert_eq!(format_ids(&formats), ["download-720p+audio", "hls-1080p+audio"].map(Box::from));
+    }
+}
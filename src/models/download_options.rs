@@ -0,0 +1,92 @@
+use crate::{config::YtDlp, sponsorblock};
+
+/// Parameters of a download command, like `/vd https://example.com gif=1 format=137+140 sb=sponsor`
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadOptions {
+    /// `gif=1`, videos are sent as animations even if they have sound
+    pub force_animation: bool,
+    /// `chapters=1`, videos with chapters are split into a file per chapter
+    pub split_chapters: bool,
+    /// `header=1`, a header is sent before videos of a playlist
+    pub with_header: bool,
+    /// `archive=1`, audios of a playlist are sent in a ZIP archive
+    pub as_archive: bool,
+    /// `format=137+140`, the format is downloaded instead of picking the best one
+    pub format_id: Option<String>,
+    /// `sb=sponsor,intro`, categories of `SponsorBlock` segments to remove instead of the configured ones.
+    /// They're empty if segments are kept with `sb=0`.
+    pub sponsorblock_categories: Option<Vec<String>>,
+}
+
+impl DownloadOptions {
+    /// Returns `None` if a parameter has an invalid value
+    #[must_use]
+    pub fn from_text(text: &str) -> Option<Self> {
+        let mut options = Self::default();
+
+        for word in text.split_whitespace() {
+            match word {
+                "gif=1" => options.force_animation = true,
+                "chapters=1" => options.split_chapters = true,
+                "header=1" => options.with_header = true,
+                "archive=1" => options.as_archive = true,
+                "sb=0" => options.sponsorblock_categories = Some(vec![]),
+                _ => {
+                    if let Some(value) = word.strip_prefix("format=") {
+                        options.format_id = Some(value.to_owned()).filter(|format_id| !format_id.is_empty());
+                    } else if let Some(value) = word.strip_prefix("sb=") {
+                        options.sponsorblock_categories = Some(sponsorblock::parse_categories(value)?);
+                    }
+                }
+            }
+        }
+
+        Some(options)
+    }
+
+    /// Categories of `SponsorBlock` segments to remove from videos, they're empty if the removal is disabled
+    #[must_use]
+    pub fn sponsorblock_categories(&self, yt_dlp_config: &YtDlp) -> Vec<String> {
+        if !yt_dlp_config.sponsorblock_enabled {
+            return vec![];
+        }
+
+        self.sponsorblock_categories
+            .clone()
+            .unwrap_or_else(|| yt_dlp_config.sponsorblock_categories.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            DownloadOptions::from_text("/vd https://example.com gif=1 header=1 format=137+140 sb=sponsor,intro"),
+            Some(DownloadOptions {
+                force_animation: true,
+                with_header: true,
+                format_id: Some("137+140".to_owned()),
+                sponsorblock_categories: Some(vec!["sponsor".to_owned(), "intro".to_owned()]),
+                ..DownloadOptions::default()
+            })
+        );
+        assert_eq!(
+            DownloadOptions::from_text("/ad https://example.com archive=1 chapters=1 format= sb=0"),
+            Some(DownloadOptions {
+                split_chapters: true,
+                as_archive: true,
+                sponsorblock_categories: Some(vec![]),
+                ..DownloadOptions::default()
+            })
+        );
+    }
+
+    #[test]
+    fn test_from_text_rejects_unknown_sponsorblock_categories() {
+        assert_eq!(DownloadOptions::from_text("/vd https://example.com sb=unknown"), None);
+    }
+}
@@ -302,6 +302,14 @@ impl AudioCodec<'_> {
         }
     }
 
+    /// Codec as it's reported by yt-dlp
+    #[must_use]
+    pub const fn as_raw_str(&self) -> &str {
+        match self {
+            Self::AAC_OR_ALAC(codec) | Self::FLAC(codec) | Self::Opus(codec) | Self::MP3(codec) | Self::PCM(codec) => codec,
+        }
+    }
+
     #[must_use]
     pub const fn get_extension(&self) -> &str {
         match self {
@@ -377,6 +385,7 @@ pub struct Video<'a> {
     pub vbr: Option<f64>,
     pub height: Option<f64>,
     pub width: Option<f64>,
+    pub fps: Option<f64>,
     pub filesize: Option<f64>,
     pub filesize_approx: Option<f64>,
 }
@@ -391,6 +400,7 @@ impl<'a> Video<'a> {
         vbr: Option<f64>,
         height: Option<f64>,
         width: Option<f64>,
+        fps: Option<f64>,
         filesize: Option<f64>,
         filesize_approx: Option<f64>,
     ) -> Self {
@@ -402,6 +412,7 @@ impl<'a> Video<'a> {
             vbr,
             height,
             width,
+            fps,
             filesize,
             filesize_approx,
         }
@@ -618,8 +629,10 @@ pub struct Any {
     pub vbr: Option<f64>,
     pub height: Option<f64>,
     pub width: Option<f64>,
+    pub fps: Option<f64>,
     pub filesize: Option<f64>,
     pub filesize_approx: Option<f64>,
+    pub language: Option<String>,
 
     acodec: Codec,
     vcodec: Codec,
@@ -645,8 +658,10 @@ impl<'de> Deserialize<'de> for Any {
             vbr: Option<f64>,
             height: Option<f64>,
             width: Option<f64>,
+            fps: Option<f64>,
             filesize: Option<f64>,
             filesize_approx: Option<f64>,
+            language: Option<String>,
         }
 
         let raw = Raw::deserialize(deserializer)?;
@@ -680,13 +695,21 @@ impl<'de> Deserialize<'de> for Any {
             vbr: raw.vbr,
             height: raw.height,
             width: raw.width,
+            fps: raw.fps,
             filesize: raw.filesize,
             filesize_approx: raw.filesize_approx,
+            language: raw.language,
         })
     }
 }
 
 impl Any {
+    /// Whether the format can have an audio track, it's unknown for some sources
+    #[must_use]
+    pub const fn has_audio(&self) -> bool {
+        !self.acodec.is_none()
+    }
+
     #[allow(clippy::similar_names)]
     pub fn kind(&self) -> Result<Kind<'_>, FormatError<'_>> {
         let acodec = &self.acodec;
@@ -722,6 +745,7 @@ impl Any {
                 self.vbr,
                 self.height,
                 self.width,
+                self.fps,
                 self.filesize,
                 self.filesize_approx,
             );
@@ -744,9 +768,10 @@ impl Any {
                 self.url.as_str(),
                 None,
                 container,
-                self.height,
                 self.vbr,
+                self.height,
                 self.width,
+                self.fps,
                 self.filesize,
                 self.filesize_approx,
             );
@@ -787,6 +812,7 @@ impl Any {
                 self.vbr,
                 self.height,
                 self.width,
+                self.fps,
                 self.filesize,
                 self.filesize_approx,
             );
@@ -819,6 +845,7 @@ impl Any {
                         self.vbr,
                         self.height,
                         self.width,
+                        self.fps,
                         self.filesize,
                         self.filesize_approx,
                     );
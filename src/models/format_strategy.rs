@@ -0,0 +1,36 @@
+use serde::Deserialize;
+
+/// Formats a site prefers, like formats without a watermark, so site quirks are set in the config instead of the code.
+/// Format IDs match a pattern if they contain it.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FormatStrategy {
+    /// Patterns in order of preference, matching formats are tried before others
+    #[serde(default)]
+    pub prefer: Vec<String>,
+    /// Patterns of formats that are tried last
+    #[serde(default)]
+    pub avoid: Vec<String>,
+}
+
+impl FormatStrategy {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.prefer.is_empty() && self.avoid.is_empty()
+    }
+
+    /// Rank of the format by its IDs, lower is better
+    #[must_use]
+    pub fn rank(&self, format_ids: &[&str]) -> usize {
+        let matches = |pattern: &String| format_ids.iter().any(|format_id| format_id.contains(pattern.as_str()));
+
+        if let Some(index) = self.prefer.iter().position(matches) {
+            return index;
+        }
+
+        if self.avoid.iter().any(matches) {
+            return self.prefer.len() + 1;
+        }
+
+        self.prefer.len()
+    }
+}
@@ -1,6 +1,6 @@
-use super::{combined_format, format};
+use super::{combined_format, format, FormatStrategy};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{collections::VecDeque, ops::Deref, path::PathBuf};
 
 #[derive(Debug, Clone, Deserialize)]
@@ -12,6 +12,13 @@ pub struct Thumbnail {
     pub filesize: Option<i64>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct Chapter {
+    pub start_time: f64,
+    pub end_time: f64,
+    pub title: Option<String>,
+}
+
 #[allow(clippy::module_name_repetitions)]
 #[derive(Debug, Clone, Deserialize)]
 pub struct VideoInYT {
@@ -24,6 +31,24 @@ pub struct VideoInYT {
     pub duration: Option<f64>,
     pub width: Option<i64>,
     pub height: Option<i64>,
+    pub uploader: Option<String>,
+    pub artist: Option<String>,
+    pub playlist_title: Option<String>,
+    /// Position in the playlist, starting from 1
+    pub playlist_index: Option<usize>,
+    pub chapters: Option<Vec<Chapter>>,
+    pub is_live: Option<bool>,
+    /// Status of a live stream, like `is_live`, `is_upcoming` or `was_live`, it's `not_live` or empty for regular videos
+    pub live_status: Option<String>,
+    /// The info is got only with the cookies of the host, so the media should be downloaded with them too
+    #[serde(skip)]
+    pub requires_cookies: bool,
+    /// The URL points directly at the media file or an HLS playlist, so it's downloaded without `yt-dlp`
+    #[serde(skip)]
+    pub is_direct: bool,
+    /// Formats the site of the media prefers, it's set from the config after getting the info
+    #[serde(skip)]
+    pub format_strategy: FormatStrategy,
 
     formats: Vec<format::Any>,
 }
@@ -59,6 +84,71 @@ impl VideoInYT {
         format::Audios::from(formats)
     }
 
+    pub fn get_video_formats(&self) -> Vec<format::Video<'_>> {
+        let mut formats = vec![];
+
+        for format in &self.formats {
+            let Ok(format) = format.kind() else {
+                continue;
+            };
+
+            if let format::Kind::Video(format) = format {
+                formats.push(format);
+            }
+        }
+
+        formats
+    }
+
+    pub fn formats(&self) -> &[format::Any] {
+        &self.formats
+    }
+
+    /// Whether the format requested by a yt-dlp style expression is listed and can be downloaded
+    #[must_use]
+    pub fn has_requested_format(&self, expression: &str) -> bool {
+        let mut combined_formats = self.get_combined_formats();
+        combined_formats.retain_requested(expression);
+
+        !combined_formats.is_empty()
+    }
+
+    /// Whether it's an ongoing or upcoming live stream, `yt-dlp` would download it until the timeout
+    #[must_use]
+    pub fn is_ongoing_live(&self) -> bool {
+        self.is_live == Some(true) || matches!(self.live_status.as_deref(), Some("is_live" | "is_upcoming"))
+    }
+
+    /// Whether it's a recording of a finished live stream, they're usually much longer than regular videos
+    #[must_use]
+    pub fn is_live_recording(&self) -> bool {
+        matches!(self.live_status.as_deref(), Some("was_live" | "post_live"))
+    }
+
+    /// Whether the video doesn't have an audio track, like GIFs that Twitter and Reddit convert to MP4
+    #[must_use]
+    pub fn is_silent(&self) -> bool {
+        !self.formats.is_empty() && self.formats.iter().all(|format| !format.has_audio())
+    }
+
+    /// Size of the video format that will be downloaded first, if it's known
+    #[must_use]
+    pub fn estimated_video_filesize(&self, max_file_size: u64, fps_weight: f64) -> Option<f64> {
+        let mut combined_formats = self.get_combined_formats();
+        combined_formats.sort_by_priority_and_skip_by_size(max_file_size, fps_weight, &self.format_strategy);
+
+        combined_formats.first().and_then(combined_format::Format::filesize_or_approx)
+    }
+
+    /// Size of the audio format that will be downloaded, if it's known
+    #[must_use]
+    pub fn estimated_audio_filesize(&self, max_file_size: u64) -> Option<f64> {
+        let mut audio_formats = self.get_audio_formats();
+        audio_formats.sort_by_priority_and_skip_by_size(max_file_size);
+
+        audio_formats.first().and_then(format::Audio::filesize_or_approx)
+    }
+
     pub fn thumbnail(&self) -> Option<&str> {
         match self.thumbnails.as_deref().and_then(|thumbnails| {
             for thumbnail in thumbnails {
@@ -75,6 +165,54 @@ impl VideoInYT {
             None => self.thumbnail.as_deref(),
         }
     }
+
+    /// URLs of all thumbnails, the preferred ones first
+    #[must_use]
+    pub fn thumbnail_urls(&self) -> Vec<String> {
+        // `yt-dlp` sorts thumbnails by preference in ascending order
+        let mut urls: Vec<String> = self
+            .thumbnails
+            .iter()
+            .flatten()
+            .rev()
+            .filter_map(|thumbnail| thumbnail.url.clone())
+            .collect();
+
+        if let Some(url) = self.thumbnail.as_ref().filter(|url| !urls.contains(url)) {
+            urls.insert(0, url.clone());
+        }
+
+        urls
+    }
+
+    pub fn performer(&self) -> Option<&str> {
+        self.artist.as_deref().or(self.uploader.as_deref())
+    }
+}
+
+/// Entry of a playlist got by flat extraction, it doesn't have formats
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlaylistEntry {
+    pub title: Option<String>,
+    pub duration: Option<f64>,
+    pub thumbnail: Option<String>,
+    pub thumbnails: Option<Vec<Thumbnail>>,
+    /// Position in the playlist, starting from 1
+    pub playlist_index: Option<usize>,
+}
+
+impl PlaylistEntry {
+    /// The preferred thumbnail, `yt-dlp` sorts thumbnails by preference in ascending order
+    #[must_use]
+    pub fn thumbnail(&self) -> Option<&str> {
+        self.thumbnail.as_deref().or_else(|| {
+            self.thumbnails
+                .iter()
+                .flatten()
+                .rev()
+                .find_map(|thumbnail| thumbnail.url.as_deref())
+        })
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -84,6 +222,14 @@ impl VideosInYT {
     pub fn new(videos: impl Into<VecDeque<VideoInYT>>) -> Self {
         Self(videos.into())
     }
+
+    /// Removes ongoing and upcoming live streams and returns how many were removed
+    pub fn remove_ongoing_lives(&mut self) -> usize {
+        let len = self.0.len();
+        self.0.retain(|video| !video.is_ongoing_live());
+
+        len - self.0.len()
+    }
 }
 
 impl Iterator for VideosInYT {
@@ -108,10 +254,12 @@ impl Deref for VideosInYT {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TgVideoInPlaylist {
     pub file_id: Box<str>,
     pub index: usize,
+    pub caption: Option<String>,
+    pub source_url: Option<Box<str>>,
 }
 
 impl TgVideoInPlaylist {
@@ -119,6 +267,21 @@ impl TgVideoInPlaylist {
         Self {
             file_id: file_id.into(),
             index,
+            caption: None,
+            source_url: None,
+        }
+    }
+
+    #[must_use]
+    pub fn caption(self, caption: Option<String>) -> Self {
+        Self { caption, ..self }
+    }
+
+    #[must_use]
+    pub fn source_url(self, source_url: impl Into<Box<str>>) -> Self {
+        Self {
+            source_url: Some(source_url.into()),
+            ..self
         }
     }
 }
@@ -138,3 +301,133 @@ impl VideoInFS {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs of `yt-dlp --dump-json` trimmed to the fields the bot reads and a few formats of each kind
+    const YOUTUBE: &str = include_str!("fixtures/youtube.json");
+    const TIKTOK: &str = include_str!("fixtures/tiktok.json");
+    const TWITTER: &str = include_str!("fixtures/twitter.json");
+    const SOUNDCLOUD: &str = include_str!("fixtures/soundcloud.json");
+    const TWITCH: &str = include_str!("fixtures/twitch.json");
+
+    const FPS_WEIGHT: f64 = 0.25;
+    const MB: u64 = 1_000_000;
+
+    fn video(fixture: &str) -> VideoInYT {
+        serde_json::from_str(fixture).unwrap()
+    }
+
+    fn chosen_format_id(video: &VideoInYT, max_file_size: u64) -> Option<Box<str>> {
+        let mut combined_formats = video.get_combined_formats();
+        combined_formats.sort_by_priority_and_skip_by_size(max_file_size, FPS_WEIGHT, &video.format_strategy);
+
+        combined_formats.first().map(combined_format::Format::format_id)
+    }
+
+    fn chosen_audio_format_id(video: &VideoInYT, max_file_size: u64) -> Option<&str> {
+        let mut audio_formats = video.get_audio_formats();
+        audio_formats.sort_by_priority_and_skip_by_size(max_file_size);
+
+        audio_formats.first().map(|format| format.id)
+    }
+
+    #[test]
+    fn test_youtube_format_selection() {
+        let video = video(YOUTUBE);
+
+        assert_eq!(chosen_format_id(&video, u64::MAX).as_deref(), Some("299+251"));
+        assert_eq!(chosen_format_id(&video, 200 * MB).as_deref(), Some("137+251"));
+        // The single H264/AAC format is preferred over merging streams of the same height
+        assert_eq!(chosen_format_id(&video, 50 * MB).as_deref(), Some("18+18"));
+        assert_eq!(chosen_format_id(&video, 20 * MB).as_deref(), Some("160+251"));
+        assert_eq!(chosen_format_id(&video, 3 * MB), None);
+
+        assert_eq!(chosen_audio_format_id(&video, u64::MAX), Some("140"));
+        assert_eq!(chosen_audio_format_id(&video, 5 * MB), Some("139"));
+        assert_eq!(chosen_audio_format_id(&video, 3 * MB), None);
+    }
+
+    #[test]
+    fn test_tiktok_format_selection() {
+        let mut video = video(TIKTOK);
+
+        assert_eq!(
+            chosen_format_id(&video, u64::MAX).as_deref(),
+            Some("bytevc1_720p_812641-0+bytevc1_720p_812641-0")
+        );
+        assert_eq!(
+            chosen_format_id(&video, 2 * MB).as_deref(),
+            Some("bytevc1_540p_519382-0+bytevc1_540p_519382-0")
+        );
+        assert_eq!(chosen_format_id(&video, MB), None);
+        assert_eq!(chosen_audio_format_id(&video, u64::MAX), None);
+
+        video.format_strategy = FormatStrategy {
+            prefer: vec!["download_addr-2".to_owned()],
+            avoid: vec!["download".to_owned()],
+        };
+
+        assert_eq!(
+            chosen_format_id(&video, u64::MAX).as_deref(),
+            Some("download_addr-2+download_addr-2")
+        );
+
+        video.format_strategy = FormatStrategy {
+            prefer: vec!["h264".to_owned()],
+            avoid: vec!["download".to_owned()],
+        };
+
+        assert_eq!(
+            chosen_format_id(&video, u64::MAX).as_deref(),
+            Some("h264_540p_1032410-0+h264_540p_1032410-0")
+        );
+    }
+
+    #[test]
+    fn test_twitter_format_selection() {
+        let video = video(TWITTER);
+
+        // HLS formats don't have a container, so progressive formats of unknown size are chosen by height
+        assert_eq!(chosen_format_id(&video, u64::MAX).as_deref(), Some("http-2176+http-2176"));
+        assert_eq!(chosen_format_id(&video, MB).as_deref(), Some("http-2176+http-2176"));
+        assert!(!video.is_silent());
+    }
+
+    #[test]
+    fn test_soundcloud_format_selection() {
+        let video = video(SOUNDCLOUD);
+
+        assert_eq!(chosen_format_id(&video, u64::MAX), None);
+        assert_eq!(chosen_audio_format_id(&video, u64::MAX), Some("hls_opus_0_0"));
+        // Formats of unknown size are kept, because their size is checked after downloading
+        let mut audio_formats = video.get_audio_formats();
+        audio_formats.sort_by_priority_and_skip_by_size(MB);
+
+        assert_eq!(
+            audio_formats.iter().map(|format| format.id).collect::<Vec<_>>(),
+            ["hls_opus_0_0", "hls_mp3_1_0"]
+        );
+    }
+
+    #[test]
+    fn test_twitch_format_selection() {
+        let video = video(TWITCH);
+
+        assert_eq!(chosen_format_id(&video, u64::MAX).as_deref(), Some("1080p60+1080p60"));
+        assert_eq!(chosen_format_id(&video, 2_000 * MB).as_deref(), Some("480p+480p"));
+        assert_eq!(chosen_format_id(&video, 200 * MB).as_deref(), Some("160p+160p"));
+        assert_eq!(chosen_format_id(&video, 50 * MB), None);
+        assert_eq!(chosen_audio_format_id(&video, u64::MAX), Some("Audio_Only"));
+        assert!(video.is_live_recording());
+    }
+
+    #[test]
+    fn test_estimated_filesize_counts_single_format_once() {
+        let video = video(TWITCH);
+
+        assert_eq!(video.estimated_video_filesize(2_000 * MB, FPS_WEIGHT), Some(947_200_000.0));
+    }
+}
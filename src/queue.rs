@@ -0,0 +1,45 @@
+use crate::{
+    config::Queue as QueueConfig,
+    metrics::DownloadWaiting,
+    scheduler::{Permit, Scheduler},
+};
+
+use tracing::{event, Level};
+
+/// Limits concurrent downloads.
+/// Media with a small estimated size is downloaded in a separate fast lane, so it isn't stuck behind large downloads.
+/// Chats get free slots of a lane in turn, so a large playlist of one chat doesn't delay other chats.
+#[derive(Debug, Clone)]
+pub struct DownloadQueue {
+    main: Option<Scheduler>,
+    fast: Scheduler,
+    fast_lane_max_file_size: u64,
+}
+
+impl DownloadQueue {
+    #[must_use]
+    pub fn new(config: QueueConfig) -> Self {
+        Self {
+            main: config.concurrency.map(|concurrency| Scheduler::new(concurrency.max(1))),
+            fast: Scheduler::new(config.fast_lane_concurrency.max(1)),
+            fast_lane_max_file_size: config.fast_lane_max_file_size,
+        }
+    }
+
+    /// Waits for a free slot in the lane of the media, the download should be held until the permit is dropped.
+    /// Media with an unknown size uses the main lane, media without a chat shares a turn with other such media.
+    /// Returns `None` if there is no queue.
+    pub async fn acquire(&self, estimated_size: Option<f64>, chat_id: Option<i64>) -> Option<Permit> {
+        let main = self.main.as_ref()?;
+
+        #[allow(clippy::cast_precision_loss)]
+        let is_small = estimated_size.is_some_and(|size| size <= self.fast_lane_max_file_size as f64);
+        let scheduler = if is_small { &self.fast } else { main };
+
+        event!(Level::TRACE, is_small, chat_id, "Wait for a download slot");
+
+        let _waiting = DownloadWaiting::start();
+
+        Some(scheduler.acquire(chat_id).await)
+    }
+}
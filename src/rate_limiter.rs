@@ -0,0 +1,144 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::{sleep_until, Instant};
+use tracing::{event, Level};
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    updated_at: Instant,
+    /// All requests wait until this time, it's set when the server asks to retry later
+    paused_until: Option<Instant>,
+}
+
+/// Token bucket shared by all requests to a server, so a burst of requests, like sending a large playlist,
+/// doesn't exceed the server limit and make every request wait for a flood-wait.
+/// Clones share the same bucket.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    state: Arc<Mutex<State>>,
+}
+
+impl RateLimiter {
+    /// Requests aren't limited if `per_second` is zero, but they still wait for pauses
+    #[must_use]
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+
+        Self {
+            per_second,
+            burst,
+            state: Arc::new(Mutex::new(State {
+                tokens: burst,
+                updated_at: Instant::now(),
+                paused_until: None,
+            })),
+        }
+    }
+
+    /// Waits until a request can be made
+    pub async fn acquire(&self) {
+        loop {
+            let wait_until = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+
+                if let Some(paused_until) = state.paused_until.filter(|paused_until| *paused_until > now) {
+                    paused_until
+                } else if self.per_second <= 0.0 {
+                    return;
+                } else {
+                    let elapsed = now.duration_since(state.updated_at).as_secs_f64();
+
+                    state.tokens = (state.tokens + elapsed * self.per_second).min(self.burst);
+                    state.updated_at = now;
+
+                    if state.tokens >= 1.0 {
+                        state.tokens -= 1.0;
+
+                        return;
+                    }
+
+                    now + Duration::from_secs_f64((1.0 - state.tokens) / self.per_second)
+                }
+            };
+
+            sleep_until(wait_until).await;
+        }
+    }
+
+    /// Pauses all requests for the duration, a longer current pause is kept
+    pub fn pause(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let paused_until = Instant::now() + duration;
+
+        if state.paused_until.is_none_or(|current| current < paused_until) {
+            event!(Level::DEBUG, ?duration, "Requests paused");
+
+            state.paused_until = Some(paused_until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_burst_is_not_delayed() {
+        let rate_limiter = RateLimiter::new(1.0, 3);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            rate_limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_after_burst_are_delayed() {
+        let rate_limiter = RateLimiter::new(2.0, 1);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            rate_limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause_delays_requests() {
+        let rate_limiter = RateLimiter::new(1.0, 3);
+        let start = Instant::now();
+
+        rate_limiter.pause(Duration::from_secs(5));
+        // A shorter pause doesn't shorten the current one
+        rate_limiter.pause(Duration::from_secs(1));
+        rate_limiter.acquire().await;
+
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_zero_rate_is_unlimited() {
+        let rate_limiter = RateLimiter::new(0.0, 1);
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            rate_limiter.acquire().await;
+        }
+
+        assert_eq!(start.elapsed(), Duration::ZERO);
+
+        rate_limiter.clone().pause(Duration::from_secs(2));
+        rate_limiter.acquire().await;
+
+        assert_eq!(start.elapsed(), Duration::from_secs(2));
+    }
+}
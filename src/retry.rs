@@ -0,0 +1,182 @@
+use crate::config::RetryPolicy;
+
+use backoff::{backoff::Backoff as _, ExponentialBackoff, ExponentialBackoffBuilder};
+use std::{fmt::Display, future::Future, thread};
+use tracing::{event, instrument, Level};
+
+impl RetryPolicy {
+    #[must_use]
+    pub fn to_backoff(&self) -> ExponentialBackoff {
+        ExponentialBackoffBuilder::new()
+            .with_initial_interval(self.backoff)
+            .with_max_elapsed_time(Some(self.max_elapsed))
+            .build()
+    }
+}
+
+/// Calls `operation` until it succeeds or the retry policy is exhausted.
+/// Sleeps the current thread between attempts, so it should be used in blocking code only.
+/// # Returns
+/// The first successful result or the last error
+#[instrument(skip_all, fields(operation = operation_name))]
+pub fn blocking<T, E: Display>(policy: &RetryPolicy, operation_name: &str, mut operation: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    let mut backoff = policy.to_backoff();
+    let mut cur_retry_count = 0;
+
+    loop {
+        let err = match operation() {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+
+        cur_retry_count += 1;
+
+        if cur_retry_count > policy.attempts {
+            return Err(err);
+        }
+
+        let Some(duration) = backoff.next_backoff() else {
+            event!(Level::WARN, %err, "Max retries elapsed time exceeded");
+
+            return Err(err);
+        };
+
+        event!(Level::WARN, %err, cur_retry_count, "Operation failed, retry after {duration:?}");
+
+        thread::sleep(duration);
+    }
+}
+
+/// Async version of [`blocking`], it sleeps without blocking the thread between attempts.
+/// # Returns
+/// The first successful result or the last error
+#[instrument(skip_all, fields(operation = operation_name))]
+pub async fn future<T, E: Display, F: Future<Output = Result<T, E>>>(
+    policy: &RetryPolicy,
+    operation_name: &str,
+    mut operation: impl FnMut() -> F,
+) -> Result<T, E> {
+    let mut backoff = policy.to_backoff();
+    let mut cur_retry_count = 0;
+
+    loop {
+        let err = match operation().await {
+            Ok(result) => return Ok(result),
+            Err(err) => err,
+        };
+
+        cur_retry_count += 1;
+
+        if cur_retry_count > policy.attempts {
+            return Err(err);
+        }
+
+        let Some(duration) = backoff.next_backoff() else {
+            event!(Level::WARN, %err, "Max retries elapsed time exceeded");
+
+            return Err(err);
+        };
+
+        event!(Level::WARN, %err, cur_retry_count, "Operation failed, retry after {duration:?}");
+
+        tokio::time::sleep(duration).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{cell::Cell, time::Duration};
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        attempts: 2,
+        backoff: Duration::from_millis(1),
+        max_elapsed: Duration::from_secs(10),
+    };
+
+    #[test]
+    fn test_blocking_returns_first_success() {
+        let calls = Cell::new(0);
+
+        let result = blocking(&POLICY, "test", || {
+            calls.set(calls.get() + 1);
+
+            if calls.get() < 3 {
+                Err("failed")
+            } else {
+                Ok(calls.get())
+            }
+        });
+
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn test_blocking_returns_last_error() {
+        let calls = Cell::new(0);
+
+        let result: Result<(), _> = blocking(&POLICY, "test", || {
+            calls.set(calls.get() + 1);
+
+            Err(calls.get())
+        });
+
+        // The first attempt and two retries
+        assert_eq!(result, Err(3));
+    }
+
+    #[test]
+    fn test_blocking_stops_after_max_elapsed() {
+        let policy = RetryPolicy {
+            attempts: u8::MAX,
+            backoff: Duration::from_millis(5),
+            max_elapsed: Duration::from_millis(20),
+        };
+        let calls = Cell::new(0);
+
+        let result: Result<(), _> = blocking(&policy, "test", || {
+            calls.set(calls.get() + 1);
+
+            Err("failed")
+        });
+
+        assert!(result.is_err());
+        assert!(calls.get() < usize::from(u8::MAX));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_future_returns_last_error() {
+        let calls = Cell::new(0);
+
+        let result: Result<(), _> = future(&POLICY, "test", || {
+            calls.set(calls.get() + 1);
+
+            async { Err(calls.get()) }
+        })
+        .await;
+
+        assert_eq!(result, Err(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_future_returns_first_success() {
+        let calls = Cell::new(0);
+
+        let result = future(&POLICY, "test", || {
+            calls.set(calls.get() + 1);
+            let calls = calls.get();
+
+            async move {
+                if calls < 2 {
+                    Err("failed")
+                } else {
+                    Ok(calls)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result, Ok(2));
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+use tracing::{event, Level};
+
+/// Chat of the work or `None` for work without a chat, like inline mode downloads
+type Key = Option<i64>;
+
+#[derive(Debug, Default)]
+struct State {
+    available: usize,
+    waiters: HashMap<Key, VecDeque<oneshot::Sender<()>>>,
+    /// Chats with waiters in the order they get a slot
+    order: VecDeque<Key>,
+}
+
+impl State {
+    /// Gives the slot to the first waiter of the next chat or frees it if there are no waiters
+    fn release(&mut self) {
+        while let Some(key) = self.order.pop_front() {
+            let Some(waiters) = self.waiters.get_mut(&key) else {
+                continue;
+            };
+
+            while let Some(waiter) = waiters.pop_front() {
+                // The waiter is skipped if it's cancelled
+                if waiter.send(()).is_ok() {
+                    if waiters.is_empty() {
+                        self.waiters.remove(&key);
+                    } else {
+                        self.order.push_back(key);
+                    }
+
+                    return;
+                }
+            }
+
+            self.waiters.remove(&key);
+        }
+
+        self.available += 1;
+    }
+}
+
+/// Limits concurrent work and gives free slots to chats in turn, so a large playlist of one chat doesn't delay other chats.
+/// Work of the same chat is done in the order it's requested.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    state: Arc<Mutex<State>>,
+}
+
+impl Scheduler {
+    #[must_use]
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                available: concurrency,
+                ..State::default()
+            })),
+        }
+    }
+
+    /// Waits for a free slot for the work of the chat, the work should be held until the permit is dropped
+    pub async fn acquire(&self, chat_id: Option<i64>) -> Permit {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+
+            if state.available > 0 && state.order.is_empty() {
+                state.available -= 1;
+
+                return Permit { state: self.state.clone() };
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            let waiters = state.waiters.entry(chat_id).or_default();
+            waiters.push_back(sender);
+
+            if waiters.len() == 1 {
+                state.order.push_back(chat_id);
+            }
+
+            event!(Level::TRACE, chat_id, chats = state.order.len(), "Wait for a slot");
+
+            receiver
+        };
+
+        let mut waiting = Waiting {
+            receiver: Some(receiver),
+            state: self.state.clone(),
+        };
+
+        waiting
+            .receiver
+            .as_mut()
+            .unwrap()
+            .await
+            .expect("Sender shouldn't be dropped because waiters are removed only when they get a slot");
+        waiting.receiver = None;
+
+        Permit { state: self.state.clone() }
+    }
+}
+
+/// Waiter for a slot, the slot is given to the next waiter if the waiting is cancelled after getting it
+struct Waiting {
+    receiver: Option<oneshot::Receiver<()>>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for Waiting {
+    fn drop(&mut self) {
+        let Some(mut receiver) = self.receiver.take() else {
+            return;
+        };
+
+        receiver.close();
+
+        if receiver.try_recv().is_ok() {
+            self.state.lock().unwrap().release();
+        }
+    }
+}
+
+/// Slot of the work, it's given to the next waiter when it's dropped
+#[derive(Debug)]
+pub struct Permit {
+    state: Arc<Mutex<State>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.state.lock().unwrap().release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Mutex as StdMutex;
+
+    #[tokio::test]
+    async fn test_concurrency_is_limited() {
+        let scheduler = Scheduler::new(1);
+        let permit = scheduler.acquire(Some(1)).await;
+
+        let waiting = tokio::spawn({
+            let scheduler = scheduler.clone();
+
+            async move { scheduler.acquire(Some(2)).await }
+        });
+        tokio::task::yield_now().await;
+
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+
+        waiting.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chats_get_slots_in_turn() {
+        let scheduler = Scheduler::new(1);
+        let order = Arc::new(StdMutex::new(vec![]));
+        let permit = scheduler.acquire(None).await;
+
+        let mut handles = vec![];
+        // The first chat requests three slots before the second chat requests one
+        for (chat_id, work) in [(1, 1), (1, 2), (1, 3), (2, 1)] {
+            handles.push(tokio::spawn({
+                let scheduler = scheduler.clone();
+                let order = order.clone();
+
+                async move {
+                    let _permit = scheduler.acquire(Some(chat_id)).await;
+
+                    order.lock().unwrap().push((chat_id, work));
+                }
+            }));
+            // Waiters are queued in the order of spawning
+            tokio::task::yield_now().await;
+        }
+
+        drop(permit);
+
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), [(1, 1), (2, 1), (1, 2), (1, 3)]);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_waiter_is_skipped() {
+        let scheduler = Scheduler::new(1);
+        let permit = scheduler.acquire(Some(1)).await;
+
+        let cancelled = tokio::spawn({
+            let scheduler = scheduler.clone();
+
+            async move { scheduler.acquire(Some(2)).await }
+        });
+        tokio::task::yield_now().await;
+        cancelled.abort();
+        let _ = cancelled.await;
+
+        drop(permit);
+
+        // The slot isn't lost on the cancelled waiter
+        let _permit = scheduler.acquire(Some(3)).await;
+    }
+}
@@ -0,0 +1,157 @@
+use crate::models::VideoInYT;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{event, Level};
+use uuid::Uuid;
+
+const REMOVE_EXPIRED_INTERVAL: Duration = Duration::from_secs(60);
+const RETENTION: Duration = Duration::from_secs(3600);
+const CALLBACK_DATA_PREFIX: &str = "pls";
+
+/// Action of the selection keyboard button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Toggle(usize),
+    Page(usize),
+    Download,
+    Cancel,
+}
+
+impl Action {
+    #[must_use]
+    pub fn to_callback_data(self, token: &str) -> String {
+        match self {
+            Self::Toggle(index) => format!("{CALLBACK_DATA_PREFIX}:{token}:t:{index}"),
+            Self::Page(page) => format!("{CALLBACK_DATA_PREFIX}:{token}:p:{page}"),
+            Self::Download => format!("{CALLBACK_DATA_PREFIX}:{token}:d"),
+            Self::Cancel => format!("{CALLBACK_DATA_PREFIX}:{token}:c"),
+        }
+    }
+
+    /// Returns the selection token and the action or `None` if the callback data isn't from the selection keyboard
+    #[must_use]
+    pub fn from_callback_data(data: &str) -> Option<(&str, Self)> {
+        let mut parts = data.split(':');
+
+        if parts.next()? != CALLBACK_DATA_PREFIX {
+            return None;
+        }
+
+        let token = parts.next()?;
+        let action = match (parts.next()?, parts.next()) {
+            ("t", Some(index)) => Self::Toggle(index.parse().ok()?),
+            ("p", Some(page)) => Self::Page(page.parse().ok()?),
+            ("d", None) => Self::Download,
+            ("c", None) => Self::Cancel,
+            _ => return None,
+        };
+
+        Some((token, action))
+    }
+}
+
+/// Playlist entries offered to the user to pick from
+#[derive(Debug)]
+pub struct Selection {
+    pub user_id: i64,
+    pub chat_id: i64,
+    /// Forum topic of the message with the playlist URL
+    pub thread_id: Option<i64>,
+    /// The message with the playlist URL, downloaded videos are sent as a reply to it
+    pub message_id: i64,
+    /// The message with the selection keyboard, it's set after the message is sent
+    pub keyboard_message_id: Option<i64>,
+    pub videos: Vec<VideoInYT>,
+    pub selected: Vec<bool>,
+    expires_at: Instant,
+}
+
+impl Selection {
+    #[must_use]
+    pub fn selected_count(&self) -> usize {
+        self.selected.iter().filter(|selected| **selected).count()
+    }
+}
+
+/// Pending playlist selections by token.
+/// The token is passed in callback data of the selection keyboard, so it has to be short.
+#[derive(Debug, Clone, Default)]
+pub struct SelectionStore {
+    selections: Arc<Mutex<HashMap<Box<str>, Selection>>>,
+}
+
+impl SelectionStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, user_id: i64, chat_id: i64, thread_id: Option<i64>, message_id: i64, videos: Vec<VideoInYT>) -> Box<str> {
+        let token: Box<str> = Uuid::new_v4().simple().to_string().into();
+
+        self.selections.lock().unwrap().insert(
+            token.clone(),
+            Selection {
+                user_id,
+                chat_id,
+                thread_id,
+                message_id,
+                keyboard_message_id: None,
+                selected: vec![false; videos.len()],
+                videos,
+                expires_at: Instant::now() + RETENTION,
+            },
+        );
+
+        token
+    }
+
+    /// Calls the function with the selection, so the keyboard can be built without cloning the videos.
+    /// Returns `None` if the selection expired or belongs to another user.
+    pub fn with<R>(&self, token: &str, user_id: i64, f: impl FnOnce(&mut Selection) -> R) -> Option<R> {
+        self.selections
+            .lock()
+            .unwrap()
+            .get_mut(token)
+            .filter(|selection| selection.user_id == user_id && selection.expires_at > Instant::now())
+            .map(f)
+    }
+
+    pub fn remove(&self, token: &str, user_id: i64) -> Option<Selection> {
+        let mut selections = self.selections.lock().unwrap();
+
+        if selections.get(token)?.user_id != user_id {
+            return None;
+        }
+
+        selections.remove(token).filter(|selection| selection.expires_at > Instant::now())
+    }
+
+    fn remove_expired(&self) -> usize {
+        let now = Instant::now();
+
+        let mut selections = self.selections.lock().unwrap();
+        let len_before = selections.len();
+        selections.retain(|_, selection| selection.expires_at > now);
+
+        len_before - selections.len()
+    }
+}
+
+pub async fn remove_expired_in_loop(selection_store: SelectionStore) {
+    let mut interval = tokio::time::interval(REMOVE_EXPIRED_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let removed_count = selection_store.remove_expired();
+
+        if removed_count > 0 {
+            event!(Level::DEBUG, removed_count, "Expired playlist selections removed");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    cmd::convert_to_jpg,
+    config::{ProcessLimits, WorkDir},
+    links::{self, LinkStore},
+    metrics,
+};
+
+use axum::{
+    body::Body,
+    extract::{FromRef, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use bytes::Bytes;
+use std::{fs, io, net::SocketAddr};
+use tempfile::Builder;
+use tokio::{fs::File, net::TcpListener, task::spawn_blocking};
+use tokio_util::io::ReaderStream;
+use tracing::{event, instrument, Level};
+
+#[derive(Clone)]
+struct AppState {
+    link_store: LinkStore,
+    process_limits: ProcessLimits,
+    work_dir: WorkDir,
+}
+
+impl FromRef<AppState> for LinkStore {
+    fn from_ref(state: &AppState) -> Self {
+        state.link_store.clone()
+    }
+}
+
+impl FromRef<AppState> for ProcessLimits {
+    fn from_ref(state: &AppState) -> Self {
+        state.process_limits
+    }
+}
+
+impl FromRef<AppState> for WorkDir {
+    fn from_ref(state: &AppState) -> Self {
+        state.work_dir.clone()
+    }
+}
+
+async fn healthz() -> &'static str {
+    "OK"
+}
+
+async fn metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], metrics::gather())
+}
+
+#[instrument(skip_all, fields(token_hash = %links::token_hash(&token)))]
+async fn download(State(link_store): State<LinkStore>, Path(token): Path<String>) -> Result<impl IntoResponse, StatusCode> {
+    let Some(path) = link_store.get(&token) else {
+        event!(Level::DEBUG, "Download link not found or expired");
+
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    let file = File::open(&path).await.map_err(|err| {
+        event!(Level::ERROR, %err, "Error opening file of download link");
+
+        StatusCode::NOT_FOUND
+    })?;
+    let file_size = file.metadata().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.len();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().replace('"', ""))
+        .unwrap_or_default();
+
+    event!(Level::DEBUG, file_size, "Serve download link");
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_owned()),
+            (header::CONTENT_LENGTH, file_size.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file_name}\"")),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    ))
+}
+
+/// Converts a thumbnail in a format unsupported by Telegram to JPEG.
+/// The JPEG is kept with the link, so the thumbnail is converted once however many times it's requested.
+#[instrument(skip_all, fields(token_hash = %links::token_hash(&token)))]
+async fn thumbnail(
+    State(link_store): State<LinkStore>,
+    State(process_limits): State<ProcessLimits>,
+    State(work_dir): State<WorkDir>,
+    Path(token): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    let token = token.trim_end_matches(".jpg");
+
+    let Some((source_url, jpeg)) = link_store.get_thumbnail(token) else {
+        event!(Level::DEBUG, "Thumbnail link not found or expired");
+
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    if let Some(jpeg) = jpeg {
+        event!(Level::DEBUG, "Serve converted thumbnail");
+
+        return Ok(([(header::CONTENT_TYPE, "image/jpeg")], jpeg));
+    }
+
+    let bytes = spawn_blocking(move || {
+        let output_file = Builder::new().suffix(".jpg").tempfile_in(&work_dir.path)?;
+
+        convert_to_jpg(source_url, output_file.path(), process_limits)?;
+
+        fs::read(output_file.path())
+    })
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    .map_err(|err| {
+        event!(Level::ERROR, %err, "Error converting thumbnail");
+
+        StatusCode::BAD_GATEWAY
+    })?;
+
+    // FFmpeg doesn't fail the conversion if the source is unavailable, it just doesn't write the output
+    if bytes.is_empty() {
+        event!(Level::WARN, "Converted thumbnail is empty");
+
+        return Err(StatusCode::BAD_GATEWAY);
+    }
+
+    let jpeg = Bytes::from(bytes);
+    link_store.set_thumbnail_jpeg(token, jpeg.clone());
+
+    Ok(([(header::CONTENT_TYPE, "image/jpeg")], jpeg))
+}
+
+#[instrument(skip_all, fields(%address))]
+pub async fn run(address: SocketAddr, link_store: LinkStore, process_limits: ProcessLimits, work_dir: WorkDir) -> Result<(), io::Error> {
+    let router = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics))
+        .route("/download/:token", get(download))
+        .route("/thumbnail/:token", get(thumbnail))
+        .with_state(AppState {
+            link_store,
+            process_limits,
+            work_dir,
+        });
+
+    let listener = TcpListener::bind(address).await?;
+
+    event!(Level::INFO, "HTTP server started");
+
+    axum::serve(listener, router).await
+}
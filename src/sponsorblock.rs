@@ -0,0 +1,140 @@
+use crate::youtube_fallback;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+use tracing::{event, instrument, Level};
+
+const API_URL: &str = "https://sponsor.ajay.app/api/skipSegments";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Categories of segments that can be removed, `poi_highlight` and `chapter` only mark a point or a title, so they aren't here
+pub const CATEGORIES: [&str; 8] = [
+    "sponsor",
+    "intro",
+    "outro",
+    "selfpromo",
+    "preview",
+    "filler",
+    "interaction",
+    "music_offtopic",
+];
+
+#[derive(thiserror::Error, Debug)]
+pub enum ErrorKind {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct Segment {
+    /// Start and end in seconds
+    segment: (f64, f64),
+}
+
+/// Parses comma-separated categories, `all` means all of [`CATEGORIES`].
+/// Returns `None` if a category is unknown.
+#[must_use]
+pub fn parse_categories(value: &str) -> Option<Vec<String>> {
+    if value == "all" {
+        return Some(CATEGORIES.iter().map(|category| (*category).to_owned()).collect());
+    }
+
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|category| !category.is_empty())
+        .map(|category| CATEGORIES.contains(&category).then(|| category.to_owned()))
+        .collect()
+}
+
+/// Gets segments of the categories of the video, sorted by start and merged if they overlap.
+/// Only videos from youtube.com have segments, others get an empty list without a request.
+#[instrument(skip_all, fields(%url))]
+pub async fn segments(url: &str, categories: &[String]) -> Result<Vec<(f64, f64)>, ErrorKind> {
+    let Some(video_id) = youtube_fallback::video_id(url) else {
+        return Ok(vec![]);
+    };
+
+    let response = reqwest::Client::new()
+        .get(API_URL)
+        .query(&[("videoID", video_id), ("categories", serde_json::to_string(categories)?)])
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await?;
+
+    // The API responds with 404 if the video doesn't have segments
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(vec![]);
+    }
+
+    let body = response.error_for_status()?.text().await?;
+    let segments = parse_segments(&body)?;
+
+    event!(Level::DEBUG, segments_len = segments.len(), "Got segments");
+
+    Ok(segments)
+}
+
+/// Parses segments of the API response, sorts them by start and merges overlapping ones
+fn parse_segments(body: &str) -> Result<Vec<(f64, f64)>, serde_json::Error> {
+    let mut segments: Vec<(f64, f64)> = serde_json::from_str::<Vec<Segment>>(body)?
+        .into_iter()
+        .map(|Segment { segment }| segment)
+        .filter(|(start, end)| end > start)
+        .collect();
+    segments.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    let mut merged: Vec<(f64, f64)> = Vec::with_capacity(segments.len());
+    for (start, end) in segments {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = last_end.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Total duration in seconds of the merged segments
+#[must_use]
+pub fn removed_duration(segments: &[(f64, f64)]) -> f64 {
+    segments.iter().map(|(start, end)| end - start).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_categories() {
+        assert_eq!(
+            parse_categories("sponsor, intro,"),
+            Some(vec!["sponsor".to_owned(), "intro".to_owned()])
+        );
+        assert_eq!(parse_categories("all").map(|categories| categories.len()), Some(CATEGORIES.len()));
+        assert_eq!(parse_categories("sponsor,chapter"), None);
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        let body = r#"[
+            {"category": "outro", "segment": [300.5, 320.0], "UUID": "c"},
+            {"category": "sponsor", "segment": [10.0, 40.0], "UUID": "a"},
+            {"category": "selfpromo", "segment": [35.0, 50.0], "UUID": "b"},
+            {"category": "intro", "segment": [60.0, 60.0], "UUID": "d"}
+        ]"#;
+
+        // Overlapping segments are merged and empty ones are skipped
+        assert_eq!(parse_segments(body).unwrap(), [(10.0, 50.0), (300.5, 320.0)]);
+        assert_eq!(parse_segments("[]").unwrap(), []);
+        assert!(parse_segments("Not Found").is_err());
+    }
+
+    #[test]
+    fn test_removed_duration() {
+        assert!((removed_duration(&[(10.0, 50.0), (300.5, 320.0)]) - 59.5).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::events::{Event, MediaKind};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::{event, Level};
+use url::Url;
+
+/// Download counters of a chat
+#[derive(Debug, Default, Clone)]
+pub struct ChatStats {
+    pub videos: u64,
+    pub audios: u64,
+    pub failed: u64,
+    domains: HashMap<Box<str>, u64>,
+}
+
+impl ChatStats {
+    #[must_use]
+    pub const fn downloads(&self) -> u64 {
+        self.videos + self.audios
+    }
+
+    /// Domains with the most downloads, the most popular first
+    #[must_use]
+    pub fn top_domains(&self, count: usize) -> Vec<(&str, u64)> {
+        let mut domains: Vec<(&str, u64)> = self.domains.iter().map(|(domain, count)| (&**domain, *count)).collect();
+        domains.sort_by(|(a_domain, a_count), (b_domain, b_count)| b_count.cmp(a_count).then(a_domain.cmp(b_domain)));
+        domains.truncate(count);
+        domains
+    }
+}
+
+/// Download counters per chat since the bot start, collected from the pipeline events.
+/// Inline mode downloads aren't counted, because they don't have a chat.
+#[derive(Debug, Clone, Default)]
+pub struct StatsStore {
+    chats: Arc<Mutex<HashMap<i64, ChatStats>>>,
+}
+
+impl StatsStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn get(&self, chat_id: i64) -> Option<ChatStats> {
+        self.chats.lock().unwrap().get(&chat_id).cloned()
+    }
+
+    /// Moves counters of a group upgraded to a supergroup to the new chat ID
+    pub fn migrate(&self, from_chat_id: i64, to_chat_id: i64) {
+        let mut chats = self.chats.lock().unwrap();
+
+        if let Some(chat_stats) = chats.remove(&from_chat_id) {
+            chats.insert(to_chat_id, chat_stats);
+        }
+    }
+
+    fn record(&self, event: &Event) {
+        match event {
+            Event::DownloadFinished {
+                chat_id: Some(chat_id),
+                url,
+                media_kind,
+            } => {
+                let mut chats = self.chats.lock().unwrap();
+                let chat_stats = chats.entry(*chat_id).or_default();
+
+                match media_kind {
+                    MediaKind::Video => chat_stats.videos += 1,
+                    MediaKind::Audio => chat_stats.audios += 1,
+                }
+
+                if let Some(domain) = get_domain(url) {
+                    *chat_stats.domains.entry(domain).or_default() += 1;
+                }
+            }
+            Event::DownloadFailed {
+                chat_id: Some(chat_id), ..
+            } => {
+                self.chats.lock().unwrap().entry(*chat_id).or_default().failed += 1;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Host of the URL without the `www.` prefix
+#[must_use]
+pub fn get_domain(url: &str) -> Option<Box<str>> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+
+    Some(host.strip_prefix("www.").unwrap_or(host).into())
+}
+
+pub async fn record_events(stats_store: StatsStore, mut receiver: Receiver<Event>) {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => stats_store.record(&event),
+            Err(RecvError::Lagged(skipped_count)) => {
+                event!(Level::WARN, skipped_count, "Stats subscriber lagged");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
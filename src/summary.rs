@@ -0,0 +1,166 @@
+use crate::{
+    cmd::download_subtitles_to_path,
+    config::{ProcessLimits, Summary as SummaryConfig},
+};
+
+use reqwest::{blocking::Client, header};
+use serde_json::{json, Value};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{event, instrument, Level};
+
+/// Max transcript length in chars sent to the LLM, long videos are cut to keep the request fast and cheap
+const MAX_TRANSCRIPT_LEN: usize = 24_000;
+/// Telegram caption limit is 1024 chars, so some space is left for the rest of the caption
+const MAX_SUMMARY_LEN: usize = 800;
+const PROMPT: &str = "Summarize the video by its transcript in 2-3 sentences. \
+    Answer in the language of the transcript, without any introduction.";
+
+#[derive(thiserror::Error, Debug)]
+pub enum ErrorKind {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("Subtitles not found")]
+    SubtitlesNotFound,
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Unexpected LLM response: {0}")]
+    UnexpectedResponse(Box<str>),
+}
+
+fn get_subtitles_path_in_dir(path_dir: impl AsRef<Path>) -> Result<Option<PathBuf>, io::Error> {
+    for entry in fs::read_dir(path_dir)? {
+        let path = entry?.path();
+
+        if path.extension().is_some_and(|extension| extension == "vtt") {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Extracts plain text from `WebVTT` subtitles.
+/// Auto-generated subtitles repeat the previous line in each cue, so consecutive duplicates are skipped.
+fn transcript_from_vtt(vtt: &str) -> String {
+    let mut transcript = String::new();
+    let mut last_line = "";
+
+    for line in vtt.lines() {
+        let line = line.trim();
+
+        if line.is_empty()
+            || line == "WEBVTT"
+            || line.contains("-->")
+            || line.starts_with("Kind:")
+            || line.starts_with("Language:")
+            || line.starts_with("NOTE")
+            || line.chars().all(|char| char.is_ascii_digit())
+        {
+            continue;
+        }
+
+        if line == last_line {
+            continue;
+        }
+
+        last_line = line;
+
+        // Remove inline timestamps and styling tags like `<00:00:01.000>` and `<c>`
+        let mut in_tag = false;
+        for char in line.chars() {
+            match char {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                _ if !in_tag => transcript.push(char),
+                _ => {}
+            }
+        }
+        transcript.push(' ');
+
+        if transcript.len() >= MAX_TRANSCRIPT_LEN {
+            break;
+        }
+    }
+
+    transcript.trim().to_owned()
+}
+
+fn summarize(config: &SummaryConfig, llm_url: &str, transcript: &str) -> Result<String, ErrorKind> {
+    let client = Client::builder().timeout(Duration::from_secs(config.timeout)).build()?;
+
+    let body = json!({
+        "model": config.llm_model,
+        "messages": [
+            { "role": "system", "content": PROMPT },
+            { "role": "user", "content": transcript },
+        ],
+    });
+
+    let mut request = client
+        .post(llm_url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(&body)?);
+
+    if let Some(api_key) = config.llm_api_key.as_deref() {
+        request = request.bearer_auth(api_key);
+    }
+
+    let response: Value = serde_json::from_slice(&request.send()?.error_for_status()?.bytes()?)?;
+
+    let Some(summary) = response["choices"][0]["message"]["content"].as_str() else {
+        return Err(ErrorKind::UnexpectedResponse(response.to_string().into_boxed_str()));
+    };
+
+    let summary = summary.trim();
+
+    Ok(match summary.char_indices().nth(MAX_SUMMARY_LEN) {
+        Some((index, _)) => format!("{}...", &summary[..index]),
+        None => summary.to_owned(),
+    })
+}
+
+/// Makes a short summary of the video by its subtitles.
+/// Each step is limited by the configured timeout, so the summary doesn't delay the video much.
+#[instrument(skip_all, fields(url = url.as_ref()))]
+pub fn video(
+    config: &SummaryConfig,
+    executable_ytdl_path: impl AsRef<str>,
+    url: impl AsRef<str>,
+    extra_args: &[String],
+    temp_dir_path: impl AsRef<Path>,
+    limits: ProcessLimits,
+) -> Result<Option<String>, ErrorKind> {
+    let Some(llm_url) = config.llm_url.as_deref() else {
+        return Ok(None);
+    };
+
+    let subtitles_dir_path = temp_dir_path.as_ref().join("subtitles");
+    fs::create_dir_all(&subtitles_dir_path)?;
+
+    download_subtitles_to_path(
+        executable_ytdl_path,
+        url,
+        &config.subtitle_languages,
+        &subtitles_dir_path,
+        extra_args,
+        config.timeout,
+        limits,
+    )?;
+
+    let subtitles_path = get_subtitles_path_in_dir(&subtitles_dir_path)?.ok_or(ErrorKind::SubtitlesNotFound)?;
+    let transcript = transcript_from_vtt(&fs::read_to_string(subtitles_path)?);
+
+    if transcript.is_empty() {
+        return Err(ErrorKind::SubtitlesNotFound);
+    }
+
+    event!(Level::DEBUG, transcript_len = transcript.len(), "Got transcript");
+
+    summarize(config, llm_url, &transcript).map(Some)
+}
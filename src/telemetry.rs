@@ -0,0 +1,49 @@
+use crate::cancellation::CancelToken;
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig as _};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tokio::task::JoinHandle;
+use tracing::{Span, Subscriber};
+use tracing_subscriber::{filter::filter_fn, registry::LookupSpan, Layer};
+
+/// Only spans of the bot are exported, so spans of HTTP clients, including the exporter one, don't get into traces
+const CRATE_NAME: &str = env!("CARGO_CRATE_NAME");
+
+/// Layer that exports spans of the bot to an OpenTelemetry collector by OTLP over HTTP.
+/// Finished spans are exported in batches by a background thread, so slow collectors don't block handlers.
+/// Spans are sent to `/v1/traces` of the endpoint.
+pub fn otlp_layer<S>(endpoint: &str, service_name: &str) -> Result<impl Layer<S>, ExporterBuildError>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_owned()).build())
+        .build();
+
+    Ok(tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(CRATE_NAME))
+        .with_filter(filter_fn(|metadata| metadata.target().starts_with(CRATE_NAME))))
+}
+
+/// Runs the blocking closure in the current span, so spans of downloads are children of the handler span in traces.
+/// The cancel token of the current task is passed too, so processes of the closure are killed if the download is cancelled.
+pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let span = Span::current();
+    let cancel_token = CancelToken::current();
+
+    tokio::task::spawn_blocking(move || {
+        let _entered = cancel_token.as_ref().map(CancelToken::enter);
+
+        span.in_scope(f)
+    })
+}
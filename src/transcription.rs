@@ -0,0 +1,112 @@
+use crate::{
+    cmd::{convert_to_wav, transcribe_to_srt},
+    config::{ProcessLimits, Transcription as TranscriptionConfig},
+};
+
+use reqwest::blocking::{multipart::Form, Client};
+use std::{fs, io, path::Path, time::Duration};
+use tracing::{event, instrument, Level};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ErrorKind {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("Transcription isn't configured")]
+    NotConfigured,
+    #[error("Audio of {size} bytes exceeds the upload limit of {max_size} bytes")]
+    TooLarge { size: u64, max_size: u64 },
+}
+
+/// Sends the audio to the OpenAI-compatible endpoint and returns the transcript in SRT.
+/// The audio is checked against the upload limit first, so the endpoint doesn't get a file it rejects.
+fn api(config: &TranscriptionConfig, api_url: &str, audio_path: &Path) -> Result<String, ErrorKind> {
+    let size = fs::metadata(audio_path)?.len();
+    if size > config.api_max_file_size {
+        return Err(ErrorKind::TooLarge {
+            size,
+            max_size: config.api_max_file_size,
+        });
+    }
+
+    let client = Client::builder().timeout(Duration::from_secs(config.timeout)).build()?;
+
+    let mut form = Form::new().text("model", config.api_model.clone()).text("response_format", "srt");
+    if let Some(language) = config.language.clone() {
+        form = form.text("language", language);
+    }
+    let form = form.file("file", audio_path)?;
+
+    let mut request = client.post(api_url).multipart(form);
+
+    if let Some(api_key) = config.api_key.as_deref() {
+        request = request.bearer_auth(api_key);
+    }
+
+    Ok(request.send()?.error_for_status()?.text()?)
+}
+
+/// Converts the audio to WAV and transcribes it with the `whisper.cpp` CLI, returns the transcript in SRT
+fn whisper_cpp(
+    config: &TranscriptionConfig,
+    executable_path: &str,
+    model_path: &Path,
+    audio_path: &Path,
+    temp_dir_path: &Path,
+    limits: ProcessLimits,
+) -> Result<String, ErrorKind> {
+    let wav_path = temp_dir_path.join("transcription.wav");
+    let output_path_without_extension = temp_dir_path.join("transcription");
+
+    convert_to_wav(audio_path, &wav_path, limits)?;
+    transcribe_to_srt(
+        executable_path,
+        model_path,
+        &wav_path,
+        &output_path_without_extension,
+        config.language.as_deref(),
+        config.timeout,
+        limits,
+    )?;
+
+    Ok(fs::read_to_string(output_path_without_extension.with_extension("srt"))?)
+}
+
+/// Transcribes the audio with the configured backend, see [`TranscriptionConfig`].
+/// Returns the transcript in SRT.
+#[instrument(skip_all, fields(audio_path = ?audio_path.as_ref()))]
+pub fn audio(
+    config: &TranscriptionConfig,
+    audio_path: impl AsRef<Path>,
+    temp_dir_path: impl AsRef<Path>,
+    limits: ProcessLimits,
+) -> Result<String, ErrorKind> {
+    let audio_path = audio_path.as_ref();
+
+    let srt = match (
+        config.api_url.as_deref(),
+        config.whisper_cpp_full_path.as_deref(),
+        config.whisper_cpp_model_path.as_deref(),
+    ) {
+        (Some(api_url), _, _) => api(config, api_url, audio_path)?,
+        (None, Some(executable_path), Some(model_path)) => {
+            whisper_cpp(config, executable_path, model_path, audio_path, temp_dir_path.as_ref(), limits)?
+        }
+        _ => return Err(ErrorKind::NotConfigured),
+    };
+
+    event!(Level::DEBUG, srt_len = srt.len(), "Audio transcribed");
+
+    Ok(srt)
+}
+
+/// Extracts plain text from SRT subtitles, cues are joined by spaces
+#[must_use]
+pub fn text_from_srt(srt: &str) -> String {
+    srt.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.contains("-->") && !line.chars().all(|char| char.is_ascii_digit()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
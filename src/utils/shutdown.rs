@@ -1,6 +1,11 @@
+use crate::jobs::JobStore;
+
 use telers::event::simple::HandlerResult;
 
+/// Keeps downloads aborted by the shutdown in the job store, so they're resumed after the restart
 #[allow(clippy::module_name_repetitions)]
-pub async fn on_shutdown() -> HandlerResult {
+pub async fn on_shutdown(job_store: JobStore) -> HandlerResult {
+    job_store.close();
+
     Ok(())
 }
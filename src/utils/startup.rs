@@ -1,18 +1,134 @@
-use telers::{event::simple::HandlerResult, methods::SetMyCommands, types::BotCommand, Bot};
+use crate::{config::WorkDir, fs::remove_stale_dirs, locale::Locale};
 
-async fn set_my_commands(bot: Bot) -> HandlerResult {
-    let commands = [
-        BotCommand::new("start", "Start the bot"),
-        BotCommand::new("vd", "Download a video"),
-        BotCommand::new("ad", "Download an audio"),
+use std::time::Duration;
+use telers::{
+    errors::{SessionErrorKind, TelegramErrorKind},
+    event::simple::HandlerResult,
+    methods::{DeleteMessage, SendMessage, SetMyCommands},
+    types::BotCommand,
+    Bot,
+};
+use tokio::task::spawn_blocking;
+use tracing::{event, instrument, Level};
+
+const REMOVE_STALE_DIRS_INTERVAL: Duration = Duration::from_secs(600);
+
+async fn set_my_commands(bot: &Bot, locale: Locale, transcription_enabled: bool) -> HandlerResult {
+    let mut commands = vec![
+        BotCommand::new("start", locale.command_start()),
+        BotCommand::new("vd", locale.command_video_download()),
+        BotCommand::new("ad", locale.command_audio_download()),
+        BotCommand::new("vs", locale.command_video_select()),
+        BotCommand::new("round", locale.command_video_note()),
+        BotCommand::new("formats", locale.command_formats()),
+        BotCommand::new("stats", locale.command_stats()),
+        BotCommand::new("autodownload", locale.command_auto_download()),
+        BotCommand::new("source_button", locale.command_source_button()),
+        BotCommand::new("audio_button", locale.command_audio_button()),
+        BotCommand::new("show_link", locale.command_show_link()),
+        BotCommand::new("description", locale.command_description()),
+        BotCommand::new("default", locale.command_default_media_type()),
+        BotCommand::new("allow_domain", locale.command_allow_domain()),
+        BotCommand::new("deny_domain", locale.command_deny_domain()),
     ];
+    if transcription_enabled {
+        commands.push(BotCommand::new("transcribe", locale.command_transcribe()));
+    }
 
     bot.send(SetMyCommands::new(commands)).await?;
 
     Ok(())
 }
 
+/// Checks that the bot can post and delete messages in the receiver chat.
+/// All downloaded media go through this chat, so a misconfigured chat breaks every download with opaque send errors.
+#[instrument(skip_all, fields(%receiver_video_chat_id))]
+async fn check_receiver_chat(bot: &Bot, receiver_video_chat_id: i64, locale: Locale) -> HandlerResult {
+    let message = match bot
+        .send(SendMessage::new(receiver_video_chat_id, locale.receiver_chat_self_test()).disable_notification(true))
+        .await
+    {
+        Ok(message) => message,
+        Err(err) => {
+            if let SessionErrorKind::Telegram(TelegramErrorKind::MigrateToChat { migrate_to_chat_id, .. }) = &err {
+                event!(
+                    Level::ERROR,
+                    "The receiver chat was upgraded to a supergroup. Set `RECEIVER_VIDEO_CHAT_ID` to {migrate_to_chat_id}",
+                );
+            } else {
+                event!(
+                    Level::ERROR,
+                    %err,
+                    "Can't post messages in the receiver chat. \
+                    Check that `RECEIVER_VIDEO_CHAT_ID` is correct, the bot is a member of the chat and it's allowed to post messages",
+                );
+            }
+
+            return Err(err.into());
+        }
+    };
+
+    if let Err(err) = bot.send(DeleteMessage::new(receiver_video_chat_id, message.id())).await {
+        // Downloads still work without this right, so we don't stop the bot
+        event!(
+            Level::ERROR,
+            %err,
+            "Can't delete messages in the receiver chat. \
+            Give the bot the `Delete messages` admin right, otherwise temporary media will pile up in the chat",
+        );
+    } else {
+        event!(Level::DEBUG, "Receiver chat is configured correctly");
+    }
+
+    Ok(())
+}
+
+async fn remove_stale_dirs_in_work_dir(work_dir: &WorkDir) {
+    let path = work_dir.path.clone();
+    let stale_after = Duration::from_secs(work_dir.stale_after);
+
+    match spawn_blocking(move || remove_stale_dirs(path, stale_after)).await {
+        Ok(Ok(removed_count)) => {
+            if removed_count > 0 {
+                event!(Level::INFO, removed_count, "Stale dirs removed from the work dir");
+            }
+        }
+        Ok(Err(err)) => {
+            event!(Level::ERROR, %err, "Error removing stale dirs from the work dir");
+        }
+        Err(err) => {
+            event!(Level::ERROR, %err, "Error while joining handle");
+        }
+    }
+}
+
+/// Removes download folders leaked if the bot crashed mid-download, then keeps doing it periodically
+#[instrument(skip_all, fields(path = ?work_dir.path))]
+async fn clean_work_dir(work_dir: WorkDir) {
+    remove_stale_dirs_in_work_dir(&work_dir).await;
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REMOVE_STALE_DIRS_INTERVAL);
+        // The first tick completes immediately, but the dir was just cleaned
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+
+            remove_stale_dirs_in_work_dir(&work_dir).await;
+        }
+    });
+}
+
 #[allow(clippy::module_name_repetitions)]
-pub async fn on_startup(bot: Bot) -> HandlerResult {
-    set_my_commands(bot).await
+pub async fn on_startup(
+    bot: Bot,
+    receiver_video_chat_id: i64,
+    work_dir: WorkDir,
+    locale: Locale,
+    transcription_enabled: bool,
+) -> HandlerResult {
+    clean_work_dir(work_dir).await;
+    check_receiver_chat(&bot, receiver_video_chat_id, locale).await?;
+    set_my_commands(&bot, locale, transcription_enabled).await
 }
@@ -0,0 +1,347 @@
+use crate::{
+    config::{FallbackApi, FallbackInstance},
+    models::{VideoInYT, VideosInYT},
+};
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tracing::{event, instrument, Level};
+use url::Url;
+
+/// Time a failed instance is skipped for, so requests don't wait for a dead instance every time
+const FAILED_INSTANCE_COOLDOWN: Duration = Duration::from_secs(300);
+
+lazy_static! {
+    static ref FAILED_AT: Mutex<HashMap<Box<str>, Instant>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_INSTANCE_INDEX: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(thiserror::Error, Debug)]
+enum ErrorKind {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Adaptive stream of a video, it has either video or audio
+#[derive(Debug)]
+struct Stream {
+    itag: String,
+    url: String,
+    mime_type: String,
+    codec: String,
+    bitrate: Option<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    content_length: u64,
+}
+
+impl Stream {
+    fn is_audio(&self) -> bool {
+        self.mime_type.starts_with("audio/")
+    }
+
+    /// Converts the stream to the format of `yt-dlp` info
+    fn to_format(&self) -> Value {
+        let subtype = self.mime_type.split_once('/').map_or("", |(_, subtype)| subtype);
+        let (ext, acodec, vcodec) = match (self.is_audio(), subtype) {
+            (true, "mp4") => ("m4a", self.codec.as_str(), "none"),
+            (true, _) => (subtype, self.codec.as_str(), "none"),
+            (false, _) => (subtype, "none", self.codec.as_str()),
+        };
+        let bitrate = self.bitrate.map(|bitrate| bitrate / 1000.0);
+        let (abr, vbr) = if self.is_audio() { (bitrate, None) } else { (None, bitrate) };
+
+        json!({
+            "format_id": self.itag,
+            "url": self.url,
+            "ext": ext,
+            "container": format!("{ext}_dash"),
+            "acodec": acodec,
+            "vcodec": vcodec,
+            "abr": abr,
+            "vbr": vbr,
+            "width": self.width,
+            "height": self.height,
+            "filesize": self.content_length,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PipedVideo {
+    title: Option<String>,
+    description: Option<String>,
+    uploader: Option<String>,
+    duration: Option<f64>,
+    thumbnail_url: Option<String>,
+    #[serde(default)]
+    video_streams: Vec<PipedStream>,
+    #[serde(default)]
+    audio_streams: Vec<PipedStream>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PipedStream {
+    url: String,
+    itag: Option<i64>,
+    mime_type: Option<String>,
+    codec: Option<String>,
+    #[serde(default)]
+    video_only: bool,
+    bitrate: Option<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    content_length: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvidiousVideo {
+    title: Option<String>,
+    description: Option<String>,
+    author: Option<String>,
+    length_seconds: Option<f64>,
+    #[serde(default)]
+    video_thumbnails: Vec<InvidiousThumbnail>,
+    #[serde(default)]
+    adaptive_formats: Vec<InvidiousStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvidiousThumbnail {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvidiousStream {
+    url: String,
+    itag: String,
+    /// MIME type with codecs, for example `video/mp4; codecs="avc1.640028"`
+    #[serde(rename = "type")]
+    kind: String,
+    bitrate: Option<String>,
+    clen: Option<String>,
+    size: Option<String>,
+}
+
+/// ID of the YouTube video or `None` if the URL isn't a YouTube video
+pub fn video_id(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    let id = match host {
+        "youtu.be" => url.path_segments()?.next()?.to_owned(),
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" => {
+            let mut segments = url.path_segments()?;
+
+            match segments.next()? {
+                "watch" => url.query_pairs().find(|(key, _)| key == "v")?.1.into_owned(),
+                "shorts" | "live" | "embed" => segments.next()?.to_owned(),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    (!id.is_empty()).then_some(id)
+}
+
+fn piped_streams(video: &mut PipedVideo) -> Vec<Stream> {
+    video
+        .video_streams
+        .drain(..)
+        .filter(|stream| stream.video_only)
+        .chain(video.audio_streams.drain(..))
+        .filter_map(|stream| {
+            Some(Stream {
+                itag: stream.itag?.to_string(),
+                url: stream.url,
+                mime_type: stream.mime_type?,
+                codec: stream.codec?,
+                bitrate: stream.bitrate,
+                width: stream.width,
+                height: stream.height,
+                content_length: stream.content_length?,
+            })
+        })
+        .collect()
+}
+
+fn invidious_streams(instance_url: &str, video: &mut InvidiousVideo) -> Vec<Stream> {
+    video
+        .adaptive_formats
+        .drain(..)
+        .filter_map(|stream| {
+            let (mime_type, codecs) = stream.kind.split_once(';')?;
+            let codec = codecs.trim().strip_prefix("codecs=")?.trim_matches('"');
+            let (width, height) = match stream.size.as_deref().and_then(|size| size.split_once('x')) {
+                Some((width, height)) => (width.parse().ok(), height.parse().ok()),
+                None => (None, None),
+            };
+            // Streams of the local proxy have relative URLs
+            let url = if stream.url.starts_with('/') {
+                format!("{instance_url}{}", stream.url)
+            } else {
+                stream.url
+            };
+
+            Some(Stream {
+                itag: stream.itag,
+                url,
+                mime_type: mime_type.trim().to_owned(),
+                codec: codec.to_owned(),
+                bitrate: stream.bitrate.and_then(|bitrate| bitrate.parse().ok()),
+                width,
+                height,
+                content_length: stream.clen?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Gets the video info from the instance in the format of `yt-dlp` info, so it's downloaded the same way.
+/// Only adaptive streams with a known size are used, because they're downloaded by ranges without `yt-dlp`.
+async fn instance_info(
+    client: &reqwest::Client,
+    instance: &FallbackInstance,
+    id: &str,
+    url: &str,
+    timeout: u64,
+) -> Result<Value, ErrorKind> {
+    let (api_url, streams, mut info) = match instance.api {
+        FallbackApi::Piped => {
+            let api_url = format!("{}/streams/{id}", instance.url);
+            let body = client
+                .get(&api_url)
+                .timeout(Duration::from_secs(timeout))
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let mut video: PipedVideo = serde_json::from_str(&body)?;
+            let streams = piped_streams(&mut video);
+
+            (
+                api_url,
+                streams,
+                json!({
+                    "title": video.title,
+                    "description": video.description,
+                    "uploader": video.uploader,
+                    "duration": video.duration,
+                    "thumbnail": video.thumbnail_url,
+                }),
+            )
+        }
+        FallbackApi::Invidious => {
+            // Streams are proxied by the instance, because YouTube binds stream URLs to the IP that requested them
+            let api_url = format!("{}/api/v1/videos/{id}?local=true", instance.url);
+            let body = client
+                .get(&api_url)
+                .timeout(Duration::from_secs(timeout))
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let mut video: InvidiousVideo = serde_json::from_str(&body)?;
+            let streams = invidious_streams(&instance.url, &mut video);
+
+            (
+                api_url,
+                streams,
+                json!({
+                    "title": video.title,
+                    "description": video.description,
+                    "uploader": video.author,
+                    "duration": video.length_seconds,
+                    "thumbnail": video.video_thumbnails.into_iter().next().map(|thumbnail| thumbnail.url),
+                }),
+            )
+        }
+    };
+
+    event!(Level::DEBUG, %api_url, streams_len = streams.len(), "Got video info from the instance");
+
+    info["id"] = json!(id);
+    info["original_url"] = json!(url);
+    info["formats"] = streams.iter().map(Stream::to_format).collect();
+
+    Ok(info)
+}
+
+/// Gets the info of the YouTube video from Piped or Invidious instances, rotating them between requests.
+/// A failed instance is skipped for [`FAILED_INSTANCE_COOLDOWN`] unless all instances failed.
+/// Returns `None` if the URL isn't a YouTube video or all instances failed.
+#[instrument(skip_all, fields(%url))]
+pub async fn media_info(instances: &[FallbackInstance], url: &str, timeout: u64) -> Option<VideosInYT> {
+    if instances.is_empty() {
+        return None;
+    }
+
+    let id = video_id(url)?;
+    let start_index = NEXT_INSTANCE_INDEX.fetch_add(1, Ordering::Relaxed);
+
+    let mut ordered_instances: Vec<&FallbackInstance> = instances
+        .iter()
+        .cycle()
+        .skip(start_index % instances.len())
+        .take(instances.len())
+        .collect();
+    // Healthy instances are tried first, the sort is stable, so the rotation order is kept
+    {
+        let failed_at = FAILED_AT.lock().unwrap();
+        ordered_instances.sort_by_key(|instance| {
+            failed_at
+                .get(instance.url.as_str())
+                .is_some_and(|failed_at| failed_at.elapsed() < FAILED_INSTANCE_COOLDOWN)
+        });
+    }
+
+    let client = reqwest::Client::new();
+
+    for instance in ordered_instances {
+        let result = instance_info(&client, instance, &id, url, timeout)
+            .await
+            .and_then(|info| Ok(serde_json::from_value::<VideoInYT>(info)?));
+
+        match result {
+            Ok(video) if video.get_combined_formats().is_empty() => {
+                event!(Level::WARN, instance = instance.url, "Instance doesn't have suitable streams");
+            }
+            Ok(video) => {
+                FAILED_AT.lock().unwrap().remove(instance.url.as_str());
+
+                event!(Level::INFO, instance = instance.url, "Got media info from the fallback instance");
+
+                return Some(VideosInYT::new(vec![video]));
+            }
+            Err(err) => {
+                event!(Level::WARN, %err, instance = instance.url, "Error getting media info from the fallback instance");
+            }
+        }
+
+        FAILED_AT
+            .lock()
+            .unwrap()
+            .insert(instance.url.clone().into_boxed_str(), Instant::now());
+    }
+
+    None
+}